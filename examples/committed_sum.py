@@ -0,0 +1,4 @@
+from zk_types.types import Committed, Array, field # zk_ignore
+
+def main(x: Committed[Array[field, 3]]) -> field:
+    return x[0] + x[1] + x[2]
@@ -0,0 +1,11 @@
+from zk_types.types import Private, Public, Array # zk_ignore
+from functools import reduce # zk_ignore
+
+def double(v: int) -> int:
+    return v * 2
+
+def main(x: Private[Array[int, 4]], bound: Public[int]) -> int:
+    doubled: Array[int, 4] = map(double, x)
+    assert all(map(lambda v: v < bound, doubled))
+    ordered: Array[int, 4] = sorted(x)
+    return reduce(lambda acc, v: acc * 10 + v, ordered) + max(x) - min(x)
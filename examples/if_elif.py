@@ -0,0 +1,23 @@
+from zk_types.types import Private # zk_ignore
+
+def is_even(v: int) -> bool:
+    return v % 2 == 0
+
+def classify(x: int) -> int:
+    if x < 10:
+        return 1
+    elif x < 100:
+        y: int = x * 2
+        if y > 100:
+            return 2
+        return 3
+    return 4
+
+def main(x: Private[int]) -> int:
+    z: int = 0
+    if is_even(x):
+        z = x + 1
+    else:
+        w: int = x * 3
+        z = w
+    return z * 10 + classify(x)
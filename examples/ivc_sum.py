@@ -0,0 +1,7 @@
+from zk_types.types import Private, Public, Array, field # zk_ignore
+
+# One step of an incrementally verifiable computation: fold a private value
+# into the running sum and sum of squares.
+def step(acc: Public[Array[field, 2]], x: Private[field]) -> Array[field, 2]:
+    out: Array[field, 2] = [acc[0] + x, acc[1] + x * x]
+    return out
@@ -0,0 +1,12 @@
+from zk_types.types import Private #zk_ignore
+
+def main(x: Private[int]) -> int:
+    y: int = 0
+    match x:
+        case 1:
+            y = 10
+        case 2 | 3:
+            y = 20
+        case _:
+            y = 30
+    return y
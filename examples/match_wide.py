@@ -0,0 +1,12 @@
+from zk_types.types import Private #zk_ignore
+
+# int has too many values to check that the cases cover them all, so the
+# circuit asserts that one of them matches
+def main(x: Private[int]) -> int:
+    y: int = 0
+    match x:
+        case 1:
+            y = 10
+        case 2:
+            y = 20
+    return y
@@ -0,0 +1,30 @@
+from __future__ import annotations #zk_ignore
+from zk_types.types import Private, field #zk_ignore
+from dataclasses import dataclass #zk_ignore
+from methods_lib import Pt as LibPt, lib_norm
+
+@dataclass
+class Pt:
+    x: field
+    y: field
+
+    @staticmethod
+    def diag(v: field) -> Pt:
+        return Pt(x=v, y=v)
+
+    def scale(self, k: field):
+        self.x = self.x * k
+        self.y = self.y * k
+
+    def norm(self) -> field:
+        return self.x + self.y
+
+    def __add__(self, other: Pt) -> Pt:
+        return Pt(x=self.x + other.x, y=self.y + other.y)
+
+def main(a: Private[field], k: Private[field]) -> field:
+    p: Pt = Pt.diag(a)
+    p.scale(k)
+    q: Pt = p + Pt(x=field(1), y=field(2))
+    r: LibPt = LibPt(x=q.x, y=q.y)
+    return q.norm() * lib_norm(r)
@@ -0,0 +1,14 @@
+from zk_types.types import field #zk_ignore
+from dataclasses import dataclass #zk_ignore
+
+# a class of the same name as the one in methods.py, with other methods
+@dataclass
+class Pt:
+    x: field
+    y: field
+
+    def norm(self) -> field:
+        return self.x * self.x + self.y * self.y
+
+def lib_norm(p: Pt) -> field:
+    return p.norm()
@@ -0,0 +1,6 @@
+from zkpyc.types import Private, Array, RAM, field # zk_ignore
+
+def main(table: Private[Array[field, 16, RAM]], i: Private[field], j: Private[field]) -> field:
+    acc: Array[field, 16, RAM] = table
+    acc[i] = acc[i] + acc[j]
+    return acc[i] * acc[j]
@@ -20,6 +20,9 @@ class Public(Generic[T]):
 class Private(Generic[T]):
     pass
 
+class Committed(Generic[T]):
+    pass
+
 class Array(Generic[T, N]):
     def __getitem__(self, key: int) -> T:
         return self[key]
@@ -1,20 +1,40 @@
 use circ::cfg::{
-    clap::{self, Parser, ValueEnum},
+    clap::{self, Parser, Subcommand, ValueEnum},
     CircOpt,
 };
 use std::path::{PathBuf, Path};
 use zkpyc_core::{export::{self, prepare_verifier_statements, prepare_prover_statements}, utilities::{scalar_fields::PrimeField, proof::{deserialize_from_file, value_map_from_path}}};
+use zkpyc_core::export::iden3;
+use zkpyc_core::utilities::{debug::DebugInfo, r1cs::ProverData};
 use circ::cfg::cfg;
 
 #[cfg(feature = "bellman")]
-use bls12_381::Bls12;
+use zkpyc_core::{export::{snarkjs, solidity}, utilities::bellman::{self, Bn254, PairingCurve}, with_pairing_engine};
 #[cfg(feature = "bellman")]
-use zkpyc_core::utilities::{bellman::Bellman, mirage::Mirage, proof::ProofSystem, r1cs::{ProverData, VerifierData}};
+use zkpyc_core::utilities::{bellman::Bellman, mirage::Mirage, proof::ProofSystem, r1cs::VerifierData};
+#[cfg(feature = "bellman")]
+use zkpyc_core::utilities::proof::CommitProofSystem;
+#[cfg(feature = "bellman")]
+use zkpyc_core::utilities::ceremony::Ceremony;
+
+#[cfg(feature = "plonk")]
+use zkpyc_core::utilities::{plonk::{Plonk, Srs}, proof::ProofSystem as _};
 
 #[cfg(feature = "spartan")]
-use circ::ir::term::text::parse_value_map;
-#[cfg(feature = "spartan")]
-use circ::target::r1cs::spartan;
+use zkpyc_core::utilities::{spartan::{self, Spartan}, proof::ProofSystem as _};
+
+#[cfg(feature = "bulletproofs")]
+use zkpyc_core::utilities::{bulletproofs::{self, Bulletproofs}, proof::ProofSystem as _};
+
+#[cfg(feature = "snarkpack")]
+use zkpyc_core::utilities::snarkpack::{self, AggregationSrs};
+
+#[cfg(feature = "nova")]
+use zkpyc_core::utilities::nova;
+#[cfg(any(feature = "nova", feature = "bellman"))]
+use zkpyc_core::utilities::proof::serialize_into_file;
+#[cfg(feature = "nova")]
+use circ::ir::term::text::serialize_value_map;
 
 use zkpyc_core::utilities::scalar_fields::bls12_381::Bls12_381;
 use zkpyc_core::utilities::scalar_fields::bn256::Bn256;
@@ -22,7 +42,7 @@ use curve25519_dalek::scalar::Scalar as Curve25519;
 
 
 #[derive(Debug, Parser)]
-#[command(name = "zk", about = "The CirC ZKP runner")]
+#[command(name = "zk", about = "The CirC ZKP runner", subcommand_negates_reqs = true)]
 struct Options {
     #[arg(long, default_value = "P")]
     prover_key: PathBuf,
@@ -38,26 +58,146 @@ struct Options {
     vin: PathBuf,
     #[arg(long, default_value = "groth16")]
     proof_impl: ProofImpl,
+    #[arg(long, required = true)]
+    action: Option<ProofAction>,
+    /// Source lines of the signals, as written by `zkpyc --debug-info`
+    #[arg(long)]
+    debug_info: Option<PathBuf>,
+    /// Format of the verifier written by `export-verifier`
+    #[arg(long, default_value = "solidity")]
+    format: VerifierFormat,
+    /// File to write exported verifiers and calldata to, instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// Directory of the files exchanged with snarkjs
+    #[arg(long, default_value = "snarkjs")]
+    snarkjs_dir: PathBuf,
+    /// Universal SRS written by `srs` or `aggregate-srs`
+    #[arg(long, default_value = "srs")]
+    srs: PathBuf,
+    /// log2 of the number of rows of the largest circuit the SRS supports,
+    /// or of the most proofs it aggregates
+    #[arg(long, default_value = "20")]
+    srs_k: u32,
+    /// Verifier inputs of a batch of proofs, in the order of `--batch-proofs`
     #[arg(long)]
-    action: ProofAction,
+    batch_inputs: Vec<PathBuf>,
+    /// Proofs to verify in a batch or to aggregate
+    #[arg(long)]
+    batch_proofs: Vec<PathBuf>,
+    /// Private inputs of one IVC step; repeat once per step
+    #[arg(long)]
+    step_inputs: Vec<PathBuf>,
+    /// Number of IVC steps, for step functions without private inputs
+    #[arg(long, default_value = "1")]
+    steps: usize,
+    /// Commitment randomness, one file per committed argument, in order
+    #[arg(long)]
+    rands: Vec<PathBuf>,
+    /// Commitments, one file per committed argument, in order
+    #[arg(long)]
+    cmts: Vec<PathBuf>,
+    /// A commitment or commitment randomness produced elsewhere, to import
+    #[arg(long)]
+    external: Option<PathBuf>,
+    /// The Phase-2 ceremony file, as written by `zkpyc --action ceremony-init`
+    #[arg(long, default_value = "ceremony")]
+    ceremony: PathBuf,
     #[command(flatten)]
     circ: CircOpt,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Take part in the Phase-2 ceremony for Groth16 keys in `--ceremony`
+    Ceremony {
+        #[command(subcommand)]
+        step: CeremonyStep,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum CeremonyStep {
+    /// Multiply delta by a fresh secret, in place, and print the contribution hash
+    Contribute {
+        /// Text mixed into the randomness of the OS
+        #[arg(long)]
+        entropy: Option<String>,
+    },
+    /// Check every contribution against the powers of tau, and print their hashes
+    Verify {
+        /// The powers of tau the ceremony was started from
+        #[arg(long)]
+        ptau: PathBuf,
+    },
+    /// Write the final keys to `--prover-key` and `--verifier-key`
+    Extract,
 }
 
 #[derive(PartialEq, Debug, Clone, ValueEnum)]
 /// `Prove`/`Verify` execute proving/verifying in bellman separately
-/// `Spartan` executes both proving/verifying in spartan
+/// `Spartan` executes both proving/verifying in spartan, from the `pin` and `vin` inputs
+/// `Debug` checks the witness for the inputs, reporting the first unsatisfied constraint
+/// `ExportVerifier` renders an on-chain verifier for a Groth16 verifying key over BN254
+/// `ExportCalldata` encodes a proof and verifier inputs as a call to that verifier
+/// `ExportR1cs`/`ExportWtns` write the relation/witness in the iden3 formats to the snarkjs directory
+/// `ExportSnarkjs` writes a Groth16 verifying key, proof and public inputs as snarkjs JSON there
+/// `ImportSnarkjs` converts snarkjs JSON there to a verifying key and proof for `Verify`
+/// `VerifySnarkjs` checks the snarkjs JSON there
+/// `Srs` samples a universal SRS for PLONK, reusable by all circuits of up to 2^k rows
+/// `VerifyBatch` checks Groth16 proofs of one circuit together, with one multi-pairing
+/// `AggregateSrs` samples the SRS for aggregating up to 2^k Groth16 proofs over BLS12-381
+/// `Aggregate` compresses the batch of proofs into one logarithmic-size SnarkPack proof
+/// `VerifyAggregate` checks that proof against the batch of verifier inputs
+/// `IvcProve` folds steps from the initial state in `inputs` and compresses them with Nova
+/// `IvcVerify` checks that proof, from the initial state to the `return` leaves in `inputs`
+/// `CpRand` samples Mirage commitment randomness into each of `rands`
+/// `CpCommit` commits to the array in `inputs` with the first of `rands`, into the first of `cmts`
+/// `CpProve`/`CpVerify` prove/verify that the committed arguments open `cmts`, with randomness `rands`
+/// `CpImportCommitment` converts a commitment made elsewhere, in hex, to the first of `cmts`
+/// `CpImportRand` converts the randomness of that commitment, an integer, to the first of `rands`
 enum ProofAction {
     Prove,
     Verify,
     Spartan,
+    Debug,
+    ExportVerifier,
+    ExportCalldata,
+    ExportR1cs,
+    ExportWtns,
+    ExportSnarkjs,
+    ImportSnarkjs,
+    VerifySnarkjs,
+    Srs,
+    VerifyBatch,
+    AggregateSrs,
+    Aggregate,
+    VerifyAggregate,
+    IvcProve,
+    IvcVerify,
+    CpRand,
+    CpCommit,
+    CpProve,
+    CpVerify,
+    CpImportCommitment,
+    CpImportRand,
+}
+
+#[derive(PartialEq, Debug, Clone, ValueEnum)]
+enum VerifierFormat {
+    Solidity,
 }
 
 #[derive(PartialEq, Debug, Clone, ValueEnum)]
-/// Whether to use Groth16 or Mirage
+/// Whether to use Groth16, Mirage, PLONK, Spartan or Bulletproofs
 enum ProofImpl {
     Groth16,
     Mirage,
+    Plonk,
+    Spartan,
+    Bulletproofs,
     ZkInterface,
 }
 
@@ -76,16 +216,36 @@ fn main() {
         .init();
     let opts = Options::parse();
     circ::cfg::set(&opts.circ);
-    match (&opts.action, &opts.proof_impl) {
+    if let Some(Command::Ceremony { step }) = &opts.command {
+        ceremony(&opts, step);
+        return;
+    }
+    let action = opts.action.clone().unwrap();
+    match (&action, &opts.proof_impl) {
         #[cfg(feature = "bellman")]
         (ProofAction::Prove, ProofImpl::Groth16) => {
             println!("Proving");
-            Bellman::<Bls12>::prove_fs(opts.prover_key, opts.inputs, opts.proof).unwrap();
+            with_pairing_engine!(pairing_curve(), E => Bellman::<E>::prove_fs(opts.prover_key, opts.inputs, opts.proof)).unwrap();
         }
         #[cfg(feature = "bellman")]
         (ProofAction::Prove, ProofImpl::Mirage) => {
             println!("Proving");
-            Mirage::<Bls12>::prove_fs(opts.prover_key, opts.inputs, opts.proof).unwrap();
+            with_pairing_engine!(pairing_curve(), E => Mirage::<E>::prove_fs(opts.prover_key, opts.inputs, opts.proof)).unwrap();
+        }
+        #[cfg(feature = "plonk")]
+        (ProofAction::Prove, ProofImpl::Plonk) => {
+            println!("Proving");
+            Plonk::prove_fs(opts.prover_key, opts.inputs, opts.proof).unwrap();
+        }
+        #[cfg(feature = "spartan")]
+        (ProofAction::Prove, ProofImpl::Spartan) => {
+            println!("Proving");
+            Spartan::prove_fs(opts.prover_key, opts.inputs, opts.proof).unwrap();
+        }
+        #[cfg(feature = "bulletproofs")]
+        (ProofAction::Prove, ProofImpl::Bulletproofs) => {
+            println!("Proving");
+            Bulletproofs::prove_fs(opts.prover_key, opts.inputs, opts.proof).unwrap();
         }
         (ProofAction::Prove, ProofImpl::ZkInterface) => {
             println!("Generating Zkif Circuit, Constraints and Witnesses");
@@ -105,7 +265,7 @@ fn main() {
         (ProofAction::Verify, ProofImpl::Groth16) => {
             println!("Verifying");
             assert!(
-                Bellman::<Bls12>::verify_fs(opts.verifier_key, opts.inputs, opts.proof).unwrap(),
+                with_pairing_engine!(pairing_curve(), E => Bellman::<E>::verify_fs(opts.verifier_key, opts.inputs, opts.proof)).unwrap(),
                 "invalid proof"
             );
         }
@@ -113,7 +273,31 @@ fn main() {
         (ProofAction::Verify, ProofImpl::Mirage) => {
             println!("Verifying");
             assert!(
-                Mirage::<Bls12>::verify_fs(opts.verifier_key, opts.inputs, opts.proof).unwrap(),
+                with_pairing_engine!(pairing_curve(), E => Mirage::<E>::verify_fs(opts.verifier_key, opts.inputs, opts.proof)).unwrap(),
+                "invalid proof"
+            );
+        }
+        #[cfg(feature = "plonk")]
+        (ProofAction::Verify, ProofImpl::Plonk) => {
+            println!("Verifying");
+            assert!(
+                Plonk::verify_fs(opts.verifier_key, opts.inputs, opts.proof).unwrap(),
+                "invalid proof"
+            );
+        }
+        #[cfg(feature = "spartan")]
+        (ProofAction::Verify, ProofImpl::Spartan) => {
+            println!("Verifying");
+            assert!(
+                Spartan::verify_fs(opts.verifier_key, opts.inputs, opts.proof).unwrap(),
+                "invalid proof"
+            );
+        }
+        #[cfg(feature = "bulletproofs")]
+        (ProofAction::Verify, ProofImpl::Bulletproofs) => {
+            println!("Verifying");
+            assert!(
+                Bulletproofs::verify_fs(opts.verifier_key, opts.inputs, opts.proof).unwrap(),
                 "invalid proof"
             );
         }
@@ -131,18 +315,328 @@ fn main() {
             result.expect("Unable to prepare verifier statements.");
         }
         #[cfg(not(feature = "bellman"))]
-        (ProofAction::Prove | ProofAction::Verify, _) => panic!("Missing feature: bellman"),
+        (ProofAction::Prove | ProofAction::Verify, ProofImpl::Groth16 | ProofImpl::Mirage) => panic!("Missing feature: bellman"),
+        #[cfg(not(feature = "plonk"))]
+        (ProofAction::Prove | ProofAction::Verify, ProofImpl::Plonk) => panic!("Missing feature: plonk"),
+        #[cfg(not(feature = "spartan"))]
+        (ProofAction::Prove | ProofAction::Verify, ProofImpl::Spartan) => panic!("Missing feature: spartan"),
+        #[cfg(not(feature = "bulletproofs"))]
+        (ProofAction::Prove | ProofAction::Verify, ProofImpl::Bulletproofs) => panic!("Missing feature: bulletproofs"),
+        #[cfg(feature = "plonk")]
+        (ProofAction::Srs, _) => {
+            println!("Generating SRS for circuits of up to 2^{} rows", opts.srs_k);
+            Srs::generate_fs(opts.srs_k, &opts.srs).unwrap();
+        }
+        #[cfg(not(feature = "plonk"))]
+        (ProofAction::Srs, _) => panic!("Missing feature: plonk"),
         #[cfg(feature = "spartan")]
         (ProofAction::Spartan, _) => {
-            let prover_input_map = parse_value_map(&std::fs::read(opts.pin).unwrap());
+            let prover_input_map = value_map_from_path(&opts.pin).unwrap();
+            let pk: spartan::ProvingKey = deserialize_from_file(&opts.prover_key).unwrap();
             println!("Spartan Proving");
-            let (gens, inst, proof) = spartan::prove(opts.prover_key, &prover_input_map).unwrap();
+            let proof = Spartan::prove(&pk, &prover_input_map).unwrap_or_else(|e| panic!("{e}"));
 
-            let verifier_input_map = parse_value_map(&std::fs::read(opts.vin).unwrap());
+            let verifier_input_map = value_map_from_path(&opts.vin).unwrap();
+            let vk: spartan::VerifyingKey = deserialize_from_file(&opts.verifier_key).unwrap();
             println!("Spartan Verifying");
-            spartan::verify(opts.verifier_key, &verifier_input_map, &gens, &inst, proof).unwrap();
+            assert!(Spartan::verify(&vk, &verifier_input_map, &proof), "invalid proof");
         }
         #[cfg(not(feature = "spartan"))]
         (ProofAction::Spartan, _) => panic!("Missing feature: spartan"),
+        #[cfg(feature = "bellman")]
+        (ProofAction::ExportVerifier | ProofAction::ExportCalldata, ProofImpl::Groth16) => {
+            assert_eq!(
+                pairing_curve(),
+                PairingCurve::Bn254,
+                "On-chain verifiers need a BN254 verifying key; compile with `--field-builtin bn254`"
+            );
+            let vk: bellman::VerifyingKey<Bn254> = deserialize_from_file(&opts.verifier_key).unwrap();
+            let out = match action {
+                ProofAction::ExportVerifier => match opts.format {
+                    VerifierFormat::Solidity => solidity::solidity_verifier(&vk),
+                },
+                _ => {
+                    let proof: bellman::Proof<Bn254> = deserialize_from_file(&opts.proof).unwrap();
+                    let inputs = value_map_from_path(&opts.inputs).unwrap();
+                    solidity::calldata_hex(&vk, &proof, &inputs) + "\n"
+                }
+            };
+            match &opts.output {
+                Some(path) => std::fs::write(path, out).unwrap(),
+                None => print!("{out}"),
+            }
+        }
+        (ProofAction::ExportVerifier | ProofAction::ExportCalldata, _) => {
+            panic!("On-chain verifiers are only exported for Groth16")
+        }
+        (ProofAction::ExportR1cs, _) => {
+            let prover_data = load_prover_data(&opts);
+            std::fs::create_dir_all(&opts.snarkjs_dir).unwrap();
+            iden3::write_r1cs(&prover_data.r1cs, opts.snarkjs_dir.join("circuit.r1cs")).unwrap();
+        }
+        (ProofAction::ExportWtns, _) => {
+            let prover_data = load_prover_data(&opts);
+            let inputs = value_map_from_path(&opts.inputs).unwrap();
+            std::fs::create_dir_all(&opts.snarkjs_dir).unwrap();
+            iden3::write_wtns(&prover_data, &inputs, opts.snarkjs_dir.join("witness.wtns")).unwrap();
+        }
+        #[cfg(feature = "bellman")]
+        (ProofAction::ExportSnarkjs | ProofAction::ImportSnarkjs, ProofImpl::Groth16) => {
+            assert_eq!(pairing_curve(), PairingCurve::Bn254, "snarkjs keys and proofs are over BN254");
+            if action == ProofAction::ExportSnarkjs {
+                snarkjs::export_fs(&opts.verifier_key, &opts.proof, &opts.inputs, &opts.snarkjs_dir).unwrap();
+            } else {
+                let vk_out = opts.snarkjs_dir.join("V");
+                let pf_out = opts.snarkjs_dir.join("pi");
+                snarkjs::import_fs(&opts.snarkjs_dir, &opts.verifier_key, &vk_out, &pf_out).unwrap();
+                println!("Wrote {} and {}", vk_out.display(), pf_out.display());
+            }
+        }
+        (ProofAction::ExportSnarkjs | ProofAction::ImportSnarkjs, _) => {
+            panic!("snarkjs keys and proofs are only converted for Groth16")
+        }
+        #[cfg(feature = "bellman")]
+        (ProofAction::VerifySnarkjs, _) => {
+            println!("Verifying");
+            assert!(snarkjs::verify_fs(&opts.snarkjs_dir).unwrap(), "invalid proof");
+        }
+        #[cfg(not(feature = "bellman"))]
+        (ProofAction::VerifySnarkjs, _) => panic!("Missing feature: bellman"),
+        #[cfg(feature = "bellman")]
+        (ProofAction::VerifyBatch, ProofImpl::Groth16) => {
+            println!("Verifying {} proofs", opts.batch_proofs.len());
+            assert!(
+                with_pairing_engine!(pairing_curve(), E => Bellman::<E>::verify_batch_fs(
+                    &opts.verifier_key,
+                    &opts.batch_inputs,
+                    &opts.batch_proofs,
+                ))
+                .unwrap(),
+                "invalid proof"
+            );
+        }
+        #[cfg(not(feature = "bellman"))]
+        (ProofAction::VerifyBatch, ProofImpl::Groth16) => panic!("Missing feature: bellman"),
+        (ProofAction::VerifyBatch, _) => panic!("Batch verification is only implemented for Groth16"),
+        #[cfg(feature = "snarkpack")]
+        (ProofAction::AggregateSrs, _) => {
+            println!("Generating SRS for aggregating up to 2^{} proofs", opts.srs_k);
+            AggregationSrs::generate_fs(opts.srs_k, &opts.srs).unwrap();
+        }
+        #[cfg(feature = "snarkpack")]
+        (ProofAction::Aggregate, ProofImpl::Groth16) => {
+            assert_eq!(pairing_curve(), PairingCurve::Bls12_381, "SnarkPack aggregates proofs over BLS12-381");
+            println!("Aggregating {} proofs", opts.batch_proofs.len());
+            snarkpack::aggregate_fs(&opts.srs, &opts.batch_proofs, &opts.proof).unwrap();
+        }
+        #[cfg(feature = "snarkpack")]
+        (ProofAction::VerifyAggregate, ProofImpl::Groth16) => {
+            assert_eq!(pairing_curve(), PairingCurve::Bls12_381, "SnarkPack aggregates proofs over BLS12-381");
+            println!("Verifying");
+            assert!(
+                snarkpack::verify_fs(&opts.srs, &opts.verifier_key, &opts.batch_inputs, &opts.proof).unwrap(),
+                "invalid proof"
+            );
+        }
+        #[cfg(feature = "snarkpack")]
+        (ProofAction::Aggregate | ProofAction::VerifyAggregate, _) => {
+            panic!("Proofs are only aggregated for Groth16")
+        }
+        #[cfg(not(feature = "snarkpack"))]
+        (ProofAction::AggregateSrs | ProofAction::Aggregate | ProofAction::VerifyAggregate, _) => {
+            panic!("Missing feature: snarkpack")
+        }
+        #[cfg(feature = "nova")]
+        (ProofAction::IvcProve, _) => {
+            let pk: nova::IvcProvingKey = deserialize_from_file(&opts.prover_key).unwrap();
+            let z0 = value_map_from_path(&opts.inputs).unwrap();
+            let advice = if opts.step_inputs.is_empty() {
+                vec![fxhash::FxHashMap::default(); opts.steps]
+            } else {
+                opts.step_inputs.iter().map(|p| value_map_from_path(p).unwrap()).collect()
+            };
+            println!("Proving {} steps", advice.len());
+            let (pf, zn) = nova::prove(&pk, &z0, &advice).unwrap();
+            serialize_into_file(&pf, &opts.proof).unwrap();
+            println!("Final state:\n{}", serialize_value_map(&zn));
+        }
+        #[cfg(feature = "nova")]
+        (ProofAction::IvcVerify, _) => {
+            println!("Verifying");
+            assert!(
+                nova::verify_fs(&opts.verifier_key, &opts.inputs, &opts.proof).unwrap(),
+                "invalid proof"
+            );
+        }
+        #[cfg(not(feature = "nova"))]
+        (ProofAction::IvcProve | ProofAction::IvcVerify, _) => panic!("Missing feature: nova"),
+        #[cfg(feature = "bellman")]
+        (ProofAction::CpRand, ProofImpl::Mirage) => {
+            for path in &opts.rands {
+                with_pairing_engine!(pairing_curve(), E => Mirage::<E>::sample_com_rand_fs(path)).unwrap();
+            }
+        }
+        #[cfg(feature = "bellman")]
+        (ProofAction::CpCommit, ProofImpl::Mirage) => {
+            let (rand, cmt) = (opts.rands.first(), opts.cmts.first());
+            let (rand, cmt) = (rand.expect("missing --rands"), cmt.expect("missing --cmts"));
+            with_pairing_engine!(pairing_curve(), E => Mirage::<E>::cp_commit_fs(&opts.verifier_key, &opts.inputs, rand, cmt)).unwrap();
+        }
+        #[cfg(feature = "bellman")]
+        (ProofAction::CpProve, ProofImpl::Mirage) => {
+            println!("Proving");
+            with_pairing_engine!(pairing_curve(), E => Mirage::<E>::cp_prove_fs(&opts.prover_key, &opts.inputs, &opts.proof, opts.rands.clone())).unwrap();
+        }
+        #[cfg(feature = "bellman")]
+        (ProofAction::CpVerify, ProofImpl::Mirage) => {
+            println!("Verifying");
+            assert!(
+                with_pairing_engine!(pairing_curve(), E => Mirage::<E>::cp_verify_fs(&opts.verifier_key, &opts.inputs, &opts.proof, opts.cmts.clone())).unwrap(),
+                "invalid proof"
+            );
+        }
+        #[cfg(feature = "bellman")]
+        (ProofAction::CpImportCommitment, ProofImpl::Mirage) => {
+            let hex = std::fs::read_to_string(opts.external.as_ref().expect("missing --external")).unwrap();
+            let cmt_path = opts.cmts.first().expect("missing --cmts");
+            with_pairing_engine!(pairing_curve(), E => {
+                let cmt = <Mirage<E> as CommitProofSystem>::Commitment::from_hex(&hex).unwrap_or_else(|e| panic!("{e}"));
+                serialize_into_file(&cmt, cmt_path)
+            })
+            .unwrap();
+        }
+        #[cfg(feature = "bellman")]
+        (ProofAction::CpImportRand, ProofImpl::Mirage) => {
+            let int = std::fs::read_to_string(opts.external.as_ref().expect("missing --external")).unwrap();
+            let int = rug::Integer::from_str_radix(int.trim(), 10).unwrap();
+            let rand_path = opts.rands.first().expect("missing --rands");
+            with_pairing_engine!(pairing_curve(), E => {
+                let rand = <Mirage<E> as CommitProofSystem>::ComRand::from_int(int);
+                serialize_into_file(&rand, rand_path)
+            })
+            .unwrap();
+        }
+        #[cfg(feature = "bellman")]
+        (
+            ProofAction::CpRand
+            | ProofAction::CpCommit
+            | ProofAction::CpProve
+            | ProofAction::CpVerify
+            | ProofAction::CpImportCommitment
+            | ProofAction::CpImportRand,
+            _,
+        ) => panic!("Only Mirage is commit-and-prove"),
+        #[cfg(not(feature = "bellman"))]
+        (
+            ProofAction::CpRand
+            | ProofAction::CpCommit
+            | ProofAction::CpProve
+            | ProofAction::CpVerify
+            | ProofAction::CpImportCommitment
+            | ProofAction::CpImportRand,
+            _,
+        ) => panic!("Missing feature: bellman"),
+        (ProofAction::Debug, _) => {
+            let prover_data = load_prover_data(&opts);
+            let debug_info: Option<DebugInfo> = opts
+                .debug_info
+                .as_ref()
+                .map(|p| deserialize_from_file(p).unwrap());
+            let inputs = value_map_from_path(&opts.inputs).unwrap();
+            match prover_data.first_unsatisfied(&inputs, debug_info.as_ref()) {
+                Some(unsatisfied) => {
+                    eprint!("{unsatisfied}");
+                    std::process::exit(1);
+                }
+                None => println!(
+                    "All {} constraints are satisfied",
+                    prover_data.r1cs.constraints.len()
+                ),
+            }
+        }
+    }
+}
+
+/// The relation and witness computation in the prover key
+fn load_prover_data(opts: &Options) -> ProverData {
+    match opts.proof_impl {
+        #[cfg(feature = "bellman")]
+        ProofImpl::Groth16 => {
+            with_pairing_engine!(pairing_curve(), E => {
+                let pk: zkpyc_core::utilities::bellman::ProvingKey<E> =
+                    deserialize_from_file(&opts.prover_key).unwrap();
+                pk.into_data()
+            })
+        }
+        #[cfg(feature = "bellman")]
+        ProofImpl::Mirage => {
+            with_pairing_engine!(pairing_curve(), E => {
+                let pk: zkpyc_core::utilities::mirage::ProvingKey<E> =
+                    deserialize_from_file(&opts.prover_key).unwrap();
+                pk.into_data()
+            })
+        }
+        #[cfg(not(feature = "bellman"))]
+        ProofImpl::Groth16 | ProofImpl::Mirage => panic!("Missing feature: bellman"),
+        #[cfg(feature = "plonk")]
+        ProofImpl::Plonk => {
+            let pk: zkpyc_core::utilities::plonk::ProvingKey = deserialize_from_file(&opts.prover_key).unwrap();
+            pk.into_data()
+        }
+        #[cfg(not(feature = "plonk"))]
+        ProofImpl::Plonk => panic!("Missing feature: plonk"),
+        #[cfg(feature = "spartan")]
+        ProofImpl::Spartan => {
+            let pk: spartan::ProvingKey = deserialize_from_file(&opts.prover_key).unwrap();
+            pk.into_data()
+        }
+        #[cfg(not(feature = "spartan"))]
+        ProofImpl::Spartan => panic!("Missing feature: spartan"),
+        #[cfg(feature = "bulletproofs")]
+        ProofImpl::Bulletproofs => {
+            let pk: bulletproofs::ProvingKey = deserialize_from_file(&opts.prover_key).unwrap();
+            pk.into_data()
+        }
+        #[cfg(not(feature = "bulletproofs"))]
+        ProofImpl::Bulletproofs => panic!("Missing feature: bulletproofs"),
+        ProofImpl::ZkInterface => deserialize_from_file(&opts.prover_key).unwrap(),
+    }
+}
+
+/// The curve of the bellman back-ends, determined by the field
+#[cfg(feature = "bellman")]
+fn pairing_curve() -> PairingCurve {
+    PairingCurve::from_modulus(cfg().field().modulus()).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Run a step of the Phase-2 ceremony
+#[cfg(feature = "bellman")]
+fn ceremony(opts: &Options, step: &CeremonyStep) {
+    assert_eq!(opts.proof_impl, ProofImpl::Groth16, "Ceremonies are only for Groth16 keys");
+    let hex = |hash: [u8; 32]| hash.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    match step {
+        CeremonyStep::Contribute { entropy } => {
+            println!("Contributing");
+            let entropy = entropy.as_deref().unwrap_or_default().as_bytes();
+            let hash = with_pairing_engine!(pairing_curve(), E => Ceremony::<E>::contribute_fs(&opts.ceremony, entropy)).unwrap();
+            println!("Contribution: {}", hex(hash));
+        }
+        CeremonyStep::Verify { ptau } => {
+            println!("Verifying ceremony");
+            let hashes = with_pairing_engine!(pairing_curve(), E => Ceremony::<E>::verify_fs(&opts.ceremony, ptau))
+                .unwrap_or_else(|e| panic!("invalid ceremony: {e}"));
+            for (i, hash) in hashes.into_iter().enumerate() {
+                println!("Contribution {i}: {}", hex(hash));
+            }
+        }
+        CeremonyStep::Extract => {
+            println!("Extracting keys");
+            with_pairing_engine!(pairing_curve(), E => Ceremony::<E>::keys_fs(&opts.ceremony, &opts.prover_key, &opts.verifier_key)).unwrap();
+        }
     }
 }
+
+#[cfg(not(feature = "bellman"))]
+fn ceremony(_opts: &Options, _step: &CeremonyStep) {
+    panic!("Missing feature: bellman")
+}
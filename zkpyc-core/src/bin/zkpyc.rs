@@ -11,14 +11,17 @@ use std::io::Read;
 use std::{env, io, path};
 use std::path::{Path, PathBuf};
 use zkpyc_core::front::{self, SourceInput};
+use zkpyc_core::front::python::{proof_opts, ArrayStrategy};
 use crate::front::{FrontEnd, Mode};
 #[cfg(feature = "r1cs")]
-use zkpyc_core::utilities::{trans::to_r1cs, opt::reduce_linearities};
+use zkpyc_core::utilities::{trans::{to_r1cs, to_r1cs_with_profile}, opt::reduce_linearities, profile::LineProfile};
+#[cfg(feature = "r1cs")]
+use zkpyc_core::utilities::{debug::DebugInfo, proof::serialize_into_file};
 use circ::ir::term::{Node, Op, BV_LSHR, BV_SHL};
 use circ::ir::{
     opt::{opt, Opt},
     term::{
-        check,
+        check, Computations,
         text::{parse_value_map, serialize_value_map},
     },
 };
@@ -36,11 +39,22 @@ use bls12_381::{Bls12, Scalar};
 // use curve25519_dalek::scalar::Scalar;
 #[cfg(feature = "bellman")]
 use zkpyc_core::utilities::{
-    bellman::Bellman,
+    bellman::{Bellman, PairingCurve},
+    ceremony::Ceremony,
     mirage::Mirage,
     proof::{CommitProofSystem, ProofSystem},
 };
 use circ::cfg::cfg;
+#[cfg(feature = "bellman")]
+use zkpyc_core::with_pairing_engine;
+#[cfg(feature = "plonk")]
+use zkpyc_core::utilities::plonk::Plonk;
+#[cfg(feature = "spartan")]
+use zkpyc_core::utilities::{proof::ProofSystem as _, spartan::Spartan};
+#[cfg(feature = "bulletproofs")]
+use zkpyc_core::utilities::{bulletproofs::Bulletproofs, proof::ProofSystem as _};
+#[cfg(feature = "nova")]
+use zkpyc_core::utilities::nova;
 use log::trace;
 
 
@@ -62,15 +76,37 @@ struct Options {
 #[derive(PartialEq, Eq, Debug, Clone, ValueEnum)]
 enum ProofAction {
     Count,
+    /// Report the constraint count of each array lowering strategy
+    CountArrays,
     Setup,
     CpSetup,
+    /// Setup with `--proof-impl spartan`
     SpartanSetup,
+    /// Nova keys for folding the `--ivc-step` function
+    IvcSetup,
+    /// Start a Phase-2 ceremony for Groth16 keys, on top of `--ptau`
+    CeremonyInit,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, ValueEnum)]
+enum ProfileFormat {
+    /// A table of the most expensive lines and functions
+    Text,
+    Json,
+    /// Folded stacks, for flamegraph tools
+    Folded,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, ValueEnum)]
 enum ProofImpl {
     Groth16,
     Mirage,
+    /// PLONK with a universal KZG setup, over BN254
+    Plonk,
+    /// Transparent Spartan, over ristretto255
+    Spartan,
+    /// Transparent Bulletproofs, over ristretto255
+    Bulletproofs,
     ZkInterface,
 }
 
@@ -79,6 +115,20 @@ struct FrontendOptions {
     /// Value threshold
     #[arg(long)]
     value_threshold: Option<u64>,
+
+    /// Lowering of arrays with witness-dependent indices: annotated, linear-scan or ram.
+    /// `--ram-enabled` implies ram.
+    #[arg(long, default_value = "annotated")]
+    array_strategy: ArrayStrategy,
+
+    /// Maximum depth of nested function calls, recursive or not, which bounds recursion
+    #[arg(long, default_value_t = front::python::DEFAULT_MAX_INLINE_DEPTH)]
+    max_inline_depth: usize,
+
+    /// Compile this function as the step of an incrementally verifiable
+    /// computation, instead of `main`
+    #[arg(long)]
+    ivc_step: Option<String>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -95,6 +145,26 @@ enum Backend {
         action: ProofAction,
         #[arg(long, default_value = "groth16")]
         proof_impl: ProofImpl,
+        /// Report the constraints and variables created by each source line
+        #[arg(long)]
+        profile_lines: Option<ProfileFormat>,
+        /// Write the source line of each signal to this file at setup, for `zk --action debug`
+        #[arg(long)]
+        debug_info: Option<PathBuf>,
+        /// Universal SRS to derive PLONK keys from, as written by `zk --action srs`.
+        /// Required for PLONK setup.
+        #[arg(long)]
+        srs: Option<PathBuf>,
+        /// Powers of tau in the snarkjs `.ptau` format, for `--action ceremony-init`
+        #[arg(long)]
+        ptau: Option<PathBuf>,
+        /// The Phase-2 ceremony file, for `zk ceremony` to contribute to
+        #[arg(long, default_value = "ceremony")]
+        ceremony: PathBuf,
+        /// A Pedersen commitment key used elsewhere, as JSON, for `--action cp-setup`
+        /// to link the committed arguments to
+        #[arg(long)]
+        commit_key: Option<PathBuf>,
     },
 }
 
@@ -105,13 +175,17 @@ fn main() {
         .init();
     let options = Options::parse();
     circ::cfg::set(&options.circ);
+    #[cfg(feature = "bellman")]
+    let curve = pairing_curve(&options.backend);
     // let path_buf = options.path.unwrap();
     let mode = match options.backend {
-        Backend::R1cs { .. } => match options.frontend.value_threshold {
-            Some(t) => Mode::ProofOfHighValue(t),
-            None => Mode::Proof,
+        Backend::R1cs { .. } => match (&options.frontend.ivc_step, options.frontend.value_threshold) {
+            (Some(_), _) => Mode::Ivc,
+            (None, Some(t)) => Mode::ProofOfHighValue(t),
+            (None, None) => Mode::Proof,
         }
     };
+    let entry_point = options.frontend.ivc_step.clone().unwrap_or_else(|| String::from("main"));
 
     let source = options.path
     .as_ref()
@@ -127,13 +201,39 @@ fn main() {
         SourceInput::String(buffer, PathBuf::default(), "<stdin>".to_owned())
     });
 
+    let array_strategy = if options.circ.ram.enabled {
+        ArrayStrategy::Ram
+    } else {
+        options.frontend.array_strategy
+    };
+
+    #[cfg(feature = "r1cs")]
+    if let Backend::R1cs { action: ProofAction::CountArrays, .. } = options.backend {
+        for strategy in ArrayStrategy::ALL {
+            println!(
+                "{strategy}: {} constraints",
+                r1cs_size(source.clone(), &entry_point, mode, strategy, options.frontend.max_inline_depth)
+            );
+        }
+        return;
+    }
+
     let inputs = front::python::Inputs {
         source,
-        entry_point: String::from("main"),
+        entry_point: entry_point.clone(),
         mode,
+        array_strategy,
+        max_inline_depth: options.frontend.max_inline_depth,
     };
 
-    let cs = front::python::PythonFE::gen(inputs);
+    let Backend::R1cs { profile_lines, debug_info, .. } = &options.backend;
+    let (cs, spans) = if profile_lines.is_some() || debug_info.is_some() {
+        front::python::PythonFE::try_gen_with_source_map(inputs)
+            .map(|(cs, spans)| (cs, Some(spans)))
+            .unwrap_or_else(|diagnostics| exit_with(diagnostics))
+    } else {
+        (gen_or_exit(inputs), None)
+    };
 
     // TEMPORARY DEBUG
     // println!("{:#?}", cs);
@@ -170,33 +270,7 @@ fn main() {
                 // vec![Opt::Sha, Opt::ConstantFold, Opt::Mem, Opt::ConstantFold],
             )
         }
-        Mode::Proof | Mode::ProofOfHighValue(_) => {
-            let mut opts = Vec::new();
-
-            opts.push(Opt::ScalarizeVars);
-            opts.push(Opt::Flatten);
-            opts.push(Opt::Sha);
-            opts.push(Opt::ConstantFold(Box::new([])));
-            opts.push(Opt::ParseCondStores);
-            // Tuples must be eliminated before oblivious array elim
-            opts.push(Opt::Tuple);
-            opts.push(Opt::ConstantFold(Box::new([])));
-            opts.push(Opt::Tuple);
-            opts.push(Opt::Obliv);
-            // The obliv elim pass produces more tuples, that must be eliminated
-            opts.push(Opt::Tuple);
-            if options.circ.ram.enabled {
-                opts.push(Opt::PersistentRam);
-                opts.push(Opt::VolatileRam);
-                opts.push(Opt::SkolemizeChallenges);
-            }
-            opts.push(Opt::LinearScan);
-            // The linear scan pass produces more tuples, that must be eliminated
-            opts.push(Opt::Tuple);
-            opts.push(Opt::Flatten);
-            opts.push(Opt::ConstantFold(Box::new([])));
-            opt(cs, opts)
-        }
+        Mode::Proof | Mode::ProofOfHighValue(_) | Mode::Ivc => opt(cs, proof_opts()),
     };
     println!("Done with IR optimization");
 
@@ -207,18 +281,34 @@ fn main() {
             prover_key,
             verifier_key,
             proof_impl,
+            profile_lines,
+            debug_info,
+            srs,
+            ptau,
+            ceremony,
+            commit_key,
             ..
         } => {
             println!("Converting to r1cs");
-            let cs = cs.get("main");
+            let cs = cs.get(&entry_point);
             trace!("IR: {}", circ::ir::term::text::serialize_computation(cs));
-            let mut r1cs = to_r1cs(cs, cfg());
+            let mut r1cs = match (profile_lines, &spans) {
+                (Some(format), Some(spans)) => {
+                    let (r1cs, metrics) = to_r1cs_with_profile(cs, cfg());
+                    print_line_profile(&LineProfile::new(&metrics, spans), format);
+                    r1cs
+                }
+                _ => to_r1cs(cs, cfg()),
+            };
 
             // println!("R1CS");
             // println!("{:#?}", &r1cs);
 
             println!("Pre-opt R1cs size: {}", r1cs.constraints().len());
             r1cs = reduce_linearities(r1cs, cfg());
+            if let (Some(path), Some(spans)) = (&debug_info, &spans) {
+                serialize_into_file(&DebugInfo::new(&r1cs, spans), path).unwrap();
+            }
 
             // TEMPORARY DEBUG
             // println!("{:#?}", &r1cs);
@@ -233,25 +323,47 @@ fn main() {
             // println!("{:#?}", &verifier_data);
 
             match action {
-                ProofAction::Count => (),
+                ProofAction::Count | ProofAction::CountArrays => (),
+                #[cfg(feature = "plonk")]
+                ProofAction::Setup if matches!(proof_impl, ProofImpl::Plonk) => {
+                    println!("Generating Parameters");
+                    let srs = srs.expect("PLONK keys are derived from a universal SRS; pass one written by `zk --action srs` with `--srs`");
+                    Plonk::setup_with_srs_fs(prover_data, verifier_data, srs, prover_key, verifier_key).unwrap();
+                }
                 #[cfg(feature = "bellman")]
                 ProofAction::Setup => {
                     println!("Generating Parameters");
                     match proof_impl {
-                        ProofImpl::Groth16 => Bellman::<Bls12>::setup_fs(
+                        ProofImpl::Groth16 => with_pairing_engine!(curve.unwrap(), E => Bellman::<E>::setup_fs(
                             prover_data,
                             verifier_data,
                             prover_key,
                             verifier_key,
-                        )
+                        ))
                         .unwrap(),
-                        ProofImpl::Mirage => Mirage::<Bls12>::setup_fs(
+                        ProofImpl::Mirage => with_pairing_engine!(curve.unwrap(), E => Mirage::<E>::setup_fs(
                             prover_data,
                             verifier_data,
                             prover_key,
                             verifier_key,
-                        )
+                        ))
                         .unwrap(),
+                        #[cfg(feature = "plonk")]
+                        ProofImpl::Plonk => unreachable!("PLONK setup has its own arm"),
+                        #[cfg(not(feature = "plonk"))]
+                        ProofImpl::Plonk => panic!("Missing feature: plonk"),
+                        #[cfg(feature = "spartan")]
+                        ProofImpl::Spartan => {
+                            Spartan::setup_fs(prover_data, verifier_data, prover_key, verifier_key).unwrap()
+                        }
+                        #[cfg(not(feature = "spartan"))]
+                        ProofImpl::Spartan => panic!("Missing feature: spartan"),
+                        #[cfg(feature = "bulletproofs")]
+                        ProofImpl::Bulletproofs => {
+                            Bulletproofs::setup_fs(prover_data, verifier_data, prover_key, verifier_key).unwrap()
+                        }
+                        #[cfg(not(feature = "bulletproofs"))]
+                        ProofImpl::Bulletproofs => panic!("Missing feature: bulletproofs"),
                         ProofImpl::ZkInterface => ZkInterface::setup_fs(
                             prover_data,
                             verifier_data,
@@ -268,13 +380,26 @@ fn main() {
                     println!("Generating Parameters");
                     match proof_impl {
                         ProofImpl::Groth16 => panic!("Groth16 is not CP"),
-                        ProofImpl::Mirage => Mirage::<Bls12>::cp_setup_fs(
-                            prover_data,
-                            verifier_data,
-                            prover_key,
-                            verifier_key,
-                        )
-                        .unwrap(),
+                        ProofImpl::Plonk => panic!("PLONK is not CP"),
+                        ProofImpl::Spartan => panic!("Spartan is not CP"),
+                        ProofImpl::Bulletproofs => panic!("Bulletproofs is not CP"),
+                        ProofImpl::Mirage => match commit_key {
+                            Some(ck) => with_pairing_engine!(curve.unwrap(), E => Mirage::<E>::cp_setup_with_ck_fs(
+                                prover_data,
+                                verifier_data,
+                                ck,
+                                prover_key,
+                                verifier_key,
+                            ))
+                            .unwrap(),
+                            None => with_pairing_engine!(curve.unwrap(), E => Mirage::<E>::cp_setup_fs(
+                                prover_data,
+                                verifier_data,
+                                prover_key,
+                                verifier_key,
+                            ))
+                            .unwrap(),
+                        },
                         ProofImpl::ZkInterface => todo!(),
                     };
                 }
@@ -282,11 +407,42 @@ fn main() {
                 ProofAction::CpSetup => panic!("Missing feature: bellman"),
                 #[cfg(feature = "spartan")]
                 ProofAction::SpartanSetup => {
-                    write_data::<_, _>(prover_key, verifier_key, &prover_data, &verifier_data)
-                        .unwrap();
+                    println!("Generating Parameters");
+                    Spartan::setup_fs(prover_data, verifier_data, prover_key, verifier_key).unwrap();
                 }
                 #[cfg(not(feature = "spartan"))]
                 ProofAction::SpartanSetup => panic!("Missing feature: spartan"),
+                #[cfg(feature = "nova")]
+                ProofAction::IvcSetup => {
+                    assert!(matches!(mode, Mode::Ivc), "IVC setup needs the step function, given by `--ivc-step`");
+                    println!("Generating Parameters");
+                    nova::setup_fs(prover_data, prover_key, verifier_key).unwrap();
+                }
+                #[cfg(not(feature = "nova"))]
+                ProofAction::IvcSetup => panic!("Missing feature: nova"),
+                #[cfg(feature = "bellman")]
+                ProofAction::CeremonyInit => {
+                    let ptau = ptau.expect("A Phase-2 ceremony needs powers of tau, given by `--ptau`");
+                    println!("Starting Phase-2 ceremony");
+                    let result = match proof_impl {
+                        ProofImpl::Groth16 => with_pairing_engine!(curve.unwrap(), E => Ceremony::<E>::init_fs(
+                            prover_data,
+                            verifier_data,
+                            ptau,
+                            ceremony,
+                        )),
+                        // Mirage keys also have a delta per committed block
+                        // and a linking key, which the ceremony does not blind
+                        ProofImpl::Mirage => Err("Phase-2 ceremonies only build Groth16 keys; Mirage keys come from `--action cp-setup`".into()),
+                        _ => Err("Phase-2 ceremonies are only for Groth16 keys".into()),
+                    };
+                    if let Err(e) = result {
+                        eprintln!("error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+                #[cfg(not(feature = "bellman"))]
+                ProofAction::CeremonyInit => panic!("Missing feature: bellman"),
             }
         }
         #[cfg(not(feature = "r1cs"))]
@@ -295,3 +451,63 @@ fn main() {
         }
     }
 }
+
+/// The curve to instantiate the bellman back-ends with, if they are used.
+/// Exits if the field has no supported curve, before compiling anything.
+#[cfg(feature = "bellman")]
+fn pairing_curve(backend: &Backend) -> Option<PairingCurve> {
+    match backend {
+        Backend::R1cs {
+            action: ProofAction::Setup | ProofAction::CpSetup | ProofAction::CeremonyInit,
+            proof_impl: ProofImpl::Groth16 | ProofImpl::Mirage,
+            ..
+        } => match PairingCurve::from_modulus(cfg().field().modulus()) {
+            Ok(curve) => Some(curve),
+            Err(e) => {
+                eprintln!("error: {e}");
+                std::process::exit(1)
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Run the front-end, printing its diagnostics and exiting if compilation fails
+fn gen_or_exit(inputs: front::python::Inputs) -> Computations {
+    front::python::PythonFE::try_gen(inputs).unwrap_or_else(|diagnostics| exit_with(diagnostics))
+}
+
+fn exit_with(diagnostics: front::diagnostic::Diagnostics) -> ! {
+    eprintln!("{diagnostics}");
+    std::process::exit(1)
+}
+
+#[cfg(feature = "r1cs")]
+fn print_line_profile(profile: &LineProfile, format: ProfileFormat) {
+    match format {
+        ProfileFormat::Text => print!("{}", profile.to_text()),
+        ProfileFormat::Json => println!("{}", profile.to_json()),
+        ProfileFormat::Folded => print!("{}", profile.to_folded()),
+    }
+}
+
+/// Number of constraints of `entry_point` when arrays are lowered with `strategy`
+#[cfg(feature = "r1cs")]
+fn r1cs_size(
+    source: SourceInput,
+    entry_point: &str,
+    mode: Mode,
+    array_strategy: ArrayStrategy,
+    max_inline_depth: usize,
+) -> usize {
+    let inputs = front::python::Inputs {
+        source,
+        entry_point: entry_point.to_owned(),
+        mode,
+        array_strategy,
+        max_inline_depth,
+    };
+    let cs = opt(gen_or_exit(inputs), proof_opts());
+    let r1cs = reduce_linearities(to_r1cs(cs.get(entry_point), cfg()), cfg());
+    r1cs.constraints().len()
+}
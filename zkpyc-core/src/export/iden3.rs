@@ -0,0 +1,176 @@
+//! Writers for the iden3 binary `.r1cs` and `.wtns` formats read by snarkjs
+//! and produced by circom.
+//!
+//! Wire 0 is the constant one, followed by the instance variables and then
+//! the witness variables, in the order of [R1csFinal::vars], the same order
+//! the zkInterface exporter assigns ids in.
+
+use crate::utilities::r1cs::{Lc, ProverData, R1csFinal, Var, VarType};
+use circ::ir::term::Value;
+use circ_fields::FieldV;
+use fxhash::FxHashMap as HashMap;
+use rug::{integer::Order, Integer};
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// field elements are written in 32-byte little-endian limbs
+const N8: usize = 32;
+
+fn write_u32(w: &mut impl Write, v: usize) -> io::Result<()> {
+    let v = u32::try_from(v)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("{v} does not fit in u32")))?;
+    w.write_all(&v.to_le_bytes())
+}
+
+fn write_u64(w: &mut impl Write, v: usize) -> io::Result<()> {
+    w.write_all(&(v as u64).to_le_bytes())
+}
+
+fn write_int(w: &mut impl Write, i: &Integer) -> io::Result<()> {
+    let mut digits = i.to_digits::<u8>(Order::Lsf);
+    if digits.len() > N8 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("field element {i} does not fit in {N8} bytes"),
+        ));
+    }
+    digits.resize(N8, 0);
+    w.write_all(&digits)
+}
+
+// A section is its type, its size in bytes, and its contents
+fn write_section(w: &mut impl Write, ty: usize, contents: &[u8]) -> io::Result<()> {
+    write_u32(w, ty)?;
+    write_u64(w, contents.len())?;
+    w.write_all(contents)
+}
+
+fn write_header(w: &mut impl Write, magic: &[u8; 4], version: usize, n_sections: usize) -> io::Result<()> {
+    w.write_all(magic)?;
+    write_u32(w, version)?;
+    write_u32(w, n_sections)
+}
+
+/// The wire of each variable
+fn wires(r1cs: &R1csFinal) -> HashMap<Var, usize> {
+    r1cs.vars.iter().enumerate().map(|(i, v)| (*v, i + 1)).collect()
+}
+
+fn write_lc(w: &mut impl Write, lc: &Lc, wires: &HashMap<Var, usize>) -> io::Result<()> {
+    let mut terms: Vec<(usize, Integer)> = lc
+        .monomials
+        .iter()
+        .filter(|(_, c)| !c.is_zero())
+        .map(|(v, c)| (wires[v], Integer::from(c)))
+        .collect();
+    if !lc.constant.is_zero() {
+        terms.push((0, Integer::from(&lc.constant)));
+    }
+    terms.sort_by_key(|(wire, _)| *wire);
+    write_u32(w, terms.len())?;
+    for (wire, coeff) in terms {
+        write_u32(w, wire)?;
+        write_int(w, &coeff)?;
+    }
+    Ok(())
+}
+
+/// Write `r1cs` in the `.r1cs` format to `path`.
+pub fn write_r1cs(r1cs: &R1csFinal, path: impl AsRef<Path>) -> io::Result<()> {
+    let wires = wires(r1cs);
+    let n_public = r1cs
+        .vars
+        .iter()
+        .filter(|v| matches!(v.ty(), VarType::Inst))
+        .count();
+
+    let mut header = Vec::new();
+    write_u32(&mut header, N8)?;
+    write_int(&mut header, r1cs.field.modulus())?;
+    write_u32(&mut header, r1cs.vars.len() + 1)?;
+    // outputs
+    write_u32(&mut header, 0)?;
+    // public and private inputs
+    write_u32(&mut header, n_public)?;
+    write_u32(&mut header, 0)?;
+    // labels
+    write_u64(&mut header, r1cs.vars.len() + 1)?;
+    write_u32(&mut header, r1cs.constraints.len())?;
+
+    let mut constraints = Vec::new();
+    for (a, b, c) in &r1cs.constraints {
+        write_lc(&mut constraints, a, &wires)?;
+        write_lc(&mut constraints, b, &wires)?;
+        write_lc(&mut constraints, c, &wires)?;
+    }
+
+    let mut labels = Vec::new();
+    for wire in 0..=r1cs.vars.len() {
+        write_u64(&mut labels, wire)?;
+    }
+
+    let mut w = BufWriter::new(File::create(path)?);
+    write_header(&mut w, b"r1cs", 1, 3)?;
+    write_section(&mut w, 1, &header)?;
+    write_section(&mut w, 2, &constraints)?;
+    write_section(&mut w, 3, &labels)?;
+    w.flush()
+}
+
+/// Evaluate the witness of `pd` for the prover inputs `inputs`, and write
+/// it in the `.wtns` format to `path`.
+pub fn write_wtns(pd: &ProverData, inputs: &HashMap<String, Value>, path: impl AsRef<Path>) -> io::Result<()> {
+    let values = pd.eval_vars(inputs);
+    let one = pd.r1cs.field.new_v(1);
+    let witness: Vec<&FieldV> = std::iter::once(&one)
+        .chain(pd.r1cs.vars.iter().map(|v| &values[v]))
+        .collect();
+
+    let mut header = Vec::new();
+    write_u32(&mut header, N8)?;
+    write_int(&mut header, pd.r1cs.field.modulus())?;
+    write_u32(&mut header, witness.len())?;
+
+    let mut contents = Vec::new();
+    for v in witness {
+        write_int(&mut contents, &Integer::from(v))?;
+    }
+
+    let mut w = BufWriter::new(File::create(path)?);
+    write_header(&mut w, b"wtns", 2, 2)?;
+    write_section(&mut w, 1, &header)?;
+    write_section(&mut w, 2, &contents)?;
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_bytes() {
+        let mut out = Vec::new();
+        write_header(&mut out, b"wtns", 2, 1).unwrap();
+        write_section(&mut out, 1, &[0xab, 0xcd]).unwrap();
+        let expected: &[u8] = &[
+            b'w', b't', b'n', b's', 2, 0, 0, 0, 1, 0, 0, 0, // magic, version, sections
+            1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0xab, 0xcd, // type, size, contents
+        ];
+        assert_eq!(out, expected);
+
+        let mut out = Vec::new();
+        write_int(&mut out, &Integer::from(0x0102)).unwrap();
+        assert_eq!(&out[..3], &[2, 1, 0]);
+        assert_eq!(out.len(), N8);
+    }
+
+    #[test]
+    fn test_oversized_values_are_errors() {
+        let mut out = Vec::new();
+        assert!(write_u32(&mut out, u32::MAX as usize + 1).is_err());
+        assert!(write_int(&mut out, &(Integer::from(1) << (8 * N8 as u32))).is_err());
+        assert!(out.is_empty());
+    }
+}
@@ -1,6 +1,11 @@
 //! ZKInterface export tools
 
 pub mod setup;
+pub mod iden3;
+#[cfg(feature = "bellman")]
+pub mod solidity;
+#[cfg(feature = "bellman")]
+pub mod snarkjs;
 
 use crate::utilities::{scalar_fields::PrimeField, proof::{deserialize_from_file, value_map_from_path}};
 
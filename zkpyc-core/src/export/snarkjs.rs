@@ -0,0 +1,298 @@
+//! Conversion of [Bellman](crate::utilities::bellman::Bellman) Groth16 keys
+//! and proofs over BN254 from and to snarkjs' `verification_key.json`,
+//! `proof.json` and `public.json`.
+//!
+//! snarkjs writes points in projective coordinates as decimal strings, with
+//! the real part of Fq2 elements first.
+
+use super::solidity::fq_to_int;
+use crate::utilities::bellman::{Bn254, Proof, VerifyingKey};
+use crate::utilities::proof::{deserialize_from_file, serialize_into_file, value_map_from_path};
+use crate::utilities::r1cs::VerifierData;
+use ::bellman::groth16;
+use circ::ir::term::Value;
+use ff::{Field, PrimeField};
+use fxhash::FxHashMap as HashMap;
+use group::prime::PrimeCurveAffine;
+use halo2curves::bn256::{Fq, Fq2, Fr, G1Affine, G2Affine, G2};
+use halo2curves::CurveAffine;
+use rug::{integer::Order, Integer};
+use serde::{Deserialize, Serialize};
+
+use std::path::Path;
+
+type G1Json = [String; 3];
+type G2Json = [[String; 2]; 3];
+
+/// snarkjs' `verification_key.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnarkjsVerificationKey {
+    pub protocol: String,
+    pub curve: String,
+    #[serde(rename = "nPublic")]
+    pub n_public: usize,
+    pub vk_alpha_1: G1Json,
+    pub vk_beta_2: G2Json,
+    pub vk_gamma_2: G2Json,
+    pub vk_delta_2: G2Json,
+    #[serde(rename = "IC")]
+    pub ic: Vec<G1Json>,
+}
+
+/// snarkjs' `proof.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnarkjsProof {
+    pub pi_a: G1Json,
+    pub pi_b: G2Json,
+    pub pi_c: G1Json,
+    pub protocol: String,
+    pub curve: String,
+}
+
+fn parse_int(s: &str) -> Result<Integer, String> {
+    Integer::from_str_radix(s, 10).map_err(|e| format!("Bad number '{s}': {e}"))
+}
+
+fn parse_field<F: PrimeField>(s: &str) -> Result<F, String> {
+    let i = parse_int(s)?;
+    let mut repr = F::Repr::default();
+    let digits = i.to_digits::<u8>(Order::Lsf);
+    if digits.len() > repr.as_ref().len() {
+        return Err(format!("'{s}' is not a field element"));
+    }
+    repr.as_mut()[..digits.len()].copy_from_slice(&digits);
+    Option::from(F::from_repr(repr)).ok_or_else(|| format!("'{s}' is not a field element"))
+}
+
+fn g1_to_json(p: &G1Affine) -> G1Json {
+    if bool::from(p.is_identity()) {
+        ["0".into(), "1".into(), "0".into()]
+    } else {
+        [fq_to_int(&p.x).to_string(), fq_to_int(&p.y).to_string(), "1".into()]
+    }
+}
+
+fn g2_to_json(p: &G2Affine) -> G2Json {
+    let fq2 = |f: &Fq2| [fq_to_int(&f.c0).to_string(), fq_to_int(&f.c1).to_string()];
+    if bool::from(p.is_identity()) {
+        [
+            ["0".into(), "0".into()],
+            ["1".into(), "0".into()],
+            ["0".into(), "0".into()],
+        ]
+    } else {
+        [fq2(&p.x), fq2(&p.y), ["1".into(), "0".into()]]
+    }
+}
+
+// Points are checked before they are used: pairing an invalid-curve or
+// small-subgroup point would break the soundness of verification
+fn g1_from_json(p: &G1Json) -> Result<G1Affine, String> {
+    match p[2].as_str() {
+        "0" => Ok(G1Affine::identity()),
+        "1" => {
+            // G1 has prime order, so being on the curve is enough
+            let (x, y) = (parse_field::<Fq>(&p[0])?, parse_field::<Fq>(&p[1])?);
+            Option::from(G1Affine::from_xy(x, y))
+                .ok_or_else(|| "G1 point is not on the curve".to_string())
+        }
+        _ => Err("Expected a G1 point in affine coordinates (z = 1)".to_string()),
+    }
+}
+
+fn g2_from_json(p: &G2Json) -> Result<G2Affine, String> {
+    let fq2 = |f: &[String; 2]| -> Result<Fq2, String> {
+        Ok(Fq2 {
+            c0: parse_field::<Fq>(&f[0])?,
+            c1: parse_field::<Fq>(&f[1])?,
+        })
+    };
+    match (p[2][0].as_str(), p[2][1].as_str()) {
+        ("0", "0") => Ok(G2Affine::identity()),
+        ("1", "0") => {
+            let (x, y) = (fq2(&p[0])?, fq2(&p[1])?);
+            let pt: G2Affine = Option::from(G2Affine::from_xy(x, y))
+                .ok_or_else(|| "G2 point is not on the curve".to_string())?;
+            // G2 has a cofactor: P is in the subgroup of order r iff (r - 1) P = -P
+            let q = G2::from(pt);
+            if q * -Fr::one() != -q {
+                return Err("G2 point is not in the prime-order subgroup".to_string());
+            }
+            Ok(pt)
+        }
+        _ => Err("Expected a G2 point in affine coordinates (z = 1)".to_string()),
+    }
+}
+
+impl SnarkjsVerificationKey {
+    pub fn new(vk: &VerifyingKey<Bn254>) -> Self {
+        let g = vk.groth16();
+        SnarkjsVerificationKey {
+            protocol: "groth16".to_string(),
+            curve: "bn128".to_string(),
+            n_public: g.ic.len() - 1,
+            vk_alpha_1: g1_to_json(&g.alpha_g1),
+            vk_beta_2: g2_to_json(&g.beta_g2),
+            vk_gamma_2: g2_to_json(&g.gamma_g2),
+            vk_delta_2: g2_to_json(&g.delta_g2),
+            ic: g.ic.iter().map(g1_to_json).collect(),
+        }
+    }
+
+    /// The key as used by bellman.
+    ///
+    /// snarkjs does not record the G1 versions of beta and delta, which
+    /// verification does not use, so they are left as the identity.
+    pub fn to_groth16(&self) -> Result<groth16::VerifyingKey<Bn254>, String> {
+        if self.protocol != "groth16" || self.curve != "bn128" {
+            return Err(format!("Expected a groth16 key over bn128, not {} over {}", self.protocol, self.curve));
+        }
+        Ok(groth16::VerifyingKey {
+            alpha_g1: g1_from_json(&self.vk_alpha_1)?,
+            beta_g1: G1Affine::identity(),
+            beta_g2: g2_from_json(&self.vk_beta_2)?,
+            gamma_g2: g2_from_json(&self.vk_gamma_2)?,
+            delta_g1: G1Affine::identity(),
+            delta_g2: g2_from_json(&self.vk_delta_2)?,
+            ic: self.ic.iter().map(g1_from_json).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// The verifying key, for the instance computation `data`
+    pub fn to_verifying_key(&self, data: VerifierData) -> Result<VerifyingKey<Bn254>, String> {
+        let n_public = data.public_input_names().len();
+        if self.ic.len() != n_public + 1 {
+            return Err(format!(
+                "The key has {} public inputs, but the relation has {n_public}",
+                self.ic.len().saturating_sub(1)
+            ));
+        }
+        Ok(VerifyingKey::new(data, self.to_groth16()?))
+    }
+}
+
+impl SnarkjsProof {
+    pub fn new(proof: &Proof<Bn254>) -> Self {
+        let pf = proof.groth16();
+        SnarkjsProof {
+            pi_a: g1_to_json(&pf.a),
+            pi_b: g2_to_json(&pf.b),
+            pi_c: g1_to_json(&pf.c),
+            protocol: "groth16".to_string(),
+            curve: "bn128".to_string(),
+        }
+    }
+
+    pub fn to_proof(&self) -> Result<Proof<Bn254>, String> {
+        Ok(Proof::new(groth16::Proof {
+            a: g1_from_json(&self.pi_a)?,
+            b: g2_from_json(&self.pi_b)?,
+            c: g1_from_json(&self.pi_c)?,
+        }))
+    }
+}
+
+/// The public inputs for the verifier inputs `inputs`, as in `public.json`
+pub fn public_json(vk: &VerifyingKey<Bn254>, inputs: &HashMap<String, Value>) -> Vec<String> {
+    vk.data().eval(inputs).into_iter().map(|v| v.i().to_string()).collect()
+}
+
+/// Check a proof in snarkjs' formats, as `snarkjs groth16 verify` does
+pub fn verify(vk: &SnarkjsVerificationKey, proof: &SnarkjsProof, public: &[String]) -> Result<bool, String> {
+    let g = vk.to_groth16()?;
+    if g.ic.len() != public.len() + 1 {
+        return Err(format!(
+            "The key has {} public inputs, but {} were given",
+            g.ic.len().saturating_sub(1),
+            public.len()
+        ));
+    }
+    let public: Vec<Fr> = public.iter().map(|s| parse_field::<Fr>(s)).collect::<Result<_, _>>()?;
+    let pf = proof.to_proof()?;
+    let pvk = groth16::prepare_verifying_key(&g);
+    Ok(groth16::verify_proof(&pvk, pf.groth16(), &public).is_ok())
+}
+
+fn write_json<T: Serialize>(data: &T, path: impl AsRef<Path>) -> std::io::Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(data)?)
+}
+
+fn read_json<T: for<'a> Deserialize<'a>>(path: impl AsRef<Path>) -> std::io::Result<T> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+/// Write `verification_key.json`, `proof.json` and `public.json` to `dir`
+/// from a [Bellman](crate::utilities::bellman::Bellman) verifying key,
+/// proof and verifier inputs.
+pub fn export_fs(
+    vk_path: impl AsRef<Path>,
+    pf_path: impl AsRef<Path>,
+    inputs_path: impl AsRef<Path>,
+    dir: impl AsRef<Path>,
+) -> std::io::Result<()> {
+    let vk: VerifyingKey<Bn254> = deserialize_from_file(vk_path)?;
+    let pf: Proof<Bn254> = deserialize_from_file(pf_path)?;
+    let inputs = value_map_from_path(inputs_path)?;
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    write_json(&SnarkjsVerificationKey::new(&vk), dir.join("verification_key.json"))?;
+    write_json(&SnarkjsProof::new(&pf), dir.join("proof.json"))?;
+    write_json(&public_json(&vk, &inputs), dir.join("public.json"))
+}
+
+/// Convert `verification_key.json` and `proof.json` in `dir` to a
+/// [Bellman](crate::utilities::bellman::Bellman) verifying key and proof,
+/// written to `vk_out` and `pf_out`. The instance computation is taken from
+/// the verifying key at `vk_path`.
+pub fn import_fs(
+    dir: impl AsRef<Path>,
+    vk_path: impl AsRef<Path>,
+    vk_out: impl AsRef<Path>,
+    pf_out: impl AsRef<Path>,
+) -> std::io::Result<()> {
+    let dir = dir.as_ref();
+    let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+    let vk: VerifyingKey<Bn254> = deserialize_from_file(vk_path)?;
+    let json_vk: SnarkjsVerificationKey = read_json(dir.join("verification_key.json"))?;
+    let json_pf: SnarkjsProof = read_json(dir.join("proof.json"))?;
+    let vk = json_vk.to_verifying_key(vk.into_data()).map_err(invalid)?;
+    serialize_into_file(&vk, vk_out)?;
+    serialize_into_file(&json_pf.to_proof().map_err(invalid)?, pf_out)
+}
+
+/// Check the proof in `dir` against `verification_key.json` and `public.json`
+pub fn verify_fs(dir: impl AsRef<Path>) -> std::io::Result<bool> {
+    let dir = dir.as_ref();
+    let vk: SnarkjsVerificationKey = read_json(dir.join("verification_key.json"))?;
+    let pf: SnarkjsProof = read_json(dir.join("proof.json"))?;
+    let public: Vec<String> = read_json(dir.join("public.json"))?;
+    verify(&vk, &pf, &public).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use group::Curve;
+
+    #[test]
+    fn test_point_round_trip() {
+        let g1 = G1Affine::generator();
+        for p in [g1, -g1, (g1 * Fr::from(5)).to_affine(), G1Affine::identity()] {
+            assert_eq!(g1_from_json(&g1_to_json(&p)).unwrap(), p);
+        }
+        let g2 = G2Affine::generator();
+        for p in [g2, -g2, (g2 * Fr::from(5)).to_affine(), G2Affine::identity()] {
+            assert_eq!(g2_from_json(&g2_to_json(&p)).unwrap(), p);
+        }
+    }
+
+    #[test]
+    fn test_off_curve_points_are_rejected() {
+        let p1: G1Json = ["1".into(), "3".into(), "1".into()];
+        assert!(g1_from_json(&p1).is_err());
+
+        let mut p2 = g2_to_json(&G2Affine::generator());
+        p2[1][0] = "1".into();
+        assert!(g2_from_json(&p2).is_err());
+    }
+}
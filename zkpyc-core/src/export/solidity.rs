@@ -0,0 +1,243 @@
+//! Solidity verifiers for [Bellman](crate::utilities::bellman::Bellman) Groth16 proofs over BN254
+
+use crate::utilities::bellman::{Bn254, Proof, VerifyingKey};
+use ::bellman::groth16;
+use circ::ir::term::Value;
+use ff::PrimeField;
+use fxhash::FxHashMap as HashMap;
+use halo2curves::bn256::{Fq, G1Affine, G2Affine};
+use rug::{integer::Order, Integer};
+use tiny_keccak::{Hasher, Keccak};
+
+use std::fmt::Write;
+
+// the order of Fq
+const BASE_FIELD: &str = "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+
+pub(super) fn fq_to_int(f: &Fq) -> Integer {
+    Integer::from_digits(f.to_repr().as_ref(), Order::Lsf)
+}
+
+fn g1_coords(p: &G1Affine) -> [Integer; 2] {
+    [fq_to_int(&p.x), fq_to_int(&p.y)]
+}
+
+// the precompiles take the imaginary part of Fq2 elements first
+fn g2_coords(p: &G2Affine) -> [Integer; 4] {
+    [
+        fq_to_int(&p.x.c1),
+        fq_to_int(&p.x.c0),
+        fq_to_int(&p.y.c1),
+        fq_to_int(&p.y.c0),
+    ]
+}
+
+/// The signature of `verifyProof` for `n_inputs` public inputs
+fn signature(n_inputs: usize) -> String {
+    match n_inputs {
+        0 => "verifyProof(uint256[2],uint256[2][2],uint256[2])".to_string(),
+        n => format!("verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[{n}])"),
+    }
+}
+
+/// The ABI function selector of `signature`
+fn selector(signature: &str) -> [u8; 4] {
+    let mut hash = [0u8; 32];
+    let mut keccak = Keccak::v256();
+    keccak.update(signature.as_bytes());
+    keccak.finalize(&mut hash);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Render a self-contained Solidity contract checking proofs for `vk` with
+/// the EVM precompiles for BN254 addition, scalar multiplication and pairing.
+///
+/// Its `verifyProof` takes the public inputs in the order of
+/// [VerifierData::public_input_names](crate::utilities::r1cs::VerifierData::public_input_names),
+/// and the proof as encoded by [calldata].
+pub fn solidity_verifier(vk: &VerifyingKey<Bn254>) -> String {
+    let names = vk.data().public_input_names();
+    let g = vk.groth16();
+    assert_eq!(g.ic.len(), names.len() + 1, "Verifying key does not match its inputs");
+
+    let mut s = String::new();
+    fn set(s: &mut String, offset: usize, values: &[Integer]) {
+        for (i, v) in values.iter().enumerate() {
+            writeln!(s, "        p[{}] = {v};", offset + i).unwrap();
+        }
+    }
+
+    writeln!(s, "// SPDX-License-Identifier: MIT").unwrap();
+    writeln!(s, "pragma solidity ^0.8.0;").unwrap();
+    writeln!(s).unwrap();
+    writeln!(s, "/// Groth16 verifier over BN254, generated by zkpyc.").unwrap();
+    writeln!(s, "///").unwrap();
+    writeln!(s, "/// Public inputs, in order:").unwrap();
+    for (i, n) in names.iter().enumerate() {
+        writeln!(s, "///   {i}: {n}").unwrap();
+    }
+    writeln!(s, "contract Verifier {{").unwrap();
+    writeln!(s, "    uint256 constant SCALAR_FIELD = {};", crate::utilities::bellman::PairingCurve::Bn254.modulus()).unwrap();
+    writeln!(s, "    uint256 constant BASE_FIELD = {BASE_FIELD};").unwrap();
+    writeln!(s).unwrap();
+    s.push_str(
+        r#"    function ecAdd(uint256[2] memory p, uint256[2] memory q) internal view returns (uint256[2] memory r) {
+        uint256[4] memory input = [p[0], p[1], q[0], q[1]];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x06, input, 0x80, r, 0x40)
+        }
+        require(ok, "ecAdd failed");
+    }
+
+    function ecMul(uint256[2] memory p, uint256 k) internal view returns (uint256[2] memory r) {
+        uint256[3] memory input = [p[0], p[1], k];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x07, input, 0x60, r, 0x40)
+        }
+        require(ok, "ecMul failed");
+    }
+
+"#,
+    );
+
+    let inputs_param = match names.len() {
+        0 => String::new(),
+        n => format!(",\n        uint256[{n}] calldata input"),
+    };
+    writeln!(s, "    function verifyProof(").unwrap();
+    writeln!(s, "        uint256[2] calldata a,").unwrap();
+    writeln!(s, "        uint256[2][2] calldata b,").unwrap();
+    write!(s, "        uint256[2] calldata c{inputs_param}\n    ) public view returns (bool) {{\n").unwrap();
+
+    // vk_x = ic[0] + sum_i input[i] * ic[i + 1]
+    let [x, y] = g1_coords(&g.ic[0]);
+    writeln!(s, "        uint256[2] memory vkX = [uint256({x}), uint256({y})];").unwrap();
+    for (i, ic) in g.ic[1..].iter().enumerate() {
+        let [x, y] = g1_coords(ic);
+        writeln!(s, "        require(input[{i}] < SCALAR_FIELD, \"input {i} is not a field element\");").unwrap();
+        writeln!(s, "        vkX = ecAdd(vkX, ecMul([uint256({x}), uint256({y})], input[{i}]));").unwrap();
+    }
+    writeln!(s).unwrap();
+
+    // e(-a, b) * e(alpha, beta) * e(vk_x, gamma) * e(c, delta) == 1
+    writeln!(s, "        uint256[24] memory p;").unwrap();
+    writeln!(s, "        p[0] = a[0];").unwrap();
+    writeln!(s, "        p[1] = (BASE_FIELD - (a[1] % BASE_FIELD)) % BASE_FIELD;").unwrap();
+    writeln!(s, "        p[2] = b[0][0];").unwrap();
+    writeln!(s, "        p[3] = b[0][1];").unwrap();
+    writeln!(s, "        p[4] = b[1][0];").unwrap();
+    writeln!(s, "        p[5] = b[1][1];").unwrap();
+    set(&mut s, 6, &g1_coords(&g.alpha_g1));
+    set(&mut s, 8, &g2_coords(&g.beta_g2));
+    writeln!(s, "        p[12] = vkX[0];").unwrap();
+    writeln!(s, "        p[13] = vkX[1];").unwrap();
+    set(&mut s, 14, &g2_coords(&g.gamma_g2));
+    writeln!(s, "        p[18] = c[0];").unwrap();
+    writeln!(s, "        p[19] = c[1];").unwrap();
+    set(&mut s, 20, &g2_coords(&g.delta_g2));
+    s.push_str(
+        r#"
+        uint256[1] memory out;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x08, p, 0x300, out, 0x20)
+        }
+        return ok && out[0] == 1;
+    }
+}
+"#,
+    );
+    s
+}
+
+/// The transaction payload calling `verifyProof` of the contract of
+/// [solidity_verifier] with `proof` and the public inputs computed from the
+/// verifier inputs `inputs` (as in a `.vin` file).
+pub fn calldata(vk: &VerifyingKey<Bn254>, proof: &Proof<Bn254>, inputs: &HashMap<String, Value>) -> Vec<u8> {
+    let public: Vec<Integer> = vk.data().eval(inputs).into_iter().map(|v| v.i()).collect();
+    encode_call(proof.groth16(), &public)
+}
+
+// The ABI encoding of a `verifyProof` call: the selector, then each
+// coordinate and public input as a big-endian 32-byte word
+fn encode_call(pf: &groth16::Proof<Bn254>, public: &[Integer]) -> Vec<u8> {
+    let mut words: Vec<Integer> = Vec::new();
+    words.extend(g1_coords(&pf.a));
+    words.extend(g2_coords(&pf.b));
+    words.extend(g1_coords(&pf.c));
+    words.extend(public.iter().cloned());
+
+    let mut data = selector(&signature(public.len())).to_vec();
+    for w in words {
+        let digits = w.to_digits::<u8>(Order::Msf);
+        assert!(digits.len() <= 32, "calldata word does not fit in 256 bits");
+        data.extend(std::iter::repeat(0u8).take(32 - digits.len()));
+        data.extend(digits);
+    }
+    data
+}
+
+/// [calldata], as a `0x`-prefixed hex string
+pub fn calldata_hex(vk: &VerifyingKey<Bn254>, proof: &Proof<Bn254>, inputs: &HashMap<String, Value>) -> String {
+    calldata(vk, proof, inputs)
+        .iter()
+        .fold("0x".to_string(), |mut s, b| {
+            write!(s, "{b:02x}").unwrap();
+            s
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use group::prime::PrimeCurveAffine;
+
+    // the coordinates of the BN254 G2 generator, as in EIP-197
+    const G2_X_C0: &str = "10857046999023057135944570762232829481370756359578518086990519993285655852781";
+    const G2_X_C1: &str = "11559732032986387107991004021392285783925812861821192530917403151452391805634";
+    const G2_Y_C0: &str = "8495653923123431417604973247489272438418190587263600148770280649306958101930";
+    const G2_Y_C1: &str = "4082367875863433681332203403145435568316851327593401208105741076214120093531";
+
+    #[test]
+    fn test_selector() {
+        // the selectors of the verifiers snarkjs generates
+        let sig = signature(1);
+        assert_eq!(sig, "verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[1])");
+        assert_eq!(selector(&sig), [0x43, 0x75, 0x3b, 0x4d]);
+        assert_eq!(selector(&signature(2)), [0xf5, 0xc9, 0xd6, 0x9e]);
+        assert_eq!(selector(&signature(0)), [0x66, 0x68, 0xa9, 0xfa]);
+    }
+
+    #[test]
+    fn test_calldata_layout() {
+        let pf = groth16::Proof::<Bn254> {
+            a: G1Affine::generator(),
+            b: G2Affine::generator(),
+            c: -G1Affine::generator(),
+        };
+        let data = encode_call(&pf, &[Integer::from(7)]);
+        assert_eq!(data.len(), 4 + 9 * 32);
+        assert_eq!(data[..4], [0x43, 0x75, 0x3b, 0x4d]);
+        let words: Vec<Integer> = data[4..]
+            .chunks(32)
+            .map(|w| Integer::from_digits(w, Order::Msf))
+            .collect();
+        let int = |s: &str| Integer::from_str_radix(s, 10).unwrap();
+        let minus_two = int(BASE_FIELD) - 2;
+        let expected = [
+            Integer::from(1),
+            Integer::from(2),
+            // the imaginary part of each G2 coordinate comes first
+            int(G2_X_C1),
+            int(G2_X_C0),
+            int(G2_Y_C1),
+            int(G2_Y_C0),
+            Integer::from(1),
+            minus_two,
+            Integer::from(7),
+        ];
+        assert_eq!(words, expected);
+    }
+}
@@ -0,0 +1,284 @@
+//! Source-mapped compiler diagnostics
+
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+
+/// How serious a [Diagnostic] is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// The class of problem a [Diagnostic] reports, rendered as `E0001`, ...
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// The source does not parse
+    Syntax,
+    /// A variable, function, class or field that does not exist
+    UndefinedName,
+    /// An operation applied to values of the wrong type
+    TypeMismatch,
+    /// Python that the compiler does not support (yet)
+    Unsupported,
+    /// A value that must be known at compile time is not
+    NotConstant,
+    /// An index or literal that does not fit
+    OutOfBounds,
+    /// A call with the wrong number of arguments
+    Arity,
+    /// A malformed type or visibility annotation
+    InvalidAnnotation,
+    /// A module that cannot be imported
+    Import,
+    /// A loop that cannot be unrolled
+    LoopBound,
+    /// A bug in the compiler rather than in the program
+    Internal,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 11] = [
+        ErrorCode::Syntax,
+        ErrorCode::UndefinedName,
+        ErrorCode::TypeMismatch,
+        ErrorCode::Unsupported,
+        ErrorCode::NotConstant,
+        ErrorCode::OutOfBounds,
+        ErrorCode::Arity,
+        ErrorCode::InvalidAnnotation,
+        ErrorCode::Import,
+        ErrorCode::LoopBound,
+        ErrorCode::Internal,
+    ];
+
+    /// The number in the rendered code
+    pub fn number(&self) -> usize {
+        match self {
+            ErrorCode::Internal => 0,
+            c => ErrorCode::ALL.iter().position(|a| a == c).unwrap() + 1,
+        }
+    }
+
+    /// A generic hint on how to fix this class of error
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            ErrorCode::Unsupported => Some("only a subset of Python can be compiled to constraints; see the README for what is supported"),
+            ErrorCode::NotConstant => Some("values that shape the circuit, e.g. array sizes and loop ranges, must be known at compile time"),
+            ErrorCode::LoopBound => Some("bound loops with witness-dependent conditions with a `# zk_bound: N` pragma"),
+            ErrorCode::InvalidAnnotation => Some("entry function parameters are annotated as `Private[T]` or `Public[T]`"),
+            ErrorCode::Internal => Some("this is a compiler bug; please report it along with the program"),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "E{:04}", self.number())
+    }
+}
+
+/// A compiler message tied to a span of the source
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: ErrorCode,
+    pub message: String,
+    pub file: PathBuf,
+    /// 1-based line and column (in characters) of the start of the span
+    pub line: usize,
+    pub column: usize,
+    /// 1-based line and column just past the end of the span
+    pub end_line: usize,
+    pub end_column: usize,
+    /// The function the span is in, if any
+    pub function: Option<String>,
+    /// The source lines `line..=end_line`
+    pub snippet: Vec<String>,
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    /// An error spanning the bytes `start..end` of `contents`, the source of `file`
+    pub fn error(
+        code: ErrorCode,
+        message: String,
+        file: PathBuf,
+        contents: &str,
+        start: usize,
+        end: usize,
+    ) -> Self {
+        let (line, column) = line_col(contents, start);
+        let (end_line, end_column) = line_col(contents, end.max(start));
+        let snippet = contents
+            .lines()
+            .skip(line - 1)
+            .take(end_line - line + 1)
+            .map(str::to_owned)
+            .collect();
+        Diagnostic {
+            severity: Severity::Error,
+            code,
+            message,
+            file,
+            line,
+            column,
+            end_line,
+            end_column,
+            function: None,
+            snippet,
+            suggestion: code.help().map(str::to_owned),
+        }
+    }
+
+    /// Render like rustc:
+    ///
+    /// ```text
+    /// error[E0003]: Cannot add u8 and field
+    ///  --> example.py:4:12
+    ///   |
+    /// 4 |     return a + b
+    ///   |            ^^^^^
+    ///   |
+    ///   = note: in function `main`
+    ///   = help: ...
+    /// ```
+    pub fn render(&self) -> String {
+        let num = self.line.to_string();
+        let pad = " ".repeat(num.len());
+        let mut s = format!(
+            "{}[{}]: {}\n{pad}--> {}:{}:{}\n",
+            self.severity,
+            self.code,
+            self.message,
+            self.file.display(),
+            self.line,
+            self.column
+        );
+        if let Some(first) = self.snippet.first() {
+            let width = first.chars().count();
+            let start = self.column.min(width + 1);
+            let end = if self.end_line == self.line {
+                self.end_column.clamp(start + 1, width.max(start) + 1)
+            } else {
+                width.max(start) + 1
+            };
+            s += &format!("{pad} |\n{num} | {first}\n");
+            s += &format!("{pad} | {}{}\n", " ".repeat(start - 1), "^".repeat(end - start));
+            if self.snippet.len() > 1 {
+                s += &format!("{pad} | ...\n");
+            }
+        }
+        if self.function.is_some() || self.suggestion.is_some() {
+            s += &format!("{pad} |\n");
+        }
+        if let Some(f) = &self.function {
+            s += &format!("{pad} = note: in function `{f}`\n");
+        }
+        if let Some(h) = &self.suggestion {
+            s += &format!("{pad} = help: {h}\n");
+        }
+        s
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// All diagnostics of a failed compilation, in the order they were found
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.0.iter().filter(|d| d.severity == Severity::Error)
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for d in &self.0 {
+            writeln!(f, "{d}")?;
+        }
+        match self.errors().count() {
+            0 => Ok(()),
+            1 => write!(f, "error: aborting due to 1 previous error"),
+            n => write!(f, "error: aborting due to {n} previous errors"),
+        }
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+// 1-based line and character column of byte `offset`
+fn line_col(contents: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(contents.len());
+    while !contents.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &contents[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "def main(a: Private[int]) -> int:\n    b: int = a + 1\n    return totl\n";
+
+    #[test]
+    fn test_span_location() {
+        let start = SRC.find("totl").unwrap();
+        let d = Diagnostic::error(
+            ErrorCode::UndefinedName,
+            "Undefined variable totl".to_string(),
+            PathBuf::from("sum.py"),
+            SRC,
+            start,
+            start + 4,
+        );
+        assert_eq!((d.line, d.column, d.end_line, d.end_column), (3, 12, 3, 16));
+        assert_eq!(d.snippet, vec!["    return totl".to_string()]);
+        assert_eq!(
+            d.render(),
+            "error[E0002]: Undefined variable totl\n \
+             --> sum.py:3:12\n  \
+              |\n\
+             3 |     return totl\n  \
+              |            ^^^^\n"
+        );
+    }
+
+    #[test]
+    fn test_code_numbers() {
+        assert_eq!(ErrorCode::Syntax.to_string(), "E0001");
+        assert_eq!(ErrorCode::Internal.to_string(), "E0000");
+    }
+
+    #[test]
+    fn test_multiline_span() {
+        let start = SRC.find("b: int").unwrap();
+        let d = Diagnostic::error(ErrorCode::TypeMismatch, "msg".to_string(), PathBuf::from("f.py"), SRC, start, SRC.len() - 1);
+        assert_eq!((d.line, d.end_line), (2, 3));
+        let rendered = d.render();
+        // underlined to the end of the first line
+        assert!(rendered.contains(&format!("  |     {}\n  | ...\n", "^".repeat(14))));
+    }
+}
@@ -1,6 +1,8 @@
 //! Input language front-ends
 
+pub mod diagnostic;
 pub mod python;
+pub mod source_map;
 
 use circ::ir::proof;
 use circ::ir::term::{Computations, PartyId};
@@ -23,6 +25,7 @@ pub trait FrontEnd {
 }
 
 /// Source code input type
+#[derive(Clone)]
 pub enum SourceInput {
     Path(PathBuf),
     String(String, PathBuf, String), // Source, working directory and identity
@@ -41,6 +44,10 @@ pub enum Mode {
     /// Find inputs that yeild an output at least this large,
     /// and then prove knowledge of them.
     ProofOfHighValue(u64),
+    /// Generating the step of an incrementally verifiable computation. The
+    /// first parameter is the state, which is public and of the same type as
+    /// the return value; the others are private to the prover in each step.
+    Ivc,
 }
 
 impl Display for Mode {
@@ -50,6 +57,7 @@ impl Display for Mode {
             Mode::Proof => write!(f, "proof"),
             Mode::Opt => write!(f, "opt"),
             Mode::ProofOfHighValue(v) => write!(f, "proof_of_high_value({v})"),
+            Mode::Ivc => write!(f, "ivc"),
         }
     }
 }
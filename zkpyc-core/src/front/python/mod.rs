@@ -5,22 +5,27 @@ mod term;
 pub mod pyvisit;
 
 use super::{FrontEnd, Mode, proof::PROVER_ID, SourceInput};
+use super::diagnostic::{Diagnostic, Diagnostics, ErrorCode};
+use super::source_map::{SourceLine, SourceMap};
 use circ::circify::{CircError, Circify, Loc, Val};
 use circ::ir::proof::ConstraintMetadata;
 use circ::cfg::cfg;
+use circ::ir::opt::Opt;
 use circ::ir::term::*;
 use circ::term;
 use rug::Integer;
 use rustpython_parser::ast::bigint::BigInt;
 use rustpython_parser::ast::{Ranged, text_size::TextRange, TextSize};
 use log::{debug, trace};
-use parser::{filter_out_zk_ignore};
+use parser::{filter_out_zk_ignore, zk_bound};
+use pyvisit::{walk_stmt, PyVisitorMut, PyVisitorResult};
 
 use std::cell::{Cell, RefCell};
 use std::fmt::Display;
+use std::panic::{self, AssertUnwindSafe};
 use std::{fs, io};
 use std::path::PathBuf;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use rustpython_parser::ast as ast;
 use rustpython_parser;
 
@@ -29,6 +34,23 @@ use term::*;
 // garbage collection increment for adaptive GC threshold
 const GC_INC: usize = 32;
 
+// unroll limit for while loops that have no `# zk_bound: N` pragma
+const WHILE_UNROLL_LIMIT: usize = 1 << 16;
+
+// builtins that take a lambda or a function, or aggregate an array,
+// unrolled at compile time unless a function of the same name is in scope
+const HIGHER_ORDER_BUILTINS: [&str; 7] = ["map", "reduce", "all", "any", "min", "max", "sorted"];
+
+// widest unsigned subject of a match statement whose values are enumerated
+// to check exhaustiveness; wider subjects get an assertion instead
+const MATCH_EXHAUSTIVE_BITS: usize = 8;
+
+// maximum number of errors reported by one compilation
+const MAX_ERRORS: usize = 32;
+
+// a statement, and the functions being executed when it was
+type SpanKey = (PathBuf, TextRange, Vec<String>);
+
 /// Inputs to the Python compiler
 pub struct Inputs {
     /// The source code, either a path or embedded input.
@@ -37,6 +59,87 @@ pub struct Inputs {
     pub entry_point: String,
     /// Mode to generate for (MPC or proof).
     pub mode: Mode,
+    /// Lowering of arrays accessed at witness-dependent indices.
+    pub array_strategy: ArrayStrategy,
+    /// Maximum depth of nested calls, which are all inlined. This bounds
+    /// recursion, which must stop on a compile-time constant argument, but
+    /// every nested call counts, recursive or not: a chain of helpers calling
+    /// each other is as deep as its longest path.
+    pub max_inline_depth: usize,
+}
+
+/// The default [Inputs::max_inline_depth]
+pub const DEFAULT_MAX_INLINE_DEPTH: usize = 64;
+
+/// How arrays accessed at witness-dependent indices are lowered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayStrategy {
+    /// Memory-checking for arrays annotated `Array[T, N, RAM]`, linear scan for the rest
+    #[default]
+    Annotated,
+    /// Linear scan for every array, ignoring annotations
+    LinearScan,
+    /// Memory-checking for every array
+    Ram,
+}
+
+impl ArrayStrategy {
+    pub const ALL: [ArrayStrategy; 3] = [
+        ArrayStrategy::Annotated,
+        ArrayStrategy::LinearScan,
+        ArrayStrategy::Ram,
+    ];
+}
+
+impl Display for ArrayStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArrayStrategy::Annotated => write!(f, "annotated"),
+            ArrayStrategy::LinearScan => write!(f, "linear-scan"),
+            ArrayStrategy::Ram => write!(f, "ram"),
+        }
+    }
+}
+
+impl std::str::FromStr for ArrayStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ArrayStrategy::ALL
+            .into_iter()
+            .find(|a| a.to_string() == s)
+            .ok_or_else(|| format!("Unknown array strategy '{s}' (expected annotated, linear-scan or ram)"))
+    }
+}
+
+/// The IR passes that prepare the computations of the front-end for proofs.
+///
+/// The memory-checking passes always run, but they only find arrays that the
+/// front-end left with witness-dependent accesses, i.e. those lowered as RAM
+/// by the [ArrayStrategy]. Anything left over is linearly scanned.
+pub fn proof_opts() -> Vec<Opt> {
+    vec![
+        Opt::ScalarizeVars,
+        Opt::Flatten,
+        Opt::Sha,
+        Opt::ConstantFold(Box::new([])),
+        Opt::ParseCondStores,
+        // Tuples must be eliminated before oblivious array elim
+        Opt::Tuple,
+        Opt::ConstantFold(Box::new([])),
+        Opt::Tuple,
+        Opt::Obliv,
+        // The obliv elim pass produces more tuples, that must be eliminated
+        Opt::Tuple,
+        Opt::PersistentRam,
+        Opt::VolatileRam,
+        Opt::SkolemizeChallenges,
+        Opt::LinearScan,
+        // The linear scan pass produces more tuples, that must be eliminated
+        Opt::Tuple,
+        Opt::Flatten,
+        Opt::ConstantFold(Box::new([])),
+    ]
 }
 
 pub struct PythonFE;
@@ -45,27 +148,74 @@ impl FrontEnd for PythonFE {
     type Inputs = Inputs;
 
     fn gen(i: Inputs) -> Computations {
+        Self::try_gen(i).unwrap_or_else(|d| panic!("{d}"))
+    }
+}
+
+impl PythonFE {
+    /// Compile the program, reporting all errors found rather than only the first.
+    pub fn try_gen(i: Inputs) -> Result<Computations, Diagnostics> {
+        Self::gen_recovering(i, false).map(|(cs, _)| cs)
+    }
+
+    /// Like [PythonFE::try_gen], also mapping the terms of the computation
+    /// to the source lines that created them.
+    pub fn try_gen_with_source_map(i: Inputs) -> Result<(Computations, SourceMap), Diagnostics> {
+        Self::gen_recovering(i, true).map(|(cs, spans)| (cs, spans.unwrap_or_default()))
+    }
+
+    fn gen_recovering(
+        i: Inputs,
+        record_spans: bool,
+    ) -> Result<(Computations, Option<SourceMap>), Diagnostics> {
         debug!(
             "Starting Python front-end, field: {}",
             Sort::Field(cfg().field().clone())
         );
         let loader = parser::PyLoad::new();
 
-        let asts = loader.load(&i.source);
+        let asts = loader.load(&i.source).map_err(|d| Diagnostics(vec![d]))?;
+        let recovery = Recovery::default();
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            Self::gen_impl(&i, asts, loader.stdlib(), &recovery, record_spans)
+        }));
+        let mut diagnostics = recovery.diagnostics.take();
+        match res {
+            Ok(Ok(cs)) if diagnostics.is_empty() => return Ok(cs),
+            Ok(Ok(_)) => {}
+            Ok(Err(d)) => diagnostics.push(d),
+            // anything after the first error may be a consequence of it
+            Err(_) if !diagnostics.is_empty() => {}
+            Err(payload) => match recovery.internal_error(&i.source, &*payload) {
+                Some(d) => diagnostics.push(d),
+                None => panic::resume_unwind(payload),
+            },
+        }
+        Err(Diagnostics(diagnostics))
+    }
+
+    fn gen_impl(
+        i: &Inputs,
+        asts: HashMap<PathBuf, ast::Mod>,
+        stdlib: &parser::PyGadgets,
+        recovery: &Recovery,
+        record_spans: bool,
+    ) -> Result<(Computations, Option<SourceMap>), Diagnostic> {
         // need to figure out how to create python config
-        let mut g = PyGen::new(asts, i.mode, loader.stdlib(), cfg().zsharp.isolate_asserts);
-        g.visit_files(&i.entry_point);
+        let mut g = PyGen::new(asts, i.mode, i.array_strategy, i.max_inline_depth, stdlib, cfg().zsharp.isolate_asserts, recovery, record_spans);
+        g.visit_files(&i.entry_point)?;
 
-        match i.source {
+        match &i.source {
             SourceInput::Path(p) => g.file_stack_push(p.to_path_buf()),
             SourceInput::String(source, _, name) => {
                 g.file_stack_push(PathBuf::from(name));
-                *g.source.borrow_mut() = Some(source);
+                *g.source.borrow_mut() = Some(source.clone());
             }
         }
 
         // no generics for now
-        g.entry_fn(&i.entry_point);
+        g.entry_fn(&i.entry_point)?;
+        let spans = g.take_source_map();
         g.file_stack_pop();
 
         let mut cs = Computations::new();
@@ -73,27 +223,39 @@ impl FrontEnd for PythonFE {
             .unwrap_or_else(|rc| (*rc).clone())
             .into_inner();
         cs.comps.insert(i.entry_point.clone(), main_comp);
-        cs
+        Ok((cs, spans))
     }
-}
 
-impl PythonFE {
     pub fn interpret(i: Inputs) -> PyTerm {
+        Self::try_interpret(i).unwrap_or_else(|d| panic!("{d}"))
+    }
+
+    /// Evaluate the entry function, which takes no arguments, at compile time.
+    pub fn try_interpret(i: Inputs) -> Result<PyTerm, Diagnostics> {
         let loader = parser::PyLoad::new();
-        let asts = loader.load(&i.source);
+        let asts = loader.load(&i.source).map_err(|d| Diagnostics(vec![d]))?;
+        let recovery = Recovery::default();
         // like before, figure out cfg() zsharp part
-        let mut g = PyGen::new(asts, i.mode, loader.stdlib(), cfg().zsharp.isolate_asserts);
-        g.visit_files(&i.entry_point);
-
-        match i.source {
-            SourceInput::Path(p) => g.file_stack_push(p.to_path_buf()),
-            SourceInput::String(source, _, name) => {
-                g.file_stack_push(PathBuf::from(name));
-                *g.source.borrow_mut() = Some(source);
+        let mut g = PyGen::new(asts, i.mode, i.array_strategy, i.max_inline_depth, loader.stdlib(), cfg().zsharp.isolate_asserts, &recovery, false);
+        let res = g.visit_files(&i.entry_point).and_then(|()| {
+            match i.source {
+                SourceInput::Path(p) => g.file_stack_push(p.to_path_buf()),
+                SourceInput::String(source, _, name) => {
+                    g.file_stack_push(PathBuf::from(name));
+                    *g.source.borrow_mut() = Some(source);
+                }
+            }
+            g.const_entry_fn(&i.entry_point)
+        });
+        let mut diagnostics = recovery.diagnostics.take();
+        match res {
+            Ok(v) if diagnostics.is_empty() => Ok(v),
+            Ok(_) => Err(Diagnostics(diagnostics)),
+            Err(d) => {
+                diagnostics.push(d);
+                Err(Diagnostics(diagnostics))
             }
         }
-
-        g.const_entry_fn(&i.entry_point)
     }
 }
 
@@ -107,6 +269,8 @@ struct PyGen<'a> {
         PathBuf,
         HashMap<String, Result<ast::StmtClassDef, ast::StmtTypeAlias>>,
     >,
+    // the methods of each class, by the file defining it: whether they are static
+    methods: HashMap<(PathBuf, String), HashMap<String, bool>>,
     // not yet sure if we need this
     constants: HashMap<PathBuf, HashMap<String, (ast::Expr, PyTerm)>>,
     import_map: HashMap<PathBuf, HashMap<String, (PathBuf, String)>>,
@@ -116,10 +280,22 @@ struct PyGen<'a> {
     curr_func: RefCell<String>,
     lhs_ty: RefCell<Option<Ty>>,
     ret_ty_stack: RefCell<Vec<Ty>>,
+    // the size parameters of each function being called, inferred from its arguments
+    generics: RefCell<Vec<HashMap<String, usize>>>,
     gc_depth_estimate: Cell<usize>,
     assertions: RefCell<Vec<Term>>,
     isolate_asserts: bool,
     source: RefCell<Option<String>>,
+    array_strategy: ArrayStrategy,
+    // (function, variable) pairs of arrays annotated with `RAM`
+    ram_arrays: RefCell<HashSet<(String, String)>>,
+    recovery: &'a Recovery,
+    // variables declared so far, to suggest a fix for misspelled names
+    known_names: RefCell<HashSet<String>>,
+    // the statements terms were created by, if requested
+    spans: Option<RefCell<SourceMap<SpanKey>>>,
+    call_stack: RefCell<Vec<String>>,
+    max_inline_depth: usize,
 }
 
 impl<'a> Drop for PyGen<'a> {
@@ -148,22 +324,149 @@ enum Literal {
     Field(String, TextRange),
 }
 
-fn loc_store(class_: PyTerm, loc: &[PyTarget], val: PyTerm) -> Result<PyTerm, String> {
+/// The errors found so far in one compilation.
+///
+/// A failing statement is reported and skipped, and compilation goes on
+/// with the next one. Names that statement would have assigned are
+/// poisoned: a later failure of a statement that reads one is most likely
+/// caused by the first, and is not reported.
+#[derive(Default)]
+struct Recovery {
+    poisoned: RefCell<HashSet<String>>,
+    diagnostics: RefCell<Vec<Diagnostic>>,
+    // statements being executed, innermost last
+    stmt_stack: RefCell<Vec<(PathBuf, TextRange)>>,
+}
+
+impl Recovery {
+    // Record the failure of statement `s` of function `function`
+    fn record(&self, d: Diagnostic, s: &ast::Stmt, function: &str) {
+        let mut names = StmtNames::new(s.range());
+        let _ = names.visit_stmt(s.clone());
+        let mut diagnostics = self.diagnostics.borrow_mut();
+        let mut poisoned = self.poisoned.borrow_mut();
+        // a function is inlined at each call, so its errors repeat
+        if !diagnostics.contains(&d)
+            && (diagnostics.is_empty() || names.loads.is_disjoint(&poisoned))
+        {
+            diagnostics.push(d);
+        }
+        poisoned.extend(names.stores);
+        if names.returns {
+            // calls of the function may not get their return value
+            poisoned.insert(function.to_string());
+        }
+    }
+
+    // Whether compilation should stop
+    fn full(&self) -> bool {
+        self.diagnostics.borrow().len() >= MAX_ERRORS
+    }
+
+    // A panic of the compiler itself, located at the statement it occurred in
+    fn internal_error(
+        &self,
+        source: &SourceInput,
+        payload: &(dyn std::any::Any + Send),
+    ) -> Option<Diagnostic> {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(|m| m.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())?;
+        let (path, range) = self.stmt_stack.borrow().last().cloned()?;
+        let contents = match source {
+            SourceInput::String(s, _, name) if path == PathBuf::from(name) => s.clone(),
+            _ => fs::read_to_string(&path).ok()?,
+        };
+        let range = range_before_filter(&range, &contents);
+        Some(Diagnostic::error(
+            ErrorCode::Internal,
+            format!("internal compiler error: {msg}"),
+            normalize_path(&path).unwrap_or(path),
+            &contents,
+            range.start().into(),
+            range.end().into(),
+        ))
+    }
+}
+
+/// The names a statement reads and assigns
+struct StmtNames {
+    range: TextRange,
+    inside: bool,
+    loads: HashSet<String>,
+    stores: HashSet<String>,
+    returns: bool,
+}
+
+impl StmtNames {
+    fn new(range: TextRange) -> Self {
+        StmtNames {
+            range,
+            inside: false,
+            loads: HashSet::new(),
+            stores: HashSet::new(),
+            returns: false,
+        }
+    }
+}
+
+impl PyVisitorMut for StmtNames {
+    fn visit_stmt(&mut self, node: ast::Stmt) -> PyVisitorResult {
+        let enter = !self.inside && node.range() == self.range;
+        self.inside |= enter;
+        self.returns |= self.inside && matches!(node, ast::Stmt::Return(_));
+        // a definition in the module body assigns its name
+        match &node {
+            ast::Stmt::FunctionDef(f) if enter => self.stores.insert(f.name.to_string()),
+            ast::Stmt::ClassDef(c) if enter => self.stores.insert(c.name.to_string()),
+            _ => false,
+        };
+        let res = walk_stmt(self, node);
+        self.inside &= !enter;
+        res
+    }
+
+    fn visit_expr_name(&mut self, node: ast::ExprName) -> PyVisitorResult {
+        if self.inside {
+            match node.ctx {
+                ast::ExprContext::Store => self.stores.insert(node.id.to_string()),
+                _ => self.loads.insert(node.id.to_string()),
+            };
+        }
+        Ok(())
+    }
+}
+
+// `ram` selects memory-checking lowering for an index into the variable itself;
+// nested accesses are always linearly scanned.
+fn loc_store(class_: PyTerm, loc: &[PyTarget], val: PyTerm, ram: bool) -> Result<PyTerm, String> {
     match loc.first() {
         None => Ok(val),
         Some(PyTarget::Member(field)) => {
             let old_inner = field_select(&class_, field)?;
-            let new_inner = loc_store(old_inner, &loc[1..], val)?;
+            let new_inner = loc_store(old_inner, &loc[1..], val, false)?;
             field_store(class_, field, new_inner)
         }
-        Some(PyTarget::Idx(idx)) => {
+        Some(PyTarget::Idx(idx)) if ram => {
             let old_inner = array_select(class_.clone(), idx.clone())?;
-            let new_inner = loc_store(old_inner, &loc[1..], val)?;
+            let new_inner = loc_store(old_inner, &loc[1..], val, false)?;
             array_store(class_, idx.clone(), new_inner)
         }
+        Some(PyTarget::Idx(idx)) => {
+            let old_inner = array_select_scan(class_.clone(), idx.clone())?;
+            let new_inner = loc_store(old_inner, &loc[1..], val, false)?;
+            array_store_scan(class_, idx.clone(), new_inner)
+        }
     }
 }
 
+/// A function argument of a higher-order builtin
+enum Callable {
+    Lambda(ast::ExprLambda),
+    Function(PathBuf, String),
+}
+
 enum PyVis {
     Public,
     Private(u8),
@@ -172,11 +475,16 @@ enum PyVis {
 }
 
 impl<'a> PyGen<'a> {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         asts: HashMap<PathBuf, ast::Mod>,
         mode: Mode,
+        array_strategy: ArrayStrategy,
+        max_inline_depth: usize,
         stdlib: &'a parser::PyGadgets,
         isolate_asserts: bool,
+        recovery: &'a Recovery,
+        record_spans: bool,
     ) -> Self {
         let this = Self {
             circ: RefCell::new(Circify::new(Python::new())),
@@ -185,6 +493,7 @@ impl<'a> PyGen<'a> {
             file_stack: Default::default(),
             functions: HashMap::new(),
             classes_and_tys: HashMap::new(),
+            methods: HashMap::new(),
             constants: HashMap::new(),
             import_map: HashMap::new(),
             mode,
@@ -193,10 +502,18 @@ impl<'a> PyGen<'a> {
             curr_func: RefCell::new(String::from("<module>")),
             lhs_ty: Default::default(),
             ret_ty_stack: Default::default(),
+            generics: Default::default(),
             gc_depth_estimate: Cell::new(2 * GC_INC),
             assertions: Default::default(),
             isolate_asserts,
             source: RefCell::new(None),
+            array_strategy,
+            ram_arrays: Default::default(),
+            recovery,
+            known_names: Default::default(),
+            spans: record_spans.then(Default::default),
+            call_stack: Default::default(),
+            max_inline_depth,
         };
         this.circ
             .borrow()
@@ -208,34 +525,81 @@ impl<'a> PyGen<'a> {
         this
     }
 
+    // Attribute the terms of `t` that are not attributed yet to the
+    // statement being executed
+    fn record_span(&self, t: &Term) {
+        if let Some((path, range)) = self.recovery.stmt_stack.borrow().last() {
+            self.record_span_at(t, path.clone(), *range);
+        }
+    }
+
+    fn record_span_at(&self, t: &Term, path: PathBuf, range: TextRange) {
+        if let Some(spans) = &self.spans {
+            let stack = self.call_stack.borrow().clone();
+            spans.borrow_mut().record(t, (path, range, stack));
+        }
+    }
+
+    // Resolve the recorded statements to source lines
+    fn take_source_map(&mut self) -> Option<SourceMap> {
+        let spans = self.spans.take()?.into_inner();
+        let mut sources: HashMap<PathBuf, String> = HashMap::new();
+        Some(spans.map_locs(|(path, range, stack)| {
+            let src = sources
+                .entry(path.clone())
+                .or_insert_with(|| self.source_contents(&path));
+            let start = range_before_filter(&range, src).start().to_usize().min(src.len());
+            let line = src[..start].matches('\n').count() + 1;
+            let text = src.lines().nth(line - 1).unwrap_or_default().trim().to_string();
+            SourceLine {
+                file: normalize_path(&path).unwrap_or(path),
+                line,
+                stack,
+                text,
+            }
+        }))
+    }
+
     fn into_circify(self) -> Circify<Python> {
         self.circ.replace(Circify::new(Python::new()))
     }
 
-    fn err<E: Display>(&self, e: E, s: &TextRange) -> ! {
-        let range = range_before_filter(s, &self.cur_source_contents());
-        let line = line_from_range(range, &self.cur_source_contents());
-        
-        // println!("ZKPyC Compilation Error -- Traceback:");
-        // println!("\tFile {}, line {:?}, in {}", self.cur_path().canonicalize().unwrap().display(), line, self.curr_func.borrow());
-        // println!("{}", range_to_string(s, self.cur_path()));
-        // println!("Error: {e}");
-        // std::process::exit(1)
-        panic!(
-            "ZKPyC Compilation Error -- Traceback:\n\
-            \tFile {}, line {:?}, in {}\n\
-            {}\n\
-            Error: {}",
-            normalize_path(&self.cur_path()).unwrap().display(),
-            line,
-            self.curr_func.borrow(),
-            range_to_string(s, &self.cur_source_contents()),
-            e
+    fn err<E: Display>(&self, code: ErrorCode, e: E, s: &TextRange) -> Diagnostic {
+        self.diagnostic(code, e.to_string(), &self.cur_path(), s)
+    }
+
+    fn err_help<E: Display>(&self, code: ErrorCode, e: E, help: String, s: &TextRange) -> Diagnostic {
+        let mut d = self.err(code, e, s);
+        d.suggestion = Some(help);
+        d
+    }
+
+    // Locate an error returned as a plain string, mostly by the term library
+    fn at<T, E: Display>(&self, r: Result<T, E>, code: ErrorCode, s: &TextRange) -> Result<T, Diagnostic> {
+        r.map_err(|e| self.err(code, e, s))
+    }
+
+    fn diagnostic(&self, code: ErrorCode, msg: String, path: &PathBuf, s: &TextRange) -> Diagnostic {
+        let contents = self.source_contents(path);
+        let range = range_before_filter(s, &contents);
+        let mut d = Diagnostic::error(
+            code,
+            msg,
+            normalize_path(path).unwrap_or_else(|_| path.clone()),
+            &contents,
+            range.start().into(),
+            range.end().into(),
         );
+        d.function = Some(self.curr_func.borrow().clone());
+        d
     }
 
-    fn unwrap<PyTerm, E: Display>(&self, r: Result<PyTerm, E>, s: &TextRange) -> PyTerm {
-        r.unwrap_or_else(|e| self.err(e, s))
+    // Report the failure of statement `s` and go on with the next one;
+    // see [Recovery]
+    fn recover(&self, d: Diagnostic, s: &ast::Stmt) {
+        // The statement may have failed before its call consumed the type
+        self.lhs_ty_take();
+        self.recovery.record(d, s, self.curr_func.borrow().as_str());
     }
 
     fn builtin_call(f_name: &str, mut args: Vec<PyTerm>) -> Result<PyTerm, String> {
@@ -353,48 +717,122 @@ impl<'a> PyGen<'a> {
                     }
                 }
             }
+            "fixed_from_uint" | "fixed_from_field" => {
+                if args.len() != 3 {
+                    Err(format!(
+                        "Got {} args to EMBED/{}, expected 3",
+                        args.len(),
+                        f_name
+                    ))
+                } else {
+                    let frac_bits = Self::builtin_usize_arg(f_name, args.pop().unwrap())?;
+                    let int_bits = Self::builtin_usize_arg(f_name, args.pop().unwrap())?;
+                    if f_name == "fixed_from_uint" {
+                        fixed_from_int(args.pop().unwrap(), int_bits, frac_bits)
+                    } else {
+                        fixed_from_field(args.pop().unwrap(), int_bits, frac_bits)
+                    }
+                }
+            }
+            "fixed_from_ratio" => {
+                if args.len() != 4 {
+                    Err(format!(
+                        "Got {} args to EMBED/fixed_from_ratio, expected 4",
+                        args.len()
+                    ))
+                } else {
+                    let frac_bits = Self::builtin_usize_arg(f_name, args.pop().unwrap())?;
+                    let int_bits = Self::builtin_usize_arg(f_name, args.pop().unwrap())?;
+                    let den = const_int(args.pop().unwrap())?;
+                    let num = const_int(args.pop().unwrap())?;
+                    fixed_ratio_lit(num, den, int_bits, frac_bits)
+                }
+            }
+            "fixed_to_uint" | "fixed_to_field" => {
+                if args.len() != 1 {
+                    Err(format!(
+                        "Got {} args to EMBED/{}, expected 1",
+                        args.len(),
+                        f_name
+                    ))
+                } else if f_name == "fixed_to_uint" {
+                    fixed_to_uint(args.pop().unwrap())
+                } else {
+                    fixed_to_field(args.pop().unwrap())
+                }
+            }
+            "fixed_rescale" => {
+                if args.len() != 4 {
+                    Err(format!(
+                        "Got {} args to EMBED/fixed_rescale, expected 4",
+                        args.len()
+                    ))
+                } else {
+                    let mode = Rounding::from_int(&const_int(args.pop().unwrap())?)?;
+                    let frac_bits = Self::builtin_usize_arg(f_name, args.pop().unwrap())?;
+                    let int_bits = Self::builtin_usize_arg(f_name, args.pop().unwrap())?;
+                    fixed_rescale(args.pop().unwrap(), int_bits, frac_bits, mode)
+                }
+            }
             _ => Err(format!("Unknown or unimplemented builtin '{f_name}'")),
         }
     }
 
+    // The overflow condition of a call to EMBED/fixed_rescale, whose bad
+    // arguments are left to `builtin_call` to report
+    fn builtin_rescale_in_range(args: &[PyTerm]) -> Result<Option<Term>, String> {
+        let [a, int_bits, frac_bits, mode] = args else { return Ok(None) };
+        let mode = Rounding::from_int(&const_int(mode.clone())?)?;
+        let frac_bits = Self::builtin_usize_arg("fixed_rescale", frac_bits.clone())?;
+        let int_bits = Self::builtin_usize_arg("fixed_rescale", int_bits.clone())?;
+        fixed_rescale_in_range(a, int_bits, frac_bits, mode).map(Some)
+    }
+
+    fn builtin_usize_arg(f_name: &str, arg: PyTerm) -> Result<usize, String> {
+        const_int(arg)?
+            .to_usize()
+            .ok_or_else(|| format!("builtin_call failed to convert {f_name}'s argument to usize"))
+    }
+
+    // Errors are reported at `s`, the span of the assignment
     fn assign_impl_<const IS_CNST: bool>(
         &self,
         name: &str,
         target: Option<ast::Expr>,
         val: PyTerm,
         strict: bool,
-    ) -> Result<(), String> {
+        s: &TextRange,
+    ) -> Result<(), Diagnostic> {
         let pytargets: Vec<PyTarget>;
         if let Some(t) = target {
-            pytargets = self.pytargets_impl_::<IS_CNST>(&t).unwrap();
+            pytargets = self.pytargets_impl_::<IS_CNST>(&t)?;
         } else {
             pytargets = Vec::new();
         }
         let old = if IS_CNST {
-            self.cvar_lookup(name)
-                .ok_or_else(|| format!("Assignment failed: no const variable {name}"))?
+            self.cvar_lookup(name).ok_or_else(|| {
+                self.err(ErrorCode::UndefinedName, format!("Assignment failed: no const variable {name}"), s)
+            })?
         } else {
-            self.circ_get_value(Loc::local(name.to_string()))
-                .map_err(|e| format!("{e}"))?
+            self.at(self.circ_get_value(Loc::local(name.to_string())), ErrorCode::UndefinedName, s)?
                 .unwrap_term()
         };
-        let new =
-            loc_store(old, &pytargets[..], val)
-                .and_then(|n| if strict { const_val(n) } else { Ok(n) })?;
+        let new = loc_store(old, &pytargets[..], val, self.is_ram_array(name))
+            .and_then(|n| if strict { const_val(n) } else { Ok(n) });
+        let new = self.at(new, ErrorCode::TypeMismatch, s)?;
         debug!("Assign: {}", name);
         if IS_CNST {
-            self.cvar_assign(name, new)
+            self.at(self.cvar_assign(name, new), ErrorCode::UndefinedName, s)
         } else {
-            self.circ_assign(Loc::local(name.to_string()), Val::Term(new))
-                .map_err(|e| format!("{e}"))
-                .map(|_| ())
+            let r = self.circ_assign(Loc::local(name.to_string()), Val::Term(new));
+            self.at(r, ErrorCode::TypeMismatch, s).map(|_| ())
         }
     }
 
     fn pytargets_impl_<const IS_CNST: bool>(
         &self,
         target: &ast::Expr,
-    ) -> Result<Vec<PyTarget>, String> {
+    ) -> Result<Vec<PyTarget>, Diagnostic> {
         match target {
             ast::Expr::Subscript(s) => {
                 let mut target: Vec<PyTarget> = self.pytargets_impl_::<IS_CNST>(&s.value)?;
@@ -415,82 +853,89 @@ impl<'a> PyGen<'a> {
                 Ok(vec)
             }
             err => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::TypeMismatch,
                     format!("Only arrays and object members can be accessed."),
                     &err.range(),
-                )
+                ))
             }
         }
     }
 
-    fn literal_(&self, e: &Literal) -> Result<PyTerm, String> {
+    fn literal_(&self, e: &Literal) -> Result<PyTerm, Diagnostic> {
         match &e {
             Literal::PyLiteral(c) => match &c.value {
                 ast::Constant::None => {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!(
                             "There is no support yet for None literals.",
                         ),
                         &c.range()
-                    )
+                    ))
                 }
                 ast::Constant::Bool(v) => Ok(py_bool_lit(*v)),
                 ast::Constant::Str(_) => {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!(
                             "There is no support yet for Str literals.",
                         ),
                         &c.range()
-                    )
+                    ))
                 }
                 ast::Constant::Bytes(_) => {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!(
                             "There is no support yet for Bytes literals.",
                         ),
                         &c.range()
-                    )
+                    ))
                 }
                 ast::Constant::Int(v) => {
                     let v_trunc  = v.to_u32_digits().1.into_iter().next().unwrap_or(0);
                     Ok(uint_lit(v_trunc, 32))
                 }
                 ast::Constant::Tuple(_) => {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!(
                             "There is no support yet for Tuple literals.",
                         ),
                         &c.range()
-                    )
+                    ))
                 }
                 ast::Constant::Float(_) => {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!(
                             "There is no support yet for Float literals.",
                         ),
                         &c.range()
-                    )
+                    ))
                 }
                 ast::Constant::Complex { real, imag } => {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!(
                             "There is no support yet for Complex literals.",
                         ),
                         &c.range()
-                    )
+                    ))
                 }
                 ast::Constant::Ellipsis => {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!(
                             "There is no support yet for Ellipsis literals.",
                         ),
                         &c.range()
-                    )
+                    ))
                 }
             }
             Literal::Field(val, range) => Ok(field_lit(Integer::from_str_radix(val, 10).unwrap())),
         }
-        .map_err(|err: String| format!("{err}"))
     }
 
     fn unary_op(&self, o: &ast::UnaryOp) -> fn(PyTerm) -> Result<PyTerm, String> {
@@ -544,12 +989,6 @@ impl<'a> PyGen<'a> {
         }
     }
 
-    fn cmp_ops(&self, o: &Vec<ast::CmpOp>) -> Vec<fn(PyTerm, PyTerm) -> Result<PyTerm, String>> {
-        o
-            .iter()
-            .map(|op| self.cmp_op(op))
-            .collect()
-    }
     
     fn file_stack_push(&self, path: PathBuf) {
         self.file_stack.borrow_mut().push(path);
@@ -574,15 +1013,16 @@ impl<'a> PyGen<'a> {
         }
     }
  
-    fn function_param_type(&self, e: &ast::Arg) -> Result<ast::Expr, String> {
+    fn function_param_type(&self, e: &ast::Arg) -> Result<ast::Expr, Diagnostic> {
         match &e.annotation {
             Some(s) => Ok(*s.clone()),
-            None => self.err(
+            None => Err(self.err(
+                ErrorCode::InvalidAnnotation,
                 format!(
                     "Missing argument type.",
                 ),
                 &e.range()
-            ),
+            )),
         }
     }
 
@@ -592,7 +1032,24 @@ impl<'a> PyGen<'a> {
         exp_ty: Option<Ty>,
         f_path: PathBuf,
         f_name: String,
-    ) -> Result<PyTerm, String> {
+        s: &TextRange,
+    ) -> Result<PyTerm, Diagnostic> {
+        self.call_impl_::<IS_CNST>(args, exp_ty, f_path, f_name, false, s)
+            .map(|(ret, _)| ret)
+    }
+
+    /// Call a function, and if `ret_self`, also return the final value of
+    /// its first parameter (the `self` of a method). Errors of the call
+    /// itself are reported at `s`, the span of the call.
+    fn call_impl_<const IS_CNST: bool>(
+        &self,
+        args: Vec<PyTerm>,
+        exp_ty: Option<Ty>,
+        f_path: PathBuf,
+        f_name: String,
+        ret_self: bool,
+        s: &TextRange,
+    ) -> Result<(PyTerm, Option<PyTerm>), Diagnostic> {
         if IS_CNST {
             debug!("Const function call: {} {:?}", f_name, f_path);
         } else {
@@ -602,157 +1059,653 @@ impl<'a> PyGen<'a> {
         let f = self
             .functions
             .get(&f_path)
-            .ok_or_else(|| format!("No file '{:?}' attempting fn call", &f_path))?
+            .ok_or_else(|| self.err(ErrorCode::UndefinedName, format!("No file '{:?}' attempting fn call", &f_path), s))?
             .get(&f_name)
-            .ok_or_else(|| format!("No function '{}' attempting fn call", &f_name))?;
-
-        let prev_func_call = self.curr_func.clone();
-        self.curr_func.borrow_mut().replace_range(.., &f_name);
-
-        let arg_tys = args.iter().map(|arg| arg.type_().clone());
+            .ok_or_else(|| self.err(ErrorCode::UndefinedName, format!("No function '{}' attempting fn call", &f_name), s))?;
 
         if self.stdlib.is_embed(&f_path) {
-            Self::builtin_call(&f_name, args)
+            let in_range = match f_name.as_str() {
+                "fixed_rescale" => self.at(Self::builtin_rescale_in_range(&args), ErrorCode::TypeMismatch, s)?,
+                _ => None,
+            };
+            let ret = self.at(Self::builtin_call(&f_name, args), ErrorCode::TypeMismatch, s)?;
+            if let Some(in_range) = in_range {
+                self.assert_fixed_in_range::<IS_CNST>(in_range, &f_name, &ret.ty, s)?;
+            }
+            Ok((ret, None))
         } else {
-            assert!(self.function_ret_type(&f).len() <= 1);
             if f.args.args.len() != args.len() {
-                return Err(format!(
-                    "Wrong number of arguments calling {} (got {}, expected {})",
-                    &f.name.as_str(),
-                    args.len(),
-                    f.args.args.len()
+                return Err(self.err(
+                    ErrorCode::Arity,
+                    format!(
+                        "Wrong number of arguments calling {} (got {}, expected {})",
+                        &f.name.as_str(),
+                        args.len(),
+                        f.args.args.len()
+                    ),
+                    s,
                 ));
             }
 
+            if self.call_stack.borrow().len() >= self.max_inline_depth {
+                return Err(self.err(
+                    ErrorCode::NotConstant,
+                    format!(
+                        "Maximum inlining depth {} exceeded calling {}; recursion must stop on a compile-time constant argument (call chain: {})",
+                        self.max_inline_depth,
+                        &f_name,
+                        call_chain(&self.call_stack.borrow()),
+                    ),
+                    s,
+                ));
+            }
+
+            // errors of the call itself belong to the caller, so only switch
+            // to the callee now, and back on every exit
             let f = f.clone();
+            let caller = self.curr_func.replace(f_name.clone());
             self.file_stack_push(f_path);
-            self.ret_ty_stack_push::<IS_CNST>(&f)?;
+            self.call_stack.borrow_mut().push(f_name.clone());
+            let res = self.inline_impl_::<IS_CNST>(&f, f_name, args, ret_self);
+            self.call_stack.borrow_mut().pop();
+            self.file_stack_pop();
+            *self.curr_func.borrow_mut() = caller;
+            let (ret, new_self) = res?;
+
+            self.maybe_garbage_collect();
+            Ok((ret, new_self))
+        }
+    }
 
-            // multi-return unimplemented
-            let ret_ty = self.function_ret_type(&f)
-                .first()
+    /// Inline the body of `f`, in the file defining it. Whatever this pushes
+    /// on the stacks of the generator is popped again, also on errors.
+    fn inline_impl_<const IS_CNST: bool>(
+        &self,
+        f: &ast::StmtFunctionDef,
+        f_name: String,
+        args: Vec<PyTerm>,
+        ret_self: bool,
+    ) -> Result<(PyTerm, Option<PyTerm>), Diagnostic> {
+        // Calls are inlined, so each call instantiates the size parameters anew
+        let mut generics = HashMap::new();
+        for (p, a) in f.args.args.iter().zip(&args) {
+            let annotation = self.function_param_type(&p.def)?;
+            let r = self.infer_generics(&annotation, a.type_(), &mut generics);
+            self.at(r, ErrorCode::TypeMismatch, &annotation.range())?;
+        }
+        self.generics.borrow_mut().push(generics);
+        // the parameter and return types may refer to the size parameters
+        let tys = (|| -> Result<_, Diagnostic> {
+            let params = f
+                .args
+                .args
+                .iter()
+                .map(|p| {
+                    let annotation = self.function_param_type(&p.def)?;
+                    Ok((self.type_impl_::<IS_CNST>(&annotation)?, annotation))
+                })
+                .collect::<Result<Vec<_>, Diagnostic>>()?;
+            // multiple return values are returned as a tuple
+            let ret_ty = f.returns
+                .as_deref()
                 .map(|r| self.type_impl_::<IS_CNST>(r))
                 .transpose()?;
-            let ret_ty = if IS_CNST {
-                self.cvar_enter_function();
-                ret_ty
-            } else {
-                self.circ_enter_fn(f_name, ret_ty);
-                None
-            };
+            Ok((params, ret_ty))
+        })();
+        let (params, ret_ty) = match tys {
+            Ok(tys) => tys,
+            Err(d) => {
+                self.generics.borrow_mut().pop();
+                return Err(d);
+            }
+        };
+        self.ret_ty_stack.borrow_mut().push(ret_ty.clone().unwrap_or(Ty::Bool));
+        let crets = self.crets_stack.borrow().len();
+        if IS_CNST {
+            self.cvar_enter_function();
+        } else {
+            self.circ_enter_fn(f_name, ret_ty.clone());
+        }
 
-            for (p, a) in f.args.args.into_iter().zip(args) {
-                let ty = self.type_impl_::<IS_CNST>(&self.function_param_type(&p.def)?)?;
-                if IS_CNST {
-                    self.cvar_declare_init(p.def.arg.to_string(), &ty, a)?;
-                } else {
-                    self.circ_declare_init(p.def.arg.to_string(), ty, Val::Term(a))
-                        .map_err(|e| format!("{e}"))?;
-                }
+        let self_name = f.args.args.first().map(|p| p.def.arg.to_string());
+        let res = (|| -> Result<_, Diagnostic> {
+            for ((p, a), (ty, annotation)) in f.args.args.iter().zip(args).zip(params) {
+                self.declare_ram_array(p.def.arg.as_str(), &annotation);
+                let r = self.declare_init_impl_::<IS_CNST>(p.def.arg.to_string(), ty, a);
+                self.at(r, ErrorCode::TypeMismatch, &p.range())?;
             }
 
-            for s in &f.body {
-                self.stmt_impl_::<IS_CNST>(s)?;
+            self.stmts_impl_::<IS_CNST>(&f.body)?;
+            if IS_CNST && f.returns.is_none() && self.crets_stack.borrow().len() == crets {
+                // a method that only mutates `self`
+                self.crets_push(py_bool_lit(false));
             }
 
-            let ret = if IS_CNST {
-                self.cvar_exit_function();
-                self.crets_pop()
-            } else {
-                self.circ_exit_fn()
-                    .map(|a| a.unwrap_term())
-                    .unwrap_or_else(|| py_bool_lit(false))
-            };
+            match self_name.filter(|_| ret_self) {
+                Some(name) if IS_CNST => self
+                    .cvar_lookup(&name)
+                    .map(Some)
+                    .ok_or_else(|| self.err(ErrorCode::Internal, format!("No const variable {name} at method exit"), &f.range())),
+                Some(name) => {
+                    let r = self.circ_get_value(Loc::local(name));
+                    self.at(r, ErrorCode::Internal, &f.range()).map(|v| Some(v.unwrap_term()))
+                }
+                None => Ok(None),
+            }
+        })();
 
-            self.ret_ty_stack_pop();
-            self.file_stack_pop();
+        let ret = if IS_CNST {
+            self.cvar_exit_function();
+            let ret = (self.crets_stack.borrow().len() > crets).then(|| self.crets_pop());
+            self.crets_stack.borrow_mut().truncate(crets);
+            ret
+        } else {
+            self.circ_exit_fn().map(|a| a.unwrap_term())
+        };
+        self.ret_ty_stack_pop();
+        self.generics.borrow_mut().pop();
+        let new_self = res?;
+        let ret = ret.unwrap_or_else(|| py_bool_lit(false));
 
-            if IS_CNST {
-                let ret_ty = ret_ty.unwrap_or(Ty::Bool);
-                if ret.type_() != &ret_ty {
-                    return Err(format!(
+        if IS_CNST {
+            let ret_ty = ret_ty.unwrap_or(Ty::Bool);
+            if ret.type_() != &ret_ty {
+                return Err(self.err(
+                    ErrorCode::TypeMismatch,
+                    format!(
                         "Return type mismatch: expected {}, got {}",
                         ret_ty,
                         ret.type_()
-                    ));
-                }
+                    ),
+                    &f.returns.as_deref().map_or(f.range(), |r| r.range()),
+                ));
             }
-
-            self.curr_func.borrow_mut().replace_range(.., prev_func_call.borrow().as_str());
-            self.maybe_garbage_collect();
-            Ok(ret)
         }
+        Ok((ret, new_self))
     }
 
-    fn maybe_garbage_collect(&self) {
-        let est = self.gc_depth_estimate.get();
-        let cur = self.file_stack_depth();
-        if GC_INC * cur < est {
-            if maybe_garbage_collect() {
-                // we ran the GC and it did something; increase depth at which we run gc by 1 call
-                self.gc_depth_estimate.set(est + GC_INC);
-            } else {
-                // otherwise, decrease depth at which we run gc by one call
-                self.gc_depth_estimate.set(est.saturating_sub(GC_INC));
+    /// A call `recv.name(...)` of a method, where mutations of `self` are
+    /// written back to `recv`, or a call `Class.name(...)` with all
+    /// arguments explicit
+    fn method_call_impl_<const IS_CNST: bool>(
+        &self,
+        c: &ast::ExprCall,
+        a: &ast::ExprAttribute,
+    ) -> Result<PyTerm, Diagnostic> {
+        let exp_ty = self.lhs_ty_take();
+        let recv = match a.value.as_ref() {
+            ast::Expr::Name(n) => match self.get_class_or_type(n.id.as_str()) {
+                Some((Ok(sdef), path)) => Err((sdef.name.to_string(), path)),
+                _ => Ok(self.expr_impl_::<IS_CNST>(&a.value)?),
+            },
+            e => Ok(self.expr_impl_::<IS_CNST>(e)?),
+        };
+        let (class, f_path) = match &recv {
+            Ok(r) => match r.type_() {
+                Ty::DataClass(class, _) => {
+                    (class.clone(), self.at(self.class_path(class), ErrorCode::UndefinedName, &a.range())?)
+                }
+                ty => {
+                    return Err(self.err(
+                        ErrorCode::TypeMismatch,
+                        format!("Method call {} on non-Class type {ty}", a.attr.as_str()),
+                        &a.range(),
+                    ))
+                }
+            },
+            Err((class, path)) => (class.clone(), path.clone()),
+        };
+        let is_static = self
+            .methods
+            .get(&(f_path.clone(), class.clone()))
+            .and_then(|m| m.get(a.attr.as_str()))
+            .copied()
+            .ok_or_else(|| {
+                self.err(
+                    ErrorCode::UndefinedName,
+                    format!("No method {} of class {class}", a.attr.as_str()),
+                    &a.range(),
+                )
+            })?;
+        let f_name = format!("{class}.{}", a.attr.as_str());
+        let args = c
+            .args
+            .iter()
+            .map(|e| self.expr_impl_::<IS_CNST>(e))
+            .collect::<Result<Vec<_>, _>>()?;
+        let recv = match recv {
+            Ok(r) if !is_static => r,
+            _ => return self.function_call_impl_::<IS_CNST>(args, exp_ty, f_path, f_name, &c.range()),
+        };
+        let args = std::iter::once(recv.clone()).chain(args).collect();
+        let (ret, new_recv) = self.call_impl_::<IS_CNST>(args, exp_ty, f_path, f_name, true, &c.range())?;
+        let new_recv = new_recv.unwrap();
+        if new_recv.term != recv.term {
+            if !is_lvalue(&a.value) {
+                return Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Method {} mutates self, so it must be called on a variable.", a.attr.as_str()),
+                    &a.value.range(),
+                ));
             }
-        } else {
-            // we didn't try to run the GC; just gradually increase the depth at which we'll run the gc
-            let est_inc = (GC_INC * cur - est) / GC_INC;
-            self.gc_depth_estimate.set(est + 1 + est_inc);
+            let name = self.get_lhs_name::<IS_CNST>(&a.value)?;
+            self.assign_impl_::<IS_CNST>(name.id.as_str(), Some(*a.value.clone()), new_recv, false, &c.range())?;
         }
+        Ok(ret)
     }
 
-    fn const_entry_fn(&self, n: &str) -> PyTerm {
-        debug!("Const entry: {}", n);
-        let (f_file, f_name) = self.deref_import(n);
-        if let Some(f) = self.functions.get(&f_file).and_then(|m| m.get(&f_name)) {
-            if !f.args.args.is_empty() {
-                panic!("const_entry_fn must be called on a function with zero arguments")
+    /// The builtins `map`, `reduce`, `all`, `any`, `min`, `max` and `sorted`,
+    /// unrolled over arrays. Their function arguments are lambdas or names
+    /// of functions, which are inlined at each application.
+    fn higher_order_impl_<const IS_CNST: bool>(
+        &self,
+        f_name: &str,
+        c: &ast::ExprCall,
+    ) -> Result<PyTerm, Diagnostic> {
+        self.lhs_ty_take();
+        let s = &c.range();
+        let array = |e: &ast::Expr| -> Result<Vec<PyTerm>, Diagnostic> {
+            let a = self.expr_impl_::<IS_CNST>(e)?;
+            match &a.ty {
+                Ty::Array(..) => self.at(a.unwrap_array(), ErrorCode::TypeMismatch, &e.range()),
+                ty => Err(self.err(ErrorCode::TypeMismatch, format!("{f_name} expected an array, got {ty}"), &e.range())),
+            }
+        };
+        let mut reverse = false;
+        for k in &c.keywords {
+            match k.arg.as_ref().map(|a| a.as_str()) {
+                Some("reverse") if f_name == "sorted" => {
+                    reverse = const_bool(self.expr_impl_::<IS_CNST>(&k.value)?).ok_or_else(|| {
+                        self.err(
+                            ErrorCode::NotConstant,
+                            "The reverse argument of sorted must be a constant bool",
+                            &k.value.range(),
+                        )
+                    })?;
+                }
+                _ => {
+                    return Err(self.err(
+                        ErrorCode::Unsupported,
+                        format!("Unsupported keyword argument of {f_name}."),
+                        &k.range(),
+                    ))
+                }
             }
-        } else {
-            panic!(
-                "No function '{:?}//{}' attempting const_entry_fn",
-                &f_file, &f_name
-            );
         }
-
-        self.function_call_impl_::<true>(Vec::new(), None, f_file, f_name)
-            .unwrap_or_else(|e| panic!("const_entry_fn failed: {}", e))
+        match (f_name, &c.args[..]) {
+            ("map", [f, arrays @ ..]) if !arrays.is_empty() => {
+                let f = self.callable(f_name, f)?;
+                let arrays = arrays.iter().map(array).collect::<Result<Vec<_>, _>>()?;
+                let n = arrays[0].len();
+                if arrays.iter().any(|a| a.len() != n) {
+                    return Err(self.err(ErrorCode::TypeMismatch, "map over arrays of different sizes", s));
+                }
+                let out = (0..n)
+                    .map(|i| self.apply_impl_::<IS_CNST>(&f, arrays.iter().map(|a| a[i].clone()).collect(), s))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.at(PyTerm::new_array(out), ErrorCode::TypeMismatch, s)
+            }
+            ("reduce", [f, xs, init @ ..]) if init.len() <= 1 => {
+                let f = self.callable(f_name, f)?;
+                let mut xs = array(xs)?.into_iter();
+                let init = match init.first() {
+                    Some(e) => self.expr_impl_::<IS_CNST>(e)?,
+                    None => xs.next().ok_or_else(|| {
+                        self.err(ErrorCode::OutOfBounds, "reduce of an empty array with no initial value", s)
+                    })?,
+                };
+                xs.try_fold(init, |acc, x| self.apply_impl_::<IS_CNST>(&f, vec![acc, x], s))
+            }
+            ("all", [xs]) => {
+                let r = array(xs)?.into_iter().try_fold(py_bool_lit(true), and);
+                self.at(r, ErrorCode::TypeMismatch, s)
+            }
+            ("any", [xs]) => {
+                let r = array(xs)?.into_iter().try_fold(py_bool_lit(false), or);
+                self.at(r, ErrorCode::TypeMismatch, s)
+            }
+            ("min" | "max", args) if !args.is_empty() => {
+                // like Python, either one array or several arguments
+                let xs = match args {
+                    [xs] => array(xs)?,
+                    args => args
+                        .iter()
+                        .map(|e| self.expr_impl_::<IS_CNST>(e))
+                        .collect::<Result<Vec<_>, _>>()?,
+                };
+                let op = if f_name == "min" { ast::CmpOp::Lt } else { ast::CmpOp::Gt };
+                let mut xs = xs.into_iter();
+                let first = xs
+                    .next()
+                    .ok_or_else(|| self.err(ErrorCode::OutOfBounds, format!("{f_name} of an empty array"), s))?;
+                // the first of equal elements wins
+                xs.try_fold(first, |m, x| {
+                    let better = self.cmp_op_impl_::<IS_CNST>(&op, x.clone(), m.clone(), s)?;
+                    self.at(cond(better, x, m), ErrorCode::TypeMismatch, s)
+                })
+            }
+            ("sorted", [xs]) => {
+                let mut xs = array(xs)?;
+                for (i, j) in sorting_network(xs.len()) {
+                    let swap = self.cmp_op_impl_::<IS_CNST>(&ast::CmpOp::Lt, xs[j].clone(), xs[i].clone(), s)?;
+                    let lo = self.at(cond(swap.clone(), xs[j].clone(), xs[i].clone()), ErrorCode::TypeMismatch, s)?;
+                    let hi = self.at(cond(swap, xs[i].clone(), xs[j].clone()), ErrorCode::TypeMismatch, s)?;
+                    xs[i] = lo;
+                    xs[j] = hi;
+                }
+                if reverse {
+                    xs.reverse();
+                }
+                self.at(PyTerm::new_array(xs), ErrorCode::TypeMismatch, s)
+            }
+            (_, args) => Err(self.err(
+                ErrorCode::Arity,
+                format!("Wrong number of arguments calling {f_name} (got {})", args.len()),
+                s,
+            )),
+        }
     }
 
-    fn entry_fn(&self, n: &str) {
-        debug!("Entry: {}", n);
+    /// The function argument of the builtin `f_name`
+    fn callable(&self, f_name: &str, e: &ast::Expr) -> Result<Callable, Diagnostic> {
+        match e {
+            ast::Expr::Lambda(l) => Ok(Callable::Lambda(l.clone())),
+            ast::Expr::Name(n) if self.get_function(n.id.as_str()).is_some() => {
+                let (f_path, f_name) = self.deref_import(n.id.as_str());
+                Ok(Callable::Function(f_path, f_name))
+            }
+            e => Err(self.err(
+                ErrorCode::TypeMismatch,
+                format!("{f_name} expected a lambda or the name of a function as its first argument"),
+                &e.range(),
+            )),
+        }
+    }
+
+    /// Apply a lambda or function to `args`. The parameters of a lambda are
+    /// declared in a scope of their own, so its body sees the variables
+    /// around it.
+    fn apply_impl_<const IS_CNST: bool>(
+        &self,
+        f: &Callable,
+        args: Vec<PyTerm>,
+        s: &TextRange,
+    ) -> Result<PyTerm, Diagnostic> {
+        match f {
+            Callable::Function(f_path, f_name) => {
+                self.function_call_impl_::<IS_CNST>(args, None, f_path.clone(), f_name.clone(), s)
+            }
+            Callable::Lambda(l) => {
+                if l.args.args.len() != args.len() {
+                    return Err(self.err(
+                        ErrorCode::Arity,
+                        format!(
+                            "Wrong number of arguments calling lambda (got {}, expected {})",
+                            args.len(),
+                            l.args.args.len()
+                        ),
+                        &l.range(),
+                    ));
+                }
+                self.enter_scope_impl_::<IS_CNST>();
+                let res = l
+                    .args
+                    .args
+                    .iter()
+                    .zip(args)
+                    .try_for_each(|(p, a)| {
+                        let r = self.declare_init_impl_::<IS_CNST>(p.def.arg.to_string(), a.ty.clone(), a);
+                        self.at(r, ErrorCode::TypeMismatch, &p.range())
+                    })
+                    .and_then(|_| self.expr_impl_::<IS_CNST>(&l.body));
+                self.exit_scope_impl_::<IS_CNST>();
+                res
+            }
+        }
+    }
+
+    /// The dunder method `name` of the class of `t`, if any
+    fn dunder(&self, t: &PyTerm, name: &str) -> Option<(PathBuf, String)> {
+        let Ty::DataClass(class, _) = t.type_() else {
+            return None;
+        };
+        let path = self.class_path(class).ok()?;
+        self.methods
+            .get(&(path.clone(), class.clone()))
+            .and_then(|m| m.get(name))
+            .filter(|is_static| !**is_static)
+            .map(|_| (path, format!("{class}.{name}")))
+    }
+
+    /// The file defining the class of a value of class `class`: the class of
+    /// that name in scope, or else the only file with methods for a class of
+    /// that name
+    fn class_path(&self, class: &str) -> Result<PathBuf, String> {
+        if let Some((Ok(_), path)) = self.get_class_or_type(class) {
+            return Ok(path);
+        }
+        let mut paths = self.methods.keys().filter(|(_, c)| c == class);
+        match (paths.next(), paths.next()) {
+            (Some((path, _)), None) => Ok(path.clone()),
+            (Some(_), Some(_)) => Err(format!(
+                "Class {class} is defined in several modules; bring it into scope to call its methods"
+            )),
+            (None, _) => Err(format!("Class {class} has no methods")),
+        }
+    }
+
+    /// A binary operation, or the dunder method of a dataclass left operand
+    fn bin_op_impl_<const IS_CNST: bool>(
+        &self,
+        o: &ast::Operator,
+        left: PyTerm,
+        right: PyTerm,
+        s: &TextRange,
+    ) -> Result<PyTerm, Diagnostic> {
+        match self.dunder(&left, bin_op_dunder(o)) {
+            Some((f_path, f_name)) => {
+                self.function_call_impl_::<IS_CNST>(vec![left, right], None, f_path, f_name, s)
+            }
+            None => {
+                let res = self.at(self.bin_op(o)(left.clone(), right.clone()), ErrorCode::TypeMismatch, s)?;
+                let name = match o {
+                    ast::Operator::Add => "+",
+                    ast::Operator::Sub => "-",
+                    ast::Operator::Mult => "*",
+                    ast::Operator::Div | ast::Operator::FloorDiv => "/",
+                    _ => return Ok(res),
+                };
+                if let Some(in_range) = self.at(fixed_in_range(name, left, right), ErrorCode::TypeMismatch, s)? {
+                    self.assert_fixed_in_range::<IS_CNST>(in_range, name, &res.ty, s)?;
+                }
+                Ok(res)
+            }
+        }
+    }
+
+    /// Fixed-point arithmetic wraps around, so overflows of the operation
+    /// `name` are asserted away, or reported if they are certain in const mode
+    fn assert_fixed_in_range<const IS_CNST: bool>(
+        &self,
+        in_range: Term,
+        name: &str,
+        ty: &Ty,
+        s: &TextRange,
+    ) -> Result<(), Diagnostic> {
+        match const_bool(PyTerm::new(Ty::Bool, in_range.clone())) {
+            Some(false) if IS_CNST => Err(self.err(
+                ErrorCode::OutOfBounds,
+                format!("Fixed-point overflow in '{name}' on {ty}"),
+                s,
+            )),
+            Some(true) => Ok(()),
+            _ => {
+                self.assert(in_range);
+                Ok(())
+            }
+        }
+    }
+
+    /// A comparison, or the dunder method of a dataclass left operand
+    fn cmp_op_impl_<const IS_CNST: bool>(
+        &self,
+        o: &ast::CmpOp,
+        left: PyTerm,
+        right: PyTerm,
+        s: &TextRange,
+    ) -> Result<PyTerm, Diagnostic> {
+        if let Some(name) = cmp_op_dunder(o) {
+            if let Some((f_path, f_name)) = self.dunder(&left, name) {
+                return self.function_call_impl_::<IS_CNST>(vec![left, right], None, f_path, f_name, s);
+            }
+            // like Python, `!=` defaults to the negation of `__eq__`
+            if let (ast::CmpOp::NotEq, Some((f_path, f_name))) = (o, self.dunder(&left, "__eq__")) {
+                let eq = self.function_call_impl_::<IS_CNST>(vec![left, right], None, f_path, f_name, s)?;
+                return self.at(not(eq), ErrorCode::TypeMismatch, s);
+            }
+        }
+        self.at(self.cmp_op(o)(left, right), ErrorCode::TypeMismatch, s)
+    }
+
+    /// Bind the size parameters of a parameter type `annotation` to the sizes
+    /// in the argument type `ty`. A size parameter is an array size that is a
+    /// name, but not that of a module constant.
+    fn infer_generics(
+        &self,
+        annotation: &ast::Expr,
+        ty: &Ty,
+        bindings: &mut HashMap<String, usize>,
+    ) -> Result<(), String> {
+        let ast::Expr::Subscript(s) = annotation else {
+            return Ok(());
+        };
+        let ast::Expr::Name(n) = s.value.as_ref() else {
+            return Ok(());
+        };
+        match (n.id.as_str(), s.slice.as_ref(), ty) {
+            ("Private" | "Public" | "Committed", inner, _) => {
+                self.infer_generics(inner, ty, bindings)
+            }
+            ("Array", ast::Expr::Tuple(t), Ty::Array(size, elem)) if t.elts.len() >= 2 => {
+                if let ast::Expr::Name(p) = &t.elts[1] {
+                    if !self.const_defined(p.id.as_str()) {
+                        match bindings.insert(p.id.to_string(), *size) {
+                            Some(prev) if prev != *size => {
+                                return Err(format!(
+                                    "Size parameter {} is both {} and {}",
+                                    p.id.as_str(),
+                                    prev,
+                                    size
+                                ));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                self.infer_generics(&t.elts[0], elem, bindings)
+            }
+            ("Tuple" | "tuple", ast::Expr::Tuple(t), Ty::Tuple(tys)) if t.elts.len() == tys.len() => {
+                t.elts
+                    .iter()
+                    .zip(tys)
+                    .try_for_each(|(e, ty)| self.infer_generics(e, ty, bindings))
+            }
+            // mismatched types are reported when the parameter is declared
+            _ => Ok(()),
+        }
+    }
+
+    fn generic_lookup(&self, i: &str) -> Option<PyTerm> {
+        self.generics
+            .borrow()
+            .last()
+            .and_then(|g| g.get(i))
+            .map(|n| uint_lit(*n, 32))
+    }
+
+    fn maybe_garbage_collect(&self) {
+        let est = self.gc_depth_estimate.get();
+        let cur = self.file_stack_depth();
+        if GC_INC * cur < est {
+            if maybe_garbage_collect() {
+                // we ran the GC and it did something; increase depth at which we run gc by 1 call
+                self.gc_depth_estimate.set(est + GC_INC);
+            } else {
+                // otherwise, decrease depth at which we run gc by one call
+                self.gc_depth_estimate.set(est.saturating_sub(GC_INC));
+            }
+        } else {
+            // we didn't try to run the GC; just gradually increase the depth at which we'll run the gc
+            let est_inc = (GC_INC * cur - est) / GC_INC;
+            self.gc_depth_estimate.set(est + 1 + est_inc);
+        }
+    }
+
+    fn const_entry_fn(&self, n: &str) -> Result<PyTerm, Diagnostic> {
+        debug!("Const entry: {}", n);
+        let (f_file, f_name) = self.deref_import(n);
+        let f = self.entry_def(&f_file, &f_name)?;
+        if !f.args.args.is_empty() {
+            return Err(self.err(
+                ErrorCode::Arity,
+                format!("The entry function {f_name} of a compile-time evaluation must take no arguments."),
+                &f.range(),
+            ));
+        }
+        let range = f.range();
+        self.function_call_impl_::<true>(Vec::new(), None, f_file, f_name, &range)
+    }
+
+    // The definition of the entry function, which is reported missing at
+    // the start of the entry file
+    fn entry_def(&self, f_file: &PathBuf, f_name: &str) -> Result<&ast::StmtFunctionDef, Diagnostic> {
+        self.functions
+            .get(f_file)
+            .and_then(|m| m.get(f_name))
+            .ok_or_else(|| {
+                self.err(
+                    ErrorCode::UndefinedName,
+                    format!("No entry function {f_name}"),
+                    &TextRange::default(),
+                )
+            })
+    }
+
+    fn entry_fn(&self, n: &str) -> Result<(), Diagnostic> {
+        debug!("Entry: {}", n);
         // find the entry function
         let (f_file, f_name) = self.deref_import(n);
         let prev_func_call = self.curr_func.clone();
         self.curr_func.borrow_mut().replace_range(.., &f_name);
-        let f = self
-            .functions
-            .get(&f_file)
-            .unwrap_or_else(|| panic!("No file '{:?}'", &f_file))
-            .get(&f_name)
-            .unwrap_or_else(|| panic!("No function '{}'", &f_name))
-            .clone();
-        // tuple returns not supported
-        assert!(self.function_ret_type(&f).len() <= 1);
-        // get return type
-        let ret_ty = self.function_ret_type(&f).first().map(|r| self.type_(r));
+        self.call_stack.borrow_mut().push(f_name.clone());
+        let f = self.entry_def(&f_file, &f_name)?.clone();
+        // get return type (multiple return values are returned as a tuple)
+        let ret_ty = f.returns.as_deref().map(|r| self.type_impl_::<false>(r)).transpose()?;
         // set up stack frame for entry function
         self.circ_enter_fn(n.to_owned(), ret_ty.clone());
         let mut persistent_arrays: Vec<String> = Vec::new();
-        for p in f.args.args.iter() {
-            let ty = self.type_(&self.function_param_type(&p.def).unwrap_or_else(|a| panic!("{a}")));
+        if let Mode::Ivc = self.mode {
+            self.check_ivc_step(&f, ret_ty.as_ref())?;
+        }
+        for (i, p) in f.args.args.iter().enumerate() {
+            let annotation = self.function_param_type(&p.def)?;
+            let ty = self.type_impl_::<false>(&annotation)?;
+            self.declare_ram_array(p.def.arg.as_str(), &annotation);
             debug!("Entry param: {}: {}", p.def.arg.as_str(), ty);
-            let vis = self.interpret_visibility(&p.def);
+            let vis = match self.mode {
+                // the state is carried between steps, the rest is per-step advice
+                Mode::Ivc if i == 0 => PyVis::Public,
+                Mode::Ivc => PyVis::Private(PROVER_ID),
+                _ => self.interpret_visibility(&p.def)?,
+            };
             if let PyVis::Committed = &vis {
                 persistent_arrays.push(p.def.arg.to_string());
             }
             let r = self.circ_declare_input(p.def.arg.to_string(), &ty, vis, None, false);
-            self.unwrap(r, &p.def.range());
-        }
-        for s in &f.body {
-            self.unwrap(self.stmt_impl_::<false>(s), &s.range());
+            let input = self.at(r, ErrorCode::TypeMismatch, &p.def.range())?;
+            self.record_span_at(&input.term, self.cur_path(), p.def.range());
         }
+        self.stmts_impl_::<false>(&f.body)?;
         for a in persistent_arrays {
             let term = self
                 .circ_get_value(Loc::local(a.clone()))
@@ -775,7 +1728,7 @@ impl<'a> PyGen<'a> {
                         .outputs
                         .extend(ret_terms);
                 }
-                Mode::Proof => {
+                Mode::Proof | Mode::Ivc => {
                     let ty = ret_ty.as_ref().unwrap();
                     let name = "return".to_owned();
                     let ret_val = r.unwrap_term();
@@ -830,47 +1783,99 @@ impl<'a> PyGen<'a> {
             }
         }
         self.curr_func.borrow_mut().replace_range(.., prev_func_call.borrow().as_str());
+        Ok(())
+    }
+
+    // An IVC step maps the state (its first parameter) to the next state, so
+    // both must have the same type for the step to be iterated.
+    fn check_ivc_step(&self, f: &ast::StmtFunctionDef, ret_ty: Option<&Ty>) -> Result<(), Diagnostic> {
+        let Some(state) = f.args.args.first() else {
+            return Err(self.err(
+                ErrorCode::Arity,
+                "An IVC step function must take the state as its first argument.",
+                &f.range(),
+            ));
+        };
+        let state_ty = self.type_impl_::<false>(&self.function_param_type(&state.def)?)?;
+        match ret_ty {
+            Some(ty) if *ty == state_ty => Ok(()),
+            Some(ty) => Err(self.err(
+                ErrorCode::TypeMismatch,
+                format!(
+                    "The IVC step function returns {}, but its state '{}' has type {}.",
+                    ty, state.def.arg, state_ty
+                ),
+                &f.range(),
+            )),
+            None => Err(self.err(
+                ErrorCode::InvalidAnnotation,
+                "An IVC step function must return the next state.",
+                &f.range(),
+            )),
+        }
     }
 
-    fn interpret_visibility(&self, arg: &ast::Arg) -> PyVis {
+    fn interpret_visibility(&self, arg: &ast::Arg) -> Result<PyVis, Diagnostic> {
         match *arg.annotation.clone().unwrap() {
             ast::Expr::Subscript(e) => if let ast::Expr::Name(n) = *e.clone().value {
                 // in ZoKrates it assumes None is public, however
                 // we have to be strict since we match against an
                 // annotation, which is a string.
                 if n.id.as_str() == "Private" {
-                    PyVis::Private(PROVER_ID)
+                    Ok(PyVis::Private(PROVER_ID))
                 } else if n.id.as_str() == "Committed" {
-                    PyVis::Committed
+                    Ok(PyVis::Committed)
                 } else if n.id.as_str() == "Public" {
-                    PyVis::Public
+                    Ok(PyVis::Public)
                 } else {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::InvalidAnnotation,
                         format!(
                             "Incorrect visibility specifier used",
                         ),
                         &arg.range()
-                    );
+                    ))
                 }
             } else {
                 // if no name is found
-                self.err(
+                Err(self.err(
+                    ErrorCode::InvalidAnnotation,
                     format!(
                         "Incorrect visibility specifier used",
                     ),
                     &arg.range()
-                );
+                ))
             }
             // if not otherwise specified, return error
-            _ => self.err(
+            _ => Err(self.err(
+                ErrorCode::InvalidAnnotation,
                 format!(
                     "Incorrect visibility specifier used",
                 ),
                 &arg.range()
-            )
+            ))
         }
     }
 
+    // A declared variable, function or constant whose name is a likely
+    // misspelling of `name`
+    fn similar_name(&self, name: &str) -> Option<String> {
+        let path = self.cur_path();
+        let known = self.known_names.borrow();
+        let candidates = known
+            .iter()
+            .chain(self.functions.get(&path).into_iter().flat_map(|m| m.keys()).filter(|f| !f.contains('.')))
+            .chain(self.constants.get(&path).into_iter().flat_map(|m| m.keys()))
+            .chain(self.import_map.get(&path).into_iter().flat_map(|m| m.keys()));
+        let max_dist = (name.chars().count() / 3).max(1);
+        candidates
+            .filter(|c| c.as_str() != name)
+            .map(|c| (edit_distance(name, c), c))
+            .filter(|(d, _)| *d <= max_dist)
+            .min()
+            .map(|(_, c)| c.clone())
+    }
+
     fn cur_path(&self) -> PathBuf {
         self.file_stack.borrow().last().unwrap().to_path_buf()
     }
@@ -888,8 +1893,11 @@ impl<'a> PyGen<'a> {
 
     // Returns the source code of the currently loaded file
     fn cur_source_contents(&self) -> String {
-        let p = self.cur_path();
-    
+        self.source_contents(&self.cur_path())
+    }
+
+    // Returns the source code of a loaded file
+    fn source_contents(&self, p: &PathBuf) -> String {
         if let Some(path_str) = p.to_str() {
             if path_str.starts_with('<') && path_str.ends_with('>') {
                 return self.source
@@ -900,8 +1908,8 @@ impl<'a> PyGen<'a> {
             }
         }
     
-        std::fs::read_to_string(&p)
-            .unwrap_or_else(|_| panic!("Failed to read file: {}", normalize_path(&p).unwrap().display()))
+        std::fs::read_to_string(p)
+            .unwrap_or_else(|_| panic!("Failed to read file: {}", normalize_path(p).unwrap().display()))
     }
     
 
@@ -940,59 +1948,96 @@ impl<'a> PyGen<'a> {
     fn identifier_impl_<const IS_CNST: bool>(
         &self,
         i: &ast::ExprName,
-    ) -> Result<PyTerm, String> {
-        match self.const_lookup_(&i.id.as_str()).cloned() {
+    ) -> Result<PyTerm, Diagnostic> {
+        match self
+            .const_lookup_(&i.id.as_str())
+            .cloned()
+            .or_else(|| self.generic_lookup(&i.id.as_str()))
+        {
             Some(v) => Ok(v),
             None if IS_CNST => self.cvar_lookup(&i.id.as_str()).ok_or_else(|| {
-                format!(
-                    "Undefined const identifier {} in {}",
-                    &i.id.as_str(),
-                    normalize_path(&self.cur_path()).unwrap().to_string_lossy()
+                self.err(
+                    ErrorCode::NotConstant,
+                    format!(
+                        "Undefined const identifier {} in {}",
+                        &i.id.as_str(),
+                        normalize_path(&self.cur_path()).unwrap().to_string_lossy()
+                    ),
+                    &i.range(),
                 )
             }),
-            _ => match self
-                .circ_get_value(Loc::local(i.id.to_string()))
-                .map_err(|e| format!("{e}"))?
-            {
-                Val::Term(t) => Ok(t),
-                _ => Err(format!("Non-Term identifier {}", &i.id.as_str())),
+            _ => match self.circ_get_value(Loc::local(i.id.to_string())) {
+                Ok(Val::Term(t)) => Ok(t),
+                Ok(_) => Err(self.err(
+                    ErrorCode::TypeMismatch,
+                    format!("Non-Term identifier {}", &i.id.as_str()),
+                    &i.range(),
+                )),
+                Err(e) => match self.similar_name(i.id.as_str()) {
+                    Some(n) => Err(self.err_help(
+                        ErrorCode::UndefinedName,
+                        format!("Undefined variable {}", i.id.as_str()),
+                        format!("a name with a similar spelling exists: `{n}`"),
+                        &i.range(),
+                    )),
+                    None => Err(self.err(ErrorCode::UndefinedName, e, &i.range())),
+                },
             },
         }
     }
 
-    fn const_isize_impl_<const IS_CNST: bool>(
-        &self,
-        e: &ast::Expr,
-    ) -> Result<isize, String> {
-        const_int(self.expr_impl_::<IS_CNST>(e)?)?
-            .to_isize()
-            .ok_or_else(|| "Constant integer outside isize range".to_string())
+    /// Try to evaluate `e` as a compile-time constant. Returns `None` if the
+    /// evaluation fails with [ErrorCode::NotConstant], e.g. because `e` reads
+    /// a variable, and rolls back any const state the failed evaluation left
+    /// behind; every other error is propagated.
+    fn try_const_impl_(&self, e: &ast::Expr) -> Result<Option<PyTerm>, Diagnostic> {
+        let cvars = self.cvars_stack.borrow().clone();
+        let crets = self.crets_stack.borrow().len();
+        let files = self.file_stack_depth();
+        let calls = self.call_stack.borrow().len();
+        let ret_tys = self.ret_ty_stack.borrow().len();
+        let generics = self.generics.borrow().len();
+        let func = self.curr_func.borrow().clone();
+        match self.expr_impl_::<true>(e) {
+            Ok(v) => Ok(Some(v)),
+            Err(d) => {
+                *self.cvars_stack.borrow_mut() = cvars;
+                self.crets_stack.borrow_mut().truncate(crets);
+                self.file_stack.borrow_mut().truncate(files);
+                self.call_stack.borrow_mut().truncate(calls);
+                self.ret_ty_stack.borrow_mut().truncate(ret_tys);
+                self.generics.borrow_mut().truncate(generics);
+                *self.curr_func.borrow_mut() = func;
+                match d.code {
+                    ErrorCode::NotConstant => Ok(None),
+                    _ => Err(d),
+                }
+            }
+        }
     }
 
     fn const_usize_impl_<const IS_CNST: bool>(
         &self,
         e: &ast::Expr,
-    ) -> Result<usize, String> {
-        const_int(self.expr_impl_::<IS_CNST>(e)?)?
+    ) -> Result<usize, Diagnostic> {
+        let v = self.expr_impl_::<IS_CNST>(e)?;
+        self.at(const_int(v), ErrorCode::NotConstant, &e.range())?
             .to_usize()
-            .ok_or_else(|| "Constant integer outside usize range".to_string())
-    }
-
-    fn const_usize_(&self, e: &ast::Expr) -> Result<usize, String> {
-        self.const_usize_impl_::<true>(e)
+            .ok_or_else(|| self.err(ErrorCode::OutOfBounds, "Constant integer outside usize range", &e.range()))
     }
 
     fn array_access_impl_<const IS_CNST: bool>(
         &self,
         target: &ast::ExprSubscript,
         val: PyTerm,
-    ) -> Result<PyTerm, String> {
+        ram: bool,
+    ) -> Result<PyTerm, Diagnostic> {
         let array_size = if let Ty::Array(s, _) = val.ty {
             s
         } else {
             0
         };
-        match target.slice.as_ref() {
+        let r = match target.slice.as_ref() {
             ast::Expr::Slice(r) => {
                 let s = r
                     .lower
@@ -1014,13 +2059,17 @@ impl<'a> PyGen<'a> {
                     .map(|value| if value <= (u32::MAX / 2) as usize {value as isize} else {(u32::MAX as usize - value + 1) as isize * (-1)});
                 slice(val, s, e, step)
             }
-            _ => {
+            _ if ram => {
                 array_select(val, self.expr_impl_::<IS_CNST>(&target.slice)?)
             }
-        }
+            _ => {
+                array_select_scan(val, self.expr_impl_::<IS_CNST>(&target.slice)?)
+            }
+        };
+        self.at(r, ErrorCode::TypeMismatch, &target.range())
     }
 
-    fn expr_impl_<const IS_CNST: bool>(&self, e: &ast::Expr) -> Result<PyTerm, String> {
+    fn expr_impl_<const IS_CNST: bool>(&self, e: &ast::Expr) -> Result<PyTerm, Diagnostic> {
         if IS_CNST {
             debug!("Const expr range: {:?}", e.range());
         } else {
@@ -1038,61 +2087,77 @@ impl<'a> PyGen<'a> {
                 let final_result = rest.iter().try_fold(init_result.clone(), |prev_result, term| {
                     op(prev_result.clone(), term.clone())
                 });
-                final_result
+                self.at(final_result, ErrorCode::TypeMismatch, &b.range())
             }
             ast::Expr::NamedExpr(n) => {
                 // This is for the famous walrus operator `:=`
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Named expressions are not supported yet."),
                     &n.range(),
-                )
+                ))
             }
             ast::Expr::BinOp(b) => {
                 let left = self.expr_impl_::<IS_CNST>(&b.left)?;
                 let right = self.expr_impl_::<IS_CNST>(&b.right)?;
-                let op = self.bin_op(&b.op);
-                op(left, right)
+                self.bin_op_impl_::<IS_CNST>(&b.op, left, right, &b.range())
             }
             ast::Expr::UnaryOp(u) => {
                 let arg = self.expr_impl_::<IS_CNST>(&u.operand)?;
+                let in_range = match u.op {
+                    ast::UnaryOp::USub => fixed_neg_in_range(&arg),
+                    _ => None,
+                };
                 let op = self.unary_op(&u.op);
-                op(arg)
+                let res = self.at(op(arg), ErrorCode::TypeMismatch, &u.range())?;
+                if let Some(in_range) = in_range {
+                    self.assert_fixed_in_range::<IS_CNST>(in_range, "unary-", &res.ty, &u.range())?;
+                }
+                Ok(res)
             }
             ast::Expr::Lambda(l) => {
-                self.err(
-                    format!("Lambda expressions are not supported yet."),
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Lambda expressions are only supported as arguments of map and reduce."),
                     &l.range(),
-                )
+                ))
             }
             ast::Expr::IfExp(u) => {
-                match self.expr_impl_::<true>(&u.test).ok().and_then(const_bool) {
+                match self.try_const_impl_(&u.test)?.and_then(const_bool) {
                     Some(true) => self.expr_impl_::<IS_CNST>(&u.body),
                     Some(false) => self.expr_impl_::<IS_CNST>(&u.orelse),
-                    None if IS_CNST => Err("ternary condition not const bool".to_string()),
+                    None if IS_CNST => Err(self.err(ErrorCode::NotConstant, "ternary condition not const bool", &u.test.range())),
                     _ => {
                         let c = self.expr_impl_::<false>(&u.test)?;
-                        let cbool = bool(c.clone())?;
+                        match const_bool(c.clone()) {
+                            Some(true) => return self.expr_impl_::<false>(&u.body),
+                            Some(false) => return self.expr_impl_::<false>(&u.orelse),
+                            None => {}
+                        }
+                        let cbool = self.at(bool(c.clone()), ErrorCode::TypeMismatch, &u.test.range())?;
                         self.circ_enter_condition(cbool.clone());
-                        let a = self.expr_impl_::<false>(&u.body)?;
+                        let a = self.expr_impl_::<false>(&u.body);
                         self.circ_exit_condition();
                         self.circ_enter_condition(term![NOT; cbool]);
-                        let b = self.expr_impl_::<false>(&u.orelse)?;
+                        let b = self.expr_impl_::<false>(&u.orelse);
                         self.circ_exit_condition();
-                        cond(c, a, b)
+                        self.at(cond(c, a?, b?), ErrorCode::TypeMismatch, &u.range())
                     }
                 }
             }
             ast::Expr::Dict(d) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Dicts are not supported yet."),
                     &d.range(),
-                )
+                ))
             }
             ast::Expr::Set(s) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Sets are not supported yet."),
                     &s.range(),
-                )
+                ))
             }
             ast::Expr::ListComp(lc) => {
                 // For now we only allow list comprehension to be used for
@@ -1108,18 +2173,20 @@ impl<'a> PyGen<'a> {
                         if let ast::Expr::Call(cc) = &lc.generators[0].iter {
                             if cc.args.len() == 1 {
                                 let num = self.const_usize_impl_::<IS_CNST>(&cc.args[0])?;
-                                fill_array(val, num)
+                                self.at(fill_array(val, num), ErrorCode::TypeMismatch, &lc.range())
                             } else {
-                                self.err(
+                                Err(self.err(
+                                    ErrorCode::Arity,
                                     format!("Range takes at most 1 element (for now)."),
                                     &cc.range(),
-                                )
+                                ))
                             }
                         } else {
-                            self.err(
+                            Err(self.err(
+                                ErrorCode::Unsupported,
                                 format!("Range is missing."),
                                 &lcc.range(),
-                            )
+                            ))
                         }
                     }
                     _ => {
@@ -1128,59 +2195,67 @@ impl<'a> PyGen<'a> {
                         if let ast::Expr::Call(cc) = &lc.generators[0].iter {
                             if cc.args.len() == 1 {
                                 let num = self.const_usize_impl_::<IS_CNST>(&cc.args[0])?;
-                                fill_array(val, num)
+                                self.at(fill_array(val, num), ErrorCode::TypeMismatch, &lc.range())
                             } else {
-                                self.err(
+                                Err(self.err(
+                                    ErrorCode::Arity,
                                     format!("Range takes at most 1 element (for now)."),
                                     &cc.range(),
-                                )
+                                ))
                             }
                         } else {
-                            self.err(
+                            Err(self.err(
+                                ErrorCode::Unsupported,
                                 format!("Range is missing."),
                                 &lc.elt.range(),
-                            )
+                            ))
                         }
                     }
                 }
             }
             ast::Expr::SetComp(sc) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Set comprehension is not supported yet."),
                     &sc.range(),
-                )
+                ))
             }
             ast::Expr::DictComp(dc) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Dictionary comprehension is not supported yet."),
                     &dc.range(),
-                )
+                ))
             }
             ast::Expr::GeneratorExp(g) => {
                 // in theory we could support this easily if we defined type
                 // casting into list, but not a priority for now.
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Generators are not supported yet."),
                     &g.range(),
-                )
+                ))
             }
             ast::Expr::Await(a) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Await expressions are not supported yet."),
                     &a.range(),
-                )
+                ))
             }
             ast::Expr::Yield(y) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Yield expressions are not supported yet."),
                     &y.range(),
-                )
+                ))
             }
             ast::Expr::YieldFrom(yf) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Yield from expressions are not supported yet."),
                     &yf.range(),
-                )
+                ))
             }
             ast::Expr::Compare(b) => {
                 // there is probably a nicer and more efficient way to do this
@@ -1190,19 +2265,28 @@ impl<'a> PyGen<'a> {
                     .map(|e| self.expr_impl_::<IS_CNST>(&e))
                     .collect::<Result<Vec<_>, _>>()?;
                 comparators.extend(comparators_rest);
-                let ops = self.cmp_ops(&b.ops);
                 let results_intm: Vec<PyTerm> = comparators
                     .windows(2)
-                    .zip(ops.iter())
-                    .map(|(pair, f)| f(pair[0].clone(), pair[1].clone()))
+                    .zip(b.ops.iter())
+                    .map(|(pair, op)| self.cmp_op_impl_::<IS_CNST>(op, pair[0].clone(), pair[1].clone(), &b.range()))
                     .collect::<Result<Vec<_>, _>>()?;
                 let (init_result, rest) = results_intm.split_first().unwrap();
                 let final_result = rest.iter().try_fold(init_result.clone(), |prev_result, term| {
                     and(prev_result.clone(), term.clone())
                 });
-                final_result
+                self.at(final_result, ErrorCode::TypeMismatch, &b.range())
             }
             ast::Expr::Call(p) => {
+                if let ast::Expr::Attribute(a) = p.func.as_ref() {
+                    return self.method_call_impl_::<IS_CNST>(p, a);
+                }
+                if let ast::Expr::Name(n) = p.func.as_ref() {
+                    if HIGHER_ORDER_BUILTINS.contains(&n.id.as_str())
+                        && self.get_function(n.id.as_str()).is_none()
+                    {
+                        return self.higher_order_impl_::<IS_CNST>(n.id.as_str(), p);
+                    }
+                }
                 // Note that args and kwargs are used in function calls
                 // and class instantiation respectively (and not interchangably).
                 // This may be improved in the future.
@@ -1229,10 +2313,11 @@ impl<'a> PyGen<'a> {
                         ast::Expr::Constant(c) if f_name == "field" => {
                             let val = match c.value.as_int() {
                                 Some(i) => i.to_string(),
-                                None => self.err(
+                                None => return Err(self.err(
+                                    ErrorCode::NotConstant,
                                     format!("Constant expected inside of field literal declaration."),
                                     &c.range(),
-                                ),
+                                )),
                             };
                             self.literal_(&Literal::Field(val, c.range()))
                         }
@@ -1246,7 +2331,8 @@ impl<'a> PyGen<'a> {
                         self.expr_impl_::<IS_CNST>(&m.value)
                             .map(|m_expr| (m.arg.clone().unwrap().to_string(), m_expr))
                     })
-                    .collect::<Result<Vec<_>, String>>();
+                    .collect::<Result<Vec<_>, Diagnostic>>();
+                let s = &p.range();
                 // As usual, there is probably a nicer way to do this:
                 // Handle "type casting" arguments as respective literals
                 // Also handle class object creation appropriately
@@ -1254,166 +2340,203 @@ impl<'a> PyGen<'a> {
                 if f_name == "int" {
                     // Unclean explicit type casting, refactor later.
                     if args[0].ty == Ty::Field && args.len() == 1 {
-                        uint_from_bits(field_to_bits(args[0].clone(), 32)?)
+                        self.at(field_to_bits(args[0].clone(), 32).and_then(uint_from_bits), ErrorCode::TypeMismatch, s)
                     } else if args[0].ty == Ty::Uint(32) && args.len() == 1 {
                         Ok(args[0].clone())
+                    } else if matches!(args[0].ty, Ty::Int(_)) && args.len() == 1 {
+                        self.at(int_to_uint(args[0].clone(), 32), ErrorCode::TypeMismatch, s)
                     } else if args[0].ty == Ty::Bool && args.len() == 1 {
-                        uint_from_bool(args[0].clone(), 32)
+                        self.at(uint_from_bool(args[0].clone(), 32), ErrorCode::TypeMismatch, s)
                     } else if args.len() != 1 {
-                        self.err(
+                        Err(self.err(
+                            ErrorCode::Arity,
                             format!("Int takes at most 1 argument."),
                             &p.range(),
-                        )
+                        ))
                     } else {
-                        self.err(
+                        Err(self.err(
+                            ErrorCode::TypeMismatch,
                             format!("Type casting into int is only possible from a field, int or bool."),
                             &p.range(),
-                        )
+                        ))
                     }
+                } else if let Some(w) = signed_int_width(&f_name) {
+                    if args.len() != 1 {
+                        return Err(self.err(
+                            ErrorCode::Arity,
+                            format!("{f_name} takes at most 1 argument."),
+                            &p.range(),
+                        ));
+                    }
+                    self.at(int_cast(args[0].clone(), w), ErrorCode::TypeMismatch, s)
                 } else if f_name == "float" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Floats are not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "complex" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Complex numbers are not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "str" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Strings are not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "ord" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Type casting into int is not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "hex" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Type casting into hexadecimal string is not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "oct" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Type casting into octal string is not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "tuple" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Type casting into tuple is not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "set" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Type casting into set is not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "frozenset" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Type casting into frozenset is not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "list" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Type casting into list is not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "dict" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Dicts are not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "bool" {
                     // Unclean explicit type casting, refactor this later.
                     if args[0].ty == Ty::Bool && args.len() == 1 {
                         Ok(args[0].clone())
                     } else if args[0].ty == Ty::Uint(32) && args.len() == 1 {
-                        neq(args[0].clone(), uint_lit(0, 32))
+                        self.at(neq(args[0].clone(), uint_lit(0, 32)), ErrorCode::TypeMismatch, s)
+                    } else if let (Ty::Int(w), 1) = (&args[0].ty, args.len()) {
+                        self.at(neq(args[0].clone(), int_lit(0, *w)), ErrorCode::TypeMismatch, s)
                     } else if args[0].ty == Ty::Field && args.len() == 1 {
-                        neq(args[0].clone(), field_lit(0))
+                        self.at(neq(args[0].clone(), field_lit(0)), ErrorCode::TypeMismatch, s)
                     } else if args.len() != 1 {
-                        self.err(
+                        Err(self.err(
+                            ErrorCode::Arity,
                             format!("Bool takes at most 1 argument."),
                             &p.range(),
-                        )
+                        ))
                     } else {
-                        self.err(
+                        Err(self.err(
+                            ErrorCode::TypeMismatch,
                             format!("Type casting into bool is only possible from a field, int or bool."),
                             &p.range(),
-                        )
+                        ))
                     }
                 } else if f_name == "range" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Iterators are not supported yet."),
                         &p.range(),
-                    )
+                    ))
                 } else if f_name == "field" {
                     // Unclean explicit type casting, refactor later.
                     if args[0].ty == Ty::Uint(32) && args.len() == 1 {
-                        uint_to_field(args[0].clone())
+                        self.at(uint_to_field(args[0].clone()), ErrorCode::TypeMismatch, s)
+                    } else if matches!(args[0].ty, Ty::Int(_)) && args.len() == 1 {
+                        self.at(int_to_field(args[0].clone()), ErrorCode::TypeMismatch, s)
                     } else if args[0].ty == Ty::Field && args.len() == 1 {
                         Ok(args[0].clone())
                     } else if args[0].ty == Ty::Bool && args.len() == 1 {
-                        uint_to_field(uint_from_bool(args[0].clone(), 32)?)
+                        self.at(uint_from_bool(args[0].clone(), 32).and_then(uint_to_field), ErrorCode::TypeMismatch, s)
                     } else if args.len() != 1 {
-                        self.err(
+                        Err(self.err(
+                            ErrorCode::Arity,
                             format!("Field takes at most 1 argument."),
                             &p.range(),
-                        )
+                        ))
                     } else {
-                        self.err(
+                        Err(self.err(
+                            ErrorCode::TypeMismatch,
                             format!("Type casting into field is only possible from a field, int or bool."),
                             &p.range(),
-                        )
+                        ))
                     }
                 } else if let Some(m) = self.classes_and_tys.get(&f_path) {
                     // If f_name is a defined class, 
                     if m.contains_key(&f_name) {
-                        return Ok(PyTerm::new_class(self.canon_class(&f_name)?, kwargs.unwrap()))
+                        let class = self.at(self.canon_class(&f_name), ErrorCode::UndefinedName, s)?;
+                        return Ok(PyTerm::new_class(class, kwargs?))
                     } else {
-                        self.function_call_impl_::<IS_CNST>(args, exp_ty, f_path, f_name)
+                        self.function_call_impl_::<IS_CNST>(args, exp_ty, f_path, f_name, s)
                     }
                     // I'm too lazy to avoid code repetition atm.
                 } else {
-                    self.function_call_impl_::<IS_CNST>(args, exp_ty, f_path, f_name)
+                    self.function_call_impl_::<IS_CNST>(args, exp_ty, f_path, f_name, s)
                 }
             }
             ast::Expr::FormattedValue(fv) => {
                 // We would need to support joined strings
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Formatted values are not supported yet."),
                     &fv.range(),
-                )
+                ))
             }
             ast::Expr::JoinedStr(js) => {
                 // We would need to support strings in the first place
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Joined strings are not supported yet."),
                     &js.range(),
-                )
+                ))
             }
             ast::Expr::Constant(c) => self.literal_(&Literal::PyLiteral(c.clone())),
             ast::Expr::Attribute(a) => {
                 match a.value.as_ref() {
                     ast::Expr::Attribute(aa) => {
                         let v = self.expr_impl_::<IS_CNST>(&ast::Expr::from(aa.clone()));
-                        field_select(&v?, &a.attr.as_str())
+                        self.at(field_select(&v?, &a.attr.as_str()), ErrorCode::UndefinedName, &a.range())
                     }
                     ast::Expr::Subscript(s) => {
                         let v = self.expr_impl_::<IS_CNST>(&ast::Expr::from(s.clone()));
-                        field_select(&v?, &a.attr.as_str())
+                        self.at(field_select(&v?, &a.attr.as_str()), ErrorCode::UndefinedName, &a.range())
                     }
                     ast::Expr::Name(n) => {
                         let v = self.expr_impl_::<IS_CNST>(&ast::Expr::from(n.clone()));
-                        field_select(&v?, &a.attr.as_str())
+                        self.at(field_select(&v?, &a.attr.as_str()), ErrorCode::UndefinedName, &a.range())
                     }
                     e => {
-                        self.err(
+                        Err(self.err(
+                            ErrorCode::Unsupported,
                             format!("Attribute or subscript must be associated to an identifier, another attribute or another subscript."),
                             &e.range(),
-                        )
+                        ))
                     }
                 }
             }
@@ -1421,21 +2544,22 @@ impl<'a> PyGen<'a> {
                 match s.value.as_ref() {
                     ast::Expr::Attribute(a) => {
                         let v = self.expr_impl_::<IS_CNST>(&ast::Expr::from(a.clone()));
-                        self.array_access_impl_::<IS_CNST>(s, v?)
+                        self.array_access_impl_::<IS_CNST>(s, v?, false)
                     }
                     ast::Expr::Subscript(ss) => {
                         let v = self.expr_impl_::<IS_CNST>(&ast::Expr::from(ss.clone()));
-                        self.array_access_impl_::<IS_CNST>(s, v?)
+                        self.array_access_impl_::<IS_CNST>(s, v?, false)
                     }
                     ast::Expr::Name(n) => {
                         let v = self.expr_impl_::<IS_CNST>(&ast::Expr::from(n.clone()));
-                        self.array_access_impl_::<IS_CNST>(s, v?)
+                        self.array_access_impl_::<IS_CNST>(s, v?, self.is_ram_array(n.id.as_str()))
                     }
                     err => {
-                        self.err(
+                        Err(self.err(
+                            ErrorCode::Unsupported,
                             format!("Attribute or subscript must be associated to an identifier, another attribute or another subscript."),
                             &err.range(),
-                        )
+                        ))
                     }
                 }
             }
@@ -1443,47 +2567,55 @@ impl<'a> PyGen<'a> {
                 // They are generally used for destructuring,
                 // but we only support it for array spreads
                 // to copy values of array
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Starred expressions are only supported inside of inline arrays."),
                     &s.range(),
-                )
+                ))
             }
             ast::Expr::Name(i) => self.identifier_impl_::<IS_CNST>(i),
             ast::Expr::List(l) => {
                 let mut avals = Vec::with_capacity(l.elts.len());
                 l.elts
                     .iter()
-                    .try_for_each::<_, Result<_, String>>(|ee| match ee {
+                    .try_for_each::<_, Result<_, Diagnostic>>(|ee| match ee {
                         ast::Expr::Starred(s) => {
-                            avals.append(
-                                &mut self.expr_impl_::<IS_CNST>(&s.value)?.unwrap_array()?,
-                            );
-                            Ok(())                        }
+                            let v = self.expr_impl_::<IS_CNST>(&s.value)?;
+                            avals.append(&mut self.at(v.unwrap_array(), ErrorCode::TypeMismatch, &s.range())?);
+                            Ok(())
+                        }
                         _ => {
                             avals.push(self.expr_impl_::<IS_CNST>(ee)?);
                             Ok(())
                         }
                     })?;
-                PyTerm::new_array(avals)
+                self.at(PyTerm::new_array(avals), ErrorCode::TypeMismatch, &l.range())
             }
             ast::Expr::Tuple(t) => {
-                // This should be relatively straight forward to implement
-                self.err(
-                    format!("Tuples are not supported yet."),
-                    &t.range(),
-                )
+                let elems = t
+                    .elts
+                    .iter()
+                    .map(|e| self.expr_impl_::<IS_CNST>(e))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(PyTerm::new_tuple(elems))
             }
             ast::Expr::Slice(s) => {
                 // This is already being handled by array_access_impl.
                 // Maybe refactor in the future.
-                self.err(
+                Err(self.err(
+                    ErrorCode::Internal,
                     format!("If we reached the slice expression then something went wrong..."),
                     &s.range(),
-                )
+                ))
             }
         }
-        .and_then(|res| if IS_CNST { const_val(res) } else { Ok(res) })
-        .map_err(|err| format!("{err}"))
+        .and_then(|res| {
+            if IS_CNST {
+                self.at(const_val(res), ErrorCode::NotConstant, &e.range())
+            } else {
+                Ok(res)
+            }
+        })
     }
 
     fn canon_class(&self, id: &str) -> Result<String, String> {
@@ -1537,67 +2669,610 @@ impl<'a> PyGen<'a> {
         }
     }
 
-    fn stmt_impl_<const IS_CNST: bool>(&self, s: &ast::Stmt) -> Result<(), String> {
-        if IS_CNST {
-            debug!("Const expr range: {:?}", s.range());
-        } else {
-            debug!("Stmt range: {:?}", s.range());
+    fn unpack_assign_impl_<const IS_CNST: bool>(
+        &self,
+        targets: &ast::ExprTuple,
+        value: &ast::Expr,
+    ) -> Result<(), Diagnostic> {
+        // e.g. `a, b = f(x)`, where f returns Tuple[T, U]
+        let v = self.expr_impl_::<IS_CNST>(value)?;
+        let elems = self.at(v.unwrap_tuple(), ErrorCode::TypeMismatch, &value.range())?;
+        if elems.len() != targets.elts.len() {
+            return Err(self.err(
+                ErrorCode::TypeMismatch,
+                format!(
+                    "Cannot unpack {} values into {} variables",
+                    elems.len(),
+                    targets.elts.len()
+                ),
+                &targets.range(),
+            ));
         }
+        for (target, e) in targets.elts.iter().zip(elems) {
+            let name = self.get_lhs_name::<IS_CNST>(target)?;
+            self.assign_impl_::<IS_CNST>(name.id.as_str(), Some(target.clone()), e, false, &target.range())?;
+        }
+        Ok(())
+    }
 
-        match s {
+    fn stmts_impl_<const IS_CNST: bool>(&self, stmts: &[ast::Stmt]) -> Result<(), Diagnostic> {
+        for s in stmts {
+            if let Err(d) = self.stmt_impl_::<IS_CNST>(s) {
+                // A const evaluation may be an attempt that is allowed to
+                // fail, so only non-const statements recover from errors.
+                if IS_CNST {
+                    return Err(d);
+                }
+                self.recover(d, s);
+            }
+            // Symbolic early returns are guarded by circify, but in a const
+            // function nothing after the return may be evaluated.
+            if IS_CNST && self.cvar_returned() {
+                break;
+            }
+            // Neither may anything after a return whose condition folds,
+            // or a recursive call after its base case would be inlined.
+            if !IS_CNST && const_bool(PyTerm::new(Ty::Bool, self.circ_condition())) == Some(false) {
+                break;
+            }
+            if !IS_CNST && self.recovery.full() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn branch_impl_<const IS_CNST: bool>(&self, stmts: &[ast::Stmt]) -> Result<(), Diagnostic> {
+        self.enter_scope_impl_::<IS_CNST>();
+        let res = self.stmts_impl_::<IS_CNST>(stmts);
+        self.exit_scope_impl_::<IS_CNST>();
+        res
+    }
+
+    /// A match statement is lowered like an if-elif chain: each case is a
+    /// branch guarded by its pattern and by the failure of all earlier ones.
+    fn match_impl_<const IS_CNST: bool>(&self, m: &ast::StmtMatch) -> Result<(), Diagnostic> {
+        let subject = self.expr_impl_::<IS_CNST>(&m.subject)?;
+        let conds = m
+            .cases
+            .iter()
+            .map(|c| self.case_impl_::<IS_CNST>(&subject, c))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.match_exhaustive_impl_::<IS_CNST>(m, &subject, &conds)?;
+        self.match_cases_impl_::<IS_CNST>(&m.cases, &conds)
+    }
+
+    fn match_cases_impl_<const IS_CNST: bool>(
+        &self,
+        cases: &[ast::MatchCase],
+        conds: &[PyTerm],
+    ) -> Result<(), Diagnostic> {
+        let (Some((case, cases)), Some((c, conds))) = (cases.split_first(), conds.split_first()) else {
+            return Ok(());
+        };
+        match const_bool(c.clone()) {
+            Some(true) => self.branch_impl_::<IS_CNST>(&case.body),
+            Some(false) => self.match_cases_impl_::<IS_CNST>(cases, conds),
+            None if IS_CNST => Err(self.err(ErrorCode::NotConstant, "match pattern not const bool", &case.pattern.range())),
+            None => {
+                let cbool = self.at(bool(c.clone()), ErrorCode::TypeMismatch, &case.pattern.range())?;
+                self.circ_enter_branch(cbool.clone());
+                let res = self.branch_impl_::<false>(&case.body);
+                self.circ_exit_branch();
+                res?;
+                self.circ_enter_branch(term![NOT; cbool]);
+                let res = self.match_cases_impl_::<false>(cases, conds);
+                self.circ_exit_branch();
+                res
+            }
+        }
+    }
+
+    /// Whether `case` matches `subject`, including its guard
+    fn case_impl_<const IS_CNST: bool>(
+        &self,
+        subject: &PyTerm,
+        case: &ast::MatchCase,
+    ) -> Result<PyTerm, Diagnostic> {
+        let c = self.pattern_impl_::<IS_CNST>(subject, &case.pattern)?;
+        let Some(guard) = &case.guard else {
+            return Ok(c);
+        };
+        match const_bool(c.clone()) {
+            Some(false) => Ok(c),
+            _ if IS_CNST => {
+                let g = self.expr_impl_::<true>(guard)?;
+                self.at(and(c, g), ErrorCode::TypeMismatch, &guard.range())
+            }
+            _ => {
+                // like the branches of a ternary, the guard is only evaluated
+                // if the pattern matches
+                self.circ_enter_condition(self.at(bool(c.clone()), ErrorCode::TypeMismatch, &case.pattern.range())?);
+                let g = self.expr_impl_::<false>(guard);
+                self.circ_exit_condition();
+                self.at(and(c, g?), ErrorCode::TypeMismatch, &guard.range())
+            }
+        }
+    }
+
+    /// Whether the pattern `p` matches `subject`
+    fn pattern_impl_<const IS_CNST: bool>(
+        &self,
+        subject: &PyTerm,
+        p: &ast::Pattern,
+    ) -> Result<PyTerm, Diagnostic> {
+        match p {
+            ast::Pattern::MatchValue(v) => {
+                let v = self.expr_impl_::<IS_CNST>(&v.value)?;
+                self.cmp_op_impl_::<IS_CNST>(&ast::CmpOp::Eq, subject.clone(), v, &p.range())
+            }
+            ast::Pattern::MatchSingleton(s) => match s.value {
+                ast::Constant::Bool(b) => self.at(eq(subject.clone(), py_bool_lit(b)), ErrorCode::TypeMismatch, &s.range()),
+                _ => Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("There is no support yet for None patterns."),
+                    &s.range(),
+                )),
+            },
+            ast::Pattern::MatchAs(a) => {
+                if a.name.is_some() {
+                    return Err(self.err(
+                        ErrorCode::Unsupported,
+                        format!("Capture patterns are not supported yet; use the wildcard `_`."),
+                        &a.range(),
+                    ));
+                }
+                match &a.pattern {
+                    Some(p) => self.pattern_impl_::<IS_CNST>(subject, p),
+                    None => Ok(py_bool_lit(true)),
+                }
+            }
+            ast::Pattern::MatchOr(o) => o
+                .patterns
+                .iter()
+                .try_fold(py_bool_lit(false), |acc, p| {
+                    let c = self.pattern_impl_::<IS_CNST>(subject, p)?;
+                    self.at(or(acc, c), ErrorCode::TypeMismatch, &o.range())
+                }),
+            ast::Pattern::MatchClass(c) => {
+                let ast::Expr::Name(n) = c.cls.as_ref() else {
+                    return Err(self.err(
+                        ErrorCode::Unsupported,
+                        format!("Class patterns must name a class."),
+                        &c.cls.range(),
+                    ));
+                };
+                let Some((Ok(sdef), _)) = self.get_class_or_type(n.id.as_str()) else {
+                    return Err(self.err(
+                        ErrorCode::UndefinedName,
+                        format!("No such class {} (did you bring it into scope?)", n.id.as_str()),
+                        &c.cls.range(),
+                    ));
+                };
+                match subject.type_() {
+                    Ty::DataClass(class, _) if class.as_str() == sdef.name.as_str() => {}
+                    ty => {
+                        return Err(self.err(
+                            ErrorCode::TypeMismatch,
+                            format!("A pattern of class {} cannot match a value of type {ty}.", sdef.name.as_str()),
+                            &c.range(),
+                        ))
+                    }
+                }
+                // positional patterns match the fields in the order they are declared
+                let fields: Vec<String> = sdef
+                    .body
+                    .iter()
+                    .filter_map(|s| match s {
+                        ast::Stmt::AnnAssign(a) => match a.target.as_ref() {
+                            ast::Expr::Name(n) => Some(n.id.to_string()),
+                            _ => None,
+                        },
+                        _ => None,
+                    })
+                    .collect();
+                if c.patterns.len() > fields.len() {
+                    return Err(self.err(
+                        ErrorCode::TypeMismatch,
+                        format!(
+                            "Class {} has {} fields, but the pattern has {} positional sub-patterns.",
+                            sdef.name.as_str(),
+                            fields.len(),
+                            c.patterns.len()
+                        ),
+                        &c.range(),
+                    ));
+                }
+                fields
+                    .iter()
+                    .map(|f| f.as_str())
+                    .zip(&c.patterns)
+                    .chain(c.kwd_attrs.iter().map(|a| a.as_str()).zip(&c.kwd_patterns))
+                    .try_fold(py_bool_lit(true), |acc, (f, p)| {
+                        let field = self.at(field_select(subject, f), ErrorCode::UndefinedName, &p.range())?;
+                        let m = self.pattern_impl_::<IS_CNST>(&field, p)?;
+                        self.at(and(acc, m), ErrorCode::TypeMismatch, &p.range())
+                    })
+            }
+            p => Err(self.err(
+                ErrorCode::Unsupported,
+                format!("Only literal, class and wildcard patterns are supported."),
+                &p.range(),
+            )),
+        }
+    }
+
+    /// A match on a `Bool` or a small `Uint` must cover every value, which
+    /// is checked by enumerating them. Otherwise, unless some case is
+    /// irrefutable, it is asserted that some case matches.
+    fn match_exhaustive_impl_<const IS_CNST: bool>(
+        &self,
+        m: &ast::StmtMatch,
+        subject: &PyTerm,
+        conds: &[PyTerm],
+    ) -> Result<(), Diagnostic> {
+        if m.cases.iter().any(|c| c.guard.is_none() && is_irrefutable(&c.pattern)) {
+            return Ok(());
+        }
+        let values: Vec<(String, PyTerm)> = match subject.type_() {
+            Ty::Bool => vec![
+                ("False".to_string(), py_bool_lit(false)),
+                ("True".to_string(), py_bool_lit(true)),
+            ],
+            Ty::Uint(w) if *w <= MATCH_EXHAUSTIVE_BITS => (0..1u32 << w)
+                .map(|v| (v.to_string(), uint_lit(v, *w)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        // Cases with guards do not count, and neither do patterns that do
+        // not constant-fold, which leaves the question to the assertion.
+        let mut decided = !values.is_empty();
+        for (name, v) in &values {
+            let covered = m
+                .cases
+                .iter()
+                .filter(|c| c.guard.is_none())
+                .map(|c| self.pattern_impl_::<IS_CNST>(v, &c.pattern).ok().and_then(const_bool))
+                .collect::<Vec<_>>();
+            if covered.contains(&Some(true)) {
+                continue;
+            }
+            if covered.iter().all(|c| *c == Some(false)) {
+                return Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Non-exhaustive match: {name} is not covered; add a wildcard case `case _:`."),
+                    &m.subject.range(),
+                ));
+            }
+            decided = false;
+            break;
+        }
+        if decided {
+            return Ok(());
+        }
+        let any = conds
+            .iter()
+            .try_fold(py_bool_lit(false), |acc, c| or(acc, c.clone()));
+        let any = self.at(any, ErrorCode::TypeMismatch, &m.range())?;
+        match const_bool(any.clone()) {
+            Some(true) => Ok(()),
+            Some(false) => Err(self.err(ErrorCode::Unsupported, "No case of the match statement matches", &m.subject.range())),
+            None if IS_CNST => Err(self.err(ErrorCode::NotConstant, "match pattern not const bool", &m.subject.range())),
+            None => {
+                self.assert(self.at(bool(any), ErrorCode::TypeMismatch, &m.range())?);
+                Ok(())
+            }
+        }
+    }
+
+    fn while_impl_<const IS_CNST: bool>(&self, w: &ast::StmtWhile) -> Result<(), Diagnostic> {
+        // The loop is unrolled for as long as the condition constant-folds. Once
+        // it does not, every further iteration is guarded by the conditions of
+        // all previous ones, which requires a `# zk_bound: N` pragma. The loop
+        // must then be known to exit within the bound at compile time: a
+        // circuit that silently stops iterating would prove wrong results.
+        let bound = zk_bound(&range_to_string(&w.range(), &self.cur_source_contents()));
+        let limit = bound.unwrap_or(WHILE_UNROLL_LIMIT);
+        let mut guards = 0;
+        let mut iters = 0;
+        let res = (|| loop {
+            let c = self.expr_impl_::<IS_CNST>(&w.test)?;
+            let c_const = const_bool(c.clone());
+            let cbool = self.at(bool(c), ErrorCode::TypeMismatch, &w.test.range())?;
+            match c_const {
+                Some(false) => return Ok(()),
+                None if IS_CNST => {
+                    return Err(self.err(ErrorCode::NotConstant, "while condition not const bool", &w.test.range()))
+                }
+                None if bound.is_none() => {
+                    return Err(self.err(
+                        ErrorCode::LoopBound,
+                        "While condition is not constant. Bound the loop with a `# zk_bound: N` pragma.",
+                        &w.test.range(),
+                    ))
+                }
+                _ => (),
+            }
+            if iters == limit {
+                return match (c_const, bound) {
+                    (None, Some(b)) => Err(self.err_help(
+                        ErrorCode::LoopBound,
+                        format!("While condition is not known to be false after the bound of {b} iterations."),
+                        format!("iterate `for _ in range({b}):` and guard the body with `if` on the condition"),
+                        &w.test.range(),
+                    )),
+                    (_, Some(b)) => Err(self.err(
+                        ErrorCode::LoopBound,
+                        format!("While loop does not terminate within its bound of {b} iterations."),
+                        &w.range(),
+                    )),
+                    (_, None) => Err(self.err(
+                        ErrorCode::LoopBound,
+                        format!("While loop exceeded the unroll limit of {limit} iterations."),
+                        &w.range(),
+                    )),
+                };
+            }
+            if c_const.is_none() {
+                self.circ_enter_branch(cbool);
+                guards += 1;
+            }
+            self.branch_impl_::<IS_CNST>(&w.body)?;
+            iters += 1;
+            if IS_CNST && self.cvar_returned() {
+                return Ok(());
+            }
+        })();
+        for _ in 0..guards {
+            self.circ_exit_branch();
+        }
+        res
+    }
+
+    fn for_targets(&self, target: &ast::Expr) -> Result<Vec<String>, Diagnostic> {
+        match target {
+            ast::Expr::Name(n) => Ok(vec![n.id.to_string()]),
+            ast::Expr::Tuple(t) => t
+                .elts
+                .iter()
+                .map(|e| match e {
+                    ast::Expr::Name(n) => Ok(n.id.to_string()),
+                    err => Err(self.err(
+                        ErrorCode::Unsupported,
+                        format!("Only names can be used as loop variables."),
+                        &err.range(),
+                    )),
+                })
+                .collect(),
+            err => Err(self.err(
+                ErrorCode::Syntax,
+                format!("Missing iteration variable in for loop."),
+                &err.range(),
+            )),
+        }
+    }
+
+    /// Statically evaluates the iterable of a for loop, returning the values
+    /// bound to the loop variables in each iteration.
+    fn for_items_impl_<const IS_CNST: bool>(
+        &self,
+        iter: &ast::Expr,
+    ) -> Result<Vec<Vec<PyTerm>>, Diagnostic> {
+        let call = match iter {
+            ast::Expr::Call(c) => match c.func.as_ref() {
+                ast::Expr::Name(n) => Some((n.id.as_str(), c)),
+                _ => None,
+            },
+            _ => None,
+        };
+        let array = |e: &ast::Expr| -> Result<Vec<PyTerm>, Diagnostic> {
+            let a = self.expr_impl_::<IS_CNST>(e)?;
+            self.at(a.unwrap_array(), ErrorCode::TypeMismatch, &e.range())
+        };
+        match call {
+            Some(("range", c)) => {
+                let args = c
+                    .args
+                    .iter()
+                    .map(|a| self.const_range_arg_impl_::<IS_CNST>(a))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // The iteration variable is signed if a bound is, and an int (u32) otherwise
+                let mut widths = args.iter().filter_map(|(_, ty)| match ty {
+                    Ty::Int(w) => Some(*w),
+                    _ => None,
+                });
+                let ty = widths.next().map_or(Ty::Uint(32), Ty::Int);
+                if widths.any(|w| Ty::Int(w) != ty) {
+                    return Err(self.err(
+                        ErrorCode::TypeMismatch,
+                        format!("For loop range arguments have different signed types."),
+                        &c.range(),
+                    ));
+                }
+                let args: Vec<isize> = args.into_iter().map(|(v, _)| v).collect();
+                let (s, e, step) = match args[..] {
+                    [e] => (0, e, 1),
+                    [s, e] => (s, e, 1),
+                    [s, e, step] => (s, e, step),
+                    _ => {
+                        return Err(self.err(
+                            ErrorCode::Arity,
+                            format!("For loop range requires at least 1 and at most 3 arguments."),
+                            &c.range(),
+                        ))
+                    }
+                };
+                let vals: Vec<isize> = if step > 0 {
+                    (s..e).step_by(step as usize).collect()
+                } else if step < 0 {
+                    ((e + 1)..=s).rev().step_by(step.unsigned_abs()).collect()
+                } else {
+                    return Err(self.err(
+                        ErrorCode::OutOfBounds,
+                        format!("For loop range step must not be zero."),
+                        &c.args[2].range(),
+                    ));
+                };
+                let (min, max) = match ty {
+                    Ty::Int(w) => (-(1i128 << (w - 1)), (1i128 << (w - 1)) - 1),
+                    _ => (0, u32::MAX as i128),
+                };
+                if let Some(v) = vals.iter().find(|v| !(min..=max).contains(&(**v as i128))) {
+                    return Err(self.err(
+                        ErrorCode::OutOfBounds,
+                        format!("For loop range value {v} is out of bounds for {ty}."),
+                        &c.range(),
+                    ));
+                }
+                Ok(vals
+                    .into_iter()
+                    .map(|v| match ty {
+                        Ty::Int(w) => vec![int_lit(v, w)],
+                        _ => vec![PyTerm::new_u32(v)],
+                    })
+                    .collect())
+            }
+            Some(("enumerate", c)) => {
+                if c.args.len() != 1 {
+                    return Err(self.err(
+                        ErrorCode::Arity,
+                        format!("enumerate requires exactly 1 argument."),
+                        &c.range(),
+                    ));
+                }
+                let elems = array(&c.args[0])?;
+                Ok(elems
+                    .into_iter()
+                    .enumerate()
+                    .map(|(j, x)| vec![PyTerm::new_u32(j), x])
+                    .collect())
+            }
+            Some(("zip", c)) => {
+                let arrays = c
+                    .args
+                    .iter()
+                    .map(|a| array(a))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // Like in Python, zip stops at the shortest array
+                let n = arrays.iter().map(Vec::len).min().unwrap_or(0);
+                Ok((0..n)
+                    .map(|j| arrays.iter().map(|a| a[j].clone()).collect())
+                    .collect())
+            }
+            _ => {
+                let elems = array(iter)?;
+                Ok(elems.into_iter().map(|x| vec![x]).collect())
+            }
+        }
+    }
+
+    // The value of a range argument, and its type
+    fn const_range_arg_impl_<const IS_CNST: bool>(
+        &self,
+        e: &ast::Expr,
+    ) -> Result<(isize, Ty), Diagnostic> {
+        // Integer literals are unsigned, so negative literals need special care
+        if let ast::Expr::UnaryOp(u) = e {
+            if matches!(u.op, ast::UnaryOp::USub) {
+                return self.const_range_arg_impl_::<IS_CNST>(&u.operand).map(|(v, ty)| (-v, ty));
+            }
+        }
+        let v = self.expr_impl_::<IS_CNST>(e)?;
+        let ty = v.ty.clone();
+        let i = self.at(const_int(v), ErrorCode::NotConstant, &e.range())?;
+        let i = match ty {
+            Ty::Int(w) if i.get_bit(w as u32 - 1) => i - (Integer::from(1) << w as u32),
+            _ => i,
+        };
+        let i = i
+            .to_isize()
+            .ok_or_else(|| self.err(ErrorCode::OutOfBounds, "Constant integer outside isize range", &e.range()))?;
+        Ok((i, ty))
+    }
+
+    fn stmt_impl_<const IS_CNST: bool>(&self, s: &ast::Stmt) -> Result<(), Diagnostic> {
+        let depth = self.recovery.stmt_stack.borrow().len();
+        self.recovery.stmt_stack.borrow_mut().push((self.cur_path(), s.range()));
+        let res = self.stmt_inner_impl_::<IS_CNST>(s);
+        self.recovery.stmt_stack.borrow_mut().truncate(depth);
+        res
+    }
+
+    fn stmt_inner_impl_<const IS_CNST: bool>(&self, s: &ast::Stmt) -> Result<(), Diagnostic> {
+        if IS_CNST {
+            debug!("Const expr range: {:?}", s.range());
+        } else {
+            debug!("Stmt range: {:?}", s.range());
+        }
+
+        match s {
             // FunctionDef, ClassDef and Import(From) are handled in visit_body()
             ast::Stmt::FunctionDef(f) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Function def statements are only supported in the module body."),
                     &f.range(),
-                )
+                ))
             }
             ast::Stmt::AsyncFunctionDef(a) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Aync function definitions are not supported yet."),
                     &a.range(),
-                )
+                ))
             }
             ast::Stmt::ClassDef(c) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Class def statements are only supported in the module body."),
                     &c.range(),
-                )
+                ))
             }
             ast::Stmt::Return(r) => {
                 // Multi-return is not implemented
-                if let Some(e) = r.value.as_ref() {
+                let res = if let Some(e) = r.value.as_ref() {
                     self.set_lhs_ty_ret(r);
                     let ret = self.expr_impl_::<IS_CNST>(&e)?;
                     self.ret_impl_::<IS_CNST>(Some(ret))
                 } else {
                     self.ret_impl_::<IS_CNST>(None)
-                }
-                .map_err(|e| format!("{e}"))
+                };
+                self.at(res, ErrorCode::TypeMismatch, &r.range())
             }
             ast::Stmt::Delete(d) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Delete statements are not supported yet."),
                     &d.range(),
-                )
+                ))
             }
             ast::Stmt::Assign(a) => {
-                // Multi-assignment is not implemented
-                assert!(a.targets.len() <= 1);
+                if a.targets.len() > 1 {
+                    return Err(self.err(
+                        ErrorCode::Unsupported,
+                        format!("Chained assignments like `a = b = ...` are not supported yet."),
+                        &a.range(),
+                    ));
+                }
+
+                if let ast::Expr::Tuple(t) = &a.targets[0] {
+                    return self.unpack_assign_impl_::<IS_CNST>(t, &a.value);
+                }
 
                 // We might not need this if we have no generics
                 self.set_lhs_ty_defn::<IS_CNST>(a.value.as_ref(), &ast::Stmt::from(a.clone()))?;
                 let e = self.expr_impl_::<IS_CNST>(&a.value)?;
-                let name = self.get_lhs_name::<IS_CNST>(&a.targets[0]).unwrap();
+                let name = self.get_lhs_name::<IS_CNST>(&a.targets[0])?;
                 // For now we pass strictness condition as false because
                 // it is not clear how this would apply in Python.
-                self.assign_impl_::<IS_CNST>(&name.id.as_str(), Some(a.targets[0].clone()), e, false)
+                self.assign_impl_::<IS_CNST>(&name.id.as_str(), Some(a.targets[0].clone()), e, false, &a.range())
             }
             ast::Stmt::TypeAlias(t) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Type aliases are not supported yet."),
                     &t.range(),
-                )
+                ))
             }
             ast::Stmt::AugAssign(a) => {
                 // Just wrap this into target = target + value assignment
@@ -1606,261 +3281,276 @@ impl<'a> PyGen<'a> {
                 self.set_lhs_ty_defn::<IS_CNST>(a.value.as_ref(), &ast::Stmt::from(a.clone()))?;
                 let left = self.expr_impl_::<IS_CNST>(&a.target)?;
                 let right = self.expr_impl_::<IS_CNST>(&a.value)?;
-                let op = self.bin_op(&a.op);
-                let e = op(left, right)?;
-                let name = self.get_lhs_name::<IS_CNST>(&a.target).unwrap();
+                let e = self.bin_op_impl_::<IS_CNST>(&a.op, left, right, &a.range())?;
+                let name = self.get_lhs_name::<IS_CNST>(&a.target)?;
                 // For now we pass strictness condition as false because
                 // it is not clear how this would apply in Python.
-                self.assign_impl_::<IS_CNST>(&name.id.as_str(), Some(*a.target.clone()), e, false)
+                self.assign_impl_::<IS_CNST>(&name.id.as_str(), Some(*a.target.clone()), e, false, &a.range())
             }
             ast::Stmt::AnnAssign(a) => {
                 // We might not need this if we have no generics
                 self.set_lhs_ty_defn::<IS_CNST>(a.value.as_ref().unwrap(), &ast::Stmt::from(a.clone()))?;
                 let e = self.expr_impl_::<IS_CNST>(&a.value.as_ref().unwrap())?;
-                let name = self.get_lhs_name::<IS_CNST>(&a.target).unwrap();
+                let name = self.get_lhs_name::<IS_CNST>(&a.target)?;
                 let decl_ty = self.type_impl_::<IS_CNST>(&a.annotation)?;
                 let ty = e.type_();
                 if &decl_ty != ty {
-                    return Err(format!(
-                        "Assignment type mismatch: {decl_ty} annotated vs {ty} actual",
+                    return Err(self.err(
+                        ErrorCode::TypeMismatch,
+                        format!("Assignment type mismatch: {decl_ty} annotated vs {ty} actual"),
+                        &a.range(),
                     ));
                 }
-                self.declare_init_impl_::<IS_CNST>(
+                self.declare_ram_array(name.id.as_str(), &a.annotation);
+                let res = self.declare_init_impl_::<IS_CNST>(
                     name.id.to_string(),
                     decl_ty,
                     e,
-                )
+                );
+                self.at(res, ErrorCode::UndefinedName, &a.target.range())
             }
             ast::Stmt::For(i) => {
-                // Assume that iteration variable is always u32 (necessary for now)
-                let ty = Ty::Uint(32);
-                let ival_cons = PyTerm::new_u32::<isize>;
-
-                let ast::Expr::Call(c) = i.iter.as_ref() else {
-                    self.err(
-                        format!("For loop range is missing."),
-                        &i.iter.range(),
-                    )
-                };
-
-                let ast::Expr::Name(n) = i.target.as_ref() else {
-                    self.err(
-                        format!("Missing iteration variable in for loop."),
-                        &i.iter.range(),
-                    )
-                };
-
-                let mut s: isize = 0;
-                let mut e: isize = 0;
-                // At some point it would be nice to be able to
-                // handle step sizes as well.
-                if c.args.len() == 1 {
-                    s = 0;
-                    // not sure if this works, if not then use commented part
-
-                    e = self.const_isize_impl_::<IS_CNST>(&c.args[0])?;
-                    // if let ast::Expr::Constant(cc) = c.args[0] {
-                    //     let e = self.const_isize_impl_::<IS_CNST>(&ast::Expr::from(cc.clone()))?;
-                    // } else {
-                    //     self.err(
-                    //         format!("For loop range must be statically bound."),
-                    //         &c.range(),
-                    //     )
-                    // }
-                } else if c.args.len() == 2 {
-                    // same argument as above
-
-                   s = self.const_isize_impl_::<IS_CNST>(&c.args[0])?;
-                    // if let ast::Expr::Constant(cc) = c.args[0] {
-                    //     let s: isize = self.const_isize_impl_::<IS_CNST>(&ast::Expr::from(cc.clone()))?;
-                    // } else {
-                    //     self.err(
-                    //         format!("For loop range must be statically bound."),
-                    //         &c.range(),
-                    //     )
-                    // }
-                    e = self.const_isize_impl_::<IS_CNST>(&c.args[1])?;
-                    // if let ast::Expr::Constant(cc) = c.args[1] {
-                    //     let e = self.const_isize_impl_::<IS_CNST>(&ast::Expr::from(cc.clone()))?;
-                    // } else {
-                    //     self.err(
-                    //         format!("For loop range must be statically bound."),
-                    //         &c.range(),
-                    //     )
-                    // }
-                } else {
-                    self.err(
-                        format!("For loop range requires at least 1 and at most 2 arguments."),
-                        &c.range(),
-                    )
+                if !i.orelse.is_empty() {
+                    return Err(self.err(
+                        ErrorCode::Unsupported,
+                        format!("For-else statements are not supported yet."),
+                        &i.range(),
+                    ));
+                }
+                let names = self.for_targets(&i.target)?;
+                let items = self.for_items_impl_::<IS_CNST>(&i.iter)?;
+                if let Some(item) = items.iter().find(|item| item.len() != names.len()) {
+                    return Err(self.err(
+                        ErrorCode::TypeMismatch,
+                        format!(
+                            "Cannot unpack {} values into {} loop variables.",
+                            item.len(),
+                            names.len()
+                        ),
+                        &i.target.range(),
+                    ));
                 }
+                // The types of the loop variables are those of the first item
+                let Some(first) = items.first() else {
+                    return Ok(());
+                };
 
-                let v_name = n.id.to_string();
                 self.enter_scope_impl_::<IS_CNST>();
-                self.decl_impl_::<IS_CNST>(v_name, &ty)?;
-                for j in s..e {
-                    self.enter_scope_impl_::<IS_CNST>();
-                    self.assign_impl_::<IS_CNST>(&n.id.as_str(), None, ival_cons(j), false)?;
-                    for s in &i.body {
-                        self.stmt_impl_::<IS_CNST>(s)?;
+                let res = (|| -> Result<(), Diagnostic> {
+                    for (name, v) in names.iter().zip(first) {
+                        let res = self.decl_impl_::<IS_CNST>(name.clone(), v.type_());
+                        self.at(res, ErrorCode::UndefinedName, &i.target.range())?;
                     }
-                    self.exit_scope_impl_::<IS_CNST>();
-                }
+                    for item in &items {
+                        self.enter_scope_impl_::<IS_CNST>();
+                        let res = names.iter().zip(item).try_for_each(|(name, v)| {
+                            self.assign_impl_::<IS_CNST>(name, None, v.clone(), false, &i.target.range())
+                        });
+                        let res = res.and_then(|()| self.stmts_impl_::<IS_CNST>(&i.body));
+                        self.exit_scope_impl_::<IS_CNST>();
+                        res?;
+                        if IS_CNST && self.cvar_returned() {
+                            break;
+                        }
+                    }
+                    Ok(())
+                })();
                 self.exit_scope_impl_::<IS_CNST>();
-                Ok(())
+                res
             }
             ast::Stmt::AsyncFor(a) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Aync for statements are not supported yet."),
                     &a.range(),
-                )
+                ))
             }
             ast::Stmt::While(w) => {
-                // I think this could be done if we can infer what the iteration variable
-                // is inside of the while loop.
-                self.err(
-                    format!("While statements are not supported yet."),
-                    &w.range(),
-                )    
+                if !w.orelse.is_empty() {
+                    return Err(self.err(
+                        ErrorCode::Unsupported,
+                        format!("While-else statements are not supported yet."),
+                        &w.range(),
+                    ));
+                }
+                self.while_impl_::<IS_CNST>(w)
             }
             ast::Stmt::If(i) => {
-                // We could implement this as a wrapper for the ternary
-                // operator, but at the moment this is not worth it.
-                self.err(
-                    format!("If statements are not supported yet. Use the ternary operator."),
-                    &i.range(),
-                )   
+                // Each branch gets its own scope, so variables that are used
+                // after the if statement must be declared before it. An elif
+                // is just an If statement nested in the orelse block.
+                match self.try_const_impl_(&i.test)?.and_then(const_bool) {
+                    Some(true) => self.branch_impl_::<IS_CNST>(&i.body),
+                    Some(false) => self.branch_impl_::<IS_CNST>(&i.orelse),
+                    None if IS_CNST => Err(self.err(
+                        ErrorCode::NotConstant,
+                        "if condition not const bool",
+                        &i.test.range(),
+                    )),
+                    _ => {
+                        let c = self.expr_impl_::<false>(&i.test)?;
+                        // A condition on constant arguments folds even if it
+                        // reads variables, which is what stops recursion.
+                        match const_bool(c.clone()) {
+                            Some(true) => return self.branch_impl_::<false>(&i.body),
+                            Some(false) => return self.branch_impl_::<false>(&i.orelse),
+                            None => {}
+                        }
+                        let cbool = self.at(bool(c), ErrorCode::TypeMismatch, &i.test.range())?;
+                        self.circ_enter_branch(cbool.clone());
+                        let res = self.branch_impl_::<false>(&i.body);
+                        self.circ_exit_branch();
+                        res?;
+                        self.circ_enter_branch(term![NOT; cbool]);
+                        let res = self.branch_impl_::<false>(&i.orelse);
+                        self.circ_exit_branch();
+                        res
+                    }
+                }
             }
             ast::Stmt::With(w) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("With statements are not supported yet."),
                     &w.range(),
-                )
+                ))
             }
             ast::Stmt::AsyncWith(a) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Aync with statements definitions are not supported yet."),
                     &a.range(),
-                )
-            }
-            ast::Stmt::Match(m) => {
-                self.err(
-                    format!("Match statements are not supported yet."),
-                    &m.range(),
-                )
+                ))
             }
+            ast::Stmt::Match(m) => self.match_impl_::<IS_CNST>(m),
             ast::Stmt::Raise(r) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Raise statements are not supported yet."),
                     &r.range(),
-                )
+                ))
             }
             ast::Stmt::Try(t) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Try statements are not supported yet."),
                     &t.range(),
-                )
+                ))
             }
             ast::Stmt::TryStar(t) => {
                 // This is new syntax from python 3.11
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Try star statements are not supported yet."),
                     &t.range(),
-                )
+                ))
             }
             ast::Stmt::Assert(e) => {
                 match self.expr_impl_::<true>(&e.test).and_then(|v| {
-                    const_bool(v)
-                        .ok_or_else(|| "interpreting expr as const bool failed".to_string())
+                    const_bool(v).ok_or_else(|| {
+                        self.err(
+                            ErrorCode::NotConstant,
+                            "interpreting expr as const bool failed",
+                            &e.test.range(),
+                        )
+                    })
                 }) {
                     Ok(true) => Ok(()),
-                    Ok(false) => Err(format!(
-                        "Const assert failed: {} at\n{:?}",
-                        e.msg
-                            .as_ref()
-                            .map(|m| if let ast::Expr::Constant(msg) = m.as_ref() {
-                                msg.value.as_str().unwrap()
-                            } else {
-                                "(no error message given)"
-                            })
-                            .unwrap(),
-                        e.test.range()
-                    )),
-                    Err(err) if IS_CNST => Err(format!(
-                        "Const assert expression eval failed {} at\n{:?}",
-                        err,
-                        e.test.range(),
+                    Ok(false) => Err(self.err(
+                        ErrorCode::TypeMismatch,
+                        format!(
+                            "Const assert failed: {}",
+                            e.msg
+                                .as_ref()
+                                .and_then(|m| match m.as_ref() {
+                                    ast::Expr::Constant(msg) => msg.value.as_str().map(|m| &m[..]),
+                                    _ => None,
+                                })
+                                .unwrap_or("(no error message given)"),
+                        ),
+                        &e.test.range(),
                     )),
+                    Err(err) if IS_CNST => Err(err),
                     _ => {
-                        let b = bool(self.expr_impl_::<false>(&e.test)?)?;
-                        self.assert(b);
+                        let b = self.expr_impl_::<false>(&e.test)?;
+                        self.assert(self.at(bool(b), ErrorCode::TypeMismatch, &e.test.range())?);
                         Ok(())
                     }
                 }
             }
             ast::Stmt::Import(i) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Import statements are only supported in the module body."),
                     &i.range(),
-                )
+                ))
             }
             ast::Stmt::ImportFrom(i) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Import from statements are only supported in the module body."),
                     &i.range(),
-                )
+                ))
             }
             ast::Stmt::Global(g) => {
                 // I may use this to declare constant variables
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Global statements are not supported yet."),
                     &g.range(),
-                )
+                ))
             }
             ast::Stmt::Nonlocal(n) => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Non-local statements are not supported yet."),
                     &n.range(),
-                )
+                ))
             }
-            ast::Stmt::Expr(_) => {
+            ast::Stmt::Expr(e) => match e.value.as_ref() {
+                // A method call may mutate its receiver
+                ast::Expr::Call(c) if matches!(c.func.as_ref(), ast::Expr::Attribute(_)) => {
+                    self.expr_impl_::<IS_CNST>(&e.value).map(|_| ())
+                }
                 // We can just escape it, since it does not contribute to the circuit
-                Ok(())
-            }
+                _ => Ok(()),
+            },
             ast::Stmt::Pass(p) => {
                 // We could probably just escape this and then check
                 // in FunctionDef whether this is or return is passed,
                 // and interpret accordingly.
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Pass statements are not supported yet."),
                     &p.range(),
-                )
+                ))
             }
             ast::Stmt::Break(b) => {
                 // This would not be possible because it is the result
                 // of a condition, and would require branching without
                 // assignment or return of same type
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Break statements are not supported yet."),
                     &b.range(),
-                )
+                ))
             }
             ast::Stmt::Continue(c) => {
                 // The issue is that branching requires assignment or return
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Continue statements are not supported yet."),
                     &c.range(),
-                )
+                ))
             }
         }
-        .map_err(|err| format!("{err}"))
     }
 
     fn set_lhs_ty_defn<const IS_CNST: bool>(
         &self,
         val: &ast::Expr,
         d: &ast::Stmt
-    ) -> Result<(), String> {
+    ) -> Result<(), Diagnostic> {
         assert!(self.lhs_ty.borrow().is_none());
         if matches!(val.clone(), ast::Expr::Call(_)) {
             let ty = Some(self.lhs_type::<IS_CNST>(
@@ -1879,7 +3569,7 @@ impl<'a> PyGen<'a> {
         }
     }
 
-    fn get_lhs_name<const IS_CNST: bool>(&self, e: &ast::Expr) -> Result<ast::ExprName, String> {
+    fn get_lhs_name<const IS_CNST: bool>(&self, e: &ast::Expr) -> Result<ast::ExprName, Diagnostic> {
         match e {
             ast::Expr::Attribute(a) => {
                 match a.value.as_ref() {
@@ -1887,10 +3577,11 @@ impl<'a> PyGen<'a> {
                     ast::Expr::Subscript(s) => self.get_lhs_name::<IS_CNST>(&ast::Expr::from(s.clone())),
                     ast::Expr::Name(n) => Ok(n.clone()),
                     err => {
-                        self.err(
+                        Err(self.err(
+                            ErrorCode::Unsupported,
                             format!("Attribute or subscript must be associated to an identifier, another attribute or another subscript."),
                             &err.range(),
-                        )
+                        ))
                     }
                 }
             }
@@ -1900,19 +3591,21 @@ impl<'a> PyGen<'a> {
                     ast::Expr::Subscript(ss) => self.get_lhs_name::<IS_CNST>(&ast::Expr::from(ss.clone())),
                     ast::Expr::Name(n) => Ok(n.clone()),
                     err => {
-                        self.err(
+                        Err(self.err(
+                            ErrorCode::Unsupported,
                             format!("Attribute or subscript must be associated to an identifier, another attribute or another subscript."),
                             &err.range(),
-                        )
+                        ))
                     }
                 }
             }
             ast::Expr::Name(n) => Ok(n.clone()),
             err => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Incorrect variable assignment."),
                     &err.range(),
-                )
+                ))
             }
         }
     }
@@ -1921,7 +3614,7 @@ impl<'a> PyGen<'a> {
         &self,
         val: &ast::Expr,
         ty: Ty,
-    ) -> Result<Ty, String> {
+    ) -> Result<Ty, Diagnostic> {
         match val {
             ast::Expr::Subscript(s) => {
                 let ty: Ty = self.lhs_type_eval_expr::<IS_CNST>(&s.value, ty)?;
@@ -1930,7 +3623,11 @@ impl<'a> PyGen<'a> {
                         ast::Expr::Slice(_) => Ok(Ty::Array(sz, ity)),
                         _ => Ok(*ity),
                     }
-                    ty => Err(format!("Attempted array access on non-Array type {ty}")),
+                    ty => Err(self.err(
+                        ErrorCode::TypeMismatch,
+                        format!("Attempted array access on non-Array type {ty}"),
+                        &s.value.range(),
+                    )),
                 }
             }
             ast::Expr::Attribute(a) => {
@@ -1940,17 +3637,26 @@ impl<'a> PyGen<'a> {
                         .search(a.attr.as_str())
                         .map(|r| r.1.clone())
                         .ok_or_else(|| {
-                            format!("No such attribute {} of class {nm}", a.attr.as_str())
+                            self.err(
+                                ErrorCode::UndefinedName,
+                                format!("No such attribute {} of class {nm}", a.attr.as_str()),
+                                &a.range(),
+                            )
                         }),
-                    ty => Err(format!("Attempted member access on non-Class type {ty}")),
+                    ty => Err(self.err(
+                        ErrorCode::TypeMismatch,
+                        format!("Attempted member access on non-Class type {ty}"),
+                        &a.value.range(),
+                    )),
                 }
             }
             ast::Expr::Name(_) => Ok(ty),
             err => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::TypeMismatch,
                     format!("Only arrays and object members can be accessed."),
                     &err.range(),
-                )
+                ))
             }
         }
     }
@@ -1958,7 +3664,7 @@ impl<'a> PyGen<'a> {
     fn lhs_type<const IS_CNST: bool>(
         &self,
         assign: &ast::Stmt,
-    ) -> Result<Ty, String> {
+    ) -> Result<Ty, Diagnostic> {
         match assign {
             // Note that right now we only support single assignments.
             // Multi-assign would be nice to add in the future.
@@ -1974,10 +3680,11 @@ impl<'a> PyGen<'a> {
             }
             ast::Stmt::AnnAssign(t) => self.type_impl_::<IS_CNST>(&t.annotation),
             err => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::Unsupported,
                     format!("Incorrect variable assignment."),
                     &err.range(),
-                )
+                ))
             }
         }
     }
@@ -2076,25 +3783,6 @@ impl<'a> PyGen<'a> {
         }
     }
 
-    fn ret_ty_stack_push<const IS_CNST: bool>(
-        &self,
-        fn_def: &ast::StmtFunctionDef,
-    ) -> Result<(), String> {
-        // Maybe there is a way to do this more nicely/efficiently,
-        // but for now this works.
-        // Also, this does not work for multi-returns since type_impl_
-        // does not handle Tuples yet.
-        let ty = fn_def
-            .returns
-            .as_ref()
-            .map(|idx| *idx.clone())
-            .map(|ty| self.type_impl_::<IS_CNST>(&ty))
-            .transpose()?
-            .unwrap_or(Ty::Bool);
-        self.ret_ty_stack.borrow_mut().push(ty);
-        Ok(())
-    }
-
     fn ret_ty_stack_pop(&self) {
         self.ret_ty_stack.borrow_mut().pop();
     }
@@ -2107,12 +3795,55 @@ impl<'a> PyGen<'a> {
         self.crets_stack.borrow_mut().push(ret)
     }
 
+    fn cvar_returned(&self) -> bool {
+        // Statements stop being evaluated after a const return, so the
+        // only pending return value can be the one of the current function.
+        !self.crets_stack.borrow().is_empty()
+    }
+
+    /* Memory-checking (RAM) lowering of arrays */
+
+    fn is_ram_annotation(&self, t: &ast::Expr) -> bool {
+        match t {
+            ast::Expr::Subscript(s) => match (s.value.as_ref(), s.slice.as_ref()) {
+                (ast::Expr::Name(n), inner) if matches!(n.id.as_str(), "Private" | "Public") => {
+                    self.is_ram_annotation(inner)
+                }
+                (ast::Expr::Name(n), ast::Expr::Tuple(t)) if n.id.as_str() == "Array" => {
+                    t.elts.len() == 3 && is_ram_marker(&t.elts[2])
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    // Remember that the variable `name` of the current function is annotated as RAM
+    fn declare_ram_array(&self, name: &str, annotation: &ast::Expr) {
+        if self.is_ram_annotation(annotation) {
+            self.ram_arrays
+                .borrow_mut()
+                .insert((self.curr_func.borrow().clone(), name.to_string()));
+        }
+    }
+
+    fn is_ram_array(&self, name: &str) -> bool {
+        match self.array_strategy {
+            ArrayStrategy::Annotated => self
+                .ram_arrays
+                .borrow()
+                .contains(&(self.curr_func.borrow().clone(), name.to_string())),
+            ArrayStrategy::LinearScan => false,
+            ArrayStrategy::Ram => true,
+        }
+    }
+
     fn crets_pop(&self) -> PyTerm {
         assert!(!self.crets_stack.borrow().is_empty());
         self.crets_stack.borrow_mut().pop().unwrap()
     }
 
-    fn const_decl_(&mut self, c: &mut ast::StmtAnnAssign) {
+    fn const_decl_(&mut self, c: &mut ast::StmtAnnAssign) -> Result<(), Diagnostic> {
         // We assume that any annotated assignment in the main body is
         // a constant declaration (since we don't have a const keyword in Python).
         // Moreover, we do very minimal type checking since we haven't implemented
@@ -2120,41 +3851,49 @@ impl<'a> PyGen<'a> {
 
         // make sure that this wasn't already an important const name
         let ast::Expr::Name(n) = c.target.as_ref() else {
-            self.err(
+            return Err(self.err(
+                ErrorCode::Unsupported,
                 format!("No name provided for const assignment."),
                 &c.target.range(),
-            )
+            ));
         };
         if self
             .cur_import_map()
             .map(|m| m.contains_key(n.id.as_str()))
             .unwrap_or(false)
         {
-            self.err(
+            return Err(self.err(
+                ErrorCode::UndefinedName,
                 format!("Constant {} clashes with import of same name", n.id.as_str()),
                 &c.range(),
-            );
+            ));
         }
         // Do we really need Python visitors/mutators?
         // Maybe use them where applicable in a future code refactor.
 
         // check that constant variable assignment has valid type
-        let ctype = self.unwrap(self.type_impl_::<true>(&c.annotation), &c.annotation.range());
+        let ctype = self.type_impl_::<true>(&c.annotation)?;
 
         // evaluate the expression and check the resulting type
-        let value = self
-            .expr_impl_::<true>(c.value.clone().unwrap().as_ref())
-            .unwrap_or_else(|e| self.err(e, &c.value.clone().unwrap().range()));
+        let Some(value) = c.value.as_ref() else {
+            return Err(self.err(
+                ErrorCode::Syntax,
+                format!("Constant {} has no value.", n.id.as_str()),
+                &c.range(),
+            ));
+        };
+        let value = self.expr_impl_::<true>(value)?;
 
         if &ctype != value.type_() {
-            self.err(
+            return Err(self.err(
+                ErrorCode::TypeMismatch,
                 format!(
                     "Type mismatch in constant definition: expected {:?}, got {:?}",
                     ctype,
                     value.type_()
                 ),
                 &c.range(),
-            );
+            ));
         }
         // insert into constant map
         if self
@@ -2164,15 +3903,12 @@ impl<'a> PyGen<'a> {
             .insert(n.id.to_string(), (*c.annotation.clone(), value))
             .is_some()
         {
-            self.err(format!("Constant {} redefined", &n.id.as_str()), &c.range());
+            return Err(self.err(ErrorCode::UndefinedName, format!("Constant {} redefined", &n.id.as_str()), &c.range()));
         }
+        Ok(())
     }
 
-    fn type_(&self, t: &ast::Expr) -> Ty {
-        self.unwrap(self.type_impl_::<false>(&t.clone()), &t.range())
-    }
-
-    fn type_impl_<const IS_CNST: bool>(&self, t: &ast::Expr) -> Result<Ty, String> {
+    fn type_impl_<const IS_CNST: bool>(&self, t: &ast::Expr) -> Result<Ty, Diagnostic> {
         if IS_CNST {
             debug!("Const type: {:?}", t);
         } else {
@@ -2183,27 +3919,31 @@ impl<'a> PyGen<'a> {
         match t {
             ast::Expr::Subscript(s) => {
                 let ast::Expr::Name(n) = s.value.as_ref() else {
-                    self.err(
+                    return Err(self.err(
+                        ErrorCode::UndefinedName,
                         format!("Error while interpreting type annotation of assignment. Subscript identifier could not be found."),
                         &s.value.range(),
-                    )
+                    ));
                 };
                 if n.id.as_str() == "Array" {
                     let ast::Expr::Tuple(t) = s.slice.as_ref() else {
-                        self.err(
+                        return Err(self.err(
+                            ErrorCode::InvalidAnnotation,
                             format!("Array type has 2 fields. The first position should be the datatype, and the second position should be the size."),
                             &s.slice.range(),
-                        )
+                        ));
                     };
-                    let expr = &t.elts[0];
-                    let dim = &t.elts[1];
-                    if t.elts.len() != 2 {
-                        self.err(
-                            format!("Array type has 2 fields. The first position should be the datatype, and the second position should be the size."),
+                    // An optional third field `RAM` only selects how the array is lowered
+                    if t.elts.len() != 2 && !(t.elts.len() == 3 && is_ram_marker(&t.elts[2])) {
+                        return Err(self.err(
+                            ErrorCode::InvalidAnnotation,
+                            format!("Array type has 2 fields. The first position should be the datatype, and the second position should be the size. An optional third field RAM requests memory-checking."),
                             &t.range(),
-                        )
+                        ));
                     }
-                    if let ast::Expr::Constant(c) = dim {
+                    let expr = &t.elts[0];
+                    let dim = &t.elts[1];
+                    let d = if let ast::Expr::Constant(c) = dim {
                         let d = c.value
                             .as_int()
                             .unwrap()
@@ -2211,15 +3951,49 @@ impl<'a> PyGen<'a> {
                             .into_iter()
                             .next()
                             .unwrap_or(0);
-                        let b = self.type_impl_::<IS_CNST>(expr);
-                        Ok(Ty::Array(d.try_into().unwrap(), Box::new(b?)))
+                        d.try_into().unwrap()
                     } else {
-                        // I suppose the static bound could be inferred, so need not 
-                        // necessarily be a constant. Improve this in the future.
-                        self.err(
-                            format!("The second field of the array type must be a constant."),
-                            &dim.range(),
-                        )
+                        // A module constant, a size parameter of the function
+                        // being called, or an expression over them.
+                        match self.const_usize_impl_::<IS_CNST>(dim) {
+                            Ok(d) => d,
+                            Err(_) => return Err(self.err(
+                                ErrorCode::NotConstant,
+                                format!("The second field of the array type must be a constant or a size parameter inferred from the arguments."),
+                                &dim.range(),
+                            )),
+                        }
+                    };
+                    let b = self.type_impl_::<IS_CNST>(expr);
+                    Ok(Ty::Array(d, Box::new(b?)))
+                } else if n.id.as_str() == "Tuple" || n.id.as_str() == "tuple" {
+                    match s.slice.as_ref() {
+                        ast::Expr::Tuple(t) => self.type_impl_::<IS_CNST>(&ast::Expr::from(t.clone())),
+                        e => Ok(Ty::Tuple(vec![self.type_impl_::<IS_CNST>(e)?])),
+                    }
+                } else if n.id.as_str() == "Fixed" {
+                    let ast::Expr::Tuple(t) = s.slice.as_ref() else {
+                        return Err(self.err(
+                            ErrorCode::InvalidAnnotation,
+                            format!("Fixed type has 2 fields: the number of integer bits and the number of fractional bits."),
+                            &s.slice.range(),
+                        ));
+                    };
+                    if t.elts.len() != 2 {
+                        return Err(self.err(
+                            ErrorCode::InvalidAnnotation,
+                            format!("Fixed type has 2 fields: the number of integer bits and the number of fractional bits."),
+                            &t.range(),
+                        ));
+                    }
+                    let bits = t.elts.iter().map(|e| self.const_usize_impl_::<IS_CNST>(e).ok());
+                    match bits.collect::<Option<Vec<usize>>>().as_deref() {
+                        Some(&[i, f]) if i > 0 => Ok(Ty::Fixed(i, f)),
+                        _ => Err(self.err(
+                            ErrorCode::NotConstant,
+                            format!("The fields of the Fixed type must be constants, with at least one integer bit."),
+                            &t.range(),
+                        )),
                     }
                 } else if n.id.as_str() == "Private" || n.id.as_str() == "Public" {
                     // Accessibilities don't have a type associated to it, so just continue
@@ -2227,101 +4001,123 @@ impl<'a> PyGen<'a> {
                 } else {
                     // If we have subscript for any other identifier, then that is wrong.
                     // Atm we only want this for arrays (maybe other types in the future).
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::InvalidAnnotation,
                         format!("Invalid type."),
                         &s.slice.range(),
-                    )
+                    ))
                 }
             }
             ast::Expr::Name(n) => {
                 let name = n.id.as_str();
                 let (class_path, class_name) = self.deref_import(name);
                 if name == "float" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Floats are not supported yet."),
                         &n.range(),
-                    )
+                    ))
                 } else if name == "complex" {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::Unsupported,
                         format!("Complex numbers are not supported yet."),
                         &n.range(),
-                    )
+                    ))
                 } else if name == "bool" {
                     Ok(Ty::Bool)
                 } else if name == "field" {
                     Ok(Ty::Field)
                 } else if name == "int" {
                     Ok(Ty::Uint(32))
+                } else if let Some(w) = signed_int_width(name) {
+                    Ok(Ty::Int(w))
                 } else if let Some(m) = self.classes_and_tys.get(&class_path) {
                     // If class_name is a defined class, 
                     let (def, path) = self.get_class_or_type(&class_name).ok_or_else(|| {
-                        format!(
-                            "No such class {} (did you bring it into scope?)",
-                            &class_name
+                        self.err(
+                            ErrorCode::UndefinedName,
+                            format!(
+                                "No such class {} (did you bring it into scope?)",
+                                &class_name
+                            ),
+                            &n.range(),
                         )
                     })?;
                     if m.contains_key(&class_name) {
                         self.file_stack_push(path);
                         let ty = match def {
-                            Ok(sdef) => Ty::new_class(
-                                sdef.name.to_string(),
-                                sdef.body
-                                    .iter()
-                                    .map::<Result<_, String>, _>(|f| {
-                                        if let ast::Stmt::AnnAssign(a) = f {
-                                            if let ast::Expr::Name(n) = a.target.as_ref() {
-                                                Ok((n.id.to_string(), self.type_impl_::<IS_CNST>(&a.annotation)?))
-                                            } else {
-                                                self.err(
-                                                    format!("Missing name of struct field."),
-                                                    &a.target.range(),
-                                                )
-                                            }
+                            Ok(sdef) => sdef
+                                .body
+                                .iter()
+                                // methods are not fields
+                                .filter(|f| !matches!(f, ast::Stmt::FunctionDef(_)))
+                                .map::<Result<_, Diagnostic>, _>(|f| {
+                                    if let ast::Stmt::AnnAssign(a) = f {
+                                        if let ast::Expr::Name(n) = a.target.as_ref() {
+                                            Ok((n.id.to_string(), self.type_impl_::<IS_CNST>(&a.annotation)?))
                                         } else {
-                                            self.err(
-                                                format!("Only annotated assignments are allowed in class definitions."),
-                                                &f.range(),
-                                            )
+                                            Err(self.err(
+                                                ErrorCode::InvalidAnnotation,
+                                                format!("Missing name of struct field."),
+                                                &a.target.range(),
+                                            ))
                                         }
-                                    })
-                                    .collect::<Result<Vec<_>, _>>()?,
-                            ),
-                            Err(tdef) => self.type_impl_::<IS_CNST>(&tdef.value)?,
+                                    } else {
+                                        Err(self.err(
+                                            ErrorCode::InvalidAnnotation,
+                                            format!("Only annotated assignments are allowed in class definitions."),
+                                            &f.range(),
+                                        ))
+                                    }
+                                })
+                                .collect::<Result<Vec<_>, _>>()
+                                .map(|fields| Ty::new_class(sdef.name.to_string(), fields)),
+                            Err(tdef) => self.type_impl_::<IS_CNST>(&tdef.value),
                         };
                         self.file_stack_pop();
-                        Ok(ty)
+                        ty
                     } else {
-                        self.err(
+                        Err(self.err(
+                            ErrorCode::InvalidAnnotation,
                             format!("Invalid type."),
                             &n.range(),
-                        )
+                        ))
                     }
                     // I'm too lazy to avoid code repetition atm.
                 } else {
-                    self.err(
+                    Err(self.err(
+                        ErrorCode::InvalidAnnotation,
                         format!("Invalid type."),
                         &n.range(),
-                    )
+                    ))
                 }
             }
+            ast::Expr::Tuple(t) => Ok(Ty::Tuple(
+                t.elts
+                    .iter()
+                    .map(|e| self.type_impl_::<IS_CNST>(e))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
             err => {
-                self.err(
+                Err(self.err(
+                    ErrorCode::InvalidAnnotation,
                     format!("Invalid type."),
                     &err.range(),
-                )
+                ))
             }
         }
     }
 
-    fn visit_files(&mut self, entry_point: &String) {
+    fn visit_files(&mut self, entry_point: &String) -> Result<(), Diagnostic> {
         // 1. go through includes and return a toposorted visit order for remaining processing
-        let files = self.visit_imports(entry_point);
+        let files = self.visit_imports(entry_point)?;
 
         // 2. visit constant, class, and function defs ; infer types
         self.visit_body(files);
+        Ok(())
     }
 
-    fn visit_imports(&mut self, entry_point: &String) -> Vec<PathBuf> {
+    fn visit_imports(&mut self, entry_point: &String) -> Result<Vec<PathBuf>, Diagnostic> {
         use petgraph::algo::toposort;
         use petgraph::graph::{DefaultIx, DiGraph, NodeIndex};
         let asts = std::mem::take(&mut self.asts);
@@ -2349,18 +4145,20 @@ impl<'a> PyGen<'a> {
                     ast::Stmt::Import(m) => (
                         m.names[0].name.to_string(),
                         vec![entry_point.to_string()],
-                        vec![m
-                            .names[0]
-                            .asname
-                            .as_ref()
-                            .map(|a| a.to_string())
-                            .unwrap_or_else(|| {
-                                PathBuf::from(m.names[0].name.to_string())
-                                    .file_stem()
-                                    .unwrap_or_else(|| panic!("Bad import: {}", m.names[0].name.as_str()))
-                                    .to_string_lossy()
-                                    .to_string()
-                            })],
+                        vec![match &m.names[0].asname {
+                            Some(a) => a.to_string(),
+                            None => PathBuf::from(m.names[0].name.to_string())
+                                .file_stem()
+                                .ok_or_else(|| {
+                                    self.err(
+                                        ErrorCode::Import,
+                                        format!("Bad import: {}", m.names[0].name.as_str()),
+                                        &m.range(),
+                                    )
+                                })?
+                                .to_string_lossy()
+                                .to_string(),
+                        }],
                         m.range(),
                     ),
                     ast::Stmt::ImportFrom(m) => (
@@ -2388,15 +4186,12 @@ impl<'a> PyGen<'a> {
                     abs_src_path.display(),
                     dst_names
                 );
-                src_names
-                    .into_iter()
-                    .zip(dst_names.into_iter())
-                    .for_each(|(sn, dn)| {
-                        if imap.contains_key(&dn) {
-                            self.err(format!("Import {dn} redeclared"), &i_span);
-                        }
-                        assert!(imap.insert(dn, (abs_src_path.clone(), sn)).is_none());
-                    });
+                for (sn, dn) in src_names.into_iter().zip(dst_names.into_iter()) {
+                    if imap.contains_key(&dn) {
+                        return Err(self.err(ErrorCode::UndefinedName, format!("Import {dn} redeclared"), &i_span));
+                    }
+                    imap.insert(dn, (abs_src_path.clone(), sn));
+                }
 
                 // add included -> includer edge for later toposort
                 if !gn.contains_key(&abs_src_path) {
@@ -2413,19 +4208,22 @@ impl<'a> PyGen<'a> {
         // flatten the import map, i.e., a -> b -> c becomes a -> c
         self.flatten_import_map();
 
-        toposort(&ig, None)
-            .unwrap_or_else(|e| {
-                use petgraph::dot::{Config, Dot};
-                panic!(
-                    "Import graph is cyclic!: {:?}\n{:?}\n",
-                    e,
-                    Dot::with_config(&ig, &[Config::EdgeNoLabel])
-                )
-            })
+        let order = toposort(&ig, None).map_err(|e| {
+            use petgraph::dot::{Config, Dot};
+            debug!("{:?}", Dot::with_config(&ig, &[Config::EdgeNoLabel]));
+            let p = &ig[e.node_id()];
+            self.diagnostic(
+                ErrorCode::Import,
+                format!("Import graph is cyclic: {} imports itself", p.display()),
+                p,
+                &TextRange::default(),
+            )
+        })?;
+        Ok(order
             .iter()
             .map(|idx| std::mem::take(ig.node_weight_mut(*idx).unwrap()))
             .filter(|p| self.asts.contains_key(p))
-            .collect()
+            .collect())
     }
 
     fn flatten_import_map(&mut self) {
@@ -2471,217 +4269,306 @@ impl<'a> PyGen<'a> {
     fn visit_body(&mut self, files: Vec<PathBuf>) {
         let mut t = std::mem::take(&mut self.asts);
         for p in files {
-            self.constants.insert(p.clone(), HashMap::new());
-            self.classes_and_tys.insert(p.clone(), HashMap::new());
-            self.functions.insert(p.clone(), HashMap::new());
-            self.file_stack_push(p.clone());
-            let ast::Mod::Module(m) = t.get_mut(&p).unwrap() else {
-                panic!("Loaded file {} does not implement module.", p.display())
-            };
-            for d in m.body.iter_mut() {
-                match d {
-                    ast::Stmt::FunctionDef(f) => {
-                        debug!("processing decl: fn {} in {}", f.name.as_str(), p.display());
-                        let f_ast = f.clone();
-
-                        // Do not check return type of embedded functions
-                        let (f_path, _) = self.deref_import(f.name.as_str());
-                        if !self.stdlib.is_embed(&f_path) {
-                            if self.function_ret_type(&f_ast).len() != 1 {
-                                // functions MUST return exactly 1 value
-                                self.err(
-                                    format!(
-                                        "Functions must return exactly 1 value; {} returns {}",
-                                        &f_ast.name.as_str(),
-                                        self.function_ret_type(&f_ast).len(),
-                                    ),
-                                    &f.range(),
-                                );
-                            }
-                        }
-
-                        if self
-                            .functions
-                            .get_mut(self.file_stack.borrow().last().unwrap())
-                            .unwrap()
-                            .insert(f.name.to_string(), f_ast)
-                            .is_some()
-                        {
-                            self.err(format!("Function {} redefined", &f.name.as_str()), &f.range());
-                        }
-                    }
-                    ast::Stmt::AsyncFunctionDef(a) => {
-                        self.err(
-                            format!("Async function def statement is not supported yet."),
-                            &a.range(),
-                        )
-                    }
-                    ast::Stmt::ClassDef(c) => {
-                        debug!("processing decl: class {} in {}", c.name.as_str(), p.display());
-                        let c_ast = c.clone();
-
-                        if self
-                            .classes_and_tys
-                            .get_mut(self.file_stack.borrow().last().unwrap())
-                            .unwrap()
-                            .insert(c.name.to_string(), Ok(c_ast))
-                            .is_some()
-                        {
-                            self.err(
-                                format!("Struct {} defined over existing name", c.name.as_str()),
-                                &c.range(),
-                            );
-                        }
-                    }
-                    ast::Stmt::Return(r) => {
-                        self.err(
-                            format!("Return statement must live inside of a function."),
-                            &r.range(),
-                        )
-                    }
-                    ast::Stmt::Delete(dd) => {
-                        self.err(
-                            format!("Delete statement is not supported yet."),
-                            &dd.range(),
-                        )
-                    }
-                    ast::Stmt::Assign(a) => {
-                        self.err(
-                            format!("Constant declaration needs type annotation."),
-                            &a.range(),
-                        )
-                    }
-                    ast::Stmt::TypeAlias(t) => {
-                        self.err(
-                            format!("Type aliases are not supported yet."),
-                            &t.range(),
-                        )
-                    }
-                    ast::Stmt::AugAssign(a) => {
-                        self.err(
-                            format!("Augmented assignment statement must live inside of a function."),
-                            &a.range(),
-                        )
-                    }
-                    ast::Stmt::AnnAssign(a) => {
-                        let ast::Expr::Name(n) = a.target.as_ref() else {
-                            self.err(
-                                format!("No name provided for const assignment."),
-                                &a.target.range(),
-                            )
-                        };
-                        debug!("processing decl: const {} in {}", n.id.as_str(), p.display());
-                        self.const_decl_(a);
-                    }
-                    ast::Stmt::For(f) => {
-                        self.err(
-                            format!("For statement must live inside of a function."),
-                            &f.range(),
-                        )
-                    }
-                    ast::Stmt::AsyncFor(a) => {
-                        self.err(
-                            format!("Async for statement must live inside of a function."),
-                            &a.range(),
-                        )
-                    }
-                    ast::Stmt::While(w) => {
-                        self.err(
-                            format!("While statement must live inside of a function."),
-                            &w.range(),
-                        )
-                    }
-                    ast::Stmt::If(i) => {
-                        self.err(
-                            format!("If statement must live inside of a function."),
-                            &i.range(),
-                        )
-                    }
-                    ast::Stmt::With(w) => {
-                        self.err(
-                            format!("With statement must live inside of a function."),
-                            &w.range(),
-                        )
-                    }
-                    ast::Stmt::AsyncWith(a) => {
-                        self.err(
-                            format!("Async with statement must live inside of a function."),
-                            &a.range(),
-                        )
-                    }
-                    ast::Stmt::Match(m) => {
-                        self.err(
-                            format!("Match statement must live inside of a function."),
-                            &m.range(),
-                        )
-                    }
-                    ast::Stmt::Raise(r) => {
-                        self.err(
-                            format!("Raise statement must live inside of a function."),
-                            &r.range(),
-                        )
-                    }
-                    ast::Stmt::Try(t) => {
-                        self.err(
-                            format!("Try statement must live inside of a function."),
-                            &t.range(),
-                        )
-                    }
-                    ast::Stmt::TryStar(t) => {
-                        self.err(
-                            format!("Try star statement must live inside of a function."),
-                            &t.range(),
-                        )
-                    }
-                    ast::Stmt::Assert(a) => {
-                        self.err(
-                            format!("Assert statement must live inside of a function."),
-                            &a.range(),
-                        )
-                    }
-                    ast::Stmt::Import(_) => (), // already handled in visit_imports.
-                    ast::Stmt::ImportFrom(_) => (), // already handled in visit_imports.
-                    ast::Stmt::Global(g) => {
-                        self.err(
-                            format!("Global statement must live inside of a function."),
-                            &g.range(),
-                        )
-                    }
-                    ast::Stmt::Nonlocal(n) => {
-                        self.err(
-                            format!("Nonlocal statement must live inside of a function."),
-                            &n.range(),
-                        )
-                    }
-                    ast::Stmt::Expr(e) => {
-                        self.err(
-                            format!("Expression statements are not supported."),
-                            &e.range(),
-                        )
-                    }
-                    ast::Stmt::Pass(pp) => {
-                        self.err(
-                            format!("Pass statement must live inside of a function."),
-                            &pp.range(),
-                        )
+            self.constants.insert(p.clone(), HashMap::new());
+            self.classes_and_tys.insert(p.clone(), HashMap::new());
+            self.functions.insert(p.clone(), HashMap::new());
+            self.file_stack_push(p.clone());
+            let ast::Mod::Module(m) = t.get_mut(&p).unwrap() else {
+                panic!("Loaded file {} does not implement module.", p.display())
+            };
+            for d in m.body.iter_mut() {
+                if let Err(e) = self.visit_decl(&p, d) {
+                    self.recover(e, d);
+                }
+            }
+            self.file_stack_pop();
+        }
+        self.asts = t;
+    }
+
+    // Declares a statement of the module body
+    fn visit_decl(&mut self, p: &PathBuf, d: &mut ast::Stmt) -> Result<(), Diagnostic> {
+        match d {
+            ast::Stmt::FunctionDef(f) => {
+                debug!("processing decl: fn {} in {}", f.name.as_str(), p.display());
+                let f_ast = f.clone();
+
+                // Do not check return type of embedded functions
+                let (f_path, _) = self.deref_import(f.name.as_str());
+                if !self.stdlib.is_embed(&f_path) {
+                    if self.function_ret_type(&f_ast).is_empty() {
+                        // functions MUST return at least 1 value
+                        return Err(self.err(
+                            ErrorCode::Unsupported,
+                            format!(
+                                "Functions must return at least 1 value; {} returns none",
+                                &f_ast.name.as_str(),
+                            ),
+                            &f.range(),
+                        ));
                     }
-                    ast::Stmt::Break(b) => {
-                        self.err(
-                            format!("Break statement must live inside of a function."),
-                            &b.range(),
-                        )
+                }
+
+                if self
+                    .functions
+                    .get_mut(self.file_stack.borrow().last().unwrap())
+                    .unwrap()
+                    .insert(f.name.to_string(), f_ast)
+                    .is_some()
+                {
+                    return Err(self.err(ErrorCode::UndefinedName, format!("Function {} redefined", &f.name.as_str()), &f.range()));
+                }
+                Ok(())
+            }
+            ast::Stmt::AsyncFunctionDef(a) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Async function def statement is not supported yet."),
+                    &a.range(),
+                ))
+            }
+            ast::Stmt::ClassDef(c) => {
+                debug!("processing decl: class {} in {}", c.name.as_str(), p.display());
+                let c_ast = c.clone();
+
+                if self
+                    .classes_and_tys
+                    .get_mut(self.file_stack.borrow().last().unwrap())
+                    .unwrap()
+                    .insert(c.name.to_string(), Ok(c_ast))
+                    .is_some()
+                {
+                    return Err(self.err(
+                        ErrorCode::UndefinedName,
+                        format!("Struct {} defined over existing name", c.name.as_str()),
+                        &c.range(),
+                    ));
+                }
+
+                for s in c.body.iter() {
+                    let ast::Stmt::FunctionDef(f) = s else {
+                        continue;
+                    };
+                    debug!("processing decl: method {}.{} in {}", c.name.as_str(), f.name.as_str(), p.display());
+                    let is_static = f.decorator_list.iter().any(|d| {
+                        matches!(d, ast::Expr::Name(n) if n.id.as_str() == "staticmethod")
+                    });
+                    let mut f_ast = f.clone();
+                    if is_static {
+                        if self.function_ret_type(&f_ast).is_empty() {
+                            return Err(self.err(
+                                ErrorCode::Unsupported,
+                                format!(
+                                    "Functions must return at least 1 value; {} returns none",
+                                    &f_ast.name.as_str(),
+                                ),
+                                &f.range(),
+                            ));
+                        }
+                    } else {
+                        // `self` is passed by value, as an instance of the class.
+                        // Methods that only mutate it need not return anything.
+                        match f_ast.args.args.first_mut() {
+                            Some(a) if a.def.annotation.is_none() => {
+                                a.def.annotation = Some(Box::new(ast::Expr::Name(ast::ExprName {
+                                    range: a.def.range,
+                                    id: c.name.clone(),
+                                    ctx: ast::ExprContext::Load,
+                                })));
+                            }
+                            Some(_) => {}
+                            None => return Err(self.err(
+                                ErrorCode::Arity,
+                                format!("Method {} must take self as its first parameter.", f.name.as_str()),
+                                &f.range(),
+                            )),
+                        }
                     }
-                    ast::Stmt::Continue(c) => {
-                        self.err(
-                            format!("Continue statement must live inside of a function."),
-                            &c.range(),
-                        )
+
+                    if self
+                        .functions
+                        .get_mut(self.file_stack.borrow().last().unwrap())
+                        .unwrap()
+                        .insert(format!("{}.{}", c.name.as_str(), f.name.as_str()), f_ast)
+                        .is_some()
+                    {
+                        return Err(self.err(ErrorCode::UndefinedName, format!("Method {} redefined", &f.name.as_str()), &f.range()));
                     }
+                    self.methods
+                        .entry((p.clone(), c.name.to_string()))
+                        .or_default()
+                        .insert(f.name.to_string(), is_static);
                 }
+                Ok(())
+            }
+            ast::Stmt::Return(r) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Return statement must live inside of a function."),
+                    &r.range(),
+                ))
+            }
+            ast::Stmt::Delete(dd) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Delete statement is not supported yet."),
+                    &dd.range(),
+                ))
+            }
+            ast::Stmt::Assign(a) => {
+                Err(self.err(
+                    ErrorCode::InvalidAnnotation,
+                    format!("Constant declaration needs type annotation."),
+                    &a.range(),
+                ))
+            }
+            ast::Stmt::TypeAlias(t) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Type aliases are not supported yet."),
+                    &t.range(),
+                ))
+            }
+            ast::Stmt::AugAssign(a) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Augmented assignment statement must live inside of a function."),
+                    &a.range(),
+                ))
+            }
+            ast::Stmt::AnnAssign(a) => {
+                let ast::Expr::Name(n) = a.target.as_ref() else {
+                    return Err(self.err(
+                        ErrorCode::Unsupported,
+                        format!("No name provided for const assignment."),
+                        &a.target.range(),
+                    ));
+                };
+                debug!("processing decl: const {} in {}", n.id.as_str(), p.display());
+                self.const_decl_(a)
+            }
+            ast::Stmt::For(f) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("For statement must live inside of a function."),
+                    &f.range(),
+                ))
+            }
+            ast::Stmt::AsyncFor(a) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Async for statement must live inside of a function."),
+                    &a.range(),
+                ))
+            }
+            ast::Stmt::While(w) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("While statement must live inside of a function."),
+                    &w.range(),
+                ))
+            }
+            ast::Stmt::If(i) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("If statement must live inside of a function."),
+                    &i.range(),
+                ))
+            }
+            ast::Stmt::With(w) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("With statement must live inside of a function."),
+                    &w.range(),
+                ))
+            }
+            ast::Stmt::AsyncWith(a) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Async with statement must live inside of a function."),
+                    &a.range(),
+                ))
+            }
+            ast::Stmt::Match(m) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Match statement must live inside of a function."),
+                    &m.range(),
+                ))
+            }
+            ast::Stmt::Raise(r) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Raise statement must live inside of a function."),
+                    &r.range(),
+                ))
+            }
+            ast::Stmt::Try(t) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Try statement must live inside of a function."),
+                    &t.range(),
+                ))
+            }
+            ast::Stmt::TryStar(t) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Try star statement must live inside of a function."),
+                    &t.range(),
+                ))
+            }
+            ast::Stmt::Assert(a) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Assert statement must live inside of a function."),
+                    &a.range(),
+                ))
+            }
+            ast::Stmt::Import(_) => Ok(()), // already handled in visit_imports.
+            ast::Stmt::ImportFrom(_) => Ok(()), // already handled in visit_imports.
+            ast::Stmt::Global(g) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Global statement must live inside of a function."),
+                    &g.range(),
+                ))
+            }
+            ast::Stmt::Nonlocal(n) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Nonlocal statement must live inside of a function."),
+                    &n.range(),
+                ))
+            }
+            ast::Stmt::Expr(e) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Expression statements are not supported."),
+                    &e.range(),
+                ))
+            }
+            ast::Stmt::Pass(pp) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Pass statement must live inside of a function."),
+                    &pp.range(),
+                ))
+            }
+            ast::Stmt::Break(b) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Break statement must live inside of a function."),
+                    &b.range(),
+                ))
+            }
+            ast::Stmt::Continue(c) => {
+                Err(self.err(
+                    ErrorCode::Unsupported,
+                    format!("Continue statement must live inside of a function."),
+                    &c.range(),
+                ))
             }
-            self.file_stack_pop();
         }
-        self.asts = t;
     }
 
     fn get_function(&self, fn_id: &str) -> Option<&ast::StmtFunctionDef> {
@@ -2705,14 +4592,13 @@ impl<'a> PyGen<'a> {
 
     fn assert(&self, asrt: Term) {
         debug_assert!(matches!(check(&asrt), Sort::Bool));
-        if self.isolate_asserts {
-            let path = self.circ_condition();
-            self.assertions
-                .borrow_mut()
-                .push(term![IMPLIES; path, asrt]);
-        } else {
-            self.assertions.borrow_mut().push(asrt);
-        }
+        // If statements (and early returns) always enter a condition, so
+        // an assertion only needs to hold on the path that reaches it.
+        // Ternary branches are only part of the path if isolate_asserts.
+        let path = self.circ_condition();
+        let asrt = term![IMPLIES; path, asrt];
+        self.record_span(&asrt);
+        self.assertions.borrow_mut().push(asrt);
     }
 
     /*** circify wrapper functions (hides RefCell) ***/
@@ -2729,11 +4615,22 @@ impl<'a> PyGen<'a> {
         }
     }
 
+    fn circ_enter_branch(&self, cond: Term) {
+        self.circ.borrow_mut().enter_condition(cond).unwrap();
+    }
+
+    fn circ_exit_branch(&self) {
+        self.circ.borrow_mut().exit_condition()
+    }
+
     fn circ_condition(&self) -> Term {
         self.circ.borrow().condition()
     }
 
     fn circ_return_(&self, ret: Option<PyTerm>) -> Result<(), CircError> {
+        if let Some(r) = &ret {
+            self.record_span(&r.term);
+        }
         self.circ.borrow_mut().return_(ret)
     }
 
@@ -2761,6 +4658,7 @@ impl<'a> PyGen<'a> {
         precomputed_value: Option<PyTerm>,
         mangle_name: bool,
     ) -> Result<PyTerm, CircError> {
+        self.known_names.borrow_mut().insert(name.clone());
         match vis {
             PyVis::Public => {
                 self.circ
@@ -2790,7 +4688,12 @@ impl<'a> PyGen<'a> {
     }
 
     fn circ_declare_init(&self, name: String, ty: Ty, val: Val<PyTerm>) -> Result<Val<PyTerm>, CircError> {
-        self.circ.borrow_mut().declare_init(name, ty, val)
+        self.known_names.borrow_mut().insert(name.clone());
+        let val = self.circ.borrow_mut().declare_init(name, ty, val)?;
+        if let Val::Term(t) = &val {
+            self.record_span(&t.term);
+        }
+        Ok(val)
     }
 
     fn circ_get_value(&self, loc: Loc) -> Result<Val<PyTerm>, CircError> {
@@ -2798,10 +4701,117 @@ impl<'a> PyGen<'a> {
     }
 
     fn circ_assign(&self, loc: Loc, val: Val<PyTerm>) -> Result<Val<PyTerm>, CircError> {
-        self.circ.borrow_mut().assign(loc, val)
+        let val = self.circ.borrow_mut().assign(loc, val)?;
+        if let Val::Term(t) = &val {
+            self.record_span(&t.term);
+        }
+        Ok(val)
+    }
+}
+
+/// A call stack, with runs of recursive calls collapsed
+fn call_chain(stack: &[String]) -> String {
+    let mut runs: Vec<(&str, usize)> = Vec::new();
+    for f in stack {
+        match runs.last_mut() {
+            Some((g, n)) if *g == f.as_str() => *n += 1,
+            _ => runs.push((f.as_str(), 1)),
+        }
+    }
+    runs.iter()
+        .map(|(f, n)| if *n > 1 { format!("{f} (x{n})") } else { f.to_string() })
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// Width of the signed integer type `int8`, `int16`, `int32` or `int64`
+fn signed_int_width(name: &str) -> Option<usize> {
+    match name {
+        "int8" => Some(8),
+        "int16" => Some(16),
+        "int32" => Some(32),
+        "int64" => Some(64),
+        _ => None,
+    }
+}
+
+// Levenshtein distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = (prev + (ca != *cb) as usize).min(row[j] + 1).min(cur + 1);
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+/// Whether `p` matches every value of the subject's type
+fn is_irrefutable(p: &ast::Pattern) -> bool {
+    match p {
+        ast::Pattern::MatchAs(a) => match &a.pattern {
+            Some(p) => is_irrefutable(p),
+            None => true,
+        },
+        ast::Pattern::MatchOr(o) => o.patterns.iter().any(is_irrefutable),
+        // the class of a class pattern is that of the subject
+        ast::Pattern::MatchClass(c) => c.patterns.iter().chain(&c.kwd_patterns).all(is_irrefutable),
+        _ => false,
+    }
+}
+
+/// Whether `e` can be assigned to
+fn is_lvalue(e: &ast::Expr) -> bool {
+    match e {
+        ast::Expr::Name(_) => true,
+        ast::Expr::Attribute(a) => is_lvalue(&a.value),
+        ast::Expr::Subscript(s) => is_lvalue(&s.value),
+        _ => false,
+    }
+}
+
+/// The dunder method overloading `o`
+fn bin_op_dunder(o: &ast::Operator) -> &'static str {
+    match o {
+        ast::Operator::Add => "__add__",
+        ast::Operator::Sub => "__sub__",
+        ast::Operator::Mult => "__mul__",
+        ast::Operator::MatMult => "__matmul__",
+        ast::Operator::Div => "__truediv__",
+        ast::Operator::Mod => "__mod__",
+        ast::Operator::Pow => "__pow__",
+        ast::Operator::LShift => "__lshift__",
+        ast::Operator::RShift => "__rshift__",
+        ast::Operator::BitOr => "__or__",
+        ast::Operator::BitXor => "__xor__",
+        ast::Operator::BitAnd => "__and__",
+        ast::Operator::FloorDiv => "__floordiv__",
+    }
+}
+
+/// The dunder method overloading `o`, if it can be overloaded
+fn cmp_op_dunder(o: &ast::CmpOp) -> Option<&'static str> {
+    match o {
+        ast::CmpOp::Eq => Some("__eq__"),
+        ast::CmpOp::NotEq => Some("__ne__"),
+        ast::CmpOp::Lt => Some("__lt__"),
+        ast::CmpOp::LtE => Some("__le__"),
+        ast::CmpOp::Gt => Some("__gt__"),
+        ast::CmpOp::GtE => Some("__ge__"),
+        _ => None,
     }
 }
 
+/// Whether `e` is the `RAM` marker of an `Array[T, N, RAM]` annotation
+fn is_ram_marker(e: &ast::Expr) -> bool {
+    matches!(e, ast::Expr::Name(n) if n.id.as_str() == "RAM")
+}
+
 /// Canonicalize a path when possible, otherwise return path
 fn normalize_path(p: &PathBuf) -> Result<PathBuf, io::Error> {
     if p.to_str()
@@ -2838,9 +4848,553 @@ fn range_before_filter(s: &TextRange, contents: &str) -> TextRange {
     s + offset
 }
 
-fn line_from_range(range: TextRange, contents: &str) -> usize {
-    let start_offset = range.start();
-    let text_before_range = &contents[0..start_offset.into()];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use circ::cfg::cfg_or_default;
+    use circ::ir::term::text::parse_value_map;
+    use std::path::Path;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init_cfg() {
+        INIT.call_once(|| {
+            cfg_or_default();
+        });
+    }
+
+    fn inputs(source: SourceInput, max_inline_depth: usize) -> Inputs {
+        init_cfg();
+        Inputs {
+            source,
+            entry_point: "main".to_owned(),
+            mode: Mode::Proof,
+            array_strategy: ArrayStrategy::default(),
+            max_inline_depth,
+        }
+    }
+
+    fn compile(src: &str) -> Result<Computations, Diagnostics> {
+        let source = SourceInput::String(src.to_owned(), PathBuf::default(), "<main>".to_owned());
+        PythonFE::try_gen(inputs(source, DEFAULT_MAX_INLINE_DEPTH))
+    }
+
+    // A value map in the text format of `.pin` and `.vin` files
+    fn values(bindings: &str) -> String {
+        format!("(set_default_modulus {} (let ({bindings}) false))", cfg().field().modulus())
+    }
+
+    // Whether the computation accepts the inputs and return value, given in
+    // the text format of `.pin` and `.vin` files
+    fn accepts(cs: &Computations, pin: &str, vin: &str) -> bool {
+        let mut values = parse_value_map(pin.as_bytes());
+        values.extend(parse_value_map(vin.as_bytes()));
+        cs.comps["main"]
+            .eval_all(&values)
+            .iter()
+            .all(|v| v.as_bool())
+    }
+
+    // Compile examples/`name` and check it against its `.pin` and `.vin` files
+    fn check_example(name: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples").join(name);
+        let read = |ext: &str| fs::read_to_string(path.with_extension(format!("py.{ext}"))).unwrap();
+        let cs = PythonFE::try_gen(inputs(SourceInput::Path(path.clone()), DEFAULT_MAX_INLINE_DEPTH))
+            .unwrap_or_else(|d| panic!("{d}"));
+        assert!(accepts(&cs, &read("pin"), &read("vin")), "{name} rejects its inputs");
+    }
+
+    #[test]
+    fn test_if_elif_else() {
+        check_example("if_elif.py");
+    }
+
+    #[test]
+    fn test_methods() {
+        check_example("methods.py");
+    }
+
+    #[test]
+    fn test_match() {
+        check_example("match_literal.py");
+        check_example("match_wide.py");
+    }
+
+    #[test]
+    fn test_higher_order() {
+        check_example("higher_order.py");
+        // without an initial value, reduce starts from the first element
+        let src = "\
+def main(x: Private[Array[int, 3]]) -> int:
+    return reduce(lambda acc, v: acc - v, x)
+";
+        let cs = compile(src).unwrap_or_else(|d| panic!("{d}"));
+        let x = "(x.0 #x0000000a) (x.1 #x00000003) (x.2 #x00000002)";
+        assert!(accepts(&cs, &values(x), &values("(return #x00000005)")));
+        assert!(!accepts(&cs, &values(x), &values("(return #xfffffff1)")));
+
+        let src = "\
+def main(x: Private[Array[int, 3]]) -> int:
+    y: Array[int, 3] = sorted(x, reverse=True)
+    return y[0] * 100 + y[1] * 10 + y[2]
+";
+        let cs = compile(src).unwrap_or_else(|d| panic!("{d}"));
+        let x = "(x.0 #x00000003) (x.1 #x00000001) (x.2 #x00000002)";
+        assert!(accepts(&cs, &values(x), &values("(return #x00000141)")));
+        assert!(!accepts(&cs, &values(x), &values("(return #x0000007b)")));
+    }
+
+    #[test]
+    fn test_higher_order_non_callable() {
+        let src = "\
+def main(x: Private[Array[int, 2]], y: Private[int]) -> int:
+    z: Array[int, 2] = map(y, x)
+    return z[0]
+";
+        let d = compile(src).map(|_| ()).unwrap_err();
+        assert_eq!((d.0[0].code, d.0[0].line), (ErrorCode::TypeMismatch, 2));
+        assert!(d.0[0].message.contains("map expected a lambda or the name of a function"), "{d}");
+    }
+
+    #[test]
+    fn test_ram_annotation() {
+        check_example("ram_lookup.py");
+        let src = "\
+def main(x: Private[Array[field, 8, RAM]], y: Private[Array[field, 8]], i: Private[field]) -> field:
+    return x[i] + y[i]
+";
+        // Arrays lowered as RAM keep their witness-dependent selects for the
+        // memory-checking passes, while the others are scanned right away
+        let selects = |array_strategy: ArrayStrategy| {
+            let source = SourceInput::String(src.to_owned(), PathBuf::default(), "<main>".to_owned());
+            let cs = PythonFE::try_gen(Inputs { array_strategy, ..inputs(source, DEFAULT_MAX_INLINE_DEPTH) })
+                .unwrap_or_else(|d| panic!("{d}"));
+            let outputs = cs.comps["main"].outputs.clone();
+            PostOrderIter::from_roots_and_skips(outputs, Default::default())
+                .filter(|t| matches!(t.op(), Op::Select))
+                .count()
+        };
+        assert_eq!(selects(ArrayStrategy::Annotated), 1);
+        assert_eq!(selects(ArrayStrategy::LinearScan), 0);
+        assert_eq!(selects(ArrayStrategy::Ram), 2);
+    }
+
+    #[test]
+    fn test_match_wide_subject_is_asserted() {
+        let src = "\
+def main(x: Private[int]) -> int:
+    y: int = 0
+    match x:
+        case 1:
+            y = 10
+        case 2:
+            y = 20
+    return y
+";
+        let cs = compile(src).unwrap_or_else(|d| panic!("{d}"));
+        let ret = |x: u32, r: u32| {
+            accepts(&cs, &values(&format!("(x #x{x:08x})")), &values(&format!("(return #x{r:08x})")))
+        };
+        assert!(ret(1, 10));
+        assert!(ret(2, 20));
+        // no case matches, whatever the return value
+        assert!(!ret(5, 0));
+    }
+
+    #[test]
+    fn test_non_exhaustive_bool_match_is_an_error() {
+        let src = "\
+def main(b: Private[bool]) -> int:
+    y: int = 0
+    match b:
+        case True:
+            y = 1
+    return y
+";
+        let err = compile(src).map(|_| ()).unwrap_err().to_string();
+        assert!(err.contains("Non-exhaustive match: False is not covered"), "{err}");
+    }
+
+    #[test]
+    fn test_if_branch_local_and_early_return() {
+        let src = "\
+def f(x: int) -> int:
+    if x < 10:
+        return 1
+    elif x < 100:
+        y: int = x * 2
+        if y > 100:
+            return 2
+        return 3
+    return 4
+
+def main(x: Private[int]) -> int:
+    return f(x)
+";
+        let cs = compile(src).unwrap_or_else(|d| panic!("{d}"));
+        let ret = |x: u32, r: u32| {
+            accepts(&cs, &values(&format!("(x #x{x:08x})")), &values(&format!("(return #x{r:08x})")))
+        };
+        assert!(ret(7, 1));
+        assert!(ret(60, 2));
+        assert!(ret(40, 3));
+        assert!(ret(400, 4));
+        assert!(!ret(60, 3));
+    }
+
+    #[test]
+    fn test_fixed_overflow_is_rejected() {
+        // Fixed[8, 4] is 12 bits wide and holds [-128, 128)
+        let op = |ret: &str| {
+            let src = format!(
+                "\
+from zkpyc.stdlib.EMBED import fixed_rescale, ROUND_FLOOR
+
+def main(x: Private[Fixed[8, 4]], y: Private[Fixed[8, 4]]) -> {ret}
+"
+            );
+            let cs = compile(&src).unwrap_or_else(|d| panic!("{d}"));
+            move |x: &str, y: &str, r: &str| {
+                accepts(&cs, &values(&format!("(x #x{x}) (y #x{y})")), &values(&format!("(return #x{r})")))
+            }
+        };
+        let add = op("Fixed[8, 4]:\n    return x + y");
+        // 1 + 2 == 3
+        assert!(add("010", "020", "030"));
+        // 100 + 100 wraps around to -56
+        assert!(!add("640", "640", "c80"));
+
+        let div = op("Fixed[8, 4]:\n    return x / y");
+        // 3 / 0.5 == 6
+        assert!(div("030", "008", "060"));
+        // 64 / 0.5 wraps around to -128
+        assert!(!div("400", "008", "800"));
+
+        let neg = op("Fixed[8, 4]:\n    return -x");
+        assert!(neg("7f0", "000", "810"));
+        // -(-128) wraps around to -128
+        assert!(!neg("800", "000", "800"));
+
+        // Fixed[4, 4] holds [-8, 8)
+        let rescale = op("Fixed[4, 4]:\n    return fixed_rescale(x, 4, 4, ROUND_FLOOR)");
+        assert!(rescale("050", "000", "50"));
+        // 100 wraps around to 4
+        assert!(!rescale("640", "000", "40"));
+    }
+
+    #[test]
+    fn test_chained_assignment_is_an_error() {
+        let src = "\
+def main(x: Private[int]) -> int:
+    a: int = 0
+    b: int = 0
+    a = b = x
+    return a + b
+";
+        let err = compile(src).map(|_| ()).unwrap_err().to_string();
+        assert!(err.contains("Chained assignments"), "{err}");
+    }
+
+    #[test]
+    fn test_tuple_return_and_unpacking() {
+        let src = "\
+def divmod10(x: int) -> Tuple[int, int]:
+    return x / 10, x % 10
+
+def main(x: Private[int]) -> Tuple[int, bool]:
+    q: int = 0
+    r: int = 0
+    q, r = divmod10(x)
+    return q + r, r == 3
+";
+        let cs = compile(src).unwrap_or_else(|d| panic!("{d}"));
+        let ret = |x: u32, a: u32, b: bool| {
+            accepts(
+                &cs,
+                &values(&format!("(x #x{x:08x})")),
+                &values(&format!("(return.0 #x{a:08x}) (return.1 {b})")),
+            )
+        };
+        // 73 == 7 * 10 + 3
+        assert!(ret(73, 10, true));
+        assert!(ret(45, 9, false));
+        assert!(!ret(73, 10, false));
+        assert!(!ret(73, 73, true));
+    }
+
+    #[test]
+    fn test_unpacking_arity_mismatch_is_an_error() {
+        let src = "\
+def pair(x: int) -> Tuple[int, int]:
+    return x, x
+
+def main(x: Private[int]) -> int:
+    a: int = 0
+    b: int = 0
+    c: int = 0
+    a, b, c = pair(x)
+    return a
+";
+        let d = compile(src).map(|_| ()).unwrap_err();
+        assert_eq!((d.0[0].code, d.0[0].line), (ErrorCode::TypeMismatch, 8));
+        assert!(d.0[0].message.contains("Cannot unpack 2 values into 3 variables"), "{d}");
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let code = |src: &str| compile(src).map(|_| ()).unwrap_err().0[0].code;
+        let sorted = "\
+def main(x: Private[Array[int, 2]]) -> int:
+    y: Array[int, 2] = sorted(x, key=x)
+    return y[0]
+";
+        assert_eq!(code(sorted), ErrorCode::Unsupported);
+        let arity = "\
+def f(x: int) -> int:
+    return x
+
+def main(x: Private[int]) -> int:
+    return f(x, x)
+";
+        assert_eq!(code(arity), ErrorCode::Arity);
+        let visibility = "\
+def main(x: Secret[int]) -> int:
+    return x
+";
+        assert_eq!(code(visibility), ErrorCode::InvalidAnnotation);
+        // reported by the const evaluation, but it is not an undefined name
+        let not_const = "\
+N: int = M
+
+def main(x: Private[int]) -> int:
+    return x
+";
+        assert_eq!(code(not_const), ErrorCode::NotConstant);
+    }
+
+    #[test]
+    fn test_independent_errors_are_all_reported() {
+        let src = "\
+def main(x: Private[int]) -> int:
+    a: int = undefined_a
+    b: int = a + 1
+    c: field = x
+    return x
+";
+        let errs = compile(src).map(|_| ()).unwrap_err().0;
+        // the error of `b` is a consequence of that of `a`
+        let codes: Vec<_> = errs.iter().map(|d| (d.line, d.code)).collect();
+        assert_eq!(codes, vec![(2, ErrorCode::UndefinedName), (4, ErrorCode::TypeMismatch)]);
+    }
+
+    #[test]
+    fn test_conflicting_size_parameter_is_an_error() {
+        let src = "\
+def f(x: Array[field, N], y: Array[field, N]) -> field:
+    return x[0] + y[0]
+
+def main(x: Private[Array[field, 2]], y: Private[Array[field, 3]]) -> field:
+    return f(x, y)
+";
+        let err = compile(src).map(|_| ()).unwrap_err().to_string();
+        assert!(err.contains("Size parameter N is both 2 and 3"), "{err}");
+    }
+
+    #[test]
+    fn test_for_range_steps() {
+        let src = "\
+def main(x: Private[int]) -> int:
+    s: int = x
+    for i in range(1, 10, 3):
+        s = s + i
+    for i in range(4, 0, -2):
+        s = s * i
+    return s
+";
+        let cs = compile(src).unwrap_or_else(|d| panic!("{d}"));
+        // (1 + 1 + 4 + 7) * 4 * 2
+        assert!(accepts(&cs, &values("(x #x00000001)"), &values("(return #x00000068)")));
+        assert!(!accepts(&cs, &values("(x #x00000001)"), &values("(return #x0000000d)")));
+    }
+
+    #[test]
+    fn test_for_range_signed_bound() {
+        // counts down through negative values, which do not fit an int
+        let src = "\
+def main(x: Private[int8]) -> int8:
+    n: int8 = int8(2)
+    s: int8 = x
+    for i in range(n, -4, -1):
+        s = s + i
+    return s
+";
+        let cs = compile(src).unwrap_or_else(|d| panic!("{d}"));
+        // 2 + 1 + 0 - 1 - 2 - 3 == -3
+        assert!(accepts(&cs, &values("(x #x0a)"), &values("(return #x07)")));
+        assert!(accepts(&cs, &values("(x #x01)"), &values("(return #xfe)")));
+    }
+
+    #[test]
+    fn test_for_enumerate() {
+        let src = "\
+def main(x: Private[Array[field, 3]]) -> field:
+    s: field = 0
+    for j, v in enumerate(x):
+        s = s + field(j) * v
+    return s
+";
+        let cs = compile(src).unwrap_or_else(|d| panic!("{d}"));
+        // 0 * 5 + 1 * 6 + 2 * 7
+        let x = "(x.0 #f5) (x.1 #f6) (x.2 #f7)";
+        assert!(accepts(&cs, &values(x), &values("(return #f20)")));
+        assert!(!accepts(&cs, &values(x), &values("(return #f38)")));
+    }
+
+    #[test]
+    fn test_for_zip_stops_at_shortest() {
+        let src = "\
+def main(x: Private[Array[field, 3]], y: Private[Array[field, 2]]) -> field:
+    s: field = 0
+    for a, b in zip(x, y):
+        s = s + a * b
+    return s
+";
+        let cs = compile(src).unwrap_or_else(|d| panic!("{d}"));
+        let xy = "(x.0 #f1) (x.1 #f2) (x.2 #f3) (y.0 #f4) (y.1 #f5)";
+        // 1 * 4 + 2 * 5, the third element of x is left out
+        assert!(accepts(&cs, &values(xy), &values("(return #f14)")));
+        assert!(!accepts(&cs, &values(xy), &values("(return #f15)")));
+    }
+
+    // `while i < 5` summing 0..5 onto x, with the header comment `pragma`
+    fn while_sum(pragma: &str) -> String {
+        format!(
+            "\
+def main(x: Private[int]) -> int:
+    i: int = 0
+    s: int = x
+    while i < 5:{pragma}
+        s = s + i
+        i = i + 1
+    return s
+"
+        )
+    }
+
+    #[test]
+    fn test_while_const_condition_terminates() {
+        for pragma in ["", "  # zk_bound: 5", "  # zk_bound: 8"] {
+            let cs = compile(&while_sum(pragma)).unwrap_or_else(|d| panic!("{d}"));
+            assert!(accepts(&cs, &values("(x #x00000001)"), &values("(return #x0000000b)")));
+            assert!(!accepts(&cs, &values("(x #x00000001)"), &values("(return #x00000001)")));
+        }
+    }
 
-    text_before_range.chars().filter(|&c| c == '\n').count() + 1
-}
\ No newline at end of file
+    #[test]
+    fn test_while_bound_exceeded_is_an_error() {
+        let errs = compile(&while_sum("  # zk_bound: 3")).map(|_| ()).unwrap_err().0;
+        assert_eq!(errs[0].code, ErrorCode::LoopBound);
+        assert!(errs[0].message.contains("does not terminate within its bound of 3"), "{}", errs[0]);
+    }
+
+    #[test]
+    fn test_while_witness_condition_is_an_error() {
+        let src = |pragma: &str| {
+            format!(
+                "\
+def main(x: Private[int]) -> int:
+    i: int = 0
+    while i < x:{pragma}
+        i = i + 1
+    return i
+"
+            )
+        };
+        // without a bound, and with one that cannot be shown to suffice
+        let errs = compile(&src("")).map(|_| ()).unwrap_err().0;
+        assert_eq!(errs[0].code, ErrorCode::LoopBound);
+        assert!(errs[0].message.contains("zk_bound"), "{}", errs[0]);
+        let errs = compile(&src("  # zk_bound: 4")).map(|_| ()).unwrap_err().0;
+        assert_eq!(errs[0].code, ErrorCode::LoopBound);
+        assert!(errs[0].message.contains("not known to be false after the bound of 4"), "{}", errs[0]);
+    }
+
+    const SUM_TO: &str = "\
+def sum_to(x: field, depth: int) -> field:
+    if depth == 0:
+        return x
+    return sum_to(x + field(depth), depth - 1)
+
+def main(x: Private[field]) -> field:
+    return sum_to(x, 4)
+";
+
+    #[test]
+    fn test_recursion_on_constant_depth() {
+        let cs = compile(SUM_TO).unwrap_or_else(|d| panic!("{d}"));
+        assert!(accepts(&cs, &values("(x #f1)"), &values("(return #f11)")));
+        assert!(!accepts(&cs, &values("(x #f1)"), &values("(return #f10)")));
+    }
+
+    #[test]
+    fn test_max_inline_depth_reports_call_chain() {
+        let source = SourceInput::String(SUM_TO.to_owned(), PathBuf::default(), "<main>".to_owned());
+        let err = PythonFE::try_gen(inputs(source, 3)).map(|_| ()).unwrap_err().to_string();
+        assert!(err.contains("Maximum inlining depth 3 exceeded calling sum_to"), "{err}");
+        assert!(err.contains("sum_to (x"), "{err}");
+    }
+
+    #[test]
+    fn test_errors_after_a_bad_call_name_the_caller() {
+        let src = "\
+def f(x: int) -> int:
+    return x
+
+def main(x: Private[int]) -> int:
+    y: int = f(x, x)
+    return x + undefined_n
+";
+        let d = compile(src).map(|_| ()).unwrap_err();
+        assert_eq!(d.0.len(), 2, "{d}");
+        assert_eq!((d.0[0].code, d.0[0].function.as_deref()), (ErrorCode::Arity, Some("main")));
+        assert_eq!((d.0[1].code, d.0[1].function.as_deref()), (ErrorCode::UndefinedName, Some("main")));
+    }
+
+    #[test]
+    fn test_if_const_condition_error_is_reported() {
+        // The condition is evaluated at compile time first; errors other
+        // than reading a witness must not be swallowed by that attempt
+        let src = "\
+N: int = 4
+
+def f(x: int) -> bool:
+    return x < N
+
+def main(x: Private[int]) -> int:
+    if f(N, N):
+        return 1
+    return x
+";
+        let err = compile(src).map(|_| ()).unwrap_err().to_string();
+        assert!(err.contains("Wrong number of arguments calling f"), "{err}");
+    }
+
+    #[test]
+    fn test_if_const_attempt_is_not_an_undefined_name() {
+        // In the const attempt, the undefined name is merely not constant;
+        // it is reported when the condition is compiled to constraints
+        let src = "\
+def f(x: int) -> bool:
+    return x < undefined_n
+
+def main(x: Private[int]) -> int:
+    if f(3):
+        return 1
+    return x
+";
+        let errs = compile(src).map(|_| ()).unwrap_err().0;
+        assert_eq!(errs.len(), 1, "{errs:?}");
+        assert_eq!((errs[0].line, errs[0].code), (2, ErrorCode::UndefinedName));
+    }
+}
@@ -8,12 +8,14 @@ use std::{collections::{HashMap, VecDeque}, fs, path::{Path, PathBuf}};
 use std::fs::File;
 use std::io::Read;
 use std::env::var_os;
+use std::sync::LazyLock;
 use typed_arena::Arena;
 use regex::Regex;
 use dirs::data_dir;
 use zkpyc_stdlib::StdLib;
 
 use super::SourceInput;
+use crate::front::diagnostic::{Diagnostic, ErrorCode};
 
 
 #[derive(Default)]
@@ -132,10 +134,14 @@ impl PyLoad {
     }
 
     /// Returns a map from file paths to parsed files.
-    pub fn load(&self, input: &SourceInput) -> HashMap<PathBuf, ast::Mod> {
+    pub fn load(&self, input: &SourceInput) -> Result<HashMap<PathBuf, ast::Mod>, Diagnostic> {
         match input {
-            SourceInput::Path(p) => self.recursive_load(p).unwrap(),
-            SourceInput::String(s, p, n) => self.load_from_string(s, &p, n).unwrap(),
+            SourceInput::Path(p) => self
+                .recursive_load(p)
+                .map_err(|e| syntax_diagnostic(e, None)),
+            SourceInput::String(s, p, n) => self
+                .load_from_string(s, &p, n)
+                .map_err(|e| syntax_diagnostic(e, Some((s, n)))),
         }
     }
 
@@ -196,11 +202,7 @@ impl <'a> Loader for &'a PyLoad {
         debug!("Parsing: {}", p.as_ref().display());
         filter_out_zk_ignore(&mut s);
         let s = self.sources.alloc(s);
-        let ast = parse(&s, Mode::Module, p.as_ref().to_str().unwrap());
-        if ast.is_err() {
-            panic!("{}", ast.unwrap_err());
-        }
-        Ok(ast.unwrap())
+        parse(&s, Mode::Module, p.as_ref().to_str().unwrap())
     }
 
     fn includes<P: AsRef<Path>>(&self, ast: &Self::AST, p: &P) -> Vec<PathBuf> {
@@ -232,6 +234,28 @@ impl <'a> Loader for &'a PyLoad {
     }
 }
 
+// A parse error as a diagnostic, located in the source before filtering
+fn syntax_diagnostic(e: ParseError, embedded: Option<(&str, &str)>) -> Diagnostic {
+    let (file, contents) = match embedded {
+        Some((source, name)) if e.source_path == "<embedded>" => {
+            (PathBuf::from(name), source.to_string())
+        }
+        _ => (
+            PathBuf::from(&e.source_path),
+            fs::read_to_string(&e.source_path).unwrap_or_default(),
+        ),
+    };
+    let at = super::range_before_filter(&TextRange::empty(e.offset), &contents);
+    Diagnostic::error(
+        ErrorCode::Syntax,
+        e.error.to_string(),
+        file,
+        &contents,
+        at.start().into(),
+        at.end().into(),
+    )
+}
+
 pub fn filter_out_zk_ignore(s: &mut String) -> Vec<TextRange> {
     let re = Regex::new(r"(?i)#\s*zk_ignore\s*$").expect("Regex compilation failed");
     let lines: Vec<&str> = s.lines().collect(); // Split the string into lines
@@ -267,6 +291,20 @@ pub fn filter_out_zk_ignore(s: &mut String) -> Vec<TextRange> {
     filtered_ranges
 }
 
+// compiled once rather than for every while statement
+static ZK_BOUND: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)#\s*zk_bound\s*:\s*(\d+)\s*$").expect("Regex compilation failed")
+});
+
+/// Returns the unroll bound set by a `# zk_bound: N` pragma at the end of
+/// the first line of `s` (i.e. the header of a while statement), if any.
+pub fn zk_bound(s: &str) -> Option<usize> {
+    s.lines()
+        .next()
+        .and_then(|line| ZK_BOUND.captures(line))
+        .and_then(|c| c[1].parse().ok())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -309,7 +347,7 @@ def func(string):
         let loader = PyLoad::new();
         let asts = loader.load(
             &SourceInput::String(MAIN_SRC.to_owned(), temp_dir.into_path(), "<embedded>".to_owned())
-        );
+        ).unwrap();
     
         // 1. Check that the correct number of files were loaded
         assert_eq!(asts.len(), 3, "Expected three parsed ASTs (stdin + dependencies)");
@@ -339,7 +377,7 @@ def func(string):
 
         // Parse the files recursively
         let loader = PyLoad::new();
-        let asts = loader.load(&SourceInput::Path(main_path));
+        let asts = loader.load(&SourceInput::Path(main_path)).unwrap();
 
         // 1. Check that the three files were (recursively) loaded
         assert_eq!(asts.len(), 3, "Expected three parsed ASTs");
@@ -369,7 +407,7 @@ def func(string):
 
         // Parse the files recursively
         let loader = PyLoad::new();
-        let asts = loader.load(&SourceInput::Path(main_path));
+        let asts = loader.load(&SourceInput::Path(main_path)).unwrap();
         let main_ast = asts.iter().find(|(p, _)| p.ends_with("main.py"))
             .expect("AST for main.py not found")
             .1;
@@ -408,7 +446,7 @@ def func(string):
 
         // Parse the files recursively
         let loader = PyLoad::new();
-        let asts = loader.load(&SourceInput::Path(foo_bar_path));
+        let asts = loader.load(&SourceInput::Path(foo_bar_path)).unwrap();
         let foo_bar_ast = asts.iter().find(|(p, _)| p.ends_with("foo/bar.py"))
             .expect("AST for foo/bar.py not found")
             .1;
@@ -453,7 +491,7 @@ def func(string):
 
         // Parse the files recursively
         let loader = PyLoad::new();
-        let asts = loader.load(&SourceInput::Path(dummy_str_path));
+        let asts = loader.load(&SourceInput::Path(dummy_str_path)).unwrap();
         let dummy_str_ast = asts.iter().find(|(p, _)|p.ends_with("foo/dummy.py"))
             .expect("AST for foo/dummy.py not found")
             .1;
@@ -469,4 +507,25 @@ def func(string):
         Ok(())
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_syntax_error_diagnostic() {
+        let src = "def main() -> int:\n    x = 1 +\n    return x\n";
+        let loader = PyLoad::new();
+        let d = loader
+            .load(&SourceInput::String(src.to_owned(), PathBuf::default(), "<main>".to_owned()))
+            .unwrap_err();
+        assert_eq!(d.code, ErrorCode::Syntax);
+        assert_eq!(d.file, Path::new("<main>"));
+        assert_eq!(d.line, 2);
+        assert!(d.render().starts_with("error[E0001]: "));
+    }
+
+    #[test]
+    fn test_zk_bound_pragma() {
+        assert_eq!(zk_bound("while i < n: # zk_bound: 64\n    i += 1"), Some(64));
+        assert_eq!(zk_bound("while i < n:  #ZK_BOUND:8"), Some(8));
+        // The pragma must be on the header line
+        assert_eq!(zk_bound("while i < n:\n    i += 1 # zk_bound: 64"), None);
+        assert_eq!(zk_bound("while i < n: # zk_bound: n"), None);
+    }
+}
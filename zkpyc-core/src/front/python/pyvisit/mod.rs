@@ -8,6 +8,7 @@ pub type PyResult<PyTerm> = Result<PyTerm, PyVisitorError>;
 pub type PyVisitorResult = PyResult<()>;
 
 pub use pyvmut::PyVisitorMut;
+pub use walkfns::walk_stmt;
 
 impl From<String> for PyVisitorError {
     fn from(f: String) -> Self {
@@ -18,9 +18,14 @@ pub enum Ty {
     Field,
     Bool,
     Uint(usize),
+    Int(usize),
+    /// Signed fixed-point number with `int_bits` (including the sign bit) and
+    /// `frac_bits`, stored as a two's complement bit-vector scaled by 2^frac_bits
+    Fixed(usize, usize),
     DataClass(String, FieldList<Ty>),
     Array(usize, Box<Ty>),
     MutArray(usize),
+    Tuple(Vec<Ty>),
     // could we support other mutable types
     // like dicts, or other PyTypes?
 }
@@ -30,7 +35,9 @@ impl Display for Ty {
         match self {
             Ty::Field => write!(f, "field"),
             Ty::Bool => write!(f, "bool"),
-            Ty::Uint(w) => write!(f, "s{w}"),
+            Ty::Uint(w) => write!(f, "u{w}"),
+            Ty::Int(w) => write!(f, "i{w}"),
+            Ty::Fixed(i, fr) => write!(f, "Fixed[{i}, {fr}]"),
             Ty::DataClass(n, fields) => {
                 let mut o = f.debug_struct(n);
                 for (f_name, f_ty) in fields.fields() {
@@ -49,6 +56,16 @@ impl Display for Ty {
                 dims.iter().try_for_each(|d| write!(f, "[{d}]"))
             }
             Ty::MutArray(n) => write!(f, "MutArray({n})"),
+            Ty::Tuple(tys) => {
+                write!(f, "Tuple[")?;
+                tys.iter().enumerate().try_for_each(|(i, ty)| {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{ty}")
+                })?;
+                write!(f, "]")
+            }
         }
     }
 }
@@ -72,7 +89,8 @@ impl Ty {
         match self {
             Self::Field => default_field_sort(),
             Self::Bool => Sort::Bool,
-            Self::Uint(w) => Sort::BitVector(*w),
+            Self::Uint(w) | Self::Int(w) => Sort::BitVector(*w),
+            Self::Fixed(i, f) => Sort::BitVector(i + f),
             Self::DataClass(_name, fs) => {
                 Sort::Tuple(fs.fields().map(|(_f_name, f_ty)| f_ty.sort()).collect())
             }
@@ -84,6 +102,7 @@ impl Ty {
                 Box::new(default_field_sort()),
                 *n,
             ),
+            Self::Tuple(tys) => Sort::Tuple(tys.iter().map(Ty::sort).collect()),
         }
     }
     
@@ -224,6 +243,22 @@ impl PyTerm {
         array(v)
     }
     
+    pub fn new_tuple(elems: Vec<PyTerm>) -> PyTerm {
+        let (tys, ir_terms): (Vec<_>, Vec<_>) = elems.into_iter().map(|t| (t.ty, t.term)).unzip();
+        PyTerm::new(Ty::Tuple(tys), term(Op::Tuple, ir_terms))
+    }
+
+    pub fn unwrap_tuple(self) -> Result<Vec<PyTerm>, String> {
+        match &self.ty {
+            Ty::Tuple(tys) => Ok(tys
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| PyTerm::new(ty.clone(), term![Op::Field(i); self.term.clone()]))
+                .collect()),
+            s => Err(format!("Not a tuple: {s}")),
+        }
+    }
+
     pub fn new_class(name: String, fields: Vec<(String, PyTerm)>) -> PyTerm {
         let (field_tys, ir_terms): (Vec<_>, Vec<_>) = fields
             .into_iter()
@@ -274,6 +309,34 @@ impl PyTerm {
     {
         PyTerm::new(Ty::Uint(64), bv_lit(v, 64))
     }
+
+    pub fn new_i8<I>(v: I) -> Self
+    where
+        Integer: From<I>,
+    {
+        int_lit(v, 8)
+    }
+
+    pub fn new_i16<I>(v: I) -> Self
+    where
+        Integer: From<I>,
+    {
+        int_lit(v, 16)
+    }
+
+    pub fn new_i32<I>(v: I) -> Self
+    where
+        Integer: From<I>,
+    {
+        int_lit(v, 32)
+    }
+
+    pub fn new_i64<I>(v: I) -> Self
+    where
+        Integer: From<I>,
+    {
+        int_lit(v, 64)
+    }
     
     pub fn pretty<W: std::io::Write>(&self, f: &mut W) -> Result<(), std::io::Error> {
         use std::io::{Error, ErrorKind};
@@ -289,8 +352,23 @@ impl PyTerm {
                 16 => write!(f, "0x{:04x}", bv.uint()),
                 32 => write!(f, "0x{:08x}", bv.uint()),
                 64 => write!(f, "0x{:016x}", bv.uint()),
-                _ => unreachable!(),
+                w => write!(f, "0x{:0w$x}", bv.uint(), w = w.div_ceil(4)),
             },
+            Value::Tuple(vs) if matches!(self.ty, Ty::Tuple(_)) => {
+                let Ty::Tuple(tys) = &self.ty else { unreachable!() };
+                write!(f, "(")?;
+                for (i, (ty, v)) in tys.iter().zip(vs.iter()).enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    PyTerm::new(ty.clone(), leaf_term(Op::Const(v.clone()))).pretty(f)?;
+                }
+                // like Python, a one-element tuple keeps its comma
+                if tys.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
             Value::Tuple(vs) => {
                 let (n, fl) = if let Ty::DataClass(n, fl) = &self.ty {
                     Ok((n, fl))
@@ -344,47 +422,152 @@ impl Display for PyTerm {
 
 // Phase 2: Hooking into Circify
 
+// Integer literals are always u32, so a constant u32 operand is
+// truncated to the width of a signed operand (e.g. `x + 1` for an `int8` x),
+// or scaled to the format of a fixed-point operand (e.g. `x * 2`).
+fn coerce_int_lits(a: PyTerm, b: PyTerm) -> (PyTerm, PyTerm) {
+    fn coerce(u: PyTerm, ty: &Ty) -> PyTerm {
+        match (const_int(u.clone()), ty) {
+            (Ok(i), Ty::Int(w)) if u.ty == Ty::Uint(32) => int_lit(i, *w),
+            (Ok(i), Ty::Fixed(ib, fb)) if u.ty == Ty::Uint(32) => fixed_int_lit(i, *ib, *fb),
+            _ => u,
+        }
+    }
+    match (&a.ty, &b.ty) {
+        (Ty::Int(_) | Ty::Fixed(..), Ty::Uint(_)) => {
+            let ty = a.ty.clone();
+            (a, coerce(b, &ty))
+        }
+        (Ty::Uint(_), Ty::Int(_) | Ty::Fixed(..)) => {
+            let ty = b.ty.clone();
+            (coerce(a, &ty), b)
+        }
+        _ => (a, b),
+    }
+}
+
+fn is_fixed(a: &PyTerm, b: &PyTerm) -> bool {
+    matches!(a.ty, Ty::Fixed(..)) || matches!(b.ty, Ty::Fixed(..))
+}
+
+// Fixed-point operands must agree on both int_bits and frac_bits
+fn fixed_operands(name: &str, a: PyTerm, b: PyTerm) -> Result<(Ty, Term, Term), String> {
+    let (a, b) = coerce_int_lits(a, b);
+    if matches!(a.ty, Ty::Fixed(..)) && a.ty == b.ty {
+        Ok((a.ty, a.term, b.term))
+    } else {
+        Err(format!("Cannot perform op '{name}' on {} and {}", a.ty, b.ty))
+    }
+}
+
+fn wrap_fixed_op(name: &str, f: fn(Term, Term) -> Term, a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
+    let (ty, a, b) = fixed_operands(name, a, b)?;
+    Ok(PyTerm::new(ty, f(a, b)))
+}
+
+// Whether a signed bit-vector fits in `w` bits, i.e. it is unchanged by
+// truncating it to `w` bits and sign-extending it back
+fn fits_int(t: Term, w: usize) -> Term {
+    let n = check(&t).as_bv();
+    term![Op::Eq; bv_resize(bv_resize(t.clone(), n, w, true), w, n, true), t]
+}
+
+/// The condition for the fixed-point `+`, `-`, `*` or `/` of `a` and `b` not
+/// to overflow their format, or `None` for other operands and operators. The
+/// operations themselves wrap around like the bit-vectors behind them, so
+/// the front-end asserts this condition.
+pub fn fixed_in_range(name: &str, a: PyTerm, b: PyTerm) -> Result<Option<Term>, String> {
+    if !is_fixed(&a, &b) {
+        return Ok(None);
+    }
+    let (ty, a, b) = fixed_operands(name, a, b)?;
+    let Ty::Fixed(_, frac) = ty else { unreachable!() };
+    let w = check(&a).as_bv();
+    // the exact result, in a width that cannot overflow
+    let exact = match name {
+        "+" => add_uint(bv_resize(a, w, w + 1, true), bv_resize(b, w, w + 1, true)),
+        "-" => sub_uint(bv_resize(a, w, w + 1, true), bv_resize(b, w, w + 1, true)),
+        "*" => term![Op::BvBinOp(BvBinOp::Ashr);
+            mul_uint(bv_resize(a, w, 2 * w, true), bv_resize(b, w, 2 * w, true)),
+            bv_lit(frac, 2 * w)
+        ],
+        // one more bit than `div_fixed` for the quotient of the minimum by -1
+        "/" => {
+            let wq = w + frac + 1;
+            div_int(
+                term![Op::BvBinOp(BvBinOp::Shl); bv_resize(a, w, wq, true), bv_lit(frac, wq)],
+                bv_resize(b, w, wq, true),
+            )
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(fits_int(exact, w)))
+}
+
+/// The condition for the fixed-point negation of `a` not to overflow its
+/// format, which only fails on the minimum value, or `None` for other operands
+pub fn fixed_neg_in_range(a: &PyTerm) -> Option<Term> {
+    let Ty::Fixed(..) = a.ty else { return None };
+    let w = check(&a.term).as_bv();
+    Some(fits_int(neg_uint(bv_resize(a.term.clone(), w, w + 1, true)), w))
+}
+
+fn wrap_fixed_pred(name: &str, f: fn(Term, Term) -> Term, a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
+    let (_, a, b) = fixed_operands(name, a, b)?;
+    Ok(PyTerm::new(Ty::Bool, f(a, b)))
+}
+
 fn wrap_bin_op(
     name: &str,
     fu: Option<fn(Term, Term) -> Term>,
+    fi: Option<fn(Term, Term) -> Term>,
     ff: Option<fn(Term, Term) -> Term>,
     fb: Option<fn(Term, Term) -> Term>,
     a: PyTerm,
     b: PyTerm,
 ) -> Result<PyTerm, String> {
-    match (&a.ty, &b.ty, fu, ff, fb) {
-        (Ty::Uint(na), Ty::Uint(nb), Some(fu), _, _) if na == nb => {
+    let (a, b) = coerce_int_lits(a, b);
+    match (&a.ty, &b.ty, fu, fi, ff, fb) {
+        (Ty::Uint(na), Ty::Uint(nb), Some(fu), _, _, _) if na == nb => {
             Ok(PyTerm::new(Ty::Uint(*na), fu(a.term.clone(), b.term.clone())))
         }
-        (Ty::Bool, Ty::Bool, _, _, Some(fb)) => {
+        (Ty::Int(na), Ty::Int(nb), _, Some(fi), _, _) if na == nb => {
+            Ok(PyTerm::new(Ty::Int(*na), fi(a.term.clone(), b.term.clone())))
+        }
+        (Ty::Bool, Ty::Bool, _, _, _, Some(fb)) => {
             Ok(PyTerm::new(Ty::Bool, fb(a.term.clone(), b.term.clone())))
         }
-        (Ty::Field, Ty::Field, _, Some(ff), _) => {
+        (Ty::Field, Ty::Field, _, _, Some(ff), _) => {
             Ok(PyTerm::new(Ty::Field, ff(a.term.clone(), b.term.clone())))
         }
-        (x, y, _, _, _) => Err(format!("Cannot perform op '{name}' on {x} and {y}")),
+        (x, y, _, _, _, _) => Err(format!("Cannot perform op '{name}' on {x} and {y}")),
     }
 }
 
 fn wrap_bin_pred(
     name: &str,
     fu: Option<fn(Term, Term) -> Term>,
+    fi: Option<fn(Term, Term) -> Term>,
     ff: Option<fn(Term, Term) -> Term>,
     fb: Option<fn(Term, Term) -> Term>,
     a: PyTerm,
     b: PyTerm,
 ) -> Result<PyTerm, String> {
-    match (&a.ty, &b.ty, fu, ff, fb) {
-        (Ty::Uint(na), Ty::Uint(nb), Some(fu), _, _) if na == nb => {
+    let (a, b) = coerce_int_lits(a, b);
+    match (&a.ty, &b.ty, fu, fi, ff, fb) {
+        (Ty::Uint(na), Ty::Uint(nb), Some(fu), _, _, _) if na == nb => {
             Ok(PyTerm::new(Ty::Bool, fu(a.term.clone(), b.term.clone())))
         }
-        (Ty::Bool, Ty::Bool, _, _, Some(fb)) => {
+        (Ty::Int(na), Ty::Int(nb), _, Some(fi), _, _) if na == nb => {
+            Ok(PyTerm::new(Ty::Bool, fi(a.term.clone(), b.term.clone())))
+        }
+        (Ty::Bool, Ty::Bool, _, _, _, Some(fb)) => {
             Ok(PyTerm::new(Ty::Bool, fb(a.term.clone(), b.term.clone())))
         }
-        (Ty::Field, Ty::Field, _, Some(ff), _) => {
+        (Ty::Field, Ty::Field, _, _, Some(ff), _) => {
             Ok(PyTerm::new(Ty::Bool, ff(a.term.clone(), b.term.clone())))
         }
-        (x, y, _, _, _) => Err(format!("Cannot perform op '{name}' on {x} and {y}")),
+        (x, y, _, _, _, _) => Err(format!("Cannot perform op '{name}' on {x} and {y}")),
     }
 }
 
@@ -397,7 +580,10 @@ fn add_field(a: Term, b: Term) -> Term {
 }
 
 pub fn add(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_op("+", Some(add_uint), Some(add_field), None, a, b)
+    if is_fixed(&a, &b) {
+        return wrap_fixed_op("+", add_uint, a, b);
+    }
+    wrap_bin_op("+", Some(add_uint), Some(add_uint), Some(add_field), None, a, b)
 }
 
 fn sub_uint(a: Term, b: Term) -> Term {
@@ -409,7 +595,10 @@ fn sub_field(a: Term, b: Term) -> Term {
 }
 
 pub fn sub(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_op("-", Some(sub_uint), Some(sub_field), None, a, b)
+    if is_fixed(&a, &b) {
+        return wrap_fixed_op("-", sub_uint, a, b);
+    }
+    wrap_bin_op("-", Some(sub_uint), Some(sub_uint), Some(sub_field), None, a, b)
 }
 
 fn mul_uint(a: Term, b: Term) -> Term {
@@ -420,8 +609,20 @@ fn mul_field(a: Term, b: Term) -> Term {
     term![Op::PfNaryOp(PfNaryOp::Mul); a, b]
 }
 
+// Multiply in double width, then drop the extra `frac` bits (rounding down)
+fn mul_fixed(a: Term, b: Term, frac: usize) -> Term {
+    let w = check(&a).as_bv();
+    let p = mul_uint(bv_resize(a, w, 2 * w, true), bv_resize(b, w, 2 * w, true));
+    fixed_rescale_ir(p, 2 * frac, w, frac, Rounding::Floor)
+}
+
 pub fn mul(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_op("*", Some(mul_uint), Some(mul_field), None, a, b)
+    if is_fixed(&a, &b) {
+        let (ty, a, b) = fixed_operands("*", a, b)?;
+        let Ty::Fixed(_, frac) = ty else { unreachable!() };
+        return Ok(PyTerm::new(ty, mul_fixed(a, b, frac)));
+    }
+    wrap_bin_op("*", Some(mul_uint), Some(mul_uint), Some(mul_field), None, a, b)
 }
 
 fn div_uint(a: Term, b: Term) -> Term {
@@ -432,8 +633,56 @@ fn div_field(a: Term, b: Term) -> Term {
     term![Op::PfNaryOp(PfNaryOp::Mul); a, term![Op::PfUnOp(PfUnOp::Recip); b]]
 }
 
+// The sign bit of a signed integer
+fn sign_int(a: &Term) -> Term {
+    let w = check(a).as_bv();
+    term![Op::BvBit(w - 1); a.clone()]
+}
+
+fn abs_int(a: &Term) -> Term {
+    term![Op::Ite; sign_int(a), neg_uint(a.clone()), a.clone()]
+}
+
+// Signed division and remainder, rounding towards negative infinity like Python
+fn divrem_int(a: Term, b: Term) -> (Term, Term) {
+    let w = check(&a).as_bv();
+    let (sa, sb) = (sign_int(&a), sign_int(&b));
+    let q = div_uint(abs_int(&a), abs_int(&b));
+    let r = rem_uint(abs_int(&a), abs_int(&b));
+    let signs_differ = term![Op::Not; term![Op::Eq; sa.clone(), sb]];
+    // truncated results
+    let q = term![Op::Ite; signs_differ.clone(), neg_uint(q.clone()), q];
+    let r = term![Op::Ite; sa, neg_uint(r.clone()), r];
+    // floored results
+    let adjust = term![Op::BoolNaryOp(BoolNaryOp::And);
+        signs_differ,
+        term![Op::Not; term![Op::Eq; r.clone(), bv_lit(0, w)]]
+    ];
+    (
+        term![Op::Ite; adjust.clone(), sub_uint(q.clone(), bv_lit(1, w)), q],
+        term![Op::Ite; adjust, add_uint(r.clone(), b), r],
+    )
+}
+
+fn div_int(a: Term, b: Term) -> Term {
+    divrem_int(a, b).0
+}
+
+// Pre-scale the dividend by 2^frac so the quotient keeps `frac` fractional bits
+fn div_fixed(a: Term, b: Term, frac: usize) -> Term {
+    let w = check(&a).as_bv();
+    let a = term![Op::BvBinOp(BvBinOp::Shl); bv_resize(a, w, w + frac, true), bv_lit(frac, w + frac)];
+    let q = div_int(a, bv_resize(b, w, w + frac, true));
+    bv_resize(q, w + frac, w, true)
+}
+
 pub fn div(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_op("/", Some(div_uint), Some(div_field), None, a, b)
+    if is_fixed(&a, &b) {
+        let (ty, a, b) = fixed_operands("/", a, b)?;
+        let Ty::Fixed(_, frac) = ty else { unreachable!() };
+        return Ok(PyTerm::new(ty, div_fixed(a, b, frac)));
+    }
+    wrap_bin_op("/", Some(div_uint), Some(div_int), Some(div_field), None, a, b)
 }
 
 fn rem_field(a: Term, b: Term) -> Term {
@@ -447,8 +696,12 @@ fn rem_uint(a: Term, b: Term) -> Term {
     term![Op::BvBinOp(BvBinOp::Urem); a, b]
 }
 
+fn rem_int(a: Term, b: Term) -> Term {
+    divrem_int(a, b).1
+}
+
 pub fn rem(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_op("%", Some(rem_uint), Some(rem_field), None, a, b)
+    wrap_bin_op("%", Some(rem_uint), Some(rem_int), Some(rem_field), None, a, b)
 }
 
 fn bitand_uint(a: Term, b: Term) -> Term {
@@ -456,7 +709,7 @@ fn bitand_uint(a: Term, b: Term) -> Term {
 }
 
 pub fn bitand(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_op("&", Some(bitand_uint), None, None, a, b)
+    wrap_bin_op("&", Some(bitand_uint), Some(bitand_uint), None, None, a, b)
 }
 
 fn bitor_uint(a: Term, b: Term) -> Term {
@@ -464,7 +717,7 @@ fn bitor_uint(a: Term, b: Term) -> Term {
 }
 
 pub fn bitor(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_op("|", Some(bitor_uint), None, None, a, b)
+    wrap_bin_op("|", Some(bitor_uint), Some(bitor_uint), None, None, a, b)
 }
 
 fn bitxor_uint(a: Term, b: Term) -> Term {
@@ -472,7 +725,7 @@ fn bitxor_uint(a: Term, b: Term) -> Term {
 }
 
 pub fn bitxor(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_op("^", Some(bitxor_uint), None, None, a, b)
+    wrap_bin_op("^", Some(bitxor_uint), Some(bitxor_uint), None, None, a, b)
 }
 
 fn or_bool(a: Term, b: Term) -> Term {
@@ -480,7 +733,7 @@ fn or_bool(a: Term, b: Term) -> Term {
 }
 
 pub fn or(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_op("or", None, None, Some(or_bool), a, b)
+    wrap_bin_op("or", None, None, None, Some(or_bool), a, b)
 }
 
 fn and_bool(a: Term, b: Term) -> Term {
@@ -488,10 +741,11 @@ fn and_bool(a: Term, b: Term) -> Term {
 }
 
 pub fn and(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_op("and", None, None, Some(and_bool), a, b)
+    wrap_bin_op("and", None, None, None, Some(and_bool), a, b)
 }
 
 fn eq_base(a: PyTerm, b: PyTerm) -> Result<Term, String> {
+    let (a, b) = coerce_int_lits(a, b);
     if a.ty != b.ty {
         Err(format!(
             "Cannot '==' dissimilar types {} and {}",
@@ -526,8 +780,15 @@ fn ult_field(a: Term, b: Term) -> Term {
     field_comp(a, b, BvBinPred::Ult)
 }
 
+fn ult_int(a: Term, b: Term) -> Term {
+    term![Op::BvBinPred(BvBinPred::Slt); a, b]
+}
+
 pub fn ult(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_pred("<", Some(ult_uint), Some(ult_field), None, a, b)
+    if is_fixed(&a, &b) {
+        return wrap_fixed_pred("<", ult_int, a, b);
+    }
+    wrap_bin_pred("<", Some(ult_uint), Some(ult_int), Some(ult_field), None, a, b)
 }
 
 fn ule_uint(a: Term, b: Term) -> Term {
@@ -538,8 +799,15 @@ fn ule_field(a: Term, b: Term) -> Term {
     field_comp(a, b, BvBinPred::Ule)
 }
 
+fn ule_int(a: Term, b: Term) -> Term {
+    term![Op::BvBinPred(BvBinPred::Sle); a, b]
+}
+
 pub fn ule(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_pred("<=", Some(ule_uint), Some(ule_field), None, a, b)
+    if is_fixed(&a, &b) {
+        return wrap_fixed_pred("<=", ule_int, a, b);
+    }
+    wrap_bin_pred("<=", Some(ule_uint), Some(ule_int), Some(ule_field), None, a, b)
 }
 
 fn ugt_uint(a: Term, b: Term) -> Term {
@@ -550,8 +818,15 @@ fn ugt_field(a: Term, b: Term) -> Term {
     field_comp(a, b, BvBinPred::Ugt)
 }
 
+fn ugt_int(a: Term, b: Term) -> Term {
+    term![Op::BvBinPred(BvBinPred::Sgt); a, b]
+}
+
 pub fn ugt(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_pred(">", Some(ugt_uint), Some(ugt_field), None, a, b)
+    if is_fixed(&a, &b) {
+        return wrap_fixed_pred(">", ugt_int, a, b);
+    }
+    wrap_bin_pred(">", Some(ugt_uint), Some(ugt_int), Some(ugt_field), None, a, b)
 }
 
 fn uge_uint(a: Term, b: Term) -> Term {
@@ -562,8 +837,15 @@ fn uge_field(a: Term, b: Term) -> Term {
     field_comp(a, b, BvBinPred::Uge)
 }
 
+fn uge_int(a: Term, b: Term) -> Term {
+    term![Op::BvBinPred(BvBinPred::Sge); a, b]
+}
+
 pub fn uge(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_bin_pred(">=", Some(uge_uint), Some(uge_field), None, a, b)
+    if is_fixed(&a, &b) {
+        return wrap_fixed_pred(">=", uge_int, a, b);
+    }
+    wrap_bin_pred(">=", Some(uge_uint), Some(uge_int), Some(uge_field), None, a, b)
 }
 
 pub fn pow(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
@@ -593,15 +875,17 @@ pub fn pow(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
 fn wrap_un_op(
     name: &str,
     fu: Option<fn(Term) -> Term>,
+    fi: Option<fn(Term) -> Term>,
     ff: Option<fn(Term) -> Term>,
     fb: Option<fn(Term) -> Term>,
     a: PyTerm,
 ) -> Result<PyTerm, String> {
-    match (&a.ty, fu, ff, fb) {
-        (Ty::Uint(_), Some(fu), _, _) => Ok(PyTerm::new(a.ty.clone(), fu(a.term.clone()))),
-        (Ty::Bool, _, _, Some(fb)) => Ok(PyTerm::new(Ty::Bool, fb(a.term.clone()))),
-        (Ty::Field, _, Some(ff), _) => Ok(PyTerm::new(Ty::Field, ff(a.term.clone()))),
-        (x, _, _, _) => Err(format!("Cannot perform op '{name}' on {x}")),
+    match (&a.ty, fu, fi, ff, fb) {
+        (Ty::Uint(_), Some(fu), _, _, _) => Ok(PyTerm::new(a.ty.clone(), fu(a.term.clone()))),
+        (Ty::Int(_), _, Some(fi), _, _) => Ok(PyTerm::new(a.ty.clone(), fi(a.term.clone()))),
+        (Ty::Bool, _, _, _, Some(fb)) => Ok(PyTerm::new(Ty::Bool, fb(a.term.clone()))),
+        (Ty::Field, _, _, Some(ff), _) => Ok(PyTerm::new(Ty::Field, ff(a.term.clone()))),
+        (x, _, _, _, _) => Err(format!("Cannot perform op '{name}' on {x}")),
     }
 }
 
@@ -614,7 +898,10 @@ fn neg_uint(a: Term) -> Term {
 }
 
 pub fn neg(a: PyTerm) -> Result<PyTerm, String> {
-    wrap_un_op("unary-", Some(neg_uint), Some(neg_field), None, a)
+    if let Ty::Fixed(..) = a.ty {
+        return Ok(PyTerm::new(a.ty, neg_uint(a.term)));
+    }
+    wrap_un_op("unary-", Some(neg_uint), Some(neg_uint), Some(neg_field), None, a)
 }
 
 fn not_bool(a: Term) -> Term {
@@ -626,7 +913,7 @@ fn not_uint(a: Term) -> Term {
 }
 
 pub fn not(a: PyTerm) -> Result<PyTerm, String> {
-    wrap_un_op("not", Some(not_uint), None, Some(not_bool), a)
+    wrap_un_op("not", Some(not_uint), Some(not_uint), None, Some(not_bool), a)
 }
 
 pub fn const_int(a: PyTerm) -> Result<Integer, String> {
@@ -669,7 +956,9 @@ pub fn bool(a: PyTerm) -> Result<Term, String> {
 fn wrap_shift(name: &str, op: BvBinOp, a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
     let bc = const_int(b)?;
     match &a.ty {
-        &Ty::Uint(na) => Ok(PyTerm::new(a.ty, term![Op::BvBinOp(op); a.term, bv_lit(bc, na)])),
+        &Ty::Uint(na) | &Ty::Int(na) => {
+            Ok(PyTerm::new(a.ty, term![Op::BvBinOp(op); a.term, bv_lit(bc, na)]))
+        }
         x => Err(format!("Cannot perform op '{name}' on {x} and {bc}")),
     }
 }
@@ -679,7 +968,12 @@ pub fn shl(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
 }
 
 pub fn shr(a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
-    wrap_shift(">>", BvBinOp::Lshr, a, b)
+    // Signed integers use an arithmetic shift, as in Python
+    if let Ty::Int(_) = a.ty {
+        wrap_shift(">>", BvBinOp::Ashr, a, b)
+    } else {
+        wrap_shift(">>", BvBinOp::Lshr, a, b)
+    }
 }
 
 fn ite(c: Term, a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
@@ -694,6 +988,36 @@ pub fn cond(c: PyTerm, a: PyTerm, b: PyTerm) -> Result<PyTerm, String> {
     ite(bool(c)?, a, b)
 }
 
+/// The comparators `(i, j)`, with `i < j`, of Batcher's merge-exchange
+/// sorting network for `n` elements (Knuth, TAOCP 5.2.2, Algorithm M)
+pub fn sorting_network(n: usize) -> Vec<(usize, usize)> {
+    let mut cmps = Vec::new();
+    if n < 2 {
+        return cmps;
+    }
+    // ceil(log2(n))
+    let t = usize::BITS - (n - 1).leading_zeros();
+    let mut p = 1 << (t - 1);
+    while p > 0 {
+        let (mut q, mut r, mut d) = (1 << (t - 1), 0, p);
+        loop {
+            for i in 0..n - d {
+                if i & p == r {
+                    cmps.push((i, i + d));
+                }
+            }
+            if q == p {
+                break;
+            }
+            d = q - p;
+            q >>= 1;
+            r = p;
+        }
+        p >>= 1;
+    }
+    cmps
+}
+
 pub fn pf_lit_ir<I>(i: I) -> Term
 where
     Integer: From<I>,
@@ -726,6 +1050,14 @@ where
     PyTerm::new(Ty::Uint(bits), bv_lit(v, bits))
 }
 
+/// A signed integer literal, stored in two's complement
+pub fn int_lit<I>(v: I, bits: usize) -> PyTerm
+where
+    Integer: From<I>,
+{
+    PyTerm::new(Ty::Int(bits), bv_lit(Integer::from(v).keep_bits(bits as u32), bits))
+}
+
 pub fn slice(arr: PyTerm, start: Option<usize>, end: Option<usize>, step: Option<isize>) -> Result<PyTerm, String> {
     match &arr.ty {
         Ty::Array(size, _) => {
@@ -839,6 +1171,51 @@ pub fn array_select(array: PyTerm, idx: PyTerm) -> Result<PyTerm, String> {
     }
 }
 
+// Index as a field element, or None if it is a constant, in which case
+// the plain select/store suffices
+fn scan_index(array: &PyTerm, idx: &PyTerm) -> Result<Option<Term>, String> {
+    match (&array.ty, &idx.ty) {
+        (Ty::Array(..), Ty::Uint(_) | Ty::Field) if const_int(idx.clone()).is_err() => {
+            coerce_to_field(idx.clone()).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Select at a witness-dependent index by muxing over every element
+/// (linear scan), so the array needs no memory-checking. Like a `Select`,
+/// an index out of range reads the default value of the element sort.
+pub fn array_select_scan(array: PyTerm, idx: PyTerm) -> Result<PyTerm, String> {
+    let Some(i) = scan_index(&array, &idx)? else {
+        return array_select(array, idx);
+    };
+    let elem_ty = array.ty.array_val_ty().clone();
+    let default = elem_ty.sort().default_term();
+    let t = array.unwrap_array_ir()?.into_iter().enumerate().fold(default, |acc, (k, e)| {
+        term![Op::Ite; term![Op::Eq; i.clone(), pf_lit_ir(k)], e, acc]
+    });
+    Ok(PyTerm::new(elem_ty, t))
+}
+
+/// Store at a witness-dependent index by conditionally updating every
+/// element (linear scan), so the array needs no memory-checking.
+pub fn array_store_scan(array: PyTerm, idx: PyTerm, val: PyTerm) -> Result<PyTerm, String> {
+    let Some(i) = scan_index(&array, &idx)? else {
+        return array_store(array, idx, val);
+    };
+    if array.ty.array_val_ty() != &val.ty {
+        return Err(format!("Cannot store {} in {}", &val.ty, &array.ty));
+    }
+    let elem_ty = val.ty.clone();
+    let elems = array.unwrap_array_ir()?.into_iter().enumerate().map(|(k, e)| {
+        PyTerm::new(
+            elem_ty.clone(),
+            term![Op::Ite; term![Op::Eq; i.clone(), pf_lit_ir(k)], val.term.clone(), e],
+        )
+    });
+    self::array(elems)
+}
+
 // pub fn mut_array_store(array: PyTerm, idx: PyTerm, val: PyTerm, cond: Term) -> Result<PyTerm, String> {
 //     if !matches!(array.ty, Ty::MutArray(_) | Ty::Array(..)) {
 //         return Err(format!(
@@ -906,6 +1283,232 @@ pub fn uint_to_field(u: PyTerm) -> Result<PyTerm, String> {
     }
 }
 
+// Two's complement value of a signed integer as a field element
+fn int_to_field_ir(i: Term) -> Term {
+    term![Op::Ite;
+        sign_int(&i),
+        term![Op::PfUnOp(PfUnOp::Neg); term![Op::UbvToPf(default_field()); neg_uint(i.clone())]],
+        term![Op::UbvToPf(default_field()); i]
+    ]
+}
+
+pub fn int_to_field(i: PyTerm) -> Result<PyTerm, String> {
+    match &i.ty {
+        Ty::Int(_) => Ok(PyTerm::new(Ty::Field, int_to_field_ir(i.term))),
+        i => Err(format!("Cannot do int-to-field on {i}")),
+    }
+}
+
+// Resize a bit-vector, sign-extending if needed
+fn bv_resize(t: Term, n: usize, w: usize, signed: bool) -> Term {
+    if n < w && signed {
+        term![Op::BvSext(w - n); t]
+    } else if n < w {
+        term![Op::BvUext(w - n); t]
+    } else if n > w {
+        term![Op::BvExtract(w - 1, 0); t]
+    } else {
+        t
+    }
+}
+
+/// Cast to a signed integer of width `w`, sign-extending signed integers
+/// and zero-extending unsigned ones. Wider values are truncated.
+pub fn int_cast(a: PyTerm, w: usize) -> Result<PyTerm, String> {
+    match &a.ty {
+        Ty::Int(n) => Ok(PyTerm::new(Ty::Int(w), bv_resize(a.term, *n, w, true))),
+        Ty::Uint(n) => Ok(PyTerm::new(Ty::Int(w), bv_resize(a.term, *n, w, false))),
+        Ty::Bool => Ok(PyTerm::new(Ty::Int(w), uint_from_bool(a, w)?.term)),
+        Ty::Field => {
+            // Field elements above (p - 1) / 2 are negative, e.g. p - 1 is -1:
+            // offset the range [-2^(w-1), 2^(w-1)) onto [0, 2^w) for the
+            // conversion, and back in two's complement. Values outside of
+            // that range are not representable in i{w}.
+            let offset = Integer::from(1) << (w - 1) as u32;
+            let shifted = term![Op::PfNaryOp(PfNaryOp::Add); a.term, pf_lit_ir(offset.clone())];
+            let bv = term![Op::PfToBv(w); shifted];
+            Ok(PyTerm::new(Ty::Int(w), term![Op::BvBinOp(BvBinOp::Sub); bv, bv_lit(offset, w)]))
+        }
+        a => Err(format!("Cannot cast {a} to i{w}")),
+    }
+}
+
+/// Cast a signed integer to an unsigned integer of width `w`, sign-extending
+pub fn int_to_uint(a: PyTerm, w: usize) -> Result<PyTerm, String> {
+    match &a.ty {
+        Ty::Int(n) => Ok(PyTerm::new(Ty::Uint(w), bv_resize(a.term, *n, w, true))),
+        a => Err(format!("Cannot do int-to-uint on {a}")),
+    }
+}
+
+/// Rounding modes for fixed-point rescaling, numbered as in `EMBED.py`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round towards negative infinity
+    Floor,
+    /// Round to the nearest value, ties towards positive infinity
+    Nearest,
+    /// Round towards zero
+    Zero,
+}
+
+impl Rounding {
+    pub fn from_int(mode: &Integer) -> Result<Self, String> {
+        match mode.to_u32() {
+            Some(0) => Ok(Rounding::Floor),
+            Some(1) => Ok(Rounding::Nearest),
+            Some(2) => Ok(Rounding::Zero),
+            _ => Err(format!("Unknown rounding mode {mode}")),
+        }
+    }
+}
+
+// Move a fixed-point bit-vector with `f1` fractional bits to width `w2` with
+// `f2` fractional bits, rounding any dropped bits and wrapping on overflow
+fn fixed_rescale_ir(t: Term, f1: usize, w2: usize, f2: usize, mode: Rounding) -> Term {
+    let exact = fixed_rescale_exact(t, f1, w2, f2, mode);
+    let wm = check(&exact).as_bv();
+    bv_resize(exact, wm, w2, true)
+}
+
+// The rescaled value of `fixed_rescale_ir` before it is cut down to `w2` bits
+fn fixed_rescale_exact(t: Term, f1: usize, w2: usize, f2: usize, mode: Rounding) -> Term {
+    let w1 = check(&t).as_bv();
+    // Leave room for the left shift and the rounding bias
+    let wm = w1.max(w2) + f2.saturating_sub(f1) + 1;
+    let t = bv_resize(t, w1, wm, true);
+    if f2 >= f1 {
+        term![Op::BvBinOp(BvBinOp::Shl); t, bv_lit(f2 - f1, wm)]
+    } else {
+        let d = f1 - f2;
+        let bias = match mode {
+            Rounding::Floor => bv_lit(0, wm),
+            Rounding::Nearest => bv_lit(Integer::from(1) << (d - 1) as u32, wm),
+            Rounding::Zero => term![Op::Ite;
+                sign_int(&t),
+                bv_lit((Integer::from(1) << d as u32) - 1, wm),
+                bv_lit(0, wm)
+            ],
+        };
+        term![Op::BvBinOp(BvBinOp::Ashr); add_uint(t, bias), bv_lit(d, wm)]
+    }
+}
+
+fn check_fixed_format(int_bits: usize, frac_bits: usize) -> Result<(), String> {
+    if int_bits == 0 {
+        Err(format!("Fixed[{int_bits}, {frac_bits}] needs at least one integer (sign) bit"))
+    } else {
+        Ok(())
+    }
+}
+
+/// A fixed-point literal for the integer `v`
+pub fn fixed_int_lit<I>(v: I, int_bits: usize, frac_bits: usize) -> PyTerm
+where
+    Integer: From<I>,
+{
+    let w = int_bits + frac_bits;
+    let raw = (Integer::from(v) << frac_bits as u32).keep_bits(w as u32);
+    PyTerm::new(Ty::Fixed(int_bits, frac_bits), bv_lit(raw, w))
+}
+
+/// A fixed-point literal for `num / den`, rounded down
+pub fn fixed_ratio_lit(num: Integer, den: Integer, int_bits: usize, frac_bits: usize) -> Result<PyTerm, String> {
+    check_fixed_format(int_bits, frac_bits)?;
+    if den == 0 {
+        return Err("Fixed-point ratio with a zero denominator".to_string());
+    }
+    let w = int_bits + frac_bits;
+    let raw = (num << frac_bits as u32).div_rem_floor(den).0.keep_bits(w as u32);
+    Ok(PyTerm::new(Ty::Fixed(int_bits, frac_bits), bv_lit(raw, w)))
+}
+
+/// Convert an (un)signed integer to fixed-point, wrapping if it does not fit
+pub fn fixed_from_int(a: PyTerm, int_bits: usize, frac_bits: usize) -> Result<PyTerm, String> {
+    check_fixed_format(int_bits, frac_bits)?;
+    let w = int_bits + frac_bits;
+    let t = match &a.ty {
+        Ty::Uint(n) => bv_resize(a.term, *n, w, false),
+        Ty::Int(n) => bv_resize(a.term, *n, w, true),
+        a => return Err(format!("Cannot do fixed-from-int on {a}")),
+    };
+    Ok(PyTerm::new(
+        Ty::Fixed(int_bits, frac_bits),
+        term![Op::BvBinOp(BvBinOp::Shl); t, bv_lit(frac_bits, w)],
+    ))
+}
+
+/// Convert a field element to fixed-point. The element must be smaller than
+/// 2^(int_bits - 1), which is enforced by the bit decomposition.
+pub fn fixed_from_field(a: PyTerm, int_bits: usize, frac_bits: usize) -> Result<PyTerm, String> {
+    check_fixed_format(int_bits, frac_bits)?;
+    let w = int_bits + frac_bits;
+    if int_bits < 2 {
+        return Err(format!("Cannot do fixed-from-field to Fixed[{int_bits}, {frac_bits}]"));
+    }
+    match &a.ty {
+        Ty::Field => {
+            let t = bv_resize(term![Op::PfToBv(int_bits - 1); a.term], int_bits - 1, w, false);
+            Ok(PyTerm::new(
+                Ty::Fixed(int_bits, frac_bits),
+                term![Op::BvBinOp(BvBinOp::Shl); t, bv_lit(frac_bits, w)],
+            ))
+        }
+        a => Err(format!("Cannot do fixed-from-field on {a}")),
+    }
+}
+
+// The integer part of a fixed-point value, rounded down, as a signed bit-vector
+fn fixed_floor_ir(a: Term, frac_bits: usize) -> Term {
+    let w = check(&a).as_bv();
+    term![Op::BvBinOp(BvBinOp::Ashr); a, bv_lit(frac_bits, w)]
+}
+
+/// The integer part of a fixed-point value (rounded down) as a u32,
+/// in two's complement if negative
+pub fn fixed_to_uint(a: PyTerm) -> Result<PyTerm, String> {
+    match &a.ty {
+        Ty::Fixed(i, f) => Ok(PyTerm::new(
+            Ty::Uint(32),
+            bv_resize(fixed_floor_ir(a.term, *f), i + f, 32, true),
+        )),
+        a => Err(format!("Cannot do fixed-to-uint on {a}")),
+    }
+}
+
+/// The integer part of a fixed-point value (rounded down) as a field element
+pub fn fixed_to_field(a: PyTerm) -> Result<PyTerm, String> {
+    match &a.ty {
+        Ty::Fixed(_, f) => Ok(PyTerm::new(Ty::Field, int_to_field_ir(fixed_floor_ir(a.term, *f)))),
+        a => Err(format!("Cannot do fixed-to-field on {a}")),
+    }
+}
+
+/// Convert between fixed-point formats, rounding dropped fractional bits
+pub fn fixed_rescale(a: PyTerm, int_bits: usize, frac_bits: usize, mode: Rounding) -> Result<PyTerm, String> {
+    check_fixed_format(int_bits, frac_bits)?;
+    match &a.ty {
+        Ty::Fixed(_, f) => Ok(PyTerm::new(
+            Ty::Fixed(int_bits, frac_bits),
+            fixed_rescale_ir(a.term, *f, int_bits + frac_bits, frac_bits, mode),
+        )),
+        a => Err(format!("Cannot do fixed-rescale on {a}")),
+    }
+}
+
+/// The condition for `fixed_rescale` of `a` not to overflow the new format,
+/// which the front-end asserts like that of `fixed_in_range`
+pub fn fixed_rescale_in_range(a: &PyTerm, int_bits: usize, frac_bits: usize, mode: Rounding) -> Result<Term, String> {
+    check_fixed_format(int_bits, frac_bits)?;
+    match &a.ty {
+        Ty::Fixed(_, f) => {
+            let w = int_bits + frac_bits;
+            Ok(fits_int(fixed_rescale_exact(a.term.clone(), *f, w, frac_bits, mode), w))
+        }
+        a => Err(format!("Cannot do fixed-rescale on {a}")),
+    }
+}
+
 // pub fn uint_to_uint(u: PyTerm, w: usize) -> Result<PyTerm, String> {
 //     match &u.ty {
 //         Ty::Uint(n) if *n <= w => Ok(PyTerm::new(Ty::Uint(w), term![Op::BvUext(w - n); u.term])),
@@ -916,7 +1519,7 @@ pub fn uint_to_field(u: PyTerm) -> Result<PyTerm, String> {
 
 pub fn uint_to_bits(u: PyTerm) -> Result<PyTerm, String> {
     match &u.ty {
-        Ty::Uint(n) => Ok(PyTerm::new(
+        Ty::Uint(n) | Ty::Int(n) => Ok(PyTerm::new(
             Ty::Array(*n, Box::new(Ty::Bool)),
             ir_array(
                 Sort::Bool,
@@ -1088,11 +1691,11 @@ impl Embeddable for Python {
                     precompute.map(|p| p.term),
                 ),
             ),
-            Ty::Uint(w) => Self::T::new(
-                Ty::Uint(*w),
+            Ty::Uint(_) | Ty::Int(_) | Ty::Fixed(..) => Self::T::new(
+                ty.clone(),
                 ctx.cs.borrow_mut().new_var(
                     &name,
-                    Sort::BitVector(*w),
+                    ty.sort(),
                     visibility,
                     precompute.map(|p| p.term),
                 ),
@@ -1125,6 +1728,23 @@ impl Embeddable for Python {
                 )
                 .unwrap()
             },
+            Ty::Tuple(tys) => {
+                let ps: Vec<Option<Self::T>> = match precompute.map(|p| p.unwrap_tuple()) {
+                    Some(Ok(v)) => v.into_iter().map(Some).collect(),
+                    Some(Err(e)) => panic!("{}", e),
+                    None => std::iter::repeat(None).take(tys.len()).collect(),
+                };
+                // Elements are named `{name}.{i}`, e.g. `return.0`
+                Self::T::new_tuple(
+                    tys.iter()
+                        .zip(ps)
+                        .enumerate()
+                        .map(|(i, (ty, p))| {
+                            self.declare_input(ctx, ty, idx_name(&name, i), visibility, p)
+                        })
+                        .collect(),
+                )
+            },
             Ty::DataClass(n, fs) => {
                 let ps = match precompute.map(|p| p.unwrap_class()) {
                     Some(Ok(fl)) => fl,
@@ -1751,6 +2371,58 @@ mod tests {
         assert_eq!(const_value(&term_u16_1024), bv_lit(1024, 16).as_value_opt().map(|v| v.clone()));
     }
 
+    #[test]
+    fn test_fixed_in_range() {
+        // Fixed[8, 4] holds [-128, 128): 100 + 100 and 16 * 8 overflow
+        let fx = |v: i32| fixed_int_lit(v, 8, 4);
+        let in_range = |name: &str, a: i32, b: i32| {
+            let t = fixed_in_range(name, fx(a), fx(b)).unwrap().unwrap();
+            const_bool(PyTerm::new(Ty::Bool, t)).unwrap()
+        };
+        assert!(in_range("+", 100, 27));
+        assert!(!in_range("+", 100, 100));
+        assert!(in_range("-", -100, 28));
+        assert!(!in_range("-", -100, 29));
+        assert!(in_range("*", -16, 8));
+        assert!(!in_range("*", 16, 8));
+        assert!(in_range("/", -128, 2));
+        assert!(!in_range("/", -128, -1));
+        // 64 / 0.5 overflows, 63 / 0.5 does not
+        let half = || fixed_ratio_lit(Integer::from(1), Integer::from(2), 8, 4).unwrap();
+        let div_half = |a: i32| {
+            let t = fixed_in_range("/", fx(a), half()).unwrap().unwrap();
+            const_bool(PyTerm::new(Ty::Bool, t)).unwrap()
+        };
+        assert!(div_half(63));
+        assert!(!div_half(64));
+        assert!(fixed_in_range("+", uint_lit(1, 32), uint_lit(2, 32)).unwrap().is_none());
+
+        // only the minimum value overflows when negated
+        let neg_in_range = |a: i32| const_bool(PyTerm::new(Ty::Bool, fixed_neg_in_range(&fx(a)).unwrap())).unwrap();
+        assert!(neg_in_range(127));
+        assert!(!neg_in_range(-128));
+        assert!(fixed_neg_in_range(&uint_lit(1, 32)).is_none());
+    }
+
+    #[test]
+    fn test_pretty_tuple() {
+        let pretty = |t: PyTerm| {
+            let mut out = Vec::new();
+            t.pretty(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let pair = PyTerm::new(
+            Ty::Tuple(vec![Ty::Uint(8), Ty::Bool]),
+            constant_fold(&term![Op::Tuple; bv_lit(1, 8), bool_lit(true)], &[]),
+        );
+        assert_eq!(pretty(pair), "(0x01, true)");
+        let single = PyTerm::new(
+            Ty::Tuple(vec![Ty::Uint(8)]),
+            constant_fold(&term![Op::Tuple; bv_lit(1, 8)], &[]),
+        );
+        assert_eq!(pretty(single), "(0x01,)");
+    }
+
     #[test]
     fn test_const_value_field() {
         init_cfg();
@@ -1815,4 +2487,184 @@ mod tests {
         assert!(result_type_mismatch.is_err(), "Expected an error for type mismatch");
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_div_rem_int() {
+        // Signed division rounds towards negative infinity, like Python
+        let cases = [(7, 2, 3, 1), (-7, 2, -4, 1), (7, -2, -4, -1), (-7, -2, 3, -1), (-8, 2, -4, 0)];
+        for (a, b, q, r) in cases {
+            let term_a = int_lit(a, 8).term;
+            let term_b = int_lit(b, 8).term;
+            assert_unary_op(|t| div_int(t, term_b.clone()), term_a.clone(), int_lit(q, 8).term);
+            assert_unary_op(|t| rem_int(t, term_b.clone()), term_a, int_lit(r, 8).term);
+        }
+    }
+
+    #[test]
+    fn test_slt_int() {
+        // Test signed comparison on 8-bit BitVectors (-1 < 1, but 0xff > 1 unsigned)
+        let term_a = int_lit(-1, 8).term;
+        let term_b = int_lit(1, 8).term;
+        assert_bool_op(ult_int, term_a.clone(), term_b.clone(), true);
+        assert_bool_op(ult_uint, term_a.clone(), term_b.clone(), false);
+        assert_bool_op(uge_int, term_a, term_b, false);
+    }
+
+    #[test]
+    fn test_shr_int() {
+        // Signed right shift is arithmetic: -128 >> 7 should yield -1
+        let term = int_lit(-128, 8);
+        assert_shift_op(shr, term, uint_lit(7, 32), Integer::from(0xff));
+    }
+
+    #[test]
+    fn test_int_cast() {
+        // Sign-extend i8 -> i32, zero-extend u8 -> i32, and truncate i32 -> i8
+        let minus_one = int_lit(-1, 8);
+        assert_unary_op(|t| int_cast(PyTerm::new(Ty::Int(8), t), 32).unwrap().term, minus_one.term.clone(), int_lit(-1, 32).term);
+        assert_unary_op(|t| int_cast(PyTerm::new(Ty::Uint(8), t), 32).unwrap().term, minus_one.term, uint_lit(255, 32).term);
+        assert_unary_op(|t| int_cast(PyTerm::new(Ty::Int(32), t), 8).unwrap().term, int_lit(300, 32).term, int_lit(44, 8).term);
+    }
+
+    #[test]
+    fn test_int_cast_field() {
+        // Field elements read as signed integers: p - 1 is -1, not its low bits
+        init_cfg();
+        let modulus = cfg().field().modulus().clone();
+        let cast = |t: Term| int_cast(PyTerm::new(Ty::Field, t), 8).unwrap().term;
+        assert_unary_op(cast, pf_lit_ir(modulus.clone() - 1), int_lit(-1, 8).term);
+        assert_unary_op(cast, pf_lit_ir(modulus - 128), int_lit(-128, 8).term);
+        assert_unary_op(cast, pf_lit_ir(127), int_lit(127, 8).term);
+        assert_unary_op(cast, pf_lit_ir(0), int_lit(0, 8).term);
+    }
+
+    #[test]
+    fn test_int_to_field() {
+        // Negative signed integers map to p - |i|
+        init_cfg();
+        let modulus = cfg().field().modulus();
+        assert_unary_op(int_to_field_ir, int_lit(-5, 8).term, pf_lit_ir(modulus.clone() - 5));
+        assert_unary_op(int_to_field_ir, int_lit(5, 8).term, pf_lit_ir(5));
+    }
+
+    #[test]
+    fn test_int_lit_coercion() {
+        // u32 literals adapt to the width of a signed operand
+        let a = int_lit(-3, 8);
+        let sum = add(a, uint_lit(1, 32)).expect("Addition failed");
+        assert_eq!(sum.ty, Ty::Int(8));
+        assert_unary_op(|t| t, sum.term, int_lit(-2, 8).term);
+        assert!(add(int_lit(1, 8), uint_lit(1, 8)).is_err());
+        assert!(add(int_lit(1, 8), int_lit(1, 16)).is_err());
+    }
+
+    #[test]
+    fn test_tuple() {
+        // Test that tuple elements keep their types and values
+        let t = PyTerm::new_tuple(vec![uint_lit(7, 32), py_bool_lit(true)]);
+        assert_eq!(t.ty, Ty::Tuple(vec![Ty::Uint(32), Ty::Bool]));
+        assert_eq!(format!("{}", t.ty), "Tuple[u32, bool]");
+        assert_eq!(t.terms().len(), 2);
+
+        let elems = t.unwrap_tuple().expect("Expected a tuple");
+        assert_eq!(elems[1].ty, Ty::Bool);
+        assert_unary_op(|t| t, elems[0].term.clone(), bv_lit(7, 32));
+        assert!(uint_lit(7, 32).unwrap_tuple().is_err());
+    }
+
+    #[test]
+    fn test_array_scan() {
+        // Linear-scan select and store with a witness-dependent index
+        init_cfg();
+        let arr = PyTerm::new_array(vec![uint_lit(10, 8), uint_lit(20, 8), uint_lit(30, 8)]).unwrap();
+        let idx = PyTerm::new(Ty::Field, leaf_term(Op::Var("i".to_string(), default_field_sort())));
+        let sel = array_select_scan(arr.clone(), idx.clone()).unwrap();
+        assert_eq!(sel.ty, Ty::Uint(8));
+        assert!(!matches!(sel.term.op(), Op::Select));
+        assert!(array_store_scan(arr.clone(), idx.clone(), py_bool_lit(true)).is_err());
+        let stored = array_store_scan(arr.clone(), idx, uint_lit(7, 8)).unwrap();
+        assert_eq!(stored.ty, arr.ty);
+
+        // A constant index is a plain select
+        let sel = array_select_scan(arr.clone(), field_lit(1)).unwrap();
+        assert_unary_op(|t| t, sel.term, bv_lit(20, 8));
+
+        // An index out of range reads the default, not the first element
+        let idx = PyTerm::new(Ty::Uint(8), leaf_term(Op::Var("i".to_string(), Sort::BitVector(8))));
+        let sel = array_select_scan(arr, idx).unwrap();
+        let read = |i: &str| eval(&sel.term, &text::parse_value_map(format!("(let ((i {i})) false)").as_bytes()));
+        assert_eq!(*read("#x02").as_bv().uint(), 30);
+        assert_eq!(*read("#x03").as_bv().uint(), 0);
+        assert_eq!(*read("#xff").as_bv().uint(), 0);
+    }
+
+    #[test]
+    fn test_fixed_arith() {
+        // Fixed[8, 4]: 3 * -1.5 == -4.5, 3 / -1.5 == -2, and literals are scaled
+        let a = fixed_int_lit(3, 8, 4);
+        let b = fixed_ratio_lit(Integer::from(3), Integer::from(2), 8, 4).unwrap();
+        let b = neg(b).unwrap();
+        assert_eq!(format!("{}", a.ty), "Fixed[8, 4]");
+        assert_unary_op(|t| t, mul(a.clone(), b.clone()).unwrap().term, bv_lit(Integer::from(-72).keep_bits(12), 12));
+        assert_unary_op(|t| t, div(a.clone(), b.clone()).unwrap().term, fixed_int_lit(-2, 8, 4).term);
+        assert_unary_op(|t| t, add(a.clone(), uint_lit(1, 32)).unwrap().term, fixed_int_lit(4, 8, 4).term);
+        assert_bool_op(|x, y| ult(PyTerm::new(b.ty.clone(), x), PyTerm::new(a.ty.clone(), y)).unwrap().term, b.term.clone(), a.term.clone(), true);
+        assert!(add(a.clone(), fixed_int_lit(3, 8, 8)).is_err());
+        assert!(add(a, int_lit(1, 12)).is_err());
+    }
+
+    #[test]
+    fn test_fixed_rescale() {
+        // -1.375 (raw -22 in Fixed[8, 4]) to one fractional bit
+        let a = PyTerm::new(Ty::Fixed(8, 4), bv_lit(Integer::from(-22).keep_bits(12), 12));
+        let cases = [(Rounding::Floor, -3), (Rounding::Nearest, -3), (Rounding::Zero, -2)];
+        for (mode, raw) in cases {
+            let r = fixed_rescale(a.clone(), 8, 1, mode).unwrap();
+            assert_eq!(r.ty, Ty::Fixed(8, 1));
+            assert_unary_op(|t| t, r.term, bv_lit(Integer::from(raw).keep_bits(9), 9));
+        }
+        // Widening keeps the value
+        let r = fixed_rescale(a.clone(), 16, 8, Rounding::Floor).unwrap();
+        assert_unary_op(|t| t, r.term, bv_lit(Integer::from(-352).keep_bits(24), 24));
+        assert!(Rounding::from_int(&Integer::from(3)).is_err());
+
+        // 127.9375 (the maximum of Fixed[8, 4]) rounds to 128 to the nearest
+        let max = PyTerm::new(Ty::Fixed(8, 4), bv_lit(2047, 12));
+        let in_range = |a: &PyTerm, i: usize, f: usize, mode: Rounding| {
+            const_bool(PyTerm::new(Ty::Bool, fixed_rescale_in_range(a, i, f, mode).unwrap())).unwrap()
+        };
+        assert!(in_range(&max, 8, 0, Rounding::Floor));
+        assert!(!in_range(&max, 8, 0, Rounding::Nearest));
+        assert!(in_range(&a, 4, 4, Rounding::Floor));
+        assert!(!in_range(&max, 4, 4, Rounding::Floor));
+    }
+
+    #[test]
+    fn test_sorting_network() {
+        // by the 0-1 principle, sorting every bit-vector suffices
+        for n in 0..=10 {
+            let cmps = sorting_network(n);
+            for mask in 0u32..1 << n {
+                let mut bits: Vec<bool> = (0..n).map(|i| (mask >> i) & 1 == 1).collect();
+                for &(i, j) in &cmps {
+                    if bits[i] > bits[j] {
+                        bits.swap(i, j);
+                    }
+                }
+                assert!(bits.windows(2).all(|w| w[0] <= w[1]), "n = {n}, input {mask:b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixed_conversions() {
+        init_cfg();
+        let modulus = cfg().field().modulus();
+        // -2.5 rounds down to -3
+        let a = fixed_ratio_lit(Integer::from(5), Integer::from(2), 8, 4).map(|a| neg(a).unwrap()).unwrap();
+        assert_unary_op(|t| fixed_to_field(PyTerm::new(Ty::Fixed(8, 4), t)).unwrap().term, a.term.clone(), pf_lit_ir(modulus.clone() - 3));
+        assert_unary_op(|t| fixed_to_uint(PyTerm::new(Ty::Fixed(8, 4), t)).unwrap().term, a.term, uint_lit(Integer::from(-3).keep_bits(32), 32).term);
+        assert_unary_op(|t| fixed_from_int(PyTerm::new(Ty::Int(8), t), 8, 4).unwrap().term, int_lit(-3, 8).term, fixed_int_lit(-3, 8, 4).term);
+        assert_unary_op(|t| fixed_from_field(PyTerm::new(Ty::Field, t), 8, 4).unwrap().term, pf_lit_ir(5), fixed_int_lit(5, 8, 4).term);
+        assert!(fixed_from_field(field_lit(5), 1, 4).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! Side table from IR terms to the source that created them
+
+use circ::ir::term::*;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::path::PathBuf;
+
+/// A line of the source program
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SourceLine {
+    pub file: PathBuf,
+    /// 1-based
+    pub line: usize,
+    /// The functions being executed, outermost first
+    pub stack: Vec<String>,
+    /// The source text of the line
+    pub text: String,
+}
+
+impl SourceLine {
+    /// The innermost function, if any
+    pub fn function(&self) -> Option<&str> {
+        self.stack.last().map(String::as_str)
+    }
+}
+
+/// Records, for each term built by a front-end, the location (e.g. a
+/// [SourceLine]) of the first statement that produced it.
+///
+/// Later recordings get higher sequence numbers, which lets terms created
+/// later (e.g. by the optimizer) be attributed to the latest location of
+/// their operands.
+pub struct SourceMap<L = SourceLine> {
+    // (sequence number, index into locs)
+    tags: TermMap<(usize, usize)>,
+    locs: Vec<L>,
+    ids: HashMap<L, usize>,
+    seq: usize,
+}
+
+impl<L> Default for SourceMap<L> {
+    fn default() -> Self {
+        SourceMap {
+            tags: Default::default(),
+            locs: Vec::new(),
+            ids: HashMap::new(),
+            seq: 0,
+        }
+    }
+}
+
+impl<L: Clone + Eq + Hash> SourceMap<L> {
+    /// Attribute `t` and all its subterms that have no location yet to `loc`
+    pub fn record(&mut self, t: &Term, loc: L) {
+        let id = match self.ids.get(&loc) {
+            Some(id) => *id,
+            None => {
+                self.locs.push(loc.clone());
+                self.ids.insert(loc, self.locs.len() - 1);
+                self.locs.len() - 1
+            }
+        };
+        self.seq += 1;
+        let tags = &self.tags;
+        let fresh: Vec<Term> =
+            extras::PostOrderSkipIter::new(t.clone(), &|s: &Term| tags.contains_key(s)).collect();
+        for s in fresh {
+            self.tags.insert(s, (self.seq, id));
+        }
+    }
+
+    /// Change the representation of the locations, e.g. to resolve them
+    pub fn map_locs<M: Clone + Eq + Hash>(self, f: impl FnMut(L) -> M) -> SourceMap<M> {
+        let locs: Vec<M> = self.locs.into_iter().map(f).collect();
+        let ids = locs.iter().cloned().enumerate().map(|(i, l)| (l, i)).collect();
+        SourceMap {
+            tags: self.tags,
+            locs,
+            ids,
+            seq: self.seq,
+        }
+    }
+}
+
+impl<L> SourceMap<L> {
+    /// The sequence number and location of `t`
+    pub fn get(&self, t: &Term) -> Option<(usize, &L)> {
+        self.tags.get(t).map(|(seq, id)| (*seq, &self.locs[*id]))
+    }
+
+    /// The sequence number and index of the location of `t`
+    pub fn get_id(&self, t: &Term) -> Option<(usize, usize)> {
+        self.tags.get(t).copied()
+    }
+
+    /// All locations, indexed as returned by [SourceMap::get_id]
+    pub fn locs(&self) -> &[L] {
+        &self.locs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Tag `roots` and their subterms with the sequence number and location
+    /// index of their source.
+    ///
+    /// The optimizer rewrites most terms the front-end built, so a term
+    /// without a location gets the most recently recorded location among its
+    /// operands, and failing that, that of a term using it.
+    pub fn attribute(&self, roots: impl IntoIterator<Item = Term>) -> TermMap<(usize, usize)> {
+        let roots: TermSet = roots.into_iter().collect();
+        let order: Vec<Term> = PostOrderIter::from_roots_and_skips(roots, Default::default()).collect();
+
+        // operands to users
+        let mut tags: TermMap<(usize, usize)> = Default::default();
+        for t in &order {
+            let tag = self
+                .get_id(t)
+                .or_else(|| t.cs().iter().filter_map(|c| tags.get(c).copied()).max());
+            if let Some(tag) = tag {
+                tags.insert(t.clone(), tag);
+            }
+        }
+        // users to operands
+        for t in order.iter().rev() {
+            if let Some(tag) = tags.get(t).copied() {
+                for c in t.cs() {
+                    tags.entry(c.clone()).or_insert(tag);
+                }
+            }
+        }
+        tags
+    }
+}
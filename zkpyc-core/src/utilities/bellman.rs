@@ -3,9 +3,9 @@ use ::bellman::{groth16, Circuit, ConstraintSystem, LinearCombination, Synthesis
 use ff::{Field, PrimeField, PrimeFieldBits};
 use fxhash::FxHashMap;
 use gmp_mpfr_sys::gmp::limb_t;
-use group::WnafGroup;
+use group::{prime::PrimeCurveAffine, Curve, Group, WnafGroup};
 use log::debug;
-use pairing::{Engine, MultiMillerLoop};
+use pairing::{Engine, MillerLoopResult, MultiMillerLoop};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
@@ -221,6 +221,76 @@ mod serde_pf {
     }
 }
 
+pub use bls12_381::Bls12;
+
+/// The BN254 (alt_bn128) pairing engine, as used by Ethereum precompiles
+pub type Bn254 = halo2curves::bn256::Bn256;
+
+// Bn254 must implement the pairing traits of the bellman fork, not those of
+// another pairing version
+const _: fn() = || {
+    fn bellman_engine<E: MultiMillerLoop>()
+    where
+        E::G1: WnafGroup,
+        E::G2: WnafGroup,
+    {
+    }
+    bellman_engine::<Bn254>();
+};
+
+/// A pairing-friendly curve the [Bellman] and [Mirage](super::mirage::Mirage)
+/// back-ends can be instantiated with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PairingCurve {
+    /// [Bls12]
+    Bls12_381,
+    /// [Bn254]
+    Bn254,
+}
+
+impl PairingCurve {
+    pub const ALL: [PairingCurve; 2] = [PairingCurve::Bls12_381, PairingCurve::Bn254];
+
+    /// The order of the scalar field
+    pub fn modulus(&self) -> Integer {
+        match self {
+            PairingCurve::Bls12_381 => get_modulus::<<Bls12 as Engine>::Fr>(),
+            PairingCurve::Bn254 => get_modulus::<<Bn254 as Engine>::Fr>(),
+        }
+    }
+
+    /// The curve whose scalar field has order `modulus`
+    pub fn from_modulus(modulus: &Integer) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|c| &c.modulus() == modulus)
+            .ok_or_else(|| {
+                format!(
+                    "No pairing-friendly curve has a scalar field of order {modulus}. \
+                     The bellman back-ends support the scalar fields of BLS12-381 and BN254."
+                )
+            })
+    }
+}
+
+/// Evaluate `$body` with the type `$E` bound to the pairing engine of the
+/// [PairingCurve] `$curve`.
+#[macro_export]
+macro_rules! with_pairing_engine {
+    ($curve:expr, $E:ident => $body:expr) => {
+        match $curve {
+            $crate::utilities::bellman::PairingCurve::Bls12_381 => {
+                type $E = $crate::utilities::bellman::Bls12;
+                $body
+            }
+            $crate::utilities::bellman::PairingCurve::Bn254 => {
+                type $E = $crate::utilities::bellman::Bn254;
+                $body
+            }
+        }
+    };
+}
+
 /// The [::bellman] implementation of Groth16.
 pub struct Bellman<E: Engine>(PhantomData<E>);
 
@@ -231,6 +301,17 @@ pub struct ProvingKey<E: Engine>(
     #[serde(with = "serde_pk")] groth16::Parameters<E>,
 );
 
+impl<E: Engine> ProvingKey<E> {
+    pub fn new(data: ProverData, params: groth16::Parameters<E>) -> Self {
+        ProvingKey(data, params)
+    }
+
+    /// The relation and witness computation
+    pub fn into_data(self) -> ProverData {
+        self.0
+    }
+}
+
 /// The vk for [Bellman]
 #[derive(Serialize, Deserialize)]
 pub struct VerifyingKey<E: Engine>(
@@ -238,10 +319,39 @@ pub struct VerifyingKey<E: Engine>(
     #[serde(with = "serde_vk")] groth16::VerifyingKey<E>,
 );
 
+impl<E: Engine> VerifyingKey<E> {
+    pub fn new(data: VerifierData, vk: groth16::VerifyingKey<E>) -> Self {
+        VerifyingKey(data, vk)
+    }
+
+    /// The instance computation
+    pub fn data(&self) -> &VerifierData {
+        &self.0
+    }
+
+    pub fn into_data(self) -> VerifierData {
+        self.0
+    }
+
+    pub fn groth16(&self) -> &groth16::VerifyingKey<E> {
+        &self.1
+    }
+}
+
 /// The proof for [Bellman]
 #[derive(Serialize, Deserialize)]
 pub struct Proof<E: Engine>(#[serde(with = "serde_pf")] groth16::Proof<E>);
 
+impl<E: Engine> Proof<E> {
+    pub fn new(proof: groth16::Proof<E>) -> Self {
+        Proof(proof)
+    }
+
+    pub fn groth16(&self) -> &groth16::Proof<E> {
+        &self.0
+    }
+}
+
 impl<E: Engine> ProofSystem for Bellman<E>
 where
     E: MultiMillerLoop,
@@ -255,19 +365,24 @@ where
 
     type Proof = Proof<E>;
 
-    fn setup(p_data: ProverData, v_data: VerifierData) -> (Self::ProvingKey, Self::VerifyingKey) {
+    fn setup(
+        p_data: ProverData,
+        v_data: VerifierData,
+    ) -> Result<(Self::ProvingKey, Self::VerifyingKey), String> {
         assert_eq!(p_data.r1cs.commitments.len(), 0);
         let rng = &mut rand::thread_rng();
-        let params =
-            groth16::generate_random_parameters::<E, _, _>(SynthInput(&p_data, None), rng).unwrap();
+        let params = groth16::generate_random_parameters::<E, _, _>(SynthInput(&p_data, None), rng)
+            .map_err(|e| e.to_string())?;
         let v_params = params.vk.clone();
-        (ProvingKey(p_data, params), VerifyingKey(v_data, v_params))
+        Ok((ProvingKey(p_data, params), VerifyingKey(v_data, v_params)))
     }
 
-    fn prove(pk: &Self::ProvingKey, witness: &FxHashMap<String, Value>) -> Self::Proof {
+    fn prove(pk: &Self::ProvingKey, witness: &FxHashMap<String, Value>) -> Result<Self::Proof, String> {
         let rng = &mut rand::thread_rng();
         pk.0.check_all(witness);
-        Proof(groth16::create_random_proof(SynthInput(&pk.0, Some(witness)), &pk.1, rng).unwrap())
+        groth16::create_random_proof(SynthInput(&pk.0, Some(witness)), &pk.1, rng)
+            .map(Proof)
+            .map_err(|e| e.to_string())
     }
 
     fn verify(vk: &Self::VerifyingKey, inst: &FxHashMap<String, Value>, pf: &Self::Proof) -> bool {
@@ -280,3 +395,70 @@ where
         groth16::verify_proof(&pvk, &pf.0, &r1cs_inst).is_ok()
     }
 }
+
+impl<E: Engine> Bellman<E>
+where
+    E: MultiMillerLoop,
+{
+    /// Verify many proofs for the same verifying key at once.
+    ///
+    /// Each proof's equation is scaled by a fresh random scalar and the
+    /// results summed, so that one multi-pairing of `n + 3` pairs replaces
+    /// `n` verifications of 4 pairs each. An invalid proof passes only with
+    /// negligible probability. An empty batch, or one without exactly one
+    /// instance per proof, is rejected.
+    pub fn verify_batch(
+        vk: &VerifyingKey<E>,
+        insts: &[FxHashMap<String, Value>],
+        pfs: &[Proof<E>],
+    ) -> bool {
+        if pfs.is_empty() || insts.len() != pfs.len() {
+            return false;
+        }
+        let rng = &mut rand::thread_rng();
+        let vk_g = &vk.1;
+        let mut ic_sum = E::G1::identity();
+        let mut c_sum = E::G1::identity();
+        let mut r_sum = E::Fr::zero();
+        let mut pairs: Vec<(E::G1Affine, E::G2Prepared)> = Vec::with_capacity(pfs.len() + 3);
+        for (inst, pf) in insts.iter().zip(pfs) {
+            let r1cs_inst: Vec<E::Fr> = vk.0.eval(inst).into_iter().map(|i| int_to_ff(i.i())).collect();
+            if r1cs_inst.len() + 1 != vk_g.ic.len() {
+                return false;
+            }
+            let mut ic = vk_g.ic[0].to_curve();
+            for (x, base) in r1cs_inst.iter().zip(&vk_g.ic[1..]) {
+                ic += *base * x;
+            }
+            let r = E::Fr::random(&mut *rng);
+            ic_sum += ic * r;
+            c_sum += pf.0.c * r;
+            r_sum += r;
+            pairs.push(((pf.0.a * r).to_affine(), pf.0.b.into()));
+        }
+        // sum_i r_i e(A_i, B_i) = e(sum_i r_i alpha, beta) + e(sum_i r_i IC_i, gamma) + e(sum_i r_i C_i, delta)
+        pairs.push(((-ic_sum).to_affine(), vk_g.gamma_g2.into()));
+        pairs.push(((-c_sum).to_affine(), vk_g.delta_g2.into()));
+        pairs.push(((vk_g.alpha_g1 * -r_sum).to_affine(), vk_g.beta_g2.into()));
+        let terms: Vec<(&E::G1Affine, &E::G2Prepared)> = pairs.iter().map(|(a, b)| (a, b)).collect();
+        bool::from(E::multi_miller_loop(&terms).final_exponentiation().is_identity())
+    }
+
+    /// Batch verification from files: the instance and proof of each entry
+    pub fn verify_batch_fs(
+        vk_path: impl AsRef<Path>,
+        instance_paths: &[impl AsRef<Path>],
+        pf_paths: &[impl AsRef<Path>],
+    ) -> std::io::Result<bool> {
+        let vk: VerifyingKey<E> = proof::deserialize_from_file(vk_path)?;
+        let insts = instance_paths
+            .iter()
+            .map(proof::value_map_from_path)
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let pfs = pf_paths
+            .iter()
+            .map(proof::deserialize_from_file)
+            .collect::<std::io::Result<Vec<Proof<E>>>>()?;
+        Ok(Self::verify_batch(&vk, &insts, &pfs))
+    }
+}
@@ -0,0 +1,262 @@
+//! A transparent Bulletproofs back-end over the ristretto255 scalar field.
+//!
+//! Each constraint `a * b = c` becomes a multiplication gate whose inputs are
+//! constrained to `a` and `b` and whose output to `c`; the witness is
+//! allocated two variables to a gate. Public inputs are folded into the
+//! constants of the linear combinations, and the transcript absorbs both the
+//! relation and the public inputs, so that a proof cannot be replayed for
+//! another circuit or statement.
+
+use ::bulletproofs::r1cs::{
+    ConstraintSystem, LinearCombination, Prover, R1CSError, R1CSProof, Verifier,
+};
+use ::bulletproofs::{BulletproofGens, PedersenGens};
+use curve25519_dalek_ng::scalar::Scalar;
+use fxhash::FxHashMap as HashMap;
+use log::debug;
+use merlin::Transcript;
+use rug::{integer::Order, Integer};
+use serde::{Deserialize, Serialize};
+
+use super::proof;
+use super::r1cs::{Lc, ProverData, R1csFinal, Var, VarType, VerifierData};
+use circ::ir::term::Value;
+
+/// The order of the ristretto255 scalar field, the only field this back-end supports
+const MODULUS: &str =
+    "7237005577332262213973186563042994240857116359379907606001950938285454250989";
+
+const TRANSCRIPT_LABEL: &[u8] = b"zkpyc-bulletproofs";
+
+fn to_scalar(i: &Integer) -> Result<Scalar, String> {
+    let not_scalar = || format!("{i} is not a ristretto255 scalar");
+    let mut bytes = [0u8; 32];
+    let digits = i.to_digits::<u8>(Order::Lsf);
+    if digits.len() > 32 {
+        return Err(not_scalar());
+    }
+    bytes[..digits.len()].copy_from_slice(&digits);
+    Scalar::from_canonical_bytes(bytes).ok_or_else(not_scalar)
+}
+
+fn invalid_data(e: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+}
+
+/// A linear combination: its constant, and (column, coefficient) terms
+type Combination = (Scalar, Vec<(usize, Scalar)>);
+
+/// An R1CS over ristretto255 scalars, with the public inputs in columns
+/// `0..num_inputs` and the witness after them.
+struct Relation {
+    num_inputs: usize,
+    num_wits: usize,
+    constraints: Vec<[Combination; 3]>,
+}
+
+impl Relation {
+    /// The relation of `r1cs`, and the column of each variable
+    fn new(r1cs: &R1csFinal) -> Result<(Self, HashMap<Var, usize>), String> {
+        if r1cs.field.modulus() != &Integer::from_str_radix(MODULUS, 10).unwrap() {
+            return Err(format!(
+                "Bulletproofs are over the ristretto255 scalar field; compile with `--field-custom-modulus {MODULUS}`"
+            ));
+        }
+        if r1cs
+            .vars
+            .iter()
+            .any(|v| matches!(v.ty(), VarType::Chall | VarType::CWit))
+        {
+            return Err("Bulletproofs does not support challenges or committed witnesses".to_string());
+        }
+        let (inputs, wits): (Vec<Var>, Vec<Var>) = r1cs
+            .vars
+            .iter()
+            .partition(|v| matches!(v.ty(), VarType::Inst));
+        let cols: HashMap<Var, usize> = inputs
+            .iter()
+            .chain(&wits)
+            .enumerate()
+            .map(|(i, v)| (*v, i))
+            .collect();
+        let lc = |lc: &Lc| -> Result<Combination, String> {
+            let mut terms: Vec<(usize, Scalar)> = lc
+                .monomials
+                .iter()
+                .filter(|(_, c)| !c.is_zero())
+                .map(|(v, c)| Ok((cols[v], to_scalar(&c.i())?)))
+                .collect::<Result<_, String>>()?;
+            // the transcript depends on the order of the terms
+            terms.sort_by_key(|(col, _)| *col);
+            Ok((to_scalar(&lc.constant.i())?, terms))
+        };
+        let rel = Relation {
+            num_inputs: inputs.len(),
+            num_wits: wits.len(),
+            constraints: r1cs
+                .constraints
+                .iter()
+                .map(|(a, b, c)| Ok([lc(a)?, lc(b)?, lc(c)?]))
+                .collect::<Result<_, String>>()?,
+        };
+        Ok((rel, cols))
+    }
+
+    /// Generators for one gate per constraint and one per two witness variables
+    fn gens(&self) -> BulletproofGens {
+        let gates = self.constraints.len() + (self.num_wits + 1) / 2;
+        debug!("Bulletproofs: {gates} multiplication gates");
+        BulletproofGens::new(gates.next_power_of_two(), 1)
+    }
+
+    /// A transcript bound to this relation and the public inputs
+    fn transcript(&self, inputs: &[Scalar]) -> Transcript {
+        let mut t = Transcript::new(TRANSCRIPT_LABEL);
+        t.append_u64(b"num_inputs", self.num_inputs as u64);
+        t.append_u64(b"num_wits", self.num_wits as u64);
+        t.append_u64(b"num_cons", self.constraints.len() as u64);
+        for lcs in &self.constraints {
+            for (constant, terms) in lcs {
+                t.append_message(b"constant", constant.as_bytes());
+                t.append_u64(b"num_terms", terms.len() as u64);
+                for (col, coeff) in terms {
+                    t.append_u64(b"col", *col as u64);
+                    t.append_message(b"coeff", coeff.as_bytes());
+                }
+            }
+        }
+        for x in inputs {
+            t.append_message(b"input", x.as_bytes());
+        }
+        t
+    }
+
+    /// Lay out the constraints in `cs`. Only the prover knows `wits`.
+    fn synthesize<CS: ConstraintSystem>(
+        &self,
+        cs: &mut CS,
+        inputs: &[Scalar],
+        wits: Option<&[Scalar]>,
+    ) -> Result<(), R1CSError> {
+        let mut vars: Vec<LinearCombination> = inputs.iter().map(|x| (*x).into()).collect();
+        for i in 0..self.num_wits {
+            vars.push(cs.allocate(wits.map(|w| w[i]))?.into());
+        }
+        let lc = |(constant, terms): &Combination| -> LinearCombination {
+            terms
+                .iter()
+                .fold(LinearCombination::from(*constant), |lc, (col, coeff)| {
+                    lc + vars[*col].clone() * *coeff
+                })
+        };
+        for [a, b, c] in &self.constraints {
+            let (_, _, o) = cs.multiply(lc(a), lc(b));
+            cs.constrain(o - lc(c));
+        }
+        Ok(())
+    }
+
+    fn prove(&self, inputs: &[Scalar], wits: &[Scalar]) -> Result<R1CSProof, String> {
+        let pc_gens = PedersenGens::default();
+        let mut prover = Prover::new(&pc_gens, self.transcript(inputs));
+        self.synthesize(&mut prover, inputs, Some(wits))
+            .map_err(|e| format!("Invalid Bulletproofs witness: {e}"))?;
+        prover
+            .prove(&self.gens())
+            .map_err(|e| format!("Bulletproofs proving failed: {e}"))
+    }
+
+    fn verify(&self, inputs: &[Scalar], pf: &R1CSProof) -> bool {
+        if inputs.len() != self.num_inputs {
+            return false;
+        }
+        let mut verifier = Verifier::new(self.transcript(inputs));
+        self.synthesize(&mut verifier, inputs, None).is_ok()
+            && verifier
+                .verify(pf, &PedersenGens::default(), &self.gens())
+                .is_ok()
+    }
+}
+
+/// The Bulletproofs proof system
+pub struct Bulletproofs;
+
+/// The pk for [Bulletproofs]
+#[derive(Serialize, Deserialize)]
+pub struct ProvingKey {
+    data: ProverData,
+}
+
+impl ProvingKey {
+    /// The relation and witness computation
+    pub fn into_data(self) -> ProverData {
+        self.data
+    }
+}
+
+/// The vk for [Bulletproofs]
+#[derive(Serialize, Deserialize)]
+pub struct VerifyingKey {
+    data: VerifierData,
+}
+
+/// The proof for [Bulletproofs]
+#[derive(Serialize, Deserialize)]
+pub struct Proof(R1CSProof);
+
+impl Bulletproofs {
+    fn try_verify(vk: &VerifyingKey, inst: &HashMap<String, Value>, pf: &Proof) -> Result<bool, String> {
+        let (rel, _) = Relation::new(&vk.data.r1cs)?;
+        let inputs = vk
+            .data
+            .eval(inst)
+            .iter()
+            .map(|v| to_scalar(&v.i()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rel.verify(&inputs, &pf.0))
+    }
+}
+
+impl proof::ProofSystem for Bulletproofs {
+    type VerifyingKey = VerifyingKey;
+
+    type ProvingKey = ProvingKey;
+
+    type Proof = Proof;
+
+    fn setup(p_data: ProverData, v_data: VerifierData) -> Result<(ProvingKey, VerifyingKey), String> {
+        // there is nothing to derive, but check that the relation is supported
+        Relation::new(&p_data.r1cs)?;
+        Ok((ProvingKey { data: p_data }, VerifyingKey { data: v_data }))
+    }
+
+    fn prove(pk: &ProvingKey, witness: &HashMap<String, Value>) -> Result<Proof, String> {
+        let values = pk.data.eval_vars(witness);
+        if let Some(c) = pk.data.r1cs.first_unsatisfied(&values) {
+            return Err(format!("The witness does not satisfy constraint {c}"));
+        }
+        let (rel, cols) = Relation::new(&pk.data.r1cs)?;
+        let mut assignment = vec![Scalar::zero(); rel.num_inputs + rel.num_wits];
+        for (v, col) in cols {
+            assignment[col] = to_scalar(&values[&v].i())?;
+        }
+        let (inputs, wits) = assignment.split_at(rel.num_inputs);
+        Ok(Proof(rel.prove(inputs, wits)?))
+    }
+
+    /// A key for another field verifies nothing
+    fn verify(vk: &VerifyingKey, inst: &HashMap<String, Value>, pf: &Proof) -> bool {
+        Self::try_verify(vk, inst, pf).unwrap_or(false)
+    }
+
+    fn verify_fs(
+        vk_path: impl AsRef<std::path::Path>,
+        instance_path: impl AsRef<std::path::Path>,
+        pf_path: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<bool> {
+        let instance = proof::value_map_from_path(&instance_path)?;
+        let vk: VerifyingKey = proof::deserialize_from_file(vk_path)?;
+        let pf: Proof = proof::deserialize_from_file(pf_path)?;
+        Self::try_verify(&vk, &instance, &pf).map_err(invalid_data)
+    }
+}
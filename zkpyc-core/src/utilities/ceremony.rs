@@ -0,0 +1,756 @@
+//! A multi-party Phase-2 ceremony for [Bellman](super::bellman::Bellman) Groth16 keys.
+//!
+//! Phase 1 is a powers-of-tau file in the snarkjs `.ptau` format, such as
+//! the output of the Perpetual Powers of Tau. From it and the relation,
+//! [Ceremony::init] derives parameters whose `delta` is one. Every
+//! participant then multiplies `delta` by a secret of their own with
+//! [Ceremony::contribute], leaving a proof of knowledge of it in the
+//! transcript. The keys are sound as long as one participant discarded
+//! their secret, which anyone can check with [Ceremony::verify] against the
+//! same `.ptau` file before [Ceremony::keys] extracts them.
+//!
+//! As in Zcash's Sapling ceremony, `gamma` is left as the generator of G2:
+//! only `delta` needs to be unknown.
+//!
+//! Mirage keys are out of scope: they also have a secret per committed block
+//! and a linking key, which would need ceremonies of their own.
+
+use ::bellman::groth16;
+use byteorder::{LittleEndian, ReadBytesExt};
+use ff::{Field, PrimeField};
+use group::{prime::PrimeCurveAffine, Curve, Group, GroupEncoding};
+use log::debug;
+use pairing::{Engine, MillerLoopResult, MultiMillerLoop};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rug::{integer::Order, Integer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Arc;
+use tiny_keccak::{Hasher, Keccak};
+
+use super::bellman::{int_to_ff, Bls12, Bn254, ProvingKey, VerifyingKey};
+use super::mirage::serde_group;
+use super::proof::{deserialize_from_file, serialize_into_file};
+use super::r1cs::{Lc, ProverData, VarType, VerifierData};
+
+type Hash = [u8; 32];
+
+fn keccak(parts: &[&[u8]]) -> Hash {
+    let mut keccak = Keccak::v256();
+    for p in parts {
+        keccak.update(p);
+    }
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+    hash
+}
+
+/// Curves whose points can be read from `.ptau` files, given their affine
+/// coordinates (the real part of Fq2 elements first)
+pub trait PtauEngine: MultiMillerLoop {
+    /// A G1 point
+    fn g1(x: &Integer, y: &Integer) -> Result<Self::G1Affine, String>;
+    /// A G2 point
+    fn g2(x: &[Integer; 2], y: &[Integer; 2]) -> Result<Self::G2Affine, String>;
+}
+
+fn be_bytes(i: &Integer, len: usize) -> Result<Vec<u8>, String> {
+    let digits = i.to_digits::<u8>(Order::Msf);
+    if digits.len() > len {
+        return Err(format!("{i} does not fit in {len} bytes"));
+    }
+    let mut bytes = vec![0u8; len - digits.len()];
+    bytes.extend(digits);
+    Ok(bytes)
+}
+
+impl PtauEngine for Bls12 {
+    fn g1(x: &Integer, y: &Integer) -> Result<Self::G1Affine, String> {
+        let mut bytes = [0u8; 96];
+        bytes[..48].copy_from_slice(&be_bytes(x, 48)?);
+        bytes[48..].copy_from_slice(&be_bytes(y, 48)?);
+        Option::from(bls12_381::G1Affine::from_uncompressed(&bytes))
+            .ok_or_else(|| "Invalid BLS12-381 G1 point".to_string())
+    }
+
+    fn g2(x: &[Integer; 2], y: &[Integer; 2]) -> Result<Self::G2Affine, String> {
+        let mut bytes = [0u8; 192];
+        // this encoding takes the imaginary part first
+        for (i, c) in [&x[1], &x[0], &y[1], &y[0]].into_iter().enumerate() {
+            bytes[48 * i..48 * (i + 1)].copy_from_slice(&be_bytes(c, 48)?);
+        }
+        Option::from(bls12_381::G2Affine::from_uncompressed(&bytes))
+            .ok_or_else(|| "Invalid BLS12-381 G2 point".to_string())
+    }
+}
+
+impl PtauEngine for Bn254 {
+    fn g1(x: &Integer, y: &Integer) -> Result<Self::G1Affine, String> {
+        use halo2curves::bn256::G1Affine;
+        let p = G1Affine {
+            x: int_to_ff(x.clone()),
+            y: int_to_ff(y.clone()),
+        };
+        check_encoding(p).ok_or_else(|| "Invalid BN254 G1 point".to_string())
+    }
+
+    fn g2(x: &[Integer; 2], y: &[Integer; 2]) -> Result<Self::G2Affine, String> {
+        use halo2curves::bn256::{Fq2, G2Affine};
+        let fq2 = |c: &[Integer; 2]| Fq2 {
+            c0: int_to_ff(c[0].clone()),
+            c1: int_to_ff(c[1].clone()),
+        };
+        let p = G2Affine {
+            x: fq2(x),
+            y: fq2(y),
+        };
+        check_encoding(p).ok_or_else(|| "Invalid BN254 G2 point".to_string())
+    }
+}
+
+/// `p`, if it survives a round trip through its compressed encoding, which
+/// rejects points off the curve
+fn check_encoding<G: GroupEncoding + PartialEq>(p: G) -> Option<G> {
+    let q: Option<G> = G::from_bytes(&p.to_bytes()).into();
+    q.filter(|q| *q == p)
+}
+
+/// The phase-1 output: `tau^i` in both groups, and `alpha tau^i`, `beta tau^i` in G1
+pub struct PowersOfTau<E: Engine> {
+    tau_g1: Vec<E::G1Affine>,
+    tau_g2: Vec<E::G2Affine>,
+    alpha_tau_g1: Vec<E::G1Affine>,
+    beta_tau_g1: Vec<E::G1Affine>,
+    beta_g2: E::G2Affine,
+}
+
+impl<E: PtauEngine> PowersOfTau<E> {
+    /// Read a snarkjs `.ptau` file
+    pub fn read_ptau(path: impl AsRef<Path>) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        let mut r = &bytes[..];
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic).map_err(|e| e.to_string())?;
+        if &magic != b"ptau" {
+            return Err("Not a .ptau file".into());
+        }
+        let _version = r.read_u32::<LittleEndian>().map_err(|e| e.to_string())?;
+        let n_sections = r.read_u32::<LittleEndian>().map_err(|e| e.to_string())?;
+        let mut sections: HashMap<u32, &[u8]> = HashMap::new();
+        for _ in 0..n_sections {
+            let ty = r.read_u32::<LittleEndian>().map_err(|e| e.to_string())?;
+            let size = r.read_u64::<LittleEndian>().map_err(|e| e.to_string())? as usize;
+            if r.len() < size {
+                return Err("Truncated .ptau file".into());
+            }
+            sections.insert(ty, &r[..size]);
+            r = &r[size..];
+        }
+        let section = |ty: u32| {
+            sections
+                .get(&ty)
+                .copied()
+                .ok_or_else(|| format!("Missing .ptau section {ty}"))
+        };
+
+        let mut header = section(1)?;
+        let n8 = header
+            .read_u32::<LittleEndian>()
+            .map_err(|e| e.to_string())? as usize;
+        if header.len() < n8 + 4 {
+            return Err("Truncated .ptau header".into());
+        }
+        let q = Integer::from_digits(&header[..n8], Order::Lsf);
+        header = &header[n8..];
+        let power = header
+            .read_u32::<LittleEndian>()
+            .map_err(|e| e.to_string())?;
+        // snarkjs stops at 2^28 constraints
+        if power > 28 {
+            return Err(format!("Bad .ptau power 2^{power}"));
+        }
+        let fields = PtauFields::new(n8, q)?;
+        let n = 1usize << power;
+        debug!("Reading powers of tau for 2^{power} constraints");
+        Ok(PowersOfTau {
+            tau_g1: fields.points(section(2)?, 2 * n - 1, |r| fields.g1::<E>(r))?,
+            tau_g2: fields.points(section(3)?, n, |r| fields.g2::<E>(r))?,
+            alpha_tau_g1: fields.points(section(4)?, n, |r| fields.g1::<E>(r))?,
+            beta_tau_g1: fields.points(section(5)?, n, |r| fields.g1::<E>(r))?,
+            beta_g2: fields.g2::<E>(&mut section(6)?)?,
+        })
+    }
+}
+
+/// The base field of a `.ptau` file, whose elements are little-endian, in
+/// Montgomery form
+struct PtauFields {
+    n8: usize,
+    q: Integer,
+    r_inv: Integer,
+}
+
+impl PtauFields {
+    fn new(n8: usize, q: Integer) -> Result<Self, String> {
+        let r_inv = (Integer::from(1) << (8 * n8 as u32))
+            .invert(&q)
+            .map_err(|_| "Bad .ptau modulus".to_string())?;
+        Ok(PtauFields { n8, q, r_inv })
+    }
+
+    fn fq(&self, r: &mut &[u8]) -> Result<Integer, String> {
+        if r.len() < self.n8 {
+            return Err("Truncated .ptau section".into());
+        }
+        let i = Integer::from_digits(&r[..self.n8], Order::Lsf);
+        *r = &r[self.n8..];
+        Ok(i * &self.r_inv % &self.q)
+    }
+
+    fn g1<E: PtauEngine>(&self, r: &mut &[u8]) -> Result<E::G1Affine, String> {
+        let x = self.fq(r)?;
+        let y = self.fq(r)?;
+        E::g1(&x, &y)
+    }
+
+    fn g2<E: PtauEngine>(&self, r: &mut &[u8]) -> Result<E::G2Affine, String> {
+        let x = [self.fq(r)?, self.fq(r)?];
+        let y = [self.fq(r)?, self.fq(r)?];
+        E::g2(&x, &y)
+    }
+
+    fn points<T>(
+        &self,
+        mut section: &[u8],
+        n: usize,
+        read: impl Fn(&mut &[u8]) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        (0..n).map(|_| read(&mut section)).collect()
+    }
+}
+
+/// In-place radix-2 FFT of group elements, as bellman's `serial_fft`
+fn fft<G: Group>(a: &mut [G], omega: G::Scalar) {
+    let n = a.len();
+    if n < 2 {
+        return;
+    }
+    let log_n = n.trailing_zeros();
+    for k in 0..n {
+        let rk = k.reverse_bits() >> (usize::BITS - log_n);
+        if k < rk {
+            a.swap(rk, k);
+        }
+    }
+    let mut m = 1;
+    for _ in 0..log_n {
+        let w_m = omega.pow_vartime([(n / (2 * m)) as u64]);
+        for k in (0..n).step_by(2 * m) {
+            let mut w = G::Scalar::one();
+            for j in 0..m {
+                let t = a[k + j + m] * w;
+                a[k + j + m] = a[k + j] - t;
+                a[k + j] += t;
+                w *= w_m;
+            }
+        }
+        m *= 2;
+    }
+}
+
+/// The Lagrange basis of the size-`m` domain at tau, in the exponent, from `tau^i`
+fn lagrange<C: PrimeCurveAffine>(powers: &[C], m: usize) -> Vec<C::Curve> {
+    let mut omega = C::Scalar::root_of_unity();
+    for _ in m.trailing_zeros()..C::Scalar::S {
+        omega = omega.square();
+    }
+    let mut a: Vec<C::Curve> = powers[..m].iter().map(|p| p.to_curve()).collect();
+    fft(&mut a, omega.invert().unwrap());
+    let m_inv = C::Scalar::from(m as u64).invert().unwrap();
+    for p in &mut a {
+        *p *= m_inv;
+    }
+    a
+}
+
+/// Parameters with `delta` one, from the relation of `p_data` and phase 1
+fn initial_params<E: PtauEngine>(
+    p_data: &ProverData,
+    pot: &PowersOfTau<E>,
+) -> Result<groth16::Parameters<E>, String> {
+    let r1cs = &p_data.r1cs;
+    if !r1cs.vars.iter().all(|v| matches!(v.ty(), VarType::Inst | VarType::FinalWit)) {
+        return Err("Groth16 ceremonies don't support rounds or committed witnesses".into());
+    }
+    // bellman's variables: the constant one and the instance are inputs
+    let num_inputs = 1 + r1cs
+        .vars
+        .iter()
+        .filter(|v| matches!(v.ty(), VarType::Inst))
+        .count();
+    let num_vars = 1 + r1cs.vars.len();
+    let index: HashMap<_, _> = r1cs
+        .vars
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (*v, 1 + i))
+        .collect();
+    let lc = |lc: &Lc| -> Vec<(usize, E::Fr)> {
+        lc.monomials
+            .iter()
+            .map(|(v, c)| (index[v], c))
+            .chain(std::iter::once((0, &lc.constant)))
+            .filter(|(_, c)| !c.is_zero())
+            .map(|(i, c)| (i, int_to_ff(c.into())))
+            .collect()
+    };
+    let mut rows: Vec<[Vec<(usize, E::Fr)>; 3]> = r1cs
+        .constraints
+        .iter()
+        .map(|(a, b, c)| [lc(a), lc(b), lc(c)])
+        .collect();
+    // bellman constrains every input, so that the IC query is fully dense
+    rows.extend((0..num_inputs).map(|i| [vec![(i, E::Fr::one())], vec![], vec![]]));
+
+    let m = rows.len().next_power_of_two();
+    if 2 * m - 1 > pot.tau_g1.len() {
+        return Err(format!(
+            "The relation needs powers of tau for 2^{} constraints, but the file has 2^{}",
+            m.trailing_zeros(),
+            pot.tau_g2.len().trailing_zeros()
+        ));
+    }
+    let lt_g1 = lagrange(&pot.tau_g1, m);
+    let lt_g2 = lagrange(&pot.tau_g2, m);
+    let alpha_lt = lagrange(&pot.alpha_tau_g1, m);
+    let beta_lt = lagrange(&pot.beta_tau_g1, m);
+
+    let mut a = vec![E::G1::identity(); num_vars];
+    let mut b_g1 = vec![E::G1::identity(); num_vars];
+    let mut b_g2 = vec![E::G2::identity(); num_vars];
+    // beta u_i(tau) + alpha v_i(tau) + w_i(tau)
+    let mut ext = vec![E::G1::identity(); num_vars];
+    for (j, [ra, rb, rc]) in rows.iter().enumerate() {
+        for (i, c) in ra {
+            a[*i] += lt_g1[j] * c;
+            ext[*i] += beta_lt[j] * c;
+        }
+        for (i, c) in rb {
+            b_g1[*i] += lt_g1[j] * c;
+            b_g2[*i] += lt_g2[j] * c;
+            ext[*i] += alpha_lt[j] * c;
+        }
+        for (i, c) in rc {
+            ext[*i] += lt_g1[j] * c;
+        }
+    }
+    // inputs come first, as bellman allocates them
+    let order: Vec<usize> = (0..num_vars)
+        .filter(|i| *i == 0 || matches!(r1cs.vars[i - 1].ty(), VarType::Inst))
+        .chain((1..num_vars).filter(|i| !matches!(r1cs.vars[i - 1].ty(), VarType::Inst)))
+        .collect();
+    let affine1 = |v: &[E::G1]| -> Vec<E::G1Affine> {
+        let mut out = vec![E::G1Affine::identity(); v.len()];
+        E::G1::batch_normalize(v, &mut out);
+        out
+    };
+    let reorder1 = |v: &[E::G1]| affine1(&order.iter().map(|i| v[*i]).collect::<Vec<_>>());
+    let non_identity1 = |v: Vec<E::G1Affine>| -> Vec<E::G1Affine> {
+        v.into_iter()
+            .filter(|p| !bool::from(p.is_identity()))
+            .collect()
+    };
+    let mut b_g2_affine = vec![E::G2Affine::identity(); num_vars];
+    E::G2::batch_normalize(
+        &order.iter().map(|i| b_g2[*i]).collect::<Vec<_>>(),
+        &mut b_g2_affine,
+    );
+
+    let ext = reorder1(&ext);
+    let (ic, l) = ext.split_at(num_inputs);
+    if l.iter().any(|p| bool::from(p.is_identity())) {
+        return Err("The relation has an unconstrained variable".into());
+    }
+    // tau^i t(tau) = tau^(i + m) - tau^i
+    let h: Vec<E::G1> = (0..m - 1)
+        .map(|i| pot.tau_g1[i + m].to_curve() - pot.tau_g1[i].to_curve())
+        .collect();
+    let g1 = pot.tau_g1[0];
+    let g2 = pot.tau_g2[0];
+    Ok(groth16::Parameters {
+        vk: groth16::VerifyingKey {
+            alpha_g1: pot.alpha_tau_g1[0],
+            beta_g1: pot.beta_tau_g1[0],
+            beta_g2: pot.beta_g2,
+            gamma_g2: g2,
+            delta_g1: g1,
+            delta_g2: g2,
+            ic: ic.to_vec(),
+        },
+        h: Arc::new(affine1(&h)),
+        l: Arc::new(l.to_vec()),
+        a: Arc::new(non_identity1(reorder1(&a))),
+        b_g1: Arc::new(non_identity1(reorder1(&b_g1))),
+        b_g2: Arc::new(
+            b_g2_affine
+                .into_iter()
+                .filter(|p| !bool::from(p.is_identity()))
+                .collect(),
+        ),
+    })
+}
+
+fn params_hash<E: Engine>(params: &groth16::Parameters<E>) -> Hash {
+    let mut bs: Vec<u8> = Vec::new();
+    params.write(&mut bs).unwrap();
+    keccak(&[&bs])
+}
+
+/// A deterministic G2 point, from which no one knows a discrete logarithm
+fn hash_to_g2<E: Engine>(hash: &Hash) -> E::G2Affine {
+    E::G2::random(ChaCha20Rng::from_seed(*hash)).to_affine()
+}
+
+/// `e(a.0, b.1) == e(a.1, b.0)`: the pairs have the same discrete logarithm ratio
+fn same_ratio<E: MultiMillerLoop>(
+    a: (E::G1Affine, E::G1Affine),
+    b: (E::G2Affine, E::G2Affine),
+) -> bool {
+    let b0: E::G2Prepared = b.0.into();
+    let b1: E::G2Prepared = b.1.into();
+    let neg_a1 = -a.1;
+    bool::from(
+        E::multi_miller_loop(&[(&a.0, &b1), (&neg_a1, &b0)])
+            .final_exponentiation()
+            .is_identity(),
+    )
+}
+
+/// A random linear combination of `a` and the same of `b`, so that
+/// `same_ratio` on them checks all pairs at once
+fn merge_pairs<C: PrimeCurveAffine>(a: &[C], b: &[C]) -> (C, C) {
+    assert_eq!(a.len(), b.len());
+    let rng = &mut rand::thread_rng();
+    let mut s = (C::Curve::identity(), C::Curve::identity());
+    for (a, b) in a.iter().zip(b) {
+        let r = C::Scalar::random(&mut *rng);
+        s.0 += *a * r;
+        s.1 += *b * r;
+    }
+    (s.0.to_affine(), s.1.to_affine())
+}
+
+/// A participant's proof of knowledge of the factor they multiplied `delta` by
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Contribution<E: Engine> {
+    /// `delta` after this contribution, in G1
+    #[serde(with = "serde_group")]
+    delta_after: E::G1Affine,
+    /// A random point and its multiple by the factor
+    #[serde(with = "serde_group")]
+    s: E::G1Affine,
+    #[serde(with = "serde_group")]
+    s_delta: E::G1Affine,
+    /// The factor times a point derived from the transcript
+    #[serde(with = "serde_group")]
+    r_delta: E::G2Affine,
+}
+
+impl<E: Engine> Contribution<E> {
+    fn r(&self, transcript: &Hash) -> E::G2Affine {
+        hash_to_g2::<E>(&keccak(&[
+            b"zkpyc-phase2",
+            transcript,
+            self.s.to_bytes().as_ref(),
+            self.s_delta.to_bytes().as_ref(),
+        ]))
+    }
+
+    /// The transcript after this contribution, which identifies it
+    fn next(&self, transcript: &Hash) -> Hash {
+        keccak(&[
+            transcript,
+            self.delta_after.to_bytes().as_ref(),
+            self.s.to_bytes().as_ref(),
+            self.s_delta.to_bytes().as_ref(),
+            self.r_delta.to_bytes().as_ref(),
+        ])
+    }
+}
+
+mod serde_params {
+    use bellman::groth16::Parameters;
+    use pairing::Engine;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer, E: Engine>(
+        p: &Parameters<E>,
+        ser: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut bs: Vec<u8> = Vec::new();
+        p.write(&mut bs).unwrap();
+        serde_bytes::ByteBuf::from(bs).serialize(ser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, E: Engine>(
+        de: D,
+    ) -> Result<Parameters<E>, D::Error> {
+        let bs: serde_bytes::ByteBuf = Deserialize::deserialize(de)?;
+        // the parameters come from other participants, so check the points
+        Parameters::read(&**bs, true).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The state of a Phase-2 ceremony: the relation, the current parameters and
+/// the contributions so far
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Ceremony<E: Engine> {
+    p_data: ProverData,
+    v_data: VerifierData,
+    #[serde(with = "serde_params")]
+    params: groth16::Parameters<E>,
+    /// Identifies the relation and phase 1
+    initial_hash: Hash,
+    contributions: Vec<Contribution<E>>,
+}
+
+impl<E: PtauEngine> Ceremony<E> {
+    /// Start a ceremony for the relation of `p_data`, on top of phase 1
+    pub fn init(
+        p_data: ProverData,
+        v_data: VerifierData,
+        pot: &PowersOfTau<E>,
+    ) -> Result<Self, String> {
+        if !p_data.r1cs.commitments.is_empty() {
+            return Err("Groth16 has no commitments".into());
+        }
+        let params = initial_params(&p_data, pot)?;
+        Ok(Ceremony {
+            p_data,
+            v_data,
+            initial_hash: params_hash(&params),
+            params,
+            contributions: Vec::new(),
+        })
+    }
+
+    /// The transcript hash after all contributions
+    pub fn transcript(&self) -> Hash {
+        self.contributions
+            .iter()
+            .fold(self.initial_hash, |t, c| c.next(&t))
+    }
+
+    /// Multiply `delta` by a secret drawn from the OS and `entropy`, which
+    /// is discarded afterwards. Returns the hash identifying this
+    /// contribution, for the participant to publish.
+    pub fn contribute(&mut self, entropy: &[u8]) -> Hash {
+        let mut seed = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut seed);
+        let rng = &mut ChaCha20Rng::from_seed(keccak(&[&seed, entropy]));
+        let delta = E::Fr::random(&mut *rng);
+        let delta_inv = delta.invert().unwrap();
+
+        let transcript = self.transcript();
+        let s = E::G1::random(&mut *rng).to_affine();
+        let mut c = Contribution::<E> {
+            delta_after: (self.params.vk.delta_g1 * delta).to_affine(),
+            s,
+            s_delta: (s * delta).to_affine(),
+            r_delta: E::G2Affine::identity(),
+        };
+        c.r_delta = (c.r(&transcript) * delta).to_affine();
+
+        let scale = |ps: &[E::G1Affine]| -> Vec<E::G1Affine> {
+            let scaled: Vec<E::G1> = ps.iter().map(|p| *p * delta_inv).collect();
+            let mut out = vec![E::G1Affine::identity(); ps.len()];
+            E::G1::batch_normalize(&scaled, &mut out);
+            out
+        };
+        self.params.h = Arc::new(scale(&self.params.h));
+        self.params.l = Arc::new(scale(&self.params.l));
+        self.params.vk.delta_g1 = c.delta_after;
+        self.params.vk.delta_g2 = (self.params.vk.delta_g2 * delta).to_affine();
+        let hash = c.next(&transcript);
+        self.contributions.push(c);
+        hash
+    }
+
+    /// Check that the parameters come from the relation and phase 1 through
+    /// the recorded contributions. Returns the hash of each contribution.
+    pub fn verify(&self, pot: &PowersOfTau<E>) -> Result<Vec<Hash>, String> {
+        let initial = initial_params(&self.p_data, pot)?;
+        if params_hash(&initial) != self.initial_hash {
+            return Err("The ceremony was started from another relation or phase 1".into());
+        }
+        let (params, vk) = (&self.params, &self.params.vk);
+        let (ivk, g1, g2) = (&initial.vk, initial.vk.delta_g1, initial.vk.delta_g2);
+        if vk.alpha_g1 != ivk.alpha_g1
+            || vk.beta_g1 != ivk.beta_g1
+            || vk.beta_g2 != ivk.beta_g2
+            || vk.gamma_g2 != ivk.gamma_g2
+            || vk.ic != ivk.ic
+            || params.a != initial.a
+            || params.b_g1 != initial.b_g1
+            || params.b_g2 != initial.b_g2
+            || params.h.len() != initial.h.len()
+            || params.l.len() != initial.l.len()
+        {
+            return Err("The parameters do not match the relation".into());
+        }
+
+        let mut hashes = Vec::new();
+        let mut transcript = self.initial_hash;
+        let mut delta = g1;
+        for (i, c) in self.contributions.iter().enumerate() {
+            let r = c.r(&transcript);
+            if bool::from(c.s.is_identity()) || !same_ratio::<E>((c.s, c.s_delta), (r, c.r_delta)) {
+                return Err(format!(
+                    "Contribution {i} does not prove knowledge of its factor"
+                ));
+            }
+            if !same_ratio::<E>((delta, c.delta_after), (r, c.r_delta)) {
+                return Err(format!(
+                    "Contribution {i} is not applied to the previous delta"
+                ));
+            }
+            delta = c.delta_after;
+            transcript = c.next(&transcript);
+            hashes.push(transcript);
+        }
+        if vk.delta_g1 != delta || !same_ratio::<E>((g1, vk.delta_g1), (g2, vk.delta_g2)) {
+            return Err("delta does not match the last contribution".into());
+        }
+        // every element of h and l is divided by delta
+        let h = merge_pairs(&params.h, &initial.h);
+        let l = merge_pairs(&params.l, &initial.l);
+        if !same_ratio::<E>(h, (g2, vk.delta_g2)) || !same_ratio::<E>(l, (g2, vk.delta_g2)) {
+            return Err("h or l is not divided by delta".into());
+        }
+        Ok(hashes)
+    }
+
+    /// The final keys for [Bellman](super::bellman::Bellman)
+    pub fn keys(self) -> (ProvingKey<E>, VerifyingKey<E>) {
+        let vk = self.params.vk.clone();
+        (
+            ProvingKey::new(self.p_data, self.params),
+            VerifyingKey::new(self.v_data, vk),
+        )
+    }
+
+    /// Start a ceremony from a `.ptau` file, writing it to `path`
+    pub fn init_fs(
+        p_data: ProverData,
+        v_data: VerifierData,
+        ptau: impl AsRef<Path>,
+        path: impl AsRef<Path>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let pot = PowersOfTau::<E>::read_ptau(ptau)?;
+        serialize_into_file(&Self::init(p_data, v_data, &pot)?, path)?;
+        Ok(())
+    }
+
+    /// Contribute to the ceremony in `path`, in place
+    pub fn contribute_fs(path: impl AsRef<Path>, entropy: &[u8]) -> io::Result<Hash> {
+        let mut ceremony: Self = deserialize_from_file(&path)?;
+        let hash = ceremony.contribute(entropy);
+        serialize_into_file(&ceremony, &path)?;
+        Ok(hash)
+    }
+
+    /// Verify the ceremony in `path` against a `.ptau` file
+    pub fn verify_fs(
+        path: impl AsRef<Path>,
+        ptau: impl AsRef<Path>,
+    ) -> Result<Vec<Hash>, Box<dyn std::error::Error>> {
+        let ceremony: Self = deserialize_from_file(path)?;
+        let pot = PowersOfTau::<E>::read_ptau(ptau)?;
+        Ok(ceremony.verify(&pot)?)
+    }
+
+    /// Write the final keys of the ceremony in `path`
+    pub fn keys_fs(
+        path: impl AsRef<Path>,
+        pk_path: impl AsRef<Path>,
+        vk_path: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        let ceremony: Self = deserialize_from_file(path)?;
+        let (pk, vk) = ceremony.keys();
+        serialize_into_file(&pk, pk_path)?;
+        serialize_into_file(&vk, vk_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::WriteBytesExt;
+    use std::io::Write;
+
+    /// A `.ptau` file with just a header section, for `2^power` constraints
+    fn ptau_header(magic: &[u8; 4], power: u32) -> tempfile::NamedTempFile {
+        let q = Integer::from_str_radix(
+            "21888242871839275222246405745257275088696311157297823662689037894645226208583",
+            10,
+        )
+        .unwrap();
+        let mut header = Vec::new();
+        header.write_u32::<LittleEndian>(32).unwrap();
+        let mut q_bytes = q.to_digits::<u8>(Order::Lsf);
+        q_bytes.resize(32, 0);
+        header.extend(q_bytes);
+        header.write_u32::<LittleEndian>(power).unwrap();
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(magic).unwrap();
+        file.write_u32::<LittleEndian>(1).unwrap();
+        file.write_u32::<LittleEndian>(1).unwrap();
+        file.write_u32::<LittleEndian>(1).unwrap();
+        file.write_u64::<LittleEndian>(header.len() as u64).unwrap();
+        file.write_all(&header).unwrap();
+        file
+    }
+
+    #[test]
+    fn malformed_ptau() {
+        let read = |f: &tempfile::NamedTempFile| PowersOfTau::<Bn254>::read_ptau(f.path());
+        assert!(read(&ptau_header(b"ptaw", 4)).is_err());
+        assert!(read(&ptau_header(b"ptau", 64)).is_err());
+        // the points are missing
+        assert!(read(&ptau_header(b"ptau", 4)).is_err());
+    }
+
+    #[test]
+    fn lagrange_basis() {
+        // the Lagrange basis at tau sums to one and interpolates tau itself
+        type Fr = <Bls12 as Engine>::Fr;
+        let tau = Fr::from(7u64);
+        let m = 8;
+        let g = bls12_381::G1Affine::generator();
+        let powers: Vec<bls12_381::G1Affine> = (0..m)
+            .map(|i| (g * tau.pow_vartime([i as u64])).to_affine())
+            .collect();
+        let basis = lagrange(&powers, m);
+        let sum = basis
+            .iter()
+            .fold(bls12_381::G1Projective::identity(), |s, p| s + p);
+        assert_eq!(sum.to_affine(), g);
+        let mut omega = Fr::root_of_unity();
+        for _ in 3..Fr::S {
+            omega = omega.square();
+        }
+        let interpolated = basis
+            .iter()
+            .enumerate()
+            .fold(bls12_381::G1Projective::identity(), |s, (j, p)| {
+                s + *p * omega.pow_vartime([j as u64])
+            });
+        assert_eq!(interpolated.to_affine(), powers[1]);
+    }
+}
@@ -34,7 +34,7 @@
 //! * For i in 0..C: scalar N * C + C + i is rands[i]
 
 use ff::{Field, PrimeFieldBits};
-use group::Group;
+use group::{Group, GroupEncoding};
 use pairing::{Engine, MultiMillerLoop};
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
@@ -52,6 +52,59 @@ pub struct CommitKey<E: Engine> {
     pub rand_key: E::G1Affine,
 }
 
+/// A commitment key as exchanged with other tools: each point in its
+/// compressed encoding, in hex
+#[derive(Serialize, Deserialize)]
+struct ExternalCommitKey {
+    data_keys: Vec<String>,
+    rand_key: String,
+}
+
+impl<E: Engine> CommitKey<E> {
+    /// Read a key produced elsewhere, from JSON of the form
+    /// `{"data_keys": ["<hex>", ...], "rand_key": "<hex>"}`
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let ext: ExternalCommitKey = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Ok(CommitKey {
+            data_keys: ext
+                .data_keys
+                .iter()
+                .map(|p| point_from_hex(p))
+                .collect::<Result<_, _>>()?,
+            rand_key: point_from_hex(&ext.rand_key)?,
+        })
+    }
+
+    /// The key as JSON, for [CommitKey::from_json]
+    pub fn to_json(&self) -> String {
+        let ext = ExternalCommitKey {
+            data_keys: self.data_keys.iter().map(point_to_hex).collect(),
+            rand_key: point_to_hex(&self.rand_key),
+        };
+        serde_json::to_string_pretty(&ext).unwrap()
+    }
+}
+
+/// Decode a point from the hex of its compressed encoding, checking that it
+/// is in the group
+pub fn point_from_hex<G: GroupEncoding>(hex: &str) -> Result<G, String> {
+    let hex = hex.trim().trim_start_matches("0x");
+    let mut encoding = G::Repr::default();
+    let bytes = encoding.as_mut();
+    if hex.len() != 2 * bytes.len() || !hex.is_ascii() {
+        return Err(format!("Expected {} hex-encoded bytes", bytes.len()));
+    }
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|e| e.to_string())?;
+    }
+    Option::from(G::from_bytes(&encoding)).ok_or_else(|| "Invalid group element".to_string())
+}
+
+/// The hex of the compressed encoding of a point
+pub fn point_to_hex<G: GroupEncoding>(p: &G) -> String {
+    p.to_bytes().as_ref().iter().map(|b| format!("{b:02x}")).collect()
+}
+
 /// For writing CP link proofs
 #[derive(Serialize, Deserialize)]
 pub struct ProvingKey<E: Engine> {
@@ -130,8 +183,8 @@ where
             matrix.add_entry(i, n * i + j, common_ck.data_keys[j]);
             matrix.add_entry(c + i, n * i + j, ck.data_keys[j]);
         }
-        matrix.add_entry(c, n * c + i, common_ck.rand_key);
-        matrix.add_entry(c, n * c + c + i, ck.rand_key);
+        matrix.add_entry(i, n * c + i, common_ck.rand_key);
+        matrix.add_entry(c + i, n * c + c + i, ck.rand_key);
     }
     let (pk, vk) = kw15::key_gen(&matrix, rng);
     (
@@ -171,8 +224,6 @@ where
     for d in &datas {
         assert_eq!(pk.data_len, d.len());
     }
-    let c = pk.num_cmts;
-    let n = pk.data_len;
     let data: Vec<E::Fr> = datas
         .into_iter()
         .flatten()
@@ -207,3 +258,39 @@ where
     let pvk = kw15::PreparedVerifyingKey::from(&vk.inner);
     kw15::verify(&pvk, &common_cmts, &pf.inner)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls12_381::{Bls12, Scalar};
+
+    #[test]
+    fn test_link_with_randomness() {
+        // with two commitments, each randomness must land in its own row
+        let rng = &mut rand::thread_rng();
+        let common_ck = sample_ck::<Bls12, _>(rng, 3);
+        let cks: Vec<_> = (0..2).map(|_| sample_ck::<Bls12, _>(rng, 3)).collect();
+        let datas: Vec<Vec<Scalar>> = (0..2)
+            .map(|_| (0..3).map(|_| Scalar::random(&mut *rng)).collect())
+            .collect();
+        let common_rands: Vec<Scalar> = (0..2).map(|_| sample_rand::<Bls12, _>(rng)).collect();
+        let rands: Vec<Scalar> = (0..2).map(|_| sample_rand::<Bls12, _>(rng)).collect();
+        let common_cmts: Vec<_> = datas
+            .iter()
+            .zip(&common_rands)
+            .map(|(d, r)| commit::<Bls12>(common_ck.clone(), d.clone(), *r))
+            .collect();
+        let cmts: Vec<_> = datas
+            .iter()
+            .zip(&cks)
+            .zip(&rands)
+            .map(|((d, ck), r)| commit::<Bls12>(ck.clone(), d.clone(), *r))
+            .collect();
+
+        let (pk, vk) = key_gen(common_ck, cks, rng);
+        let pf = prove(&pk, common_rands, rands, datas);
+        assert!(verify(&vk, common_cmts.clone(), cmts.clone(), &pf));
+        let swapped = vec![cmts[1], cmts[0]];
+        assert!(!verify(&vk, common_cmts, swapped, &pf));
+    }
+}
@@ -0,0 +1,128 @@
+//! Locating the constraints a witness does not satisfy
+
+use crate::front::source_map::{SourceLine, SourceMap};
+use crate::utilities::r1cs::{ProverData, R1cs};
+use circ::ir::term::Value;
+use circ_fields::FieldV;
+use fxhash::FxHashMap as HashMap;
+use serde::{Deserialize, Serialize};
+
+use std::fmt::{self, Display, Formatter};
+
+/// The source line each R1CS signal was computed by, written next to the
+/// keys at setup.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DebugInfo {
+    /// Signal name to (sequence number, line); a higher sequence number
+    /// means the line was executed later.
+    pub lines: HashMap<String, (usize, SourceLine)>,
+}
+
+impl DebugInfo {
+    /// Map the signals of `r1cs` to the lines of `spans`.
+    ///
+    /// Must be called before `r1cs` is finalized, while it still knows the
+    /// terms computing its signals.
+    pub fn new(r1cs: &R1cs, spans: &SourceMap) -> Self {
+        let tags = spans.attribute(r1cs.terms.values().cloned());
+        let lines = r1cs
+            .terms
+            .iter()
+            .filter_map(|(var, t)| {
+                let (seq, id) = tags.get(t)?;
+                let name = r1cs.idx_to_sig.get_fwd(var)?;
+                Some((name.clone(), (*seq, spans.locs()[*id].clone())))
+            })
+            .collect();
+        DebugInfo { lines }
+    }
+}
+
+/// A constraint that a witness does not satisfy
+#[derive(Debug)]
+pub struct UnsatisfiedConstraint {
+    /// Index among the constraints of the relation
+    pub index: usize,
+    pub num_constraints: usize,
+    /// The constraint, as `(a)(b) = c`
+    pub constraint: String,
+    pub a: FieldV,
+    pub b: FieldV,
+    pub c: FieldV,
+    /// The signals in the constraint and their values, sorted by name
+    pub signals: Vec<(String, FieldV)>,
+    /// The line that computed the signal of the constraint computed last,
+    /// usually the assertion or assignment that failed
+    pub source: Option<SourceLine>,
+}
+
+impl ProverData {
+    /// Evaluate the witness for the prover inputs `inputs`, and find the first
+    /// constraint it does not satisfy.
+    pub fn first_unsatisfied(
+        &self,
+        inputs: &HashMap<String, Value>,
+        info: Option<&DebugInfo>,
+    ) -> Option<UnsatisfiedConstraint> {
+        let values = self.eval_vars(inputs);
+        let index = self.r1cs.first_unsatisfied(&values)?;
+        let qeq = &self.r1cs.constraints[index];
+        let (a, b, c) = qeq;
+
+        let mut vars: Vec<_> = a
+            .monomials
+            .keys()
+            .chain(b.monomials.keys())
+            .chain(c.monomials.keys())
+            .copied()
+            .collect();
+        vars.sort_by_key(|v| &self.r1cs.names[v]);
+        vars.dedup();
+        let signals: Vec<(String, FieldV)> = vars
+            .iter()
+            .map(|v| (self.r1cs.names[v].clone(), values[v].clone()))
+            .collect();
+        let source = info.and_then(|info| {
+            signals
+                .iter()
+                .filter_map(|(name, _)| info.lines.get(name))
+                .max_by_key(|(seq, _)| *seq)
+                .map(|(_, line)| line.clone())
+        });
+
+        Some(UnsatisfiedConstraint {
+            index,
+            num_constraints: self.r1cs.constraints.len(),
+            constraint: self.r1cs.format_qeq(qeq),
+            a: self.r1cs.eval(a, &values),
+            b: self.r1cs.eval(b, &values),
+            c: self.r1cs.eval(c, &values),
+            signals,
+            source,
+        })
+    }
+}
+
+impl Display for UnsatisfiedConstraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "constraint {} of {} is not satisfied:",
+            self.index, self.num_constraints
+        )?;
+        writeln!(f, "    {}", self.constraint)?;
+        writeln!(f, "  with ({})({}) = {}, but expected {}", self.a, self.b, self.a.clone() * &self.b, self.c)?;
+        writeln!(f, "  signals:")?;
+        for (name, value) in &self.signals {
+            writeln!(f, "    {name} = {value}")?;
+        }
+        if let Some(line) = &self.source {
+            write!(f, "  from {}:{}", line.file.display(), line.line)?;
+            if let Some(func) = line.function() {
+                write!(f, " in `{func}`")?;
+            }
+            writeln!(f, ": {}", line.text)?;
+        }
+        Ok(())
+    }
+}
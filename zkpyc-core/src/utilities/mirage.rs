@@ -6,7 +6,7 @@ use ::bellman::{
 };
 use ff::{Field, PrimeField, PrimeFieldBits};
 use fxhash::FxHashMap;
-use group::GroupEncoding;
+use group::{Curve, GroupEncoding};
 use group::WnafGroup;
 use log::debug;
 use pairing::{Engine, MultiMillerLoop};
@@ -363,6 +363,13 @@ pub struct ProvingKey<E: Engine> {
     link: cp_link::ProvingKey<E>,
 }
 
+impl<E: Engine> ProvingKey<E> {
+    /// The relation and witness computation
+    pub fn into_data(self) -> ProverData {
+        self.data
+    }
+}
+
 /// The vk for [mirage]
 #[derive(Serialize, Deserialize)]
 #[serde(bound = "")]
@@ -391,38 +398,55 @@ pub struct Commitment<G: GroupEncoding>(#[serde(with = "serde_group")] G);
 #[derive(Serialize, Deserialize, Default)]
 pub struct ComRand<F: PrimeField>(#[serde(with = "serde_field")] F);
 
-impl<E: Engine> proof::CommitProofSystem for Mirage<E>
+impl<G: GroupEncoding> Commitment<G> {
+    /// A commitment produced elsewhere, from the hex of its compressed encoding
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        cp_link::point_from_hex(hex).map(Commitment)
+    }
+
+    /// The hex of the compressed encoding
+    pub fn to_hex(&self) -> String {
+        cp_link::point_to_hex(&self.0)
+    }
+}
+
+impl<F: PrimeField> ComRand<F> {
+    /// The randomness of a commitment produced elsewhere
+    pub fn from_int(i: Integer) -> Self {
+        ComRand(int_to_ff(i))
+    }
+}
+
+impl<E: Engine> Mirage<E>
 where
     E: MultiMillerLoop,
     E::G1: WnafGroup,
     E::G2: WnafGroup,
     E::Fr: PrimeFieldBits,
 {
-    type VerifyingKey = VerifyingKey<E>;
-
-    type ProvingKey = ProvingKey<E>;
-
-    type Proof = Proof<E>;
-
-    type Commitment = Commitment<E::G1>;
-
-    type ComRand = ComRand<E::Fr>;
-
-    fn cp_setup(
+    /// Setup, for commitments under `ck` rather than a fresh key. With a
+    /// Pedersen key used elsewhere (for example, for commitments posted on a
+    /// ledger), the proofs show that the committed arguments open those
+    /// commitments.
+    pub fn cp_setup_with_ck(
         p_data: ProverData,
         v_data: VerifierData,
-    ) -> (Self::ProvingKey, Self::VerifyingKey) {
+        ck: cp_link::CommitKey<E>,
+    ) -> Result<(ProvingKey<E>, VerifyingKey<E>), String> {
         let rng = &mut rand::thread_rng();
         let num_cmts = p_data.r1cs.commitments.len();
-        let data_len = p_data
-            .r1cs
-            .commitments
-            .first()
-            .map(|c| c.len())
-            .unwrap_or(0);
+        for c in &p_data.r1cs.commitments {
+            if c.len() != ck.data_keys.len() {
+                return Err(format!(
+                    "The commitment key is for arrays of length {}, but a committed argument has length {}",
+                    ck.data_keys.len(),
+                    c.len()
+                ));
+            }
+        }
         let params =
             mirage::generate_random_parameters::<E, _, _>(SynthInput(&p_data, None, None), rng)
-                .unwrap();
+                .map_err(|e| e.to_string())?;
         let cks = (0..num_cmts)
             .map(|i| {
                 let mut all_keys: Vec<_> = (*params.ls[i]).clone();
@@ -433,10 +457,9 @@ where
                 }
             })
             .collect();
-        let ck = cp_link::sample_ck(rng, data_len);
         let (link_pk, link_vk) = cp_link::key_gen(ck.clone(), cks, rng);
         let v_params = params.vk.clone();
-        (
+        Ok((
             ProvingKey {
                 data: p_data,
                 mirage: params,
@@ -448,7 +471,56 @@ where
                 mirage: v_params,
                 link: link_vk,
             },
-        )
+        ))
+    }
+
+    /// Setup to files, for commitments under the key in `ck_path`, as read by
+    /// [cp_link::CommitKey::from_json]
+    pub fn cp_setup_with_ck_fs(
+        p_data: ProverData,
+        v_data: VerifierData,
+        ck_path: impl AsRef<Path>,
+        pk_path: impl AsRef<Path>,
+        vk_path: impl AsRef<Path>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let ck = cp_link::CommitKey::from_json(&std::fs::read_to_string(ck_path)?)?;
+        let (pk, vk) = Self::cp_setup_with_ck(p_data, v_data, ck)?;
+        proof::serialize_into_file(&pk, pk_path)?;
+        proof::serialize_into_file(&vk, vk_path)?;
+        Ok(())
+    }
+}
+
+impl<E: Engine> proof::CommitProofSystem for Mirage<E>
+where
+    E: MultiMillerLoop,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    E::Fr: PrimeFieldBits,
+{
+    type VerifyingKey = VerifyingKey<E>;
+
+    type ProvingKey = ProvingKey<E>;
+
+    type Proof = Proof<E>;
+
+    type Commitment = Commitment<E::G1>;
+
+    type ComRand = ComRand<E::Fr>;
+
+    fn cp_setup(
+        p_data: ProverData,
+        v_data: VerifierData,
+    ) -> (Self::ProvingKey, Self::VerifyingKey) {
+        let data_len = p_data
+            .r1cs
+            .commitments
+            .first()
+            .map(|c| c.len())
+            .unwrap_or(0);
+        let ck = cp_link::sample_ck(&mut rand::thread_rng(), data_len);
+        // the sampled key fits unless the committed arguments differ in length
+        Self::cp_setup_with_ck(p_data, v_data, ck).unwrap_or_else(|e| panic!("{e}"))
     }
 
     fn cp_prove(
@@ -495,7 +567,14 @@ where
             .into_iter()
             .map(|i| int_to_ff(i.i()))
             .collect();
+        // the first blocks of the proof commit to the committed arguments
+        let block_cmts = match pf.mirage.ds.get(..cmts.len()) {
+            Some(ds) => ds.to_vec(),
+            None => return false,
+        };
+        let cmts: Vec<E::G1Affine> = cmts.iter().map(|c| c.0.to_affine()).collect();
         mirage::verify_proof(&pvk, &pf.mirage, &r1cs_inst).is_ok()
+            && cp_link::verify(&vk.link, cmts, block_cmts, &pf.link)
     }
 
     fn cp_commit(vk: &Self::VerifyingKey, data: Value, rand: &Self::ComRand) -> Self::Commitment {
@@ -3,7 +3,20 @@ pub mod trans;
 pub mod opt;
 pub mod bellman;
 pub mod mirage;
+pub mod ceremony;
+#[cfg(feature = "snarkpack")]
+pub mod snarkpack;
+#[cfg(feature = "plonk")]
+pub mod plonk;
+#[cfg(feature = "spartan")]
+pub mod spartan;
+#[cfg(feature = "bulletproofs")]
+pub mod bulletproofs;
+#[cfg(feature = "nova")]
+pub mod nova;
 pub mod wit_comp;
 pub mod proof;
 pub mod cp_link;
 pub mod scalar_fields;
+pub mod profile;
+pub mod debug;
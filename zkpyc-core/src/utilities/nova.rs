@@ -0,0 +1,445 @@
+//! Incrementally verifiable computation with Nova, over the Pallas/Vesta cycle.
+//!
+//! A step function `f(state, *advice) -> state` is compiled once to R1CS in
+//! [Mode::Ivc](crate::front::Mode::Ivc). The prover folds any number of
+//! applications of it, each with its own private advice, and compresses the
+//! result into a single Spartan proof that `z_n = f(...f(z_0)...)`.
+//!
+//! The Nova state is the vector of the public leaves of the state parameter,
+//! in the order of [Layout::leaves]; the step outputs the `return` leaves with
+//! the same suffixes.
+
+use bellperson::{gadgets::num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError, Variable};
+use fxhash::FxHashMap as HashMap;
+use log::debug;
+use nova_snark::{
+    provider::ipa_pc,
+    spartan::snark::RelaxedR1CSSNARK,
+    traits::circuit::{StepCircuit, TrivialTestCircuit},
+    CompressedSNARK, PublicParams, RecursiveSNARK,
+};
+use pasta_curves::{
+    group::ff::{Field, PrimeField},
+    pallas, vesta,
+};
+use rug::{integer::Order, Integer};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::proof::{deserialize_from_file, serialize_into_file, value_map_from_path};
+use super::r1cs::{Lc, ProverData, Var, VarType};
+use circ::ir::term::{bv::BitVector, Value};
+
+/// The order of the Pallas scalar field, the only field this back-end supports
+const MODULUS: &str =
+    "28948022309329048855892746252171976963363056481941647379679742748393362948097";
+
+type G1 = pallas::Point;
+type G2 = vesta::Point;
+type Scalar = pallas::Scalar;
+type Secondary = TrivialTestCircuit<vesta::Scalar>;
+type S1 = RelaxedR1CSSNARK<G1, ipa_pc::EvaluationEngine<G1>>;
+type S2 = RelaxedR1CSSNARK<G2, ipa_pc::EvaluationEngine<G2>>;
+
+fn check_modulus(modulus: &Integer) -> Result<(), String> {
+    if modulus != &Integer::from_str_radix(MODULUS, 10).unwrap() {
+        return Err(format!(
+            "Nova proofs are over the Pallas scalar field; compile with `--field-custom-modulus {MODULUS}`"
+        ));
+    }
+    Ok(())
+}
+
+fn scalar(i: &Integer) -> Scalar {
+    let mut repr = <Scalar as PrimeField>::Repr::default();
+    let digits = i.to_digits::<u8>(Order::Lsf);
+    assert!(digits.len() <= repr.as_ref().len(), "{i} is not a Pallas scalar");
+    repr.as_mut()[..digits.len()].copy_from_slice(&digits);
+    Option::from(Scalar::from_repr(repr)).unwrap_or_else(|| panic!("{i} is not a Pallas scalar"))
+}
+
+fn integer(s: &Scalar) -> Integer {
+    Integer::from_digits(s.to_repr().as_ref(), Order::Lsf)
+}
+
+/// The field encoding of a public leaf, as the R1CS instance holds it
+fn value_scalar(v: &Value) -> Scalar {
+    match v {
+        Value::Field(f) => scalar(&f.i()),
+        Value::BitVector(bv) => scalar(bv.uint()),
+        Value::Bool(b) => scalar(&Integer::from(*b as u8)),
+        v => panic!("Cannot encode {v} as a state leaf"),
+    }
+}
+
+/// Decode `s` as a leaf of the same sort as `like`
+fn scalar_value(s: &Scalar, like: &Value) -> Value {
+    let i = integer(s);
+    match like {
+        Value::Field(f) => Value::Field(f.ty().new_v(i)),
+        Value::BitVector(bv) => Value::BitVector(BitVector::new(i, bv.width())),
+        Value::Bool(_) => Value::Bool(i == 1),
+        v => panic!("Cannot decode a state leaf as {v}"),
+    }
+}
+
+fn split_leaf(name: &str) -> (&str, &str) {
+    name.split_once('.').unwrap_or((name, ""))
+}
+
+fn leaf_name(base: &str, suffix: &str) -> String {
+    if suffix.is_empty() {
+        base.to_owned()
+    } else {
+        format!("{base}.{suffix}")
+    }
+}
+
+/// Which public leaves make up the IVC state
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Layout {
+    /// The name of the state parameter
+    pub state: String,
+    /// The suffixes of its leaves, which are also those of the `return` leaves
+    pub leaves: Vec<String>,
+}
+
+impl Layout {
+    /// The layout of a step compiled in [Mode::Ivc](crate::front::Mode::Ivc)
+    fn new(pd: &ProverData) -> Result<Self, String> {
+        let mut state: Option<&str> = None;
+        let mut leaves = Vec::new();
+        let mut ret_leaves = Vec::new();
+        for v in pd.r1cs.vars.iter().filter(|v| matches!(v.ty(), VarType::Inst)) {
+            let (base, suffix) = split_leaf(&pd.r1cs.names[v]);
+            if base == "return" {
+                ret_leaves.push(suffix.to_owned());
+                continue;
+            }
+            match state {
+                Some(s) if s != base => {
+                    return Err(format!(
+                        "An IVC step has a single public parameter, but found '{s}' and '{base}'"
+                    ))
+                }
+                _ => state = Some(base),
+            }
+            leaves.push(suffix.to_owned());
+        }
+        let state = state.ok_or("The IVC step has no public state")?.to_owned();
+        leaves.sort();
+        ret_leaves.sort();
+        if leaves != ret_leaves {
+            return Err(format!(
+                "The leaves of the state '{state}' ({}) differ from those of the return value ({}); \
+                 is part of the state unused?",
+                leaves.join(", "),
+                ret_leaves.join(", ")
+            ));
+        }
+        Ok(Layout { state, leaves })
+    }
+
+    /// The leaves of the state, named after the state parameter
+    pub fn state_names(&self) -> Vec<String> {
+        self.leaves.iter().map(|s| leaf_name(&self.state, s)).collect()
+    }
+
+    /// The leaves of the state, named after the return value
+    pub fn return_names(&self) -> Vec<String> {
+        self.leaves.iter().map(|s| leaf_name("return", s)).collect()
+    }
+
+    /// The Nova state for the leaves `names` in `values`
+    fn encode(&self, names: &[String], values: &HashMap<String, Value>) -> Result<Vec<Scalar>, String> {
+        names
+            .iter()
+            .map(|n| {
+                values
+                    .get(n)
+                    .map(value_scalar)
+                    .ok_or_else(|| format!("Missing state leaf '{n}'"))
+            })
+            .collect()
+    }
+}
+
+/// A linear combination over the step's variables; `None` is the constant one
+type StepLc = Vec<(Option<usize>, Scalar)>;
+
+/// The step relation, over the variables of the step's R1CS in order
+struct StepShape {
+    num_vars: usize,
+    /// Indices of the state inputs, in layout order
+    state: Vec<usize>,
+    /// Indices of the return outputs, in layout order
+    ret: Vec<usize>,
+    constraints: Vec<[StepLc; 3]>,
+}
+
+impl StepShape {
+    fn new(pd: &ProverData, layout: &Layout) -> Result<Self, String> {
+        let r1cs = &pd.r1cs;
+        check_modulus(r1cs.field.modulus())?;
+        if r1cs.vars.iter().any(|v| matches!(v.ty(), VarType::Chall | VarType::CWit)) {
+            return Err("Nova does not support challenges or committed witnesses".to_string());
+        }
+        let index: HashMap<&str, usize> = r1cs
+            .vars
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (r1cs.names[v].as_str(), i))
+            .collect();
+        let indices = |names: Vec<String>| -> Vec<usize> {
+            names.iter().map(|n| index[n.as_str()]).collect()
+        };
+        let vars: HashMap<Var, usize> = r1cs.vars.iter().enumerate().map(|(i, v)| (*v, i)).collect();
+        let lc = |lc: &Lc| -> StepLc {
+            lc.monomials
+                .iter()
+                .map(|(v, c)| (Some(vars[v]), scalar(&c.i())))
+                .chain(std::iter::once((None, scalar(&lc.constant.i()))))
+                .collect()
+        };
+        Ok(StepShape {
+            num_vars: r1cs.vars.len(),
+            state: indices(layout.state_names()),
+            ret: indices(layout.return_names()),
+            constraints: r1cs.constraints.iter().map(|(a, b, c)| [lc(a), lc(b), lc(c)]).collect(),
+        })
+    }
+}
+
+/// One application of the step function, with the values of all its
+/// variables if it is being proven
+#[derive(Clone)]
+pub struct Step {
+    shape: Arc<StepShape>,
+    values: Option<Vec<Scalar>>,
+}
+
+impl StepCircuit<Scalar> for Step {
+    fn arity(&self) -> usize {
+        self.shape.state.len()
+    }
+
+    fn synthesize<CS: ConstraintSystem<Scalar>>(
+        &self,
+        cs: &mut CS,
+        z: &[AllocatedNum<Scalar>],
+    ) -> Result<Vec<AllocatedNum<Scalar>>, SynthesisError> {
+        let shape = &self.shape;
+        let mut nums: Vec<Option<AllocatedNum<Scalar>>> = vec![None; shape.num_vars];
+        for (i, z_i) in shape.state.iter().zip(z) {
+            nums[*i] = Some(z_i.clone());
+        }
+        for (i, num) in nums.iter_mut().enumerate() {
+            if num.is_none() {
+                let value = self.values.as_ref().map(|vs| vs[i]);
+                *num = Some(AllocatedNum::alloc(cs.namespace(|| format!("var {i}")), || {
+                    value.ok_or(SynthesisError::AssignmentMissing)
+                })?);
+            }
+        }
+        let vars: Vec<Variable> = nums.iter().map(|n| n.as_ref().unwrap().get_variable()).collect();
+        let lc = |lc: &StepLc, mut acc: LinearCombination<Scalar>| {
+            for (v, c) in lc {
+                acc = acc + (*c, v.map_or(CS::one(), |v| vars[v]));
+            }
+            acc
+        };
+        for (i, [a, b, c]) in shape.constraints.iter().enumerate() {
+            cs.enforce(
+                || format!("constraint {i}"),
+                |z| lc(a, z),
+                |z| lc(b, z),
+                |z| lc(c, z),
+            );
+        }
+        Ok(shape.ret.iter().map(|i| nums[*i].clone().unwrap()).collect())
+    }
+
+    fn output(&self, _z: &[Scalar]) -> Vec<Scalar> {
+        let values = self.values.as_ref().expect("The output of a step needs its witness");
+        self.shape.ret.iter().map(|i| values[*i]).collect()
+    }
+}
+
+type Params = PublicParams<G1, G2, Step, Secondary>;
+
+/// The pk for IVC over a step
+#[derive(Serialize, Deserialize)]
+pub struct IvcProvingKey {
+    data: ProverData,
+    layout: Layout,
+    pp: Params,
+    pk: nova_snark::ProverKey<G1, G2, Step, Secondary, S1, S2>,
+}
+
+/// The vk for IVC over a step
+#[derive(Serialize, Deserialize)]
+pub struct IvcVerifyingKey {
+    layout: Layout,
+    vk: nova_snark::VerifierKey<G1, G2, Step, Secondary, S1, S2>,
+}
+
+impl IvcVerifyingKey {
+    /// Which leaves make up the state
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+}
+
+/// A proof of some number of steps from an initial state to a final one
+#[derive(Serialize, Deserialize)]
+pub struct IvcProof {
+    num_steps: usize,
+    snark: CompressedSNARK<G1, G2, Step, Secondary, S1, S2>,
+}
+
+impl IvcProof {
+    /// How many steps were folded
+    pub fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+}
+
+/// Generate the public parameters and compression keys of a step compiled in
+/// [Mode::Ivc](crate::front::Mode::Ivc)
+pub fn setup(data: ProverData) -> Result<(IvcProvingKey, IvcVerifyingKey), String> {
+    let layout = Layout::new(&data)?;
+    let step = Step {
+        shape: Arc::new(StepShape::new(&data, &layout)?),
+        values: None,
+    };
+    debug!(
+        "Nova: state of {} leaves, {} step constraints",
+        layout.leaves.len(),
+        step.shape.constraints.len()
+    );
+    let pp = Params::setup(&step, &Secondary::default());
+    let (pk, vk) = CompressedSNARK::<_, _, _, _, S1, S2>::setup(&pp).map_err(|e| format!("{e:?}"))?;
+    Ok((
+        IvcProvingKey {
+            data,
+            layout: layout.clone(),
+            pp,
+            pk,
+        },
+        IvcVerifyingKey { layout, vk },
+    ))
+}
+
+/// Fold one step per entry of `advice`, starting from the state leaves in
+/// `z0`, and compress the result. Returns the proof and the final state,
+/// named after `return` as [verify] expects it.
+pub fn prove(
+    pk: &IvcProvingKey,
+    z0: &HashMap<String, Value>,
+    advice: &[HashMap<String, Value>],
+) -> Result<(IvcProof, HashMap<String, Value>), String> {
+    if advice.is_empty() {
+        return Err("IVC needs at least one step".into());
+    }
+    let layout = &pk.layout;
+    let shape = Arc::new(StepShape::new(&pk.data, layout)?);
+    let state_names = layout.state_names();
+    let z0_primary = layout.encode(&state_names, z0)?;
+    let z0_secondary = vec![vesta::Scalar::ZERO];
+    let secondary = Secondary::default();
+
+    let mut state = z0.clone();
+    let mut steps = Vec::with_capacity(advice.len());
+    for (i, step_advice) in advice.iter().enumerate() {
+        let mut inputs = step_advice.clone();
+        inputs.extend(state.iter().map(|(k, v)| (k.clone(), v.clone())));
+        if let Some(unsatisfied) = pk.data.first_unsatisfied(&inputs, None) {
+            return Err(format!("Step {i} does not satisfy the step function: {unsatisfied}"));
+        }
+        let values = pk.data.eval_vars(&inputs);
+        let values: Vec<Scalar> = pk.data.r1cs.vars.iter().map(|v| scalar(&values[v].i())).collect();
+        for (name, r) in state_names.iter().zip(&shape.ret) {
+            let next = scalar_value(&values[*r], &z0[name]);
+            state.insert(name.clone(), next);
+        }
+        debug!("Nova: computed step {i}");
+        steps.push(Step {
+            shape: shape.clone(),
+            values: Some(values),
+        });
+    }
+
+    let mut rs = RecursiveSNARK::new(
+        &pk.pp,
+        &steps[0],
+        &secondary,
+        z0_primary.clone(),
+        z0_secondary.clone(),
+    );
+    for (i, step) in steps.iter().enumerate() {
+        rs.prove_step(&pk.pp, step, &secondary, z0_primary.clone(), z0_secondary.clone())
+            .map_err(|e| format!("Folding step {i} failed: {e:?}"))?;
+    }
+    let snark = CompressedSNARK::<_, _, _, _, S1, S2>::prove(&pk.pp, &pk.pk, &rs)
+        .map_err(|e| format!("{e:?}"))?;
+    let zn = state_names
+        .iter()
+        .zip(layout.return_names())
+        .map(|(s, r)| (r, state[s].clone()))
+        .collect();
+    Ok((
+        IvcProof {
+            num_steps: steps.len(),
+            snark,
+        },
+        zn,
+    ))
+}
+
+/// Check that `pf` takes the state `z0` to `zn`; both are leaf maps, the
+/// former named after the state parameter and the latter after `return`
+pub fn verify(
+    vk: &IvcVerifyingKey,
+    z0: &HashMap<String, Value>,
+    zn: &HashMap<String, Value>,
+    pf: &IvcProof,
+) -> Result<bool, String> {
+    let layout = &vk.layout;
+    let z0_primary = layout.encode(&layout.state_names(), z0)?;
+    let zn_primary = layout.encode(&layout.return_names(), zn)?;
+    match pf
+        .snark
+        .verify(&vk.vk, pf.num_steps, z0_primary, vec![vesta::Scalar::ZERO])
+    {
+        Ok((out, _)) => Ok(out == zn_primary),
+        Err(e) => {
+            debug!("Nova: {e:?}");
+            Ok(false)
+        }
+    }
+}
+
+/// Setup from the step's prover data, writing the keys to files
+pub fn setup_fs<P1: AsRef<Path>, P2: AsRef<Path>>(
+    data: ProverData,
+    pk_path: P1,
+    vk_path: P2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (pk, vk) = setup(data)?;
+    serialize_into_file(&pk, pk_path)?;
+    serialize_into_file(&vk, vk_path)?;
+    Ok(())
+}
+
+/// Verify from files: `inputs` holds the initial state and final `return` leaves
+pub fn verify_fs<P: AsRef<Path>>(
+    vk_path: P,
+    inputs_path: P,
+    pf_path: P,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let vk: IvcVerifyingKey = deserialize_from_file(vk_path)?;
+    let inputs = value_map_from_path(inputs_path)?;
+    let pf: IvcProof = deserialize_from_file(pf_path)?;
+    Ok(verify(&vk, &inputs, &inputs, &pf)?)
+}
@@ -0,0 +1,794 @@
+//! A PLONK back-end with a universal KZG setup over BN254, via halo2.
+//!
+//! The R1CS is arithmetized into rows of the standard PLONK gate
+//!
+//! ```text
+//! q_l * l + q_r * r + q_o * o + q_m * l * r + q_c = 0
+//! ```
+//!
+//! over three advice columns, with copy constraints tying together the cells
+//! of each wire. Unlike [Bellman](super::bellman::Bellman), the only secret
+//! randomness is in the structured reference string ([Srs]), which can be
+//! generated once and shared by every circuit of up to `2^k` rows; the
+//! per-circuit keys are derived from it deterministically.
+
+use halo2_proofs::circuit::{Cell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2_proofs::plonk::{
+    self, create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column,
+    ConstraintSystem, Error, Fixed, Instance,
+};
+use halo2_proofs::poly::commitment::{Params, ParamsProver};
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2_proofs::SerdeFormat;
+
+use fxhash::FxHashMap as HashMap;
+use log::debug;
+use rand::rngs::OsRng;
+use rug::{integer::Order, Integer};
+use serde::{Deserialize, Serialize};
+
+use std::path::Path;
+
+use super::proof::{self, deserialize_from_file, serialize_into_file};
+use super::r1cs::{Lc, ProverData, R1csFinal, Var, VarType, VerifierData};
+use circ::ir::term::Value as IrValue;
+
+/// The order of the BN254 scalar field, the only field this back-end supports
+const MODULUS: &str =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+pub(crate) fn int_to_fr(i: &Integer) -> Result<Fr, String> {
+    let not_scalar = || format!("{i} is not a BN254 scalar");
+    let mut bytes = [0u8; 32];
+    let digits = i.to_digits::<u8>(Order::Lsf);
+    if digits.len() > 32 {
+        return Err(not_scalar());
+    }
+    bytes[..digits.len()].copy_from_slice(&digits);
+    Option::from(Fr::from_bytes(&bytes)).ok_or_else(not_scalar)
+}
+
+/// Read a little-endian encoded BN254 scalar, as in zkInterface messages
+fn le_bytes_to_fr(encoded: &[u8]) -> Result<Fr, String> {
+    int_to_fr(&Integer::from_digits(encoded, Order::Lsf))
+}
+
+mod serde_fr {
+    use super::Fr;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(f: &Fr, ser: S) -> Result<S::Ok, S::Error> {
+        f.to_bytes().serialize(ser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Fr, D::Error> {
+        let bs: [u8; 32] = Deserialize::deserialize(de)?;
+        Option::from(Fr::from_bytes(&bs)).ok_or_else(|| D::Error::custom("not a BN254 scalar"))
+    }
+}
+
+mod serde_params {
+    use super::{Bn256, Params, ParamsKZG};
+    use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(p: &ParamsKZG<Bn256>, ser: S) -> Result<S::Ok, S::Error> {
+        let mut bs: Vec<u8> = Vec::new();
+        p.write(&mut bs).map_err(ser::Error::custom)?;
+        serde_bytes::ByteBuf::from(bs).serialize(ser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<ParamsKZG<Bn256>, D::Error> {
+        let bs: serde_bytes::ByteBuf = Deserialize::deserialize(de)?;
+        ParamsKZG::read(&mut &**bs).map_err(de::Error::custom)
+    }
+}
+
+mod serde_pk {
+    use super::{G1Affine, PlonkCircuit, SerdeFormat};
+    use halo2_proofs::plonk::ProvingKey;
+    use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(pk: &ProvingKey<G1Affine>, ser: S) -> Result<S::Ok, S::Error> {
+        let mut bs: Vec<u8> = Vec::new();
+        pk.write(&mut bs, SerdeFormat::RawBytes).map_err(ser::Error::custom)?;
+        serde_bytes::ByteBuf::from(bs).serialize(ser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<ProvingKey<G1Affine>, D::Error> {
+        let bs: serde_bytes::ByteBuf = Deserialize::deserialize(de)?;
+        ProvingKey::read::<_, PlonkCircuit<'static>>(&mut &**bs, SerdeFormat::RawBytes).map_err(de::Error::custom)
+    }
+}
+
+mod serde_vk {
+    use super::{G1Affine, PlonkCircuit, SerdeFormat};
+    use halo2_proofs::plonk::VerifyingKey;
+    use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(vk: &VerifyingKey<G1Affine>, ser: S) -> Result<S::Ok, S::Error> {
+        let mut bs: Vec<u8> = Vec::new();
+        vk.write(&mut bs, SerdeFormat::RawBytes).map_err(ser::Error::custom)?;
+        serde_bytes::ByteBuf::from(bs).serialize(ser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<VerifyingKey<G1Affine>, D::Error> {
+        let bs: serde_bytes::ByteBuf = Deserialize::deserialize(de)?;
+        VerifyingKey::read::<_, PlonkCircuit<'static>>(&mut &**bs, SerdeFormat::RawBytes).map_err(de::Error::custom)
+    }
+}
+
+/// One row of the PLONK gate: `q_l * l + q_r * r + q_o * o + q_m * l * r + q_c = 0`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Gate {
+    pub l: usize,
+    pub r: usize,
+    pub o: usize,
+    #[serde(with = "serde_fr")]
+    pub q_l: Fr,
+    #[serde(with = "serde_fr")]
+    pub q_r: Fr,
+    #[serde(with = "serde_fr")]
+    pub q_o: Fr,
+    #[serde(with = "serde_fr")]
+    pub q_m: Fr,
+    #[serde(with = "serde_fr")]
+    pub q_c: Fr,
+}
+
+impl Gate {
+    fn linear(l: usize, q_l: Fr, r: usize, q_r: Fr, o: usize) -> Self {
+        Gate {
+            l,
+            r,
+            o,
+            q_l,
+            q_r,
+            q_o: -Fr::one(),
+            q_m: Fr::zero(),
+            q_c: Fr::zero(),
+        }
+    }
+}
+
+/// A linear combination of wires; constants are multiples of wire 0.
+pub type WireLc = Vec<(usize, Fr)>;
+
+/// An R1CS relation arithmetized into PLONK gates.
+///
+/// Wire 0 is the constant one and wires `1..n_vars` are the R1CS variables;
+/// the remaining wires hold intermediate sums of linear combinations, each
+/// computed by the `o` of the first row it appears in.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Gates {
+    pub n_vars: usize,
+    pub n_wires: usize,
+    /// The instance wires, in order. Row `1 + i` holds public input `i`.
+    pub public: Vec<usize>,
+    pub rows: Vec<Gate>,
+}
+
+impl Gates {
+    /// Arithmetize the constraints `a * b = c` over `n_vars` wires.
+    pub fn new(
+        n_vars: usize,
+        public: Vec<usize>,
+        constraints: impl IntoIterator<Item = (WireLc, WireLc, WireLc)>,
+    ) -> Self {
+        let mut gates = Gates {
+            n_vars,
+            n_wires: n_vars,
+            rows: Vec::new(),
+            public: public.clone(),
+        };
+        // wire 0 is one
+        gates.rows.push(Gate {
+            q_o: Fr::zero(),
+            q_c: -Fr::one(),
+            ..Gate::linear(0, Fr::one(), 0, Fr::zero(), 0)
+        });
+        // a cell for each public input, whether or not it is constrained
+        for w in public {
+            gates.rows.push(Gate {
+                q_o: Fr::zero(),
+                ..Gate::linear(w, Fr::zero(), 0, Fr::zero(), 0)
+            });
+        }
+        for (a, b, c) in constraints {
+            let a = gates.reduce(&a);
+            let b = gates.reduce(&b);
+            let c = nonzero(&c);
+            let (o, q_o) = match c.as_slice() {
+                [] => (0, Fr::zero()),
+                [(w, coeff)] => (*w, -*coeff),
+                _ => (gates.reduce(&c), -Fr::one()),
+            };
+            gates.rows.push(Gate {
+                l: a,
+                r: b,
+                o,
+                q_l: Fr::zero(),
+                q_r: Fr::zero(),
+                q_o,
+                q_m: Fr::one(),
+                q_c: Fr::zero(),
+            });
+        }
+        gates
+    }
+
+    /// Arithmetize a finalized R1CS; wire `1 + i` is `r1cs.vars[i]`.
+    pub fn from_r1cs(r1cs: &R1csFinal) -> Result<Self, String> {
+        if r1cs.field.modulus() != &Integer::from_str_radix(MODULUS, 10).unwrap() {
+            return Err(
+                "PLONK proofs are over the BN254 scalar field; compile with `--field-builtin bn254`".to_string(),
+            );
+        }
+        if r1cs.vars.iter().any(|v| matches!(v.ty(), VarType::Chall | VarType::CWit)) {
+            return Err("PLONK does not support challenges or committed witnesses".to_string());
+        }
+        let wires: HashMap<Var, usize> = r1cs.vars.iter().enumerate().map(|(i, v)| (*v, i + 1)).collect();
+        let public = r1cs
+            .vars
+            .iter()
+            .filter(|v| matches!(v.ty(), VarType::Inst))
+            .map(|v| wires[v])
+            .collect();
+        let lc = |lc: &Lc| -> Result<WireLc, String> {
+            let mut terms: WireLc = lc
+                .monomials
+                .iter()
+                .map(|(v, c)| Ok((wires[v], int_to_fr(&c.i())?)))
+                .collect::<Result<_, String>>()?;
+            terms.sort_by_key(|(w, _)| *w);
+            terms.push((0, int_to_fr(&lc.constant.i())?));
+            Ok(terms)
+        };
+        let constraints = r1cs
+            .constraints
+            .iter()
+            .map(|(a, b, c)| Ok((lc(a)?, lc(b)?, lc(c)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Gates::new(r1cs.vars.len() + 1, public, constraints))
+    }
+
+    fn fresh(&mut self) -> usize {
+        self.n_wires += 1;
+        self.n_wires - 1
+    }
+
+    /// A wire equal to `lc`, summing its terms pairwise
+    fn reduce(&mut self, lc: &[(usize, Fr)]) -> usize {
+        let terms = nonzero(lc);
+        let mut terms = terms.into_iter();
+        let (mut acc, mut acc_coeff) = terms.next().unwrap_or((0, Fr::zero()));
+        for (w, coeff) in terms {
+            let sum = self.fresh();
+            self.rows.push(Gate::linear(acc, acc_coeff, w, coeff, sum));
+            acc = sum;
+            acc_coeff = Fr::one();
+        }
+        if acc_coeff != Fr::one() {
+            let scaled = self.fresh();
+            self.rows.push(Gate::linear(acc, acc_coeff, 0, Fr::zero(), scaled));
+            acc = scaled;
+        }
+        acc
+    }
+
+    /// The values of all wires, from the values of the variables
+    pub fn witness(&self, mut values: Vec<Fr>) -> Vec<Fr> {
+        assert_eq!(values.len(), self.n_vars);
+        values.reserve(self.n_wires - self.n_vars);
+        for g in &self.rows {
+            if g.o == values.len() {
+                // q_o = -1
+                let (l, r) = (values[g.l], values[g.r]);
+                values.push(g.q_l * l + g.q_r * r + g.q_m * l * r + g.q_c);
+            }
+        }
+        assert_eq!(values.len(), self.n_wires);
+        values
+    }
+
+    /// The smallest `k` such that the gates fit in `2^k` rows
+    pub fn k(&self) -> u32 {
+        let mut cs = ConstraintSystem::default();
+        PlonkCircuit::configure(&mut cs);
+        let needed = self.rows.len() + cs.blinding_factors() + 1;
+        needed.next_power_of_two().trailing_zeros().max(4)
+    }
+}
+
+// terms with a non-zero coefficient, merging repeated wires
+fn nonzero(lc: &[(usize, Fr)]) -> WireLc {
+    let mut merged: WireLc = Vec::with_capacity(lc.len());
+    for (w, c) in lc {
+        match merged.iter_mut().find(|(v, _)| v == w) {
+            Some((_, acc)) => *acc += c,
+            None => merged.push((*w, *c)),
+        }
+    }
+    merged.retain(|(_, c)| *c != Fr::zero());
+    merged
+}
+
+#[derive(Clone, Debug)]
+pub struct PlonkConfig {
+    l: Column<Advice>,
+    r: Column<Advice>,
+    o: Column<Advice>,
+    q_l: Column<Fixed>,
+    q_r: Column<Fixed>,
+    q_o: Column<Fixed>,
+    q_m: Column<Fixed>,
+    q_c: Column<Fixed>,
+    instance: Column<Instance>,
+}
+
+/// The halo2 circuit of some [Gates], optionally with the values of its wires
+pub struct PlonkCircuit<'a> {
+    gates: &'a Gates,
+    values: Option<Vec<Fr>>,
+}
+
+impl<'a> Circuit<Fr> for PlonkCircuit<'a> {
+    type Config = PlonkConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        PlonkCircuit {
+            gates: self.gates,
+            values: None,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> PlonkConfig {
+        let config = PlonkConfig {
+            l: meta.advice_column(),
+            r: meta.advice_column(),
+            o: meta.advice_column(),
+            q_l: meta.fixed_column(),
+            q_r: meta.fixed_column(),
+            q_o: meta.fixed_column(),
+            q_m: meta.fixed_column(),
+            q_c: meta.fixed_column(),
+            instance: meta.instance_column(),
+        };
+        meta.enable_equality(config.l);
+        meta.enable_equality(config.r);
+        meta.enable_equality(config.o);
+        meta.enable_equality(config.instance);
+        meta.create_gate("plonk", |meta| {
+            let l = meta.query_advice(config.l, Rotation::cur());
+            let r = meta.query_advice(config.r, Rotation::cur());
+            let o = meta.query_advice(config.o, Rotation::cur());
+            let q_l = meta.query_fixed(config.q_l, Rotation::cur());
+            let q_r = meta.query_fixed(config.q_r, Rotation::cur());
+            let q_o = meta.query_fixed(config.q_o, Rotation::cur());
+            let q_m = meta.query_fixed(config.q_m, Rotation::cur());
+            let q_c = meta.query_fixed(config.q_c, Rotation::cur());
+            vec![q_l * l.clone() + q_r * r.clone() + q_o * o + q_m * l * r + q_c]
+        });
+        config
+    }
+
+    fn synthesize(&self, config: PlonkConfig, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        let gates = self.gates;
+        let value = |w: usize| match &self.values {
+            Some(values) => Value::known(values[w]),
+            None => Value::unknown(),
+        };
+        let public_cells = layouter.assign_region(
+            || "gates",
+            |mut region| {
+                // the first cell of each wire, which its other cells are equal to
+                let mut first: Vec<Option<Cell>> = vec![None; gates.n_wires];
+                let mut public_cells = Vec::with_capacity(gates.public.len());
+                for (row, g) in gates.rows.iter().enumerate() {
+                    for (col, q) in [
+                        (config.q_l, g.q_l),
+                        (config.q_r, g.q_r),
+                        (config.q_o, g.q_o),
+                        (config.q_m, g.q_m),
+                        (config.q_c, g.q_c),
+                    ] {
+                        region.assign_fixed(|| "selector", col, row, || Value::known(q))?;
+                    }
+                    for (col, w) in [(config.l, g.l), (config.r, g.r), (config.o, g.o)] {
+                        let cell = region.assign_advice(|| format!("w{w}"), col, row, || value(w))?.cell();
+                        match first[w] {
+                            Some(f) => region.constrain_equal(f, cell)?,
+                            None => first[w] = Some(cell),
+                        }
+                        if col == config.l && (1..=gates.public.len()).contains(&row) {
+                            public_cells.push(cell);
+                        }
+                    }
+                }
+                Ok(public_cells)
+            },
+        )?;
+        for (i, cell) in public_cells.into_iter().enumerate() {
+            layouter.constrain_instance(cell, config.instance, i)?;
+        }
+        Ok(())
+    }
+}
+
+/// A universal structured reference string, for circuits of up to `2^k` rows
+#[derive(Serialize, Deserialize)]
+pub struct Srs(#[serde(with = "serde_params")] ParamsKZG<Bn256>);
+
+impl Srs {
+    /// Sample a fresh SRS. Whoever runs this learns its trapdoor, so it
+    /// should only be used for testing or by a party the verifiers trust.
+    pub fn generate(k: u32) -> Self {
+        Srs(ParamsKZG::setup(k, OsRng))
+    }
+
+    pub fn k(&self) -> u32 {
+        self.0.k()
+    }
+
+    /// The SRS for circuits of up to `2^k` rows
+    fn downsize(&self, k: u32) -> Result<ParamsKZG<Bn256>, String> {
+        if k > self.k() {
+            return Err(format!(
+                "The circuit needs an SRS of degree 2^{k}, but the SRS has degree 2^{}",
+                self.k()
+            ));
+        }
+        let mut params = self.0.clone();
+        params.downsize(k);
+        Ok(params)
+    }
+
+    /// Generate an SRS to a file
+    pub fn generate_fs(k: u32, path: impl AsRef<Path>) -> std::io::Result<()> {
+        serialize_into_file(&Srs::generate(k), path)
+    }
+}
+
+/// The PLONK proof system
+pub struct Plonk;
+
+/// The pk for [Plonk]
+#[derive(Serialize, Deserialize)]
+pub struct ProvingKey {
+    data: ProverData,
+    gates: Gates,
+    #[serde(with = "serde_params")]
+    params: ParamsKZG<Bn256>,
+    #[serde(with = "serde_pk")]
+    pk: plonk::ProvingKey<G1Affine>,
+}
+
+impl ProvingKey {
+    /// The relation and witness computation
+    pub fn into_data(self) -> ProverData {
+        self.data
+    }
+}
+
+/// The vk for [Plonk]
+#[derive(Serialize, Deserialize)]
+pub struct VerifyingKey {
+    data: VerifierData,
+    #[serde(with = "serde_params")]
+    params: ParamsKZG<Bn256>,
+    #[serde(with = "serde_vk")]
+    vk: plonk::VerifyingKey<G1Affine>,
+}
+
+/// The proof for [Plonk]
+#[derive(Serialize, Deserialize)]
+pub struct Proof(#[serde(with = "serde_bytes")] Vec<u8>);
+
+impl Plonk {
+    /// Derive the keys of a circuit from a universal SRS
+    pub fn setup_with_srs(
+        p_data: ProverData,
+        v_data: VerifierData,
+        srs: &Srs,
+    ) -> Result<(ProvingKey, VerifyingKey), String> {
+        let gates = Gates::from_r1cs(&p_data.r1cs)?;
+        let (params, pk) = keygen(&gates, srs)?;
+        let vk = pk.get_vk().clone();
+        Ok((
+            ProvingKey {
+                data: p_data,
+                gates,
+                params: params.clone(),
+                pk,
+            },
+            VerifyingKey {
+                data: v_data,
+                params,
+                vk,
+            },
+        ))
+    }
+
+    /// Setup to files, from an SRS file
+    pub fn setup_with_srs_fs(
+        p_data: ProverData,
+        v_data: VerifierData,
+        srs_path: impl AsRef<Path>,
+        pk_path: impl AsRef<Path>,
+        vk_path: impl AsRef<Path>,
+    ) -> std::io::Result<()> {
+        let srs: Srs = deserialize_from_file(srs_path)?;
+        let (pk, vk) = Self::setup_with_srs(p_data, v_data, &srs).map_err(invalid_data)?;
+        serialize_into_file(&pk, pk_path)?;
+        serialize_into_file(&vk, vk_path)
+    }
+}
+
+fn invalid_data(e: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+}
+
+/// The SRS for `gates` and its proving key
+fn keygen(gates: &Gates, srs: &Srs) -> Result<(ParamsKZG<Bn256>, plonk::ProvingKey<G1Affine>), String> {
+    let params = srs.downsize(gates.k())?;
+    debug!("PLONK: {} rows, k = {}", gates.rows.len(), params.k());
+    let circuit = PlonkCircuit {
+        gates,
+        values: None,
+    };
+    let keygen_err = |e: Error| format!("PLONK key generation failed: {e:?}");
+    let vk = keygen_vk(&params, &circuit).map_err(keygen_err)?;
+    let pk = keygen_pk(&params, vk, &circuit).map_err(keygen_err)?;
+    Ok((params, pk))
+}
+
+/// Prove that `wires` (see [Gates::witness]) satisfy `gates`
+fn prove_gates(
+    gates: &Gates,
+    params: &ParamsKZG<Bn256>,
+    pk: &plonk::ProvingKey<G1Affine>,
+    wires: Vec<Fr>,
+) -> Result<Proof, String> {
+    let instance: Vec<Fr> = gates.public.iter().map(|w| wires[*w]).collect();
+    let circuit = PlonkCircuit {
+        gates,
+        values: Some(wires),
+    };
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[&instance[..]]],
+        OsRng,
+        &mut transcript,
+    )
+    .map_err(|e| format!("PLONK proving failed: {e:?}"))?;
+    Ok(Proof(transcript.finalize()))
+}
+
+fn verify_gates(
+    params: &ParamsKZG<Bn256>,
+    vk: &plonk::VerifyingKey<G1Affine>,
+    instance: &[Fr],
+    pf: &Proof,
+) -> bool {
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&pf.0[..]);
+    verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<'_, Bn256>, _, _, _>(
+        params.verifier_params(),
+        vk,
+        SingleStrategy::new(params),
+        &[&[instance]],
+        &mut transcript,
+    )
+    .is_ok()
+}
+
+impl proof::ProofSystem for Plonk {
+    type VerifyingKey = VerifyingKey;
+
+    type ProvingKey = ProvingKey;
+
+    type Proof = Proof;
+
+    /// Setup with a fresh SRS of just the size of the circuit, whose
+    /// trapdoor is known to the caller; see [Plonk::setup_with_srs] to use a
+    /// shared one.
+    fn setup(p_data: ProverData, v_data: VerifierData) -> Result<(ProvingKey, VerifyingKey), String> {
+        let k = Gates::from_r1cs(&p_data.r1cs)?.k();
+        Self::setup_with_srs(p_data, v_data, &Srs::generate(k))
+    }
+
+    fn prove(pk: &ProvingKey, witness: &HashMap<String, IrValue>) -> Result<Proof, String> {
+        let values = pk.data.eval_vars(witness);
+        if let Some(c) = pk.data.r1cs.first_unsatisfied(&values) {
+            return Err(format!("The witness does not satisfy constraint {c}"));
+        }
+        let vars = std::iter::once(Ok(Fr::one()))
+            .chain(pk.data.r1cs.vars.iter().map(|v| int_to_fr(&values[v].i())))
+            .collect::<Result<Vec<Fr>, String>>()?;
+        prove_gates(&pk.gates, &pk.params, &pk.pk, pk.gates.witness(vars))
+    }
+
+    fn verify(vk: &VerifyingKey, inst: &HashMap<String, IrValue>, pf: &Proof) -> bool {
+        let instance: Result<Vec<Fr>, _> = vk.data.eval(inst).into_iter().map(|v| int_to_fr(&v.i())).collect();
+        match instance {
+            Ok(instance) => verify_gates(&vk.params, &vk.vk, &instance, pf),
+            Err(_) => false,
+        }
+    }
+}
+
+/// [Plonk] on zkInterface messages, as used by the Python bindings.
+///
+/// Keys are derived from a universal SRS kept in the key workspace as
+/// `plonk_srs.dat`, which must be written beforehand by `zk --action srs`.
+pub mod zkif_backend {
+    use super::*;
+    use std::error::Error;
+    use zkinterface::consumers::reader::Term;
+    use zkinterface::Reader;
+
+    const SRS_NAME: &str = "plonk_srs.dat";
+
+    /// The keys of a zkInterface circuit
+    #[derive(Serialize, Deserialize)]
+    struct Key {
+        gates: Gates,
+        #[serde(with = "serde_params")]
+        params: ParamsKZG<Bn256>,
+        #[serde(with = "serde_pk")]
+        pk: plonk::ProvingKey<G1Affine>,
+    }
+
+    /// The gates of the constraints in `reader`, and the values of their
+    /// variables (all zero if the messages carry no witness).
+    fn gates_and_values(reader: &Reader) -> Result<(Gates, Vec<Fr>), Box<dyn Error>> {
+        let header = reader.first_header().ok_or("Missing circuit header")?;
+        if let Some(max) = header.field_maximum() {
+            if Integer::from_digits(max, Order::Lsf) + 1u32 != Integer::from_str_radix(MODULUS, 10).unwrap() {
+                return Err("PLONK proofs are over the BN254 scalar field".into());
+            }
+        }
+        let instance = reader.instance_variables().ok_or("Missing circuit header")?;
+        let private = reader.private_variables().ok_or("Missing circuit header")?;
+        let mut wires: HashMap<u64, usize> = HashMap::default();
+        let mut values = vec![Fr::one()];
+        wires.insert(0, 0);
+        for var in instance.iter().chain(private.iter()) {
+            wires.insert(var.id, values.len());
+            values.push(le_bytes_to_fr(var.value)?);
+        }
+        let lc = |terms: &[Term]| -> Result<WireLc, Box<dyn Error>> {
+            terms
+                .iter()
+                .map(|t| {
+                    let w = *wires.get(&t.id).ok_or_else(|| format!("Unknown variable {}", t.id))?;
+                    Ok((w, le_bytes_to_fr(t.value)?))
+                })
+                .collect()
+        };
+        let constraints = reader
+            .iter_constraints()
+            .map(|c| Ok((lc(&c.a)?, lc(&c.b)?, lc(&c.c)?)))
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        let public = (1..=instance.len()).collect();
+        Ok((Gates::new(values.len(), public, constraints), values))
+    }
+
+    /// The SRS of the workspace, if it is large enough for `2^k` rows. It is
+    /// never sampled here: a new one would not match the keys already
+    /// derived from the old one.
+    pub(super) fn load_srs(workspace: &Path, k: u32) -> Result<Srs, Box<dyn Error>> {
+        let path = workspace.join(SRS_NAME);
+        if !path.exists() {
+            return Err(format!(
+                "Missing PLONK SRS {}; write one with `zk --action srs --srs-k {k} --srs {}`",
+                path.display(),
+                path.display()
+            )
+            .into());
+        }
+        let srs: Srs = deserialize_from_file(&path)?;
+        if srs.k() < k {
+            return Err(format!(
+                "The circuit needs a PLONK SRS of degree 2^{k}, but {} has degree 2^{}",
+                path.display(),
+                srs.k()
+            )
+            .into());
+        }
+        Ok(srs)
+    }
+
+    pub fn setup(reader: &Reader, workspace: &Path, key_name: &str) -> Result<(), Box<dyn Error>> {
+        let (gates, _) = gates_and_values(reader)?;
+        let srs = load_srs(workspace, gates.k())?;
+        let (params, pk) = keygen(&gates, &srs)?;
+        serialize_into_file(&Key { gates, params, pk }, workspace.join(key_name))?;
+        Ok(())
+    }
+
+    pub fn prove(
+        reader: &Reader,
+        workspace: &Path,
+        key_name: &str,
+        proof_name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let key: Key = deserialize_from_file(workspace.join(key_name))?;
+        let (_, values) = gates_and_values(reader)?;
+        let pf = prove_gates(&key.gates, &key.params, &key.pk, key.gates.witness(values))?;
+        serialize_into_file(&pf, workspace.join(proof_name))?;
+        Ok(())
+    }
+
+    pub fn verify(
+        reader: &Reader,
+        workspace: &Path,
+        key_name: &str,
+        proof_name: &str,
+    ) -> Result<bool, Box<dyn Error>> {
+        let key: Key = deserialize_from_file(workspace.join(key_name))?;
+        let pf: Proof = deserialize_from_file(workspace.join(proof_name))?;
+        let instance: Vec<Fr> = reader
+            .instance_variables()
+            .ok_or("Missing circuit header")?
+            .iter()
+            .map(|v| le_bytes_to_fr(v.value))
+            .collect::<Result<_, _>>()?;
+        Ok(verify_gates(&key.params, key.pk.get_vk(), &instance, &pf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fr(i: u64) -> Fr {
+        Fr::from(i)
+    }
+
+    #[test]
+    fn test_arithmetization() {
+        // x * (y + 2) = 3z, with x public
+        let gates = Gates::new(
+            4,
+            vec![1],
+            vec![(
+                vec![(1, fr(1))],
+                vec![(2, fr(1)), (0, fr(2))],
+                vec![(3, fr(3))],
+            )],
+        );
+        let satisfied = |wires: &[Fr]| {
+            gates.rows.iter().all(|g| {
+                let (l, r, o) = (wires[g.l], wires[g.r], wires[g.o]);
+                g.q_l * l + g.q_r * r + g.q_o * o + g.q_m * l * r + g.q_c == Fr::zero()
+            })
+        };
+        // 4 * (1 + 2) = 3 * 4
+        let wires = gates.witness(vec![fr(1), fr(4), fr(1), fr(4)]);
+        assert!(satisfied(&wires));
+        let wires = gates.witness(vec![fr(1), fr(4), fr(1), fr(5)]);
+        assert!(!satisfied(&wires));
+    }
+
+    #[test]
+    fn test_load_srs() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = zkif_backend::load_srs(dir.path(), 4).err().unwrap().to_string();
+        assert!(err.contains("Missing PLONK SRS"), "{err}");
+        Srs::generate_fs(3, dir.path().join("plonk_srs.dat")).unwrap();
+        let err = zkif_backend::load_srs(dir.path(), 4).err().unwrap().to_string();
+        assert!(err.contains("has degree 2^3"), "{err}");
+        // a failed load leaves the SRS as it was
+        assert_eq!(zkif_backend::load_srs(dir.path(), 3).unwrap().k(), 3);
+    }
+}
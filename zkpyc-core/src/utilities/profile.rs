@@ -0,0 +1,210 @@
+//! Attribution of R1CS constraints to the Python source lines that caused them
+
+use crate::front::source_map::{SourceLine, SourceMap};
+use crate::utilities::trans::Metric;
+use circ::ir::term::*;
+use serde::Serialize;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// The cost of one source line, when executed in one call stack
+#[derive(Clone, Debug, Serialize)]
+pub struct LineCost {
+    pub file: String,
+    pub line: usize,
+    pub function: Option<String>,
+    /// The functions being executed, outermost first
+    pub stack: Vec<String>,
+    pub text: String,
+    pub constraints: u32,
+    pub vars: u32,
+}
+
+/// The cost of one function, excluding the functions it calls
+#[derive(Clone, Debug, Serialize)]
+pub struct FunctionCost {
+    pub function: String,
+    pub constraints: u32,
+    pub vars: u32,
+}
+
+/// Constraint and variable counts per source line and function.
+///
+/// Counts are taken before linear constraints are eliminated, so they add
+/// up to the pre-optimization R1CS size.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LineProfile {
+    /// Sorted by decreasing constraint count
+    pub lines: Vec<LineCost>,
+    /// Sorted by decreasing constraint count
+    pub functions: Vec<FunctionCost>,
+    /// Constraints of terms no source line could be found for
+    pub unattributed_constraints: u32,
+    pub unattributed_vars: u32,
+}
+
+impl LineProfile {
+    /// Attribute the per-term `metrics` of the R1CS lowering to the lines of
+    /// `spans` (see [SourceMap::attribute]).
+    pub fn new(metrics: &TermMap<Metric>, spans: &SourceMap) -> Self {
+        let tags = spans.attribute(metrics.keys().cloned());
+
+        let mut per_loc: BTreeMap<usize, Metric> = BTreeMap::new();
+        let mut profile = LineProfile::default();
+        for (t, m) in metrics {
+            match tags.get(t) {
+                Some((_, id)) => *per_loc.entry(*id).or_default() += m,
+                None => {
+                    profile.unattributed_constraints += m.n_constraints;
+                    profile.unattributed_vars += m.n_vars;
+                }
+            }
+        }
+
+        let mut per_fn: BTreeMap<String, Metric> = BTreeMap::new();
+        for (id, m) in per_loc {
+            let loc: &SourceLine = &spans.locs()[id];
+            let function = loc.function().map(str::to_owned);
+            *per_fn.entry(function.clone().unwrap_or_default()).or_default() += &m;
+            profile.lines.push(LineCost {
+                file: loc.file.display().to_string(),
+                line: loc.line,
+                function,
+                stack: loc.stack.clone(),
+                text: loc.text.clone(),
+                constraints: m.n_constraints,
+                vars: m.n_vars,
+            });
+        }
+        profile.functions = per_fn
+            .into_iter()
+            .map(|(function, m)| FunctionCost {
+                function,
+                constraints: m.n_constraints,
+                vars: m.n_vars,
+            })
+            .collect();
+        profile.lines.sort_by(|a, b| {
+            (b.constraints, b.vars)
+                .cmp(&(a.constraints, a.vars))
+                .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+        });
+        profile.functions.sort_by(|a, b| {
+            (b.constraints, b.vars)
+                .cmp(&(a.constraints, a.vars))
+                .then_with(|| a.function.cmp(&b.function))
+        });
+        profile
+    }
+
+    /// A table of the lines and functions, the most expensive first.
+    ///
+    /// A line executed in several call stacks (e.g. in a function called
+    /// twice) is listed once with the sum of its costs.
+    pub fn to_text(&self) -> String {
+        let mut lines: BTreeMap<(&str, usize), (u32, u32, &str)> = BTreeMap::new();
+        for l in &self.lines {
+            let e = lines.entry((l.file.as_str(), l.line)).or_insert((0, 0, &l.text));
+            e.0 += l.constraints;
+            e.1 += l.vars;
+        }
+        let mut lines: Vec<_> = lines.into_iter().collect();
+        lines.sort_by(|(_, a), (_, b)| (b.0, b.1).cmp(&(a.0, a.1)));
+
+        let mut s = String::new();
+        writeln!(s, "{:>12} {:>12}  line", "constraints", "vars").unwrap();
+        for ((file, line), (cs, vs, text)) in lines {
+            writeln!(s, "{cs:>12} {vs:>12}  {file}:{line}  {text}").unwrap();
+        }
+        if self.unattributed_constraints != 0 || self.unattributed_vars != 0 {
+            writeln!(
+                s,
+                "{:>12} {:>12}  <unattributed>",
+                self.unattributed_constraints, self.unattributed_vars
+            )
+            .unwrap();
+        }
+        writeln!(s).unwrap();
+        writeln!(s, "{:>12} {:>12}  function", "constraints", "vars").unwrap();
+        for f in &self.functions {
+            writeln!(s, "{:>12} {:>12}  {}", f.constraints, f.vars, f.function).unwrap();
+        }
+        s
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to serialize the profile")
+    }
+
+    /// Folded stacks (`main;f;file:line constraints`), as read by
+    /// flamegraph tools
+    pub fn to_folded(&self) -> String {
+        let mut s = String::new();
+        for l in &self.lines {
+            if l.constraints == 0 {
+                continue;
+            }
+            for f in &l.stack {
+                write!(s, "{f};").unwrap();
+            }
+            writeln!(s, "{}:{} {}", l.file, l.line, l.constraints).unwrap();
+        }
+        if self.unattributed_constraints != 0 {
+            writeln!(s, "<unattributed> {}", self.unattributed_constraints).unwrap();
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use circ::term;
+    use std::path::PathBuf;
+
+    fn line(n: usize, stack: &[&str]) -> SourceLine {
+        SourceLine {
+            file: PathBuf::from("t.py"),
+            line: n,
+            stack: stack.iter().map(|f| f.to_string()).collect(),
+            text: format!("line {n}"),
+        }
+    }
+
+    fn metric(n_constraints: u32) -> Metric {
+        Metric {
+            n_constraints,
+            n_vars: n_constraints,
+        }
+    }
+
+    #[test]
+    fn test_attribution() {
+        let var = |n: &str| leaf_term(Op::Var(n.to_owned(), Sort::BitVector(8)));
+        let a = var("a");
+        let b = var("b");
+        let sum = term![BV_ADD; a.clone(), b.clone()];
+        // built by the optimizer, from operands of both lines
+        let prod = term![BV_MUL; sum.clone(), a.clone()];
+        let unknown = var("c");
+
+        let mut spans = SourceMap::default();
+        spans.record(&a, line(1, &["main"]));
+        spans.record(&b, line(1, &["main"]));
+        spans.record(&sum, line(2, &["main", "f"]));
+
+        let mut metrics: TermMap<Metric> = Default::default();
+        metrics.insert(a, metric(1));
+        metrics.insert(sum, metric(2));
+        metrics.insert(prod, metric(4));
+        metrics.insert(unknown, metric(8));
+
+        let p = LineProfile::new(&metrics, &spans);
+        let costs: Vec<(usize, u32)> = p.lines.iter().map(|l| (l.line, l.constraints)).collect();
+        assert_eq!(costs, vec![(2, 6), (1, 1)]);
+        assert_eq!(p.unattributed_constraints, 8);
+        assert_eq!(p.functions[0].function, "f");
+        assert_eq!(p.to_folded(), "main;f;t.py:2 6\nmain;t.py:1 1\n<unattributed> 8\n");
+    }
+}
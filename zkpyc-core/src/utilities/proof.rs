@@ -24,6 +24,10 @@ pub fn deserialize_from_file<D: for<'a> Deserialize<'a>, P: AsRef<Path>>(
     Ok(deserialize_from(File::open(path.as_ref())?).unwrap())
 }
 
+fn invalid_data(e: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+}
+
 pub fn value_map_from_path<P: AsRef<Path>>(path: P) -> std::io::Result<HashMap<String, Value>> {
     Ok(parse_value_map(&std::fs::read(path)?))
 }
@@ -38,9 +42,12 @@ pub trait ProofSystem {
     type Proof: Serialize + for<'a> Deserialize<'a>;
 
     /// Setup
-    fn setup(p_data: ProverData, v_data: VerifierData) -> (Self::ProvingKey, Self::VerifyingKey);
+    fn setup(
+        p_data: ProverData,
+        v_data: VerifierData,
+    ) -> Result<(Self::ProvingKey, Self::VerifyingKey), String>;
     /// Proving
-    fn prove(pk: &Self::ProvingKey, witness: &HashMap<String, Value>) -> Self::Proof;
+    fn prove(pk: &Self::ProvingKey, witness: &HashMap<String, Value>) -> Result<Self::Proof, String>;
     /// Verification
     fn verify(vk: &Self::VerifyingKey, inst: &HashMap<String, Value>, pf: &Self::Proof) -> bool;
 
@@ -51,7 +58,7 @@ pub trait ProofSystem {
         pk_path: impl AsRef<Path>,
         vk_path: impl AsRef<Path>,
     ) -> std::io::Result<()> {
-        let (pk, vk) = Self::setup(p_data, v_data);
+        let (pk, vk) = Self::setup(p_data, v_data).map_err(invalid_data)?;
         serialize_into_file(&pk, pk_path)?;
         serialize_into_file(&vk, vk_path)?;
         Ok(())
@@ -64,7 +71,7 @@ pub trait ProofSystem {
     ) -> std::io::Result<()> {
         let pk: Self::ProvingKey = deserialize_from_file(pk_path)?;
         let witness = value_map_from_path(witness_path)?;
-        let pf = Self::prove(&pk, &witness);
+        let pf = Self::prove(&pk, &witness).map_err(invalid_data)?;
         serialize_into_file(&pf, pf_path)
     }
     /// Verify from files
@@ -184,22 +191,18 @@ impl<P: CommitProofSystem> ProofSystem for P {
     type ProvingKey = <P as CommitProofSystem>::ProvingKey;
     type Proof = <P as CommitProofSystem>::Proof;
 
-    fn setup(p_data: ProverData, v_data: VerifierData) -> (Self::ProvingKey, Self::VerifyingKey) {
-        assert_eq!(
-            0,
-            p_data.num_commitments(),
-            "This predicate has commitments---use a CP proof system"
-        );
-        assert_eq!(
-            0,
-            v_data.num_commitments(),
-            "This predicate has commitments---use a CP proof system"
-        );
-        Self::cp_setup(p_data, v_data)
+    fn setup(
+        p_data: ProverData,
+        v_data: VerifierData,
+    ) -> Result<(Self::ProvingKey, Self::VerifyingKey), String> {
+        if p_data.num_commitments() != 0 || v_data.num_commitments() != 0 {
+            return Err("This predicate has commitments---use a CP proof system".into());
+        }
+        Ok(Self::cp_setup(p_data, v_data))
     }
 
-    fn prove(pk: &Self::ProvingKey, witness: &HashMap<String, Value>) -> Self::Proof {
-        Self::cp_prove(pk, witness, &[])
+    fn prove(pk: &Self::ProvingKey, witness: &HashMap<String, Value>) -> Result<Self::Proof, String> {
+        Ok(Self::cp_prove(pk, witness, &[]))
     }
 
     fn verify(vk: &Self::VerifyingKey, inst: &HashMap<String, Value>, pf: &Self::Proof) -> bool {
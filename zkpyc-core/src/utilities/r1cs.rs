@@ -419,7 +419,7 @@ impl R1csFinal {
     }
 
     /// Get a nice string represenation of the combination `a`.
-    fn format_lc(&self, a: &Lc) -> String {
+    pub fn format_lc(&self, a: &Lc) -> String {
         let mut s = String::new();
 
         let half_m: Integer = self.field.modulus().clone() / 2;
@@ -443,7 +443,18 @@ impl R1csFinal {
         s
     }
 
-    fn eval(&self, lc: &Lc, values: &HashMap<Var, FieldV>) -> FieldV {
+    /// Get a nice string represenation of the tuple.
+    pub fn format_qeq(&self, (a, b, c): &(Lc, Lc, Lc)) -> String {
+        format!(
+            "({})({}) = {}",
+            self.format_lc(a),
+            self.format_lc(b),
+            self.format_lc(c)
+        )
+    }
+
+    /// Evaluate the combination `lc`.
+    pub fn eval(&self, lc: &Lc, values: &HashMap<Var, FieldV>) -> FieldV {
         let mut acc = lc.constant.clone();
         for (var, coeff) in &lc.monomials {
             let val = values
@@ -461,11 +472,25 @@ impl R1csFinal {
             self.check(a, b, c, values)
         }
     }
+
+    /// The index of the first constraint `values` do not satisfy
+    pub fn first_unsatisfied(&self, values: &HashMap<Var, FieldV>) -> Option<usize> {
+        self.constraints.iter().position(|(a, b, c)| {
+            self.eval(a, values) * &self.eval(b, values) != self.eval(c, values)
+        })
+    }
 }
 
 impl ProverData {
     /// Check all assertions. Puts in 1 for challenges.
     pub fn check_all(&self, values: &HashMap<String, Value>) {
+        let var_values = self.eval_vars(values);
+        self.r1cs.check_all(&var_values);
+    }
+
+    /// Compute the values of all R1CS variables from the inputs `values`.
+    /// Puts in 1 for challenges.
+    pub fn eval_vars(&self, values: &HashMap<String, Value>) -> HashMap<Var, FieldV> {
         // we need to evaluate all R1CS variables
         let mut var_values: HashMap<Var, FieldV> = Default::default();
         let mut eval = StagedWitCompEvaluator::new(&self.precompute);
@@ -504,7 +529,7 @@ impl ProverData {
                 }
             }
         }
-        self.r1cs.check_all(&var_values);
+        var_values
     }
 
     /// How many commitments?
@@ -1033,6 +1058,17 @@ impl VerifierData {
     pub fn num_commitments(&self) -> usize {
         self.num_commitments
     }
+
+    /// The names of the public inputs of the proof system, in the order
+    /// [VerifierData::eval] computes them.
+    pub fn public_input_names(&self) -> Vec<&str> {
+        self.r1cs
+            .vars
+            .iter()
+            .filter(|v| matches!(v.ty(), VarType::Inst))
+            .map(|v| self.r1cs.names[v].as_str())
+            .collect()
+    }
 }
 
 /// Relation-related data that a prover needs to make a proof.
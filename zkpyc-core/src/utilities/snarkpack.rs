@@ -0,0 +1,216 @@
+//! SnarkPack aggregation of [Bellman](super::bellman::Bellman) proofs over BLS12-381.
+//!
+//! Many Groth16 proofs for the same verifying key are compressed into one
+//! proof whose size and verification time are logarithmic in their number.
+//! The inner pairing product arguments are those of [bellperson]; keys and
+//! proofs are carried over to its BLS12-381 types through their common
+//! serialization.
+//!
+//! Aggregation needs a structured reference string with two sets of powers,
+//! [AggregationSrs]. Whoever knows its trapdoors can forge aggregates, so it
+//! must come from a trusted party or ceremony, like the Groth16 keys
+//! themselves.
+
+use bellperson::groth16::{self as bp, aggregate};
+use blstrs::{Bls12 as BpBls12, Scalar as BpScalar};
+use fxhash::FxHashMap;
+use rug::{integer::Order, Integer};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::bellman::{Bls12, Proof, VerifyingKey};
+use super::proof::{deserialize_from_file, serialize_into_file, value_map_from_path};
+use circ::ir::term::Value;
+
+/// Binds aggregates to this application
+const TRANSCRIPT_INCLUDE: &[u8] = b"zkpyc-snarkpack";
+
+/// Binds an aggregate to the number of proofs in it as well, which padding
+/// would otherwise hide
+fn transcript_include(n: usize) -> Vec<u8> {
+    let mut include = TRANSCRIPT_INCLUDE.to_vec();
+    include.extend_from_slice(&(n as u64).to_le_bytes());
+    include
+}
+
+const VERSION: aggregate::AggregateVersion = aggregate::AggregateVersion::V2;
+
+mod serde_srs {
+    use bellperson::groth16::aggregate::GenericSRS;
+    use blstrs::Bls12;
+    use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(p: &GenericSRS<Bls12>, ser: S) -> Result<S::Ok, S::Error> {
+        let mut bs: Vec<u8> = Vec::new();
+        p.write(&mut bs).map_err(ser::Error::custom)?;
+        serde_bytes::ByteBuf::from(bs).serialize(ser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<GenericSRS<Bls12>, D::Error> {
+        let bs: serde_bytes::ByteBuf = Deserialize::deserialize(de)?;
+        GenericSRS::read(&mut &bs[..]).map_err(de::Error::custom)
+    }
+}
+
+mod serde_agg {
+    use bellperson::groth16::aggregate::AggregateProof;
+    use blstrs::Bls12;
+    use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(p: &AggregateProof<Bls12>, ser: S) -> Result<S::Ok, S::Error> {
+        let mut bs: Vec<u8> = Vec::new();
+        p.write(&mut bs).map_err(ser::Error::custom)?;
+        serde_bytes::ByteBuf::from(bs).serialize(ser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        de: D,
+    ) -> Result<AggregateProof<Bls12>, D::Error> {
+        let bs: serde_bytes::ByteBuf = Deserialize::deserialize(de)?;
+        AggregateProof::read(&mut &bs[..]).map_err(de::Error::custom)
+    }
+}
+
+/// The structured reference string for aggregating proofs
+#[derive(Serialize, Deserialize)]
+pub struct AggregationSrs(#[serde(with = "serde_srs")] aggregate::GenericSRS<BpBls12>);
+
+impl AggregationSrs {
+    /// Sample an SRS for aggregating up to `2^k` proofs
+    pub fn generate(k: u32) -> Self {
+        // specializing to n proofs takes 2n powers
+        AggregationSrs(aggregate::setup_fake_srs(&mut rand::thread_rng(), 2 << k))
+    }
+
+    /// Sample an SRS and write it to a file
+    pub fn generate_fs(k: u32, path: impl AsRef<Path>) -> std::io::Result<()> {
+        serialize_into_file(&Self::generate(k), path)
+    }
+}
+
+/// An aggregate of many [Proof]s
+#[derive(Serialize, Deserialize)]
+pub struct AggregateProof(#[serde(with = "serde_agg")] aggregate::AggregateProof<BpBls12>);
+
+fn to_bellperson_proof(pf: &Proof<Bls12>) -> Result<bp::Proof<BpBls12>, String> {
+    let mut bs: Vec<u8> = Vec::new();
+    pf.groth16().write(&mut bs).map_err(|e| e.to_string())?;
+    bp::Proof::read(&mut &bs[..]).map_err(|e| format!("Invalid proof: {e}"))
+}
+
+fn to_bellperson_vk(vk: &VerifyingKey<Bls12>) -> Result<bp::VerifyingKey<BpBls12>, String> {
+    let mut bs: Vec<u8> = Vec::new();
+    vk.groth16().write(&mut bs).map_err(|e| e.to_string())?;
+    bp::VerifyingKey::read(&mut &bs[..]).map_err(|e| format!("Invalid verifying key: {e}"))
+}
+
+fn to_bellperson_fr(i: &Integer) -> Result<BpScalar, String> {
+    let not_scalar = || format!("{i} is not a BLS12-381 scalar");
+    let mut bytes = [0u8; 32];
+    let digits = i.to_digits::<u8>(Order::Lsf);
+    if digits.len() > 32 {
+        return Err(not_scalar());
+    }
+    bytes[..digits.len()].copy_from_slice(&digits);
+    Option::from(BpScalar::from_bytes_le(&bytes)).ok_or_else(not_scalar)
+}
+
+/// Aggregation takes a power of two (and at least two) proofs, so we repeat
+/// the last one. The verifier repeats the last instance to match, and the
+/// transcript binds the original number.
+fn pad<T: Clone>(mut items: Vec<T>) -> Vec<T> {
+    let n = items.len().max(2).next_power_of_two();
+    let last = items.last().cloned().expect("nothing to aggregate");
+    items.resize(n, last);
+    items
+}
+
+/// Aggregate proofs for the same verifying key
+pub fn aggregate(srs: &AggregationSrs, pfs: &[Proof<Bls12>]) -> Result<AggregateProof, String> {
+    if pfs.is_empty() {
+        return Err("There are no proofs to aggregate".to_string());
+    }
+    let include = transcript_include(pfs.len());
+    let pfs = pad(pfs.iter().map(to_bellperson_proof).collect::<Result<_, _>>()?);
+    let (prover_srs, _) = srs.0.specialize(pfs.len());
+    aggregate::aggregate_proofs(&prover_srs, &include, &pfs, VERSION)
+        .map(AggregateProof)
+        .map_err(|e| format!("Aggregation failed: {e}"))
+}
+
+/// Check an aggregate of the proofs for the instances `insts`, in order
+pub fn verify(
+    srs: &AggregationSrs,
+    vk: &VerifyingKey<Bls12>,
+    insts: &[FxHashMap<String, Value>],
+    pf: &AggregateProof,
+) -> bool {
+    try_verify(srs, vk, insts, pf).unwrap_or(false)
+}
+
+fn try_verify(
+    srs: &AggregationSrs,
+    vk: &VerifyingKey<Bls12>,
+    insts: &[FxHashMap<String, Value>],
+    pf: &AggregateProof,
+) -> Result<bool, String> {
+    if insts.is_empty() {
+        return Ok(false);
+    }
+    let inputs = insts
+        .iter()
+        .map(|inst| {
+            vk.data()
+                .eval(inst)
+                .into_iter()
+                .map(|i| to_bellperson_fr(&i.i()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let include = transcript_include(inputs.len());
+    let inputs = pad(inputs);
+    let (_, verifier_srs) = srs.0.specialize(inputs.len());
+    let pvk = bp::prepare_verifying_key(&to_bellperson_vk(vk)?);
+    Ok(aggregate::verify_aggregate_proof(
+        &verifier_srs,
+        &pvk,
+        rand::rngs::OsRng,
+        &inputs,
+        &pf.0,
+        &include,
+        VERSION,
+    )
+    .unwrap_or(false))
+}
+
+/// Aggregate proofs from files
+pub fn aggregate_fs(
+    srs_path: impl AsRef<Path>,
+    pf_paths: &[impl AsRef<Path>],
+    agg_path: impl AsRef<Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let srs: AggregationSrs = deserialize_from_file(srs_path)?;
+    let pfs = pf_paths
+        .iter()
+        .map(deserialize_from_file)
+        .collect::<std::io::Result<Vec<Proof<Bls12>>>>()?;
+    serialize_into_file(&aggregate(&srs, &pfs)?, agg_path)?;
+    Ok(())
+}
+
+/// Verify an aggregate from files: the instance of each proof, in order
+pub fn verify_fs(
+    srs_path: impl AsRef<Path>,
+    vk_path: impl AsRef<Path>,
+    instance_paths: &[impl AsRef<Path>],
+    agg_path: impl AsRef<Path>,
+) -> std::io::Result<bool> {
+    let srs: AggregationSrs = deserialize_from_file(srs_path)?;
+    let vk: VerifyingKey<Bls12> = deserialize_from_file(vk_path)?;
+    let insts = instance_paths
+        .iter()
+        .map(value_map_from_path)
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let pf: AggregateProof = deserialize_from_file(agg_path)?;
+    Ok(verify(&srs, &vk, &insts, &pf))
+}
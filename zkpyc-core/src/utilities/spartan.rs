@@ -0,0 +1,362 @@
+//! A transparent Spartan back-end over the ristretto255 scalar field.
+//!
+//! Setup needs no trusted randomness: the generators are derived from the
+//! size of the relation, and the verifying key is a commitment to its
+//! matrices, so that verification is sublinear in the number of constraints.
+
+use ::spartan::{
+    ComputationCommitment, ComputationDecommitment, Instance, InputsAssignment, SNARKGens,
+    VarsAssignment, SNARK,
+};
+use fxhash::FxHashMap as HashMap;
+use log::debug;
+use merlin::Transcript;
+use rug::{integer::Order, Integer};
+use serde::{Deserialize, Serialize};
+
+use super::proof;
+use super::r1cs::{Lc, ProverData, Var, VarType, VerifierData};
+use circ::ir::term::Value;
+
+/// The order of the ristretto255 scalar field, the only field this back-end supports
+const MODULUS: &str =
+    "7237005577332262213973186563042994240857116359379907606001950938285454250989";
+
+const TRANSCRIPT_LABEL: &[u8] = b"zkpyc-spartan";
+
+fn scalar_bytes(i: &Integer) -> Result<[u8; 32], String> {
+    let mut bytes = [0u8; 32];
+    let digits = i.to_digits::<u8>(Order::Lsf);
+    if digits.len() > 32 {
+        return Err(format!("{i} is not a ristretto255 scalar"));
+    }
+    bytes[..digits.len()].copy_from_slice(&digits);
+    Ok(bytes)
+}
+
+fn check_modulus(modulus: &Integer) -> Result<(), String> {
+    if modulus != &Integer::from_str_radix(MODULUS, 10).unwrap() {
+        return Err(format!(
+            "Spartan proofs are over the ristretto255 scalar field; compile with `--field-custom-modulus {MODULUS}`"
+        ));
+    }
+    Ok(())
+}
+
+/// The sizes of a relation, from which its Spartan generators are derived
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct Shape {
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    num_nz: usize,
+}
+
+impl Shape {
+    fn gens(&self) -> SNARKGens {
+        SNARKGens::new(self.num_cons, self.num_vars, self.num_inputs, self.num_nz)
+    }
+}
+
+type Entries = Vec<(usize, usize, [u8; 32])>;
+
+/// An R1CS in Spartan's layout: the witness in columns `0..num_vars`, the
+/// constant one in column `num_vars`, and the public inputs after it.
+struct Relation {
+    num_vars: usize,
+    num_inputs: usize,
+    a: Entries,
+    b: Entries,
+    c: Entries,
+    num_cons: usize,
+}
+
+impl Relation {
+    /// Lay out constraints `a * b = c` whose linear combinations are lists
+    /// of (column, coefficient).
+    fn new(
+        num_vars: usize,
+        num_inputs: usize,
+        constraints: impl IntoIterator<Item = [Vec<(usize, Integer)>; 3]>,
+    ) -> Result<Self, String> {
+        let mut rel = Relation {
+            num_vars,
+            num_inputs,
+            a: Vec::new(),
+            b: Vec::new(),
+            c: Vec::new(),
+            num_cons: 0,
+        };
+        for (row, lcs) in constraints.into_iter().enumerate() {
+            for (entries, mut lc) in [&mut rel.a, &mut rel.b, &mut rel.c].into_iter().zip(lcs) {
+                // the commitment depends on the order of the entries
+                lc.sort_by_key(|(col, _)| *col);
+                for (col, coeff) in lc.into_iter().filter(|(_, coeff)| *coeff != 0) {
+                    entries.push((row, col, scalar_bytes(&coeff)?));
+                }
+            }
+            rel.num_cons = row + 1;
+        }
+        // the generators of an empty relation are undefined
+        if rel.num_cons == 0 {
+            return Err("Spartan needs a relation with at least one constraint".to_string());
+        }
+        Ok(rel)
+    }
+
+    fn shape(&self) -> Shape {
+        Shape {
+            num_cons: self.num_cons,
+            num_vars: self.num_vars,
+            num_inputs: self.num_inputs,
+            num_nz: self.a.len().max(self.b.len()).max(self.c.len()),
+        }
+    }
+
+    fn instance(&self) -> Result<Instance, String> {
+        Instance::new(
+            self.num_cons,
+            self.num_vars,
+            self.num_inputs,
+            &self.a,
+            &self.b,
+            &self.c,
+        )
+        .map_err(|e| format!("Invalid Spartan instance: {e:?}"))
+    }
+
+    /// Commit to the matrices
+    fn encode(&self) -> Result<(Shape, ComputationCommitment, ComputationDecommitment), String> {
+        let shape = self.shape();
+        debug!("Spartan: {shape:?}");
+        let (comm, decomm) = SNARK::encode(&self.instance()?, &shape.gens());
+        Ok((shape, comm, decomm))
+    }
+
+    fn prove(&self, vars: Vec<[u8; 32]>, inputs: Vec<[u8; 32]>) -> Result<SNARK, String> {
+        let inst = self.instance()?;
+        let (shape, comm, decomm) = self.encode()?;
+        let vars = VarsAssignment::new(&vars).map_err(|e| format!("Invalid Spartan witness: {e:?}"))?;
+        let inputs = InputsAssignment::new(&inputs).map_err(|e| format!("Invalid Spartan inputs: {e:?}"))?;
+        Ok(SNARK::prove(
+            &inst,
+            &comm,
+            &decomm,
+            vars,
+            &inputs,
+            &shape.gens(),
+            &mut Transcript::new(TRANSCRIPT_LABEL),
+        ))
+    }
+}
+
+fn verify(shape: &Shape, comm: &ComputationCommitment, inputs: Vec<[u8; 32]>, pf: &SNARK) -> bool {
+    let inputs = match InputsAssignment::new(&inputs) {
+        Ok(inputs) => inputs,
+        Err(_) => return false,
+    };
+    pf.verify(comm, &inputs, &mut Transcript::new(TRANSCRIPT_LABEL), &shape.gens())
+        .is_ok()
+}
+
+/// The relation of `pd`, and the column of each variable
+fn relation(pd: &ProverData) -> Result<(Relation, HashMap<Var, usize>), String> {
+    let r1cs = &pd.r1cs;
+    check_modulus(r1cs.field.modulus())?;
+    if r1cs.vars.iter().any(|v| matches!(v.ty(), VarType::Chall | VarType::CWit)) {
+        return Err("Spartan does not support challenges or committed witnesses".to_string());
+    }
+    let (inputs, wits): (Vec<Var>, Vec<Var>) =
+        r1cs.vars.iter().partition(|v| matches!(v.ty(), VarType::Inst));
+    let cols: HashMap<Var, usize> = wits
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (*v, i))
+        .chain(inputs.iter().enumerate().map(|(i, v)| (*v, wits.len() + 1 + i)))
+        .collect();
+    let lc = |lc: &Lc| -> Vec<(usize, Integer)> {
+        lc.monomials
+            .iter()
+            .map(|(v, c)| (cols[v], c.i()))
+            .chain(std::iter::once((wits.len(), lc.constant.i())))
+            .collect()
+    };
+    let rel = Relation::new(
+        wits.len(),
+        inputs.len(),
+        r1cs.constraints.iter().map(|(a, b, c)| [lc(a), lc(b), lc(c)]),
+    )?;
+    Ok((rel, cols))
+}
+
+/// The Spartan proof system
+pub struct Spartan;
+
+/// The pk for [Spartan]
+#[derive(Serialize, Deserialize)]
+pub struct ProvingKey {
+    data: ProverData,
+}
+
+impl ProvingKey {
+    /// The relation and witness computation
+    pub fn into_data(self) -> ProverData {
+        self.data
+    }
+}
+
+/// The vk for [Spartan]
+#[derive(Serialize, Deserialize)]
+pub struct VerifyingKey {
+    data: VerifierData,
+    shape: Shape,
+    comm: ComputationCommitment,
+}
+
+/// The proof for [Spartan]
+#[derive(Serialize, Deserialize)]
+pub struct Proof(SNARK);
+
+impl proof::ProofSystem for Spartan {
+    type VerifyingKey = VerifyingKey;
+
+    type ProvingKey = ProvingKey;
+
+    type Proof = Proof;
+
+    fn setup(p_data: ProverData, v_data: VerifierData) -> Result<(ProvingKey, VerifyingKey), String> {
+        let (rel, _) = relation(&p_data)?;
+        let (shape, comm, _) = rel.encode()?;
+        Ok((
+            ProvingKey { data: p_data },
+            VerifyingKey {
+                data: v_data,
+                shape,
+                comm,
+            },
+        ))
+    }
+
+    fn prove(pk: &ProvingKey, witness: &HashMap<String, Value>) -> Result<Proof, String> {
+        let values = pk.data.eval_vars(witness);
+        if let Some(c) = pk.data.r1cs.first_unsatisfied(&values) {
+            return Err(format!("The witness does not satisfy constraint {c}"));
+        }
+        let (rel, cols) = relation(&pk.data)?;
+        let mut vars = vec![[0u8; 32]; rel.num_vars];
+        let mut inputs = vec![[0u8; 32]; rel.num_inputs];
+        for (v, col) in cols {
+            let bytes = scalar_bytes(&values[&v].i())?;
+            match col.checked_sub(rel.num_vars + 1) {
+                Some(i) => inputs[i] = bytes,
+                None => vars[col] = bytes,
+            }
+        }
+        Ok(Proof(rel.prove(vars, inputs)?))
+    }
+
+    fn verify(vk: &VerifyingKey, inst: &HashMap<String, Value>, pf: &Proof) -> bool {
+        let inputs: Result<Vec<_>, _> = vk.data.eval(inst).iter().map(|v| scalar_bytes(&v.i())).collect();
+        match inputs {
+            Ok(inputs) => verify(&vk.shape, &vk.comm, inputs, &pf.0),
+            Err(_) => false,
+        }
+    }
+}
+
+/// [Spartan] on zkInterface messages, as used by the Python bindings
+pub mod zkif_backend {
+    use super::*;
+    use crate::utilities::proof::{deserialize_from_file, serialize_into_file};
+    use std::error::Error;
+    use std::path::Path;
+    use zkinterface::consumers::reader::Term;
+    use zkinterface::Reader;
+
+    /// The key of a zkInterface circuit
+    #[derive(Serialize, Deserialize)]
+    struct Key {
+        shape: Shape,
+        comm: ComputationCommitment,
+    }
+
+    fn le_bytes_to_int(encoded: &[u8]) -> Integer {
+        Integer::from_digits(encoded, Order::Lsf)
+    }
+
+    /// The relation of the constraints in `reader`, with its witness and
+    /// public inputs (all zero if the messages carry no witness).
+    #[allow(clippy::type_complexity)]
+    fn relation(reader: &Reader) -> Result<(Relation, Vec<[u8; 32]>, Vec<[u8; 32]>), Box<dyn Error>> {
+        let header = reader.first_header().ok_or("Missing circuit header")?;
+        if let Some(max) = header.field_maximum() {
+            check_modulus(&(le_bytes_to_int(max) + 1u32))?;
+        }
+        let instance = reader.instance_variables().ok_or("Missing circuit header")?;
+        let private = reader.private_variables().ok_or("Missing circuit header")?;
+        let mut cols: HashMap<u64, usize> = HashMap::default();
+        cols.insert(0, private.len());
+        for (i, var) in private.iter().enumerate() {
+            cols.insert(var.id, i);
+        }
+        for (i, var) in instance.iter().enumerate() {
+            cols.insert(var.id, private.len() + 1 + i);
+        }
+        let lc = |terms: &[Term]| -> Result<Vec<(usize, Integer)>, Box<dyn Error>> {
+            terms
+                .iter()
+                .map(|t| {
+                    let col = *cols.get(&t.id).ok_or_else(|| format!("Unknown variable {}", t.id))?;
+                    Ok((col, le_bytes_to_int(t.value)))
+                })
+                .collect()
+        };
+        let constraints = reader
+            .iter_constraints()
+            .map(|c| Ok([lc(&c.a)?, lc(&c.b)?, lc(&c.c)?]))
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        let values = |vars: &[zkinterface::consumers::reader::Variable]| -> Result<Vec<[u8; 32]>, String> {
+            vars.iter().map(|v| scalar_bytes(&le_bytes_to_int(v.value))).collect()
+        };
+        Ok((
+            Relation::new(private.len(), instance.len(), constraints)?,
+            values(&private)?,
+            values(&instance)?,
+        ))
+    }
+
+    pub fn setup(reader: &Reader, workspace: &Path, key_name: &str) -> Result<(), Box<dyn Error>> {
+        let (rel, _, _) = relation(reader)?;
+        let (shape, comm, _) = rel.encode()?;
+        serialize_into_file(&Key { shape, comm }, workspace.join(key_name))?;
+        Ok(())
+    }
+
+    pub fn prove(
+        reader: &Reader,
+        workspace: &Path,
+        _key_name: &str,
+        proof_name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let (rel, vars, inputs) = relation(reader)?;
+        serialize_into_file(&Proof(rel.prove(vars, inputs)?), workspace.join(proof_name))?;
+        Ok(())
+    }
+
+    pub fn verify(
+        reader: &Reader,
+        workspace: &Path,
+        key_name: &str,
+        proof_name: &str,
+    ) -> Result<bool, Box<dyn Error>> {
+        let key: Key = deserialize_from_file(workspace.join(key_name))?;
+        let pf: Proof = deserialize_from_file(workspace.join(proof_name))?;
+        let inputs = reader
+            .instance_variables()
+            .ok_or("Missing circuit header")?
+            .iter()
+            .map(|v| scalar_bytes(&le_bytes_to_int(v.value)))
+            .collect::<Result<_, _>>()?;
+        Ok(super::verify(&key.shape, &key.comm, inputs, &pf.0))
+    }
+}
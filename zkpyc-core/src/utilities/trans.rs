@@ -40,10 +40,11 @@ enum EmbeddedTerm {
     Tuple(Vec<EmbeddedTerm>),
 }
 
-#[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct Metric {
-    n_constraints: u32,
-    n_vars: u32,
+/// Constraints and variables created while lowering a term
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Metric {
+    pub n_constraints: u32,
+    pub n_vars: u32,
 }
 
 impl std::ops::AddAssign<&Metric> for Metric {
@@ -66,6 +67,7 @@ struct ToR1cs<'cfg> {
     profiling_data: TermMap<Metric>,
     metric: Metric,
     term_in_progress: Option<Term>,
+    profile: bool,
 }
 
 impl<'cfg> ToR1cs<'cfg> {
@@ -88,11 +90,12 @@ impl<'cfg> ToR1cs<'cfg> {
             profiling_data: Default::default(),
             term_in_progress: None,
             metric: Default::default(),
+            profile: cfg.r1cs.profile,
         }
     }
 
     fn profile_start_term(&mut self, t: Term) {
-        if self.cfg.r1cs.profile {
+        if self.profile {
             assert!(self.term_in_progress.is_none());
             self.term_in_progress = Some(t);
             self.metric = Default::default();
@@ -100,7 +103,7 @@ impl<'cfg> ToR1cs<'cfg> {
     }
 
     fn profile_end_term(&mut self) {
-        if self.cfg.r1cs.profile {
+        if self.profile {
             assert!(self.term_in_progress.is_some());
             let t = self.term_in_progress.take().unwrap();
             *self.profiling_data.entry(t).or_default() += &self.metric;
@@ -1122,10 +1125,21 @@ impl<'cfg> ToR1cs<'cfg> {
 /// * Prover data (including the R1CS instance)
 /// * Verifier data
 pub fn to_r1cs(cs: &Computation, cfg: &CircCfg) -> R1cs {
+    to_r1cs_impl(cs, cfg, false).0
+}
+
+/// Like [to_r1cs], also returning the constraints and variables created
+/// while lowering each term.
+pub fn to_r1cs_with_profile(cs: &Computation, cfg: &CircCfg) -> (R1cs, TermMap<Metric>) {
+    to_r1cs_impl(cs, cfg, true)
+}
+
+fn to_r1cs_impl(cs: &Computation, cfg: &CircCfg, profile: bool) -> (R1cs, TermMap<Metric>) {
     let public_inputs = cs.metadata.public_input_names_set();
     debug!("public inputs: {:?}", public_inputs);
     let used_vars = extras::free_variables(term(Op::Tuple, cs.outputs.clone()));
     let mut converter = ToR1cs::new(cfg, cs.precomputes.clone(), used_vars);
+    converter.profile |= profile;
     debug!(
         "Term count: {}",
         cs.outputs
@@ -1163,5 +1177,5 @@ pub fn to_r1cs(cs: &Computation, cfg: &CircCfg) -> R1cs {
         converter.assert(c.clone());
     }
     converter.profile_print();
-    converter.r1cs
+    (converter.r1cs, converter.profiling_data)
 }
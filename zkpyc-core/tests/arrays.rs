@@ -0,0 +1,30 @@
+#![cfg(feature = "r1cs")]
+
+mod common;
+
+use common::example;
+use std::collections::HashMap;
+use std::process::Command;
+
+#[test]
+fn test_count_arrays() {
+    let out = Command::new(env!("CARGO_BIN_EXE_zkpyc"))
+        .arg(example("ram_lookup.py"))
+        .args(["r1cs", "--action", "count-arrays"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    // one `<strategy>: <n> constraints` line per strategy
+    let counts: HashMap<&str, usize> = stdout
+        .lines()
+        .filter_map(|l| {
+            let (strategy, n) = l.split_once(": ")?;
+            Some((strategy, n.strip_suffix(" constraints")?.parse().ok()?))
+        })
+        .collect();
+    assert_eq!(counts.len(), 3, "{stdout}");
+    // every array of the example is annotated as RAM
+    assert_eq!(counts["annotated"], counts["ram"], "{stdout}");
+    assert_ne!(counts["annotated"], counts["linear-scan"], "{stdout}");
+}
@@ -0,0 +1,20 @@
+#![cfg(feature = "bulletproofs")]
+
+mod common;
+
+use common::{compile, init, values};
+use zkpyc_core::front::Mode;
+use zkpyc_core::utilities::bulletproofs::Bulletproofs;
+use zkpyc_core::utilities::proof::ProofSystem;
+
+const RISTRETTO255: &str = "7237005577332262213973186563042994240857116359379907606001950938285454250989";
+
+#[test]
+fn test_prove_and_verify() {
+    init(RISTRETTO255);
+    let (p_data, v_data) = compile("mul.py", "main", Mode::Proof);
+    let (pk, vk) = Bulletproofs::setup(p_data, v_data).unwrap();
+    let pf = Bulletproofs::prove(&pk, &values("(x #f4) (y #f5)")).unwrap();
+    assert!(Bulletproofs::verify(&vk, &values("(return #f20)"), &pf));
+    assert!(!Bulletproofs::verify(&vk, &values("(return #f21)"), &pf));
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "bellman")]
+
+mod common;
+
+use bls12_381::{G1Affine, G2Affine, Scalar};
+use common::{compile, init, values};
+use rug::{integer::Order, Integer};
+use std::io::Write;
+use zkpyc_core::front::Mode;
+use zkpyc_core::utilities::bellman::{Bellman, Bls12};
+use zkpyc_core::utilities::ceremony::{Ceremony, PowersOfTau};
+use zkpyc_core::utilities::proof::ProofSystem;
+
+const BLS12_381: &str = "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+
+/// The base field of BLS12-381
+const Q: &str = "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab";
+
+/// Coordinates in the `.ptau` encoding: little-endian, in Montgomery form
+fn ptau_coordinates(big_endian: &[u8]) -> Vec<u8> {
+    let q = Integer::from_str_radix(Q, 16).unwrap();
+    big_endian
+        .chunks(48)
+        .flat_map(|c| {
+            let x = (Integer::from_digits(c, Order::Msf) << 384) % &q;
+            let mut bytes = x.to_digits::<u8>(Order::Lsf);
+            bytes.resize(48, 0);
+            bytes
+        })
+        .collect()
+}
+
+fn g1_bytes(ps: impl IntoIterator<Item = G1Affine>) -> Vec<u8> {
+    ps.into_iter().flat_map(|p| ptau_coordinates(&p.to_uncompressed())).collect()
+}
+
+/// G2 coordinates are written with the imaginary part last, unlike in the
+/// uncompressed encoding
+fn g2_bytes(ps: impl IntoIterator<Item = G2Affine>) -> Vec<u8> {
+    ps.into_iter()
+        .flat_map(|p| {
+            let bytes = p.to_uncompressed();
+            ptau_coordinates(&[&bytes[48..96], &bytes[..48], &bytes[144..], &bytes[96..144]].concat())
+        })
+        .collect()
+}
+
+/// A `.ptau` file for `2^power` constraints, from a known tau, alpha and beta
+fn ptau(power: u32) -> tempfile::NamedTempFile {
+    let n = 1usize << power;
+    let (tau, alpha, beta) = (Scalar::from(5u64), Scalar::from(11u64), Scalar::from(13u64));
+    let powers: Vec<Scalar> = std::iter::successors(Some(Scalar::one()), |p| Some(p * tau))
+        .take(2 * n - 1)
+        .collect();
+    let (g1, g2) = (G1Affine::generator(), G2Affine::generator());
+    let mut header = 48u32.to_le_bytes().to_vec();
+    let mut q = Integer::from_str_radix(Q, 16).unwrap().to_digits::<u8>(Order::Lsf);
+    q.resize(48, 0);
+    header.extend(q);
+    header.extend(power.to_le_bytes());
+    let sections = [
+        header,
+        g1_bytes(powers.iter().map(|p| G1Affine::from(g1 * p))),
+        g2_bytes(powers[..n].iter().map(|p| G2Affine::from(g2 * p))),
+        g1_bytes(powers[..n].iter().map(|p| G1Affine::from(g1 * (alpha * p)))),
+        g1_bytes(powers[..n].iter().map(|p| G1Affine::from(g1 * (beta * p)))),
+        g2_bytes([G2Affine::from(g2 * beta)]),
+    ];
+
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(b"ptau").unwrap();
+    file.write_all(&1u32.to_le_bytes()).unwrap();
+    file.write_all(&(sections.len() as u32).to_le_bytes()).unwrap();
+    for (ty, data) in sections.iter().enumerate() {
+        file.write_all(&(ty as u32 + 1).to_le_bytes()).unwrap();
+        file.write_all(&(data.len() as u64).to_le_bytes()).unwrap();
+        file.write_all(data).unwrap();
+    }
+    file
+}
+
+fn ceremony() -> (Ceremony<Bls12>, PowersOfTau<Bls12>) {
+    init(BLS12_381);
+    let (p_data, v_data) = compile("mul.py", "main", Mode::Proof);
+    let pot = PowersOfTau::<Bls12>::read_ptau(ptau(3).path()).unwrap();
+    (Ceremony::init(p_data, v_data, &pot).unwrap(), pot)
+}
+
+fn copy(ceremony: &Ceremony<Bls12>) -> Ceremony<Bls12> {
+    bincode::deserialize(&bincode::serialize(ceremony).unwrap()).unwrap()
+}
+
+#[test]
+fn test_ceremony() {
+    let (mut ceremony, pot) = ceremony();
+    let first = ceremony.contribute(b"first participant");
+    let second = ceremony.contribute(b"second participant");
+    assert_eq!(ceremony.verify(&pot).unwrap(), vec![first, second]);
+    assert_eq!(ceremony.transcript(), second);
+
+    let (pk, vk) = ceremony.keys();
+    let pf = Bellman::<Bls12>::prove(&pk, &values("(x #f4) (y #f5)")).unwrap();
+    assert!(Bellman::<Bls12>::verify(&vk, &values("(return #f20)"), &pf));
+    assert!(!Bellman::<Bls12>::verify(&vk, &values("(return #f21)"), &pf));
+}
+
+#[test]
+fn test_bad_contributions() {
+    let (mut ceremony, pot) = ceremony();
+    ceremony.contribute(b"first participant");
+    let mut fork = copy(&ceremony);
+    let one = bincode::serialize(&ceremony).unwrap();
+    ceremony.contribute(b"second participant");
+    fork.contribute(b"someone else");
+    let two = bincode::serialize(&ceremony).unwrap();
+    let forked = bincode::serialize(&fork).unwrap();
+    // a serialized ceremony ends with its contributions, all of one size
+    let size = two.len() - one.len();
+    let (first, second) = (two.len() - 2 * size, two.len() - size);
+
+    // the second contribution of another ceremony, with our parameters
+    let mut tampered = two.clone();
+    tampered[second..].copy_from_slice(&forked[second..]);
+    let tampered: Ceremony<Bls12> = bincode::deserialize(&tampered).unwrap();
+    assert!(tampered.verify(&pot).is_err());
+
+    let mut swapped = two[..first].to_vec();
+    swapped.extend_from_slice(&two[second..]);
+    swapped.extend_from_slice(&two[first..second]);
+    let swapped: Ceremony<Bls12> = bincode::deserialize(&swapped).unwrap();
+    assert!(swapped.verify(&pot).is_err());
+
+    // and the untouched ceremony still verifies
+    let two: Ceremony<Bls12> = bincode::deserialize(&two).unwrap();
+    assert!(two.verify(&pot).is_ok());
+}
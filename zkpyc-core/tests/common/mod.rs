@@ -0,0 +1,57 @@
+//! Compiling the examples for the back-end tests.
+//!
+//! CirC's field is process-wide, so each test binary covers the back-ends of
+//! a single field and sets it with [init] before compiling anything.
+
+#![allow(dead_code)]
+
+use circ::cfg::cfg;
+use circ::ir::opt::opt;
+use circ::ir::term::text::parse_value_map;
+use circ::ir::term::Value;
+use circ_opt::CircOpt;
+use fxhash::FxHashMap as HashMap;
+use std::path::PathBuf;
+use std::sync::Once;
+use zkpyc_core::front::python::{proof_opts, ArrayStrategy, Inputs, PythonFE, DEFAULT_MAX_INLINE_DEPTH};
+use zkpyc_core::front::{Mode, SourceInput};
+use zkpyc_core::utilities::opt::reduce_linearities;
+use zkpyc_core::utilities::r1cs::{ProverData, VerifierData};
+use zkpyc_core::utilities::trans::to_r1cs;
+
+/// Use the prime field of order `modulus`
+pub fn init(modulus: &str) {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let mut options = CircOpt::default();
+        options.field.custom_modulus = modulus.to_owned();
+        circ::cfg::set(&options);
+    });
+}
+
+/// The path of `examples/name`
+pub fn example(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../examples").join(name)
+}
+
+/// A value map from `bindings`, as written in `.pin` and `.vin` files
+pub fn values(bindings: &str) -> HashMap<String, Value> {
+    let text = format!("(set_default_modulus {} (let ({bindings}) false))", cfg().field().modulus());
+    parse_value_map(text.as_bytes())
+}
+
+/// Compile `examples/name` to R1CS, as `zkpyc ... r1cs` does
+pub fn compile(name: &str, entry_point: &str, mode: Mode) -> (ProverData, VerifierData) {
+    let inputs = Inputs {
+        source: SourceInput::Path(example(name)),
+        entry_point: entry_point.to_owned(),
+        mode,
+        array_strategy: ArrayStrategy::default(),
+        max_inline_depth: DEFAULT_MAX_INLINE_DEPTH,
+    };
+    let cs = PythonFE::try_gen(inputs).unwrap_or_else(|d| panic!("{d}"));
+    let cs = opt(cs, proof_opts());
+    let cs = cs.get(entry_point);
+    let r1cs = reduce_linearities(to_r1cs(cs, cfg()), cfg());
+    r1cs.finalize(cs)
+}
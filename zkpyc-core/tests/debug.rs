@@ -0,0 +1,96 @@
+#![cfg(feature = "r1cs")]
+
+mod common;
+
+use circ::cfg::cfg;
+use circ::ir::opt::opt;
+use common::{example, init, values};
+use zkpyc_core::front::python::{proof_opts, ArrayStrategy, Inputs, PythonFE, DEFAULT_MAX_INLINE_DEPTH};
+use zkpyc_core::front::{Mode, SourceInput};
+use zkpyc_core::utilities::debug::DebugInfo;
+use zkpyc_core::utilities::opt::reduce_linearities;
+use zkpyc_core::utilities::r1cs::ProverData;
+use zkpyc_core::utilities::trans::to_r1cs;
+
+const BLS12_381: &str = "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+
+/// Compile `examples/name` with the source lines of its signals, as
+/// `zkpyc ... r1cs --debug-info` does
+fn compile_with_debug_info(name: &str) -> (ProverData, DebugInfo) {
+    let inputs = Inputs {
+        source: SourceInput::Path(example(name)),
+        entry_point: "main".to_owned(),
+        mode: Mode::Proof,
+        array_strategy: ArrayStrategy::default(),
+        max_inline_depth: DEFAULT_MAX_INLINE_DEPTH,
+    };
+    let (cs, spans) = PythonFE::try_gen_with_source_map(inputs).unwrap_or_else(|d| panic!("{d}"));
+    let cs = opt(cs, proof_opts());
+    let cs = cs.get("main");
+    let r1cs = reduce_linearities(to_r1cs(cs, cfg()), cfg());
+    let info = DebugInfo::new(&r1cs, &spans);
+    (r1cs.finalize(cs).0, info)
+}
+
+#[test]
+fn test_first_unsatisfied() {
+    init(BLS12_381);
+    let (p_data, info) = compile_with_debug_info("assert.py");
+    assert!(p_data.first_unsatisfied(&values("(x #f4) (y #f3)"), Some(&info)).is_none());
+
+    // x == y breaks `assert(x != y)`
+    let unsatisfied = p_data
+        .first_unsatisfied(&values("(x #f3) (y #f3)"), Some(&info))
+        .expect("the assertion does not hold");
+    assert!(unsatisfied.index < unsatisfied.num_constraints);
+    assert_ne!(unsatisfied.a.clone() * &unsatisfied.b, unsatisfied.c);
+    let line = unsatisfied.source.as_ref().expect("no source line");
+    assert_eq!((line.line, line.function()), (4, Some("main")));
+    assert_eq!(line.text, "assert(x != y)");
+    assert!(unsatisfied.to_string().contains("assert.py:4 in `main`: assert(x != y)"), "{unsatisfied}");
+}
+
+#[cfg(feature = "bellman")]
+#[test]
+fn test_zk_debug() {
+    use std::fs;
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().unwrap();
+    let (pk, vk, info) = (dir.path().join("P"), dir.path().join("V"), dir.path().join("D"));
+    let setup = Command::new(env!("CARGO_BIN_EXE_zkpyc"))
+        .arg(example("assert.py"))
+        .args(["r1cs", "--action", "setup", "--prover-key"])
+        .arg(&pk)
+        .arg("--verifier-key")
+        .arg(&vk)
+        .arg("--debug-info")
+        .arg(&info)
+        .output()
+        .unwrap();
+    assert!(setup.status.success(), "{}", String::from_utf8_lossy(&setup.stderr));
+
+    let debug = |pin: &std::path::Path| {
+        Command::new(env!("CARGO_BIN_EXE_zk"))
+            .args(["--action", "debug", "--prover-key"])
+            .arg(&pk)
+            .arg("--debug-info")
+            .arg(&info)
+            .arg("--inputs")
+            .arg(pin)
+            .output()
+            .unwrap()
+    };
+    let ok = debug(&example("assert.py.pin"));
+    assert!(ok.status.success(), "{}", String::from_utf8_lossy(&ok.stderr));
+    assert!(String::from_utf8_lossy(&ok.stdout).contains("constraints are satisfied"));
+
+    let pin = dir.path().join("pin");
+    let text = fs::read_to_string(example("assert.py.pin")).unwrap().replace("(x #f4)", "(x #f3)");
+    fs::write(&pin, text).unwrap();
+    let bad = debug(&pin);
+    assert_eq!(bad.status.code(), Some(1));
+    let report = String::from_utf8_lossy(&bad.stderr);
+    assert!(report.contains("is not satisfied"), "{report}");
+    assert!(report.contains("assert.py:4 in `main`: assert(x != y)"), "{report}");
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "bellman")]
+
+mod common;
+
+use bls12_381::{G1Affine, G1Projective};
+use common::{compile, example, init};
+use zkpyc_core::front::Mode;
+use zkpyc_core::utilities::bellman::{Bellman, Bls12, Proof};
+use zkpyc_core::utilities::proof::{value_map_from_path, ProofSystem};
+
+const BLS12_381: &str = "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+
+#[test]
+fn test_verify_batch() {
+    init(BLS12_381);
+    let (p_data, v_data) = compile("mm.py", "main", Mode::Proof);
+    let (pk, vk) = Bellman::<Bls12>::setup(p_data, v_data).unwrap();
+    let witness = value_map_from_path(example("mm.py.pin")).unwrap();
+    let inst = value_map_from_path(example("mm.py.vin")).unwrap();
+    let pfs: Vec<Proof<Bls12>> = (0..3).map(|_| Bellman::<Bls12>::prove(&pk, &witness).unwrap()).collect();
+    let insts = vec![inst; 3];
+    assert!(Bellman::<Bls12>::verify_batch(&vk, &insts, &pfs));
+
+    let mut tampered = pfs[1].groth16().clone();
+    tampered.c = (G1Projective::from(tampered.c) + G1Affine::generator()).to_affine();
+    let mut bad = pfs;
+    bad[1] = Proof::new(tampered);
+    assert!(!Bellman::<Bls12>::verify_batch(&vk, &insts, &bad));
+
+    assert!(!Bellman::<Bls12>::verify_batch(&vk, &[], &[]));
+    assert!(!Bellman::<Bls12>::verify_batch(&vk, &insts[..2], &bad[..1]));
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use circ::cfg::cfg;
+use common::{compile, init, values};
+use rug::{integer::Order, Integer};
+use std::fs;
+use zkpyc_core::export::iden3::{write_r1cs, write_wtns};
+use zkpyc_core::front::Mode;
+
+const BN254: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+/// Reads the little-endian fields of a `.r1cs` or `.wtns` file in order
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> &'a [u8] {
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        head
+    }
+
+    fn u32(&mut self) -> usize {
+        u32::from_le_bytes(self.take(4).try_into().unwrap()) as usize
+    }
+
+    fn u64(&mut self) -> usize {
+        u64::from_le_bytes(self.take(8).try_into().unwrap()) as usize
+    }
+
+    fn int(&mut self) -> Integer {
+        Integer::from_digits(self.take(32), Order::Lsf)
+    }
+
+    /// The sections after the header, checking the magic and version
+    fn sections(mut self, magic: &[u8; 4], version: usize) -> Vec<(usize, Reader<'a>)> {
+        assert_eq!(self.take(4), magic);
+        assert_eq!(self.u32(), version);
+        let n = self.u32();
+        let sections = (0..n)
+            .map(|_| {
+                let ty = self.u32();
+                let size = self.u64();
+                (ty, Reader(self.take(size)))
+            })
+            .collect();
+        assert!(self.0.is_empty());
+        sections
+    }
+}
+
+#[test]
+fn test_r1cs_and_wtns_read_back() {
+    init(BN254);
+    let (p_data, _) = compile("mul.py", "main", Mode::Proof);
+    let dir = tempfile::tempdir().unwrap();
+    let (r1cs_path, wtns_path) = (dir.path().join("circuit.r1cs"), dir.path().join("witness.wtns"));
+    write_r1cs(&p_data.r1cs, &r1cs_path).unwrap();
+    write_wtns(&p_data, &values("(x #f4) (y #f5)"), &wtns_path).unwrap();
+    let modulus = cfg().field().modulus().clone();
+
+    let wtns = fs::read(&wtns_path).unwrap();
+    let mut sections = Reader(&wtns).sections(b"wtns", 2);
+    assert_eq!(sections.iter().map(|(ty, _)| *ty).collect::<Vec<_>>(), [1, 2]);
+    let (_, mut header) = sections.remove(0);
+    assert_eq!(header.u32(), 32);
+    assert_eq!(header.int(), modulus);
+    let n_wires = header.u32();
+    let (_, mut contents) = sections.remove(0);
+    let witness: Vec<Integer> = (0..n_wires).map(|_| contents.int()).collect();
+    assert!(contents.0.is_empty());
+    // the constant one, then the public return value x * y
+    assert_eq!(witness[0], 1);
+    assert_eq!(witness[1], 20);
+
+    let r1cs = fs::read(&r1cs_path).unwrap();
+    let mut sections = Reader(&r1cs).sections(b"r1cs", 1);
+    assert_eq!(sections.iter().map(|(ty, _)| *ty).collect::<Vec<_>>(), [1, 2, 3]);
+    let (_, mut header) = sections.remove(0);
+    assert_eq!(header.u32(), 32);
+    assert_eq!(header.int(), modulus);
+    assert_eq!(header.u32(), n_wires);
+    // public outputs, public inputs and private inputs
+    assert_eq!((header.u32(), header.u32(), header.u32()), (0, 1, 0));
+    assert_eq!(header.u64(), n_wires);
+    let n_constraints = header.u32();
+    assert_eq!(n_constraints, p_data.r1cs.constraints.len());
+    assert!(n_constraints > 0);
+
+    // every constraint holds on the witness
+    let (_, mut constraints) = sections.remove(0);
+    let mut lc = || -> Integer {
+        let n = constraints.u32();
+        (0..n).fold(Integer::new(), |acc, _| {
+            let wire = constraints.u32();
+            acc + constraints.int() * &witness[wire]
+        })
+    };
+    for _ in 0..n_constraints {
+        let (a, b, c) = (lc(), lc(), lc());
+        assert_eq!((a * b - c) % &modulus, 0);
+    }
+    assert!(constraints.0.is_empty());
+
+    let (_, mut labels) = sections.remove(0);
+    assert_eq!((0..n_wires).map(|_| labels.u64()).collect::<Vec<_>>(), (0..n_wires).collect::<Vec<_>>());
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "bellman")]
+
+mod common;
+
+use bls12_381::Scalar;
+use circ::cfg::cfg;
+use circ::ir::term::{Array, Sort, Value};
+use common::{compile, init, values};
+use fxhash::FxHashMap;
+use rug::Integer;
+use zkpyc_core::front::Mode;
+use zkpyc_core::utilities::bellman::Bls12;
+use zkpyc_core::utilities::cp_link;
+use zkpyc_core::utilities::mirage::{ComRand, Commitment, Mirage};
+use zkpyc_core::utilities::proof::CommitProofSystem;
+
+const BLS12_381: &str =
+    "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+
+/// The committed argument `x`, as a field-to-field array
+fn committed_array(xs: &[u64]) -> Value {
+    let field = cfg().field().clone();
+    let vals = xs.iter().map(|x| Value::Field(field.new_v(*x))).collect();
+    Value::Array(Array::from_vec(
+        Sort::Field(field.clone()),
+        Sort::Field(field),
+        vals,
+    ))
+}
+
+/// A commitment made with `ck` outside of zkpyc, imported from its hex
+fn external_commitment(
+    ck: &cp_link::CommitKey<Bls12>,
+    xs: &[u64],
+    rand: u64,
+) -> Commitment<bls12_381::G1Projective> {
+    let data = xs.iter().map(|x| Scalar::from(*x)).collect();
+    let cmt = cp_link::commit::<Bls12>(ck.clone(), data, Scalar::from(rand));
+    Commitment::from_hex(&cp_link::point_to_hex(&cmt)).unwrap()
+}
+
+#[test]
+fn test_external_commitments() {
+    init(BLS12_381);
+    let (p_data, v_data) = compile("committed_sum.py", "main", Mode::Proof);
+    let num_cmts = p_data.num_commitments();
+    assert!(num_cmts > 0);
+    let ck = cp_link::sample_ck::<Bls12, _>(&mut rand::thread_rng(), 3);
+    let (pk, vk) = Mirage::<Bls12>::cp_setup_with_ck(p_data, v_data, ck.clone()).unwrap();
+
+    let xs = [1, 2, 3];
+    let rand = 12345;
+    let mut witness = FxHashMap::default();
+    witness.insert("x".to_owned(), committed_array(&xs));
+    let inst = values("(return #f6)");
+    let rands: Vec<ComRand<Scalar>> = (0..num_cmts)
+        .map(|_| ComRand::from_int(Integer::from(rand)))
+        .collect();
+    let pf = Mirage::<Bls12>::cp_prove(&pk, &witness, &rands);
+
+    // x is only read, so every commitment opens to the same array
+    let cmts: Vec<_> = (0..num_cmts)
+        .map(|_| external_commitment(&ck, &xs, rand))
+        .collect();
+    let ours = Mirage::<Bls12>::cp_commit(&vk, committed_array(&xs), &rands[0]);
+    assert_eq!(ours.to_hex(), cmts[0].to_hex());
+    assert!(Mirage::<Bls12>::cp_verify(&vk, &inst, &pf, &cmts));
+
+    let mut wrong: Vec<_> = (0..num_cmts)
+        .map(|_| external_commitment(&ck, &xs, rand))
+        .collect();
+    wrong[0] = external_commitment(&ck, &[1, 2, 4], rand);
+    assert!(!Mirage::<Bls12>::cp_verify(&vk, &inst, &pf, &wrong));
+}
+
+#[test]
+fn test_commit_key_of_wrong_length() {
+    init(BLS12_381);
+    let (p_data, v_data) = compile("committed_sum.py", "main", Mode::Proof);
+    let ck = cp_link::sample_ck::<Bls12, _>(&mut rand::thread_rng(), 4);
+    let err = Mirage::<Bls12>::cp_setup_with_ck(p_data, v_data, ck).err().unwrap();
+    assert!(err.contains("arrays of length 4"), "{err}");
+}
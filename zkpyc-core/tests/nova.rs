@@ -0,0 +1,31 @@
+#![cfg(feature = "nova")]
+
+mod common;
+
+use common::{compile, example, init};
+use zkpyc_core::front::Mode;
+use zkpyc_core::utilities::nova;
+use zkpyc_core::utilities::proof::value_map_from_path;
+
+const PALLAS: &str = "28948022309329048855892746252171976963363056481941647379679742748393362948097";
+
+#[test]
+fn test_ivc_sum() {
+    init(PALLAS);
+    let (data, _) = compile("ivc_sum.py", "step", Mode::Ivc);
+    let (pk, vk) = nova::setup(data).unwrap();
+    let z0 = value_map_from_path(example("ivc_sum.py.pin")).unwrap();
+    let step = value_map_from_path(example("ivc_sum.py.step")).unwrap();
+    let expected = value_map_from_path(example("ivc_sum.py.vin")).unwrap();
+
+    let (pf, zn) = nova::prove(&pk, &z0, &[step.clone(), step]).unwrap();
+    assert_eq!(pf.num_steps(), 2);
+    for name in vk.layout().return_names() {
+        assert_eq!(zn[&name], expected[&name]);
+    }
+    assert!(nova::verify(&vk, &z0, &zn, &pf).unwrap());
+
+    let mut wrong = zn.clone();
+    wrong.insert("return.1".to_owned(), zn["return.0"].clone());
+    assert!(!nova::verify(&vk, &z0, &wrong, &pf).unwrap());
+}
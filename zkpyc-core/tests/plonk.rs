@@ -0,0 +1,33 @@
+#![cfg(feature = "plonk")]
+
+mod common;
+
+use common::{compile, init, values};
+use zkpyc_core::front::Mode;
+use zkpyc_core::utilities::plonk::{Plonk, Proof, Srs};
+use zkpyc_core::utilities::proof::ProofSystem;
+
+const BN254: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+#[test]
+fn test_prove_and_verify() {
+    init(BN254);
+    let (p_data, v_data) = compile("mul.py", "main", Mode::Proof);
+    let (pk, vk) = Plonk::setup_with_srs(p_data, v_data, &Srs::generate(8)).unwrap();
+    let pf = Plonk::prove(&pk, &values("(x #f4) (y #f5)")).unwrap();
+    assert!(Plonk::verify(&vk, &values("(return #f20)"), &pf));
+    assert!(!Plonk::verify(&vk, &values("(return #f21)"), &pf));
+
+    let mut bytes = bincode::serialize(&pf).unwrap();
+    *bytes.last_mut().unwrap() ^= 1;
+    let tampered: Proof = bincode::deserialize(&bytes).unwrap();
+    assert!(!Plonk::verify(&vk, &values("(return #f20)"), &tampered));
+}
+
+#[test]
+fn test_srs_too_small() {
+    init(BN254);
+    let (p_data, v_data) = compile("mul.py", "main", Mode::Proof);
+    let err = Plonk::setup_with_srs(p_data, v_data, &Srs::generate(1)).err().unwrap();
+    assert!(err.contains("the SRS has degree 2^1"), "{err}");
+}
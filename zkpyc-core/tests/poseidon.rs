@@ -0,0 +1,50 @@
+mod common;
+
+use common::{init, values};
+use std::path::PathBuf;
+use zkpyc_core::front::python::{ArrayStrategy, Inputs, PythonFE, DEFAULT_MAX_INLINE_DEPTH};
+use zkpyc_core::front::{Mode, SourceInput};
+
+const BN254: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+// poseidon([1, ..., N]) for N = 1..6, as computed by circomlib and ZoKrates
+const REFERENCE: [&str; 6] = [
+    "18586133768512220936620570745912940619677854269274689475585506675881198879027",
+    "7853200120776062878684798364095072458815029376092732009249414926327459813530",
+    "6542985608222806190361240322586112750744169038454362455181422643027100751666",
+    "18821383157269793795438455681495246036402687001665670618754263018637548127333",
+    "6183221330272524995739186171720101788151706631170188140075976616310159254464",
+    "20400040500897583745843009878988256314335038853985262692600694741116813247201",
+];
+
+#[test]
+fn test_poseidon_matches_reference() {
+    init(BN254);
+    for (i, expected) in REFERENCE.iter().enumerate() {
+        let n = i + 1;
+        let src = format!(
+            "\
+from zkpyc.types import Private, Array, field # zk_ignore
+from zkpyc.stdlib.hashes.poseidon.poseidon import poseidon
+
+def main(inputs: Private[Array[field, {n}]]) -> field:
+    return poseidon(inputs)
+"
+        );
+        let inputs = Inputs {
+            source: SourceInput::String(src, PathBuf::default(), "<main>".to_owned()),
+            entry_point: "main".to_owned(),
+            mode: Mode::Proof,
+            array_strategy: ArrayStrategy::default(),
+            max_inline_depth: DEFAULT_MAX_INLINE_DEPTH,
+        };
+        let cs = PythonFE::try_gen(inputs).unwrap_or_else(|d| panic!("{d}"));
+        let accepts = |ret: &str| {
+            let mut vals = values(&(1..=n).map(|j| format!("(inputs.{} #f{j})", j - 1)).collect::<String>());
+            vals.extend(values(&format!("(return #f{ret})")));
+            cs.comps["main"].eval_all(&vals).iter().all(|v| v.as_bool())
+        };
+        assert!(accepts(expected), "poseidon with {n} inputs");
+        assert!(!accepts("0"), "poseidon with {n} inputs");
+    }
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "snarkpack")]
+
+mod common;
+
+use common::{compile, init, values};
+use zkpyc_core::front::Mode;
+use zkpyc_core::utilities::bellman::{Bellman, Bls12};
+use zkpyc_core::utilities::proof::ProofSystem;
+use zkpyc_core::utilities::snarkpack::{aggregate, verify, AggregationSrs};
+
+const BLS12_381: &str = "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+
+#[test]
+fn test_aggregate_and_verify() {
+    init(BLS12_381);
+    let (p_data, v_data) = compile("mul.py", "main", Mode::Proof);
+    let (pk, vk) = Bellman::<Bls12>::setup(p_data, v_data).unwrap();
+    let pfs = ["(x #f4) (y #f5)", "(x #f2) (y #f3)", "(x #f7) (y #f1)"]
+        .iter()
+        .map(|w| Bellman::<Bls12>::prove(&pk, &values(w)).unwrap())
+        .collect::<Vec<_>>();
+    let srs = AggregationSrs::generate(2);
+    let agg = aggregate(&srs, &pfs).unwrap();
+
+    let insts = |returns: &[u32]| -> Vec<_> {
+        returns.iter().map(|r| values(&format!("(return #f{r})"))).collect()
+    };
+    assert!(verify(&srs, &vk, &insts(&[20, 6, 7]), &agg));
+    // a changed instance
+    assert!(!verify(&srs, &vk, &insts(&[20, 6, 8]), &agg));
+    // the instances out of order
+    assert!(!verify(&srs, &vk, &insts(&[6, 20, 7]), &agg));
+    // the padding of 3 proofs repeats the last one, so check a 4th copy of it
+    assert!(!verify(&srs, &vk, &insts(&[20, 6, 7, 7]), &agg));
+    assert!(!verify(&srs, &vk, &insts(&[20, 6]), &agg));
+    assert!(!verify(&srs, &vk, &[], &agg));
+}
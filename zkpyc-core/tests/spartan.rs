@@ -0,0 +1,29 @@
+#![cfg(feature = "spartan")]
+
+mod common;
+
+use common::{compile, init, values};
+use zkpyc_core::front::Mode;
+use zkpyc_core::utilities::proof::ProofSystem;
+use zkpyc_core::utilities::spartan::Spartan;
+
+const RISTRETTO255: &str = "7237005577332262213973186563042994240857116359379907606001950938285454250989";
+
+#[test]
+fn test_prove_and_verify() {
+    init(RISTRETTO255);
+    let (p_data, v_data) = compile("mul.py", "main", Mode::Proof);
+    let (pk, vk) = Spartan::setup(p_data, v_data).unwrap();
+    let pf = Spartan::prove(&pk, &values("(x #f4) (y #f5)")).unwrap();
+    assert!(Spartan::verify(&vk, &values("(return #f20)"), &pf));
+}
+
+#[test]
+fn test_wrong_inputs_are_rejected() {
+    init(RISTRETTO255);
+    let (p_data, v_data) = compile("mul.py", "main", Mode::Proof);
+    let (pk, vk) = Spartan::setup(p_data, v_data).unwrap();
+    let pf = Spartan::prove(&pk, &values("(x #f4) (y #f5)")).unwrap();
+    assert!(!Spartan::verify(&vk, &values("(return #f21)"), &pf));
+    assert!(!Spartan::verify(&vk, &values("(return #f0)"), &pf));
+}
@@ -3,6 +3,7 @@ from __future__ import annotations
 import os
 from zkpyc.__about__ import __author__, __version__
 from zkpyc.zkp import ZKP
+from zkpyc.errors import ZKPyCError, CompileError
 
 current_directory = os.path.dirname(os.path.abspath(__file__))
 stdlib_path = os.path.dirname(current_directory)
@@ -13,5 +14,7 @@ os.environ['ZKPYC_STDLIB_PATH'] = stdlib_path
 __all__ = [
     "__version__",
     "__author__",
-    "ZKP"
+    "ZKP",
+    "ZKPyCError",
+    "CompileError",
 ]
\ No newline at end of file
@@ -0,0 +1,45 @@
+"""Exceptions raised by ZKPyC.
+
+Compilation errors are instances of ``CompileError``, or of the subclass
+matching the first error found. They carry the attributes of that error
+(``code``, ``severity``, ``file``, ``line``, ``column``, ``function`` and
+``suggestion``), and ``diagnostics`` lists every error of the program as
+``Diagnostic`` objects with the same attributes.
+
+All of them derive from ``ZKPyCError``, a subclass of ``SyntaxError``, which
+is what compilation raised before, so ``except SyntaxError`` still works.
+"""
+
+from zkpyc.bindings import compiler
+
+ZKPyCError = compiler.ZKPyCError
+CompileError = compiler.CompileError
+ParseError = compiler.ParseError
+UndefinedNameError = compiler.UndefinedNameError
+TypeMismatchError = compiler.TypeMismatchError
+UnsupportedFeatureError = compiler.UnsupportedFeatureError
+NotConstantError = compiler.NotConstantError
+OutOfBoundsError = compiler.OutOfBoundsError
+ArityError = compiler.ArityError
+AnnotationError = compiler.AnnotationError
+ModuleImportError = compiler.ModuleImportError
+LoopBoundError = compiler.LoopBoundError
+InternalCompilerError = compiler.InternalCompilerError
+Diagnostic = compiler.Diagnostic
+
+__all__ = [
+    "ZKPyCError",
+    "CompileError",
+    "ParseError",
+    "UndefinedNameError",
+    "TypeMismatchError",
+    "UnsupportedFeatureError",
+    "NotConstantError",
+    "OutOfBoundsError",
+    "ArityError",
+    "AnnotationError",
+    "ModuleImportError",
+    "LoopBoundError",
+    "InternalCompilerError",
+    "Diagnostic",
+]
@@ -1,6 +1,6 @@
 import inspect
 import textwrap
-from zkpyc.types import Public, Private, Array, field
+from zkpyc.types import Public, Private, Array, field, _SignedInt, Fixed
 from dataclasses import fields
 from typing import get_type_hints
 
@@ -13,6 +13,8 @@ def contains_field_recursive(arg_type):
     elif getattr(arg_type, "__origin__", None) == Array:
         inner_type = arg_type.__args__[0]
         return contains_field_recursive(inner_type)
+    elif getattr(arg_type, "__origin__", None) is tuple:
+        return any(contains_field_recursive(t) for t in arg_type.__args__)
     else:
         return False
 
@@ -22,6 +24,12 @@ def parse_argument_value(value, arg_type, prefix=''):
         return parse_argument_value(value, inner_type, f'{prefix}')
     elif arg_type == int:
         return f'({prefix} #x{value:08x})'
+    elif isinstance(arg_type, type) and issubclass(arg_type, _SignedInt):
+        bits = arg_type.bits
+        return f'({prefix} #x{value & ((1 << bits) - 1):0{bits // 4}x})'
+    elif isinstance(arg_type, type) and issubclass(arg_type, Fixed):
+        bits = arg_type.int_bits + arg_type.frac_bits
+        return f'({prefix} #b{value.raw & ((1 << bits) - 1):0{bits}b})'
     elif arg_type == field:
         return f'({prefix} #f{value})'
     elif arg_type == bool:
@@ -41,6 +49,12 @@ def parse_argument_value(value, arg_type, prefix=''):
                 result.append(f'{inner_values}')
 
         return '\n'.join(result)
+    elif getattr(arg_type, "__origin__", None) is tuple:
+        # Tuple elements (e.g. of the return value) are named `{prefix}.{i}`
+        return '\n'.join(
+            parse_argument_value(inner_value, inner_type, f'{prefix}.{i}')
+            for i, (inner_value, inner_type) in enumerate(zip(value, arg_type.__args__))
+        )
     else:
         return str(value)
 
@@ -128,7 +142,9 @@ def get_variable_name(obj, global_vars, local_vars):
     return None
 
 def convert_literals(obj, class_type, field_tmp):
-    if isinstance(obj, (int, bool)):
+    if isinstance(obj, _SignedInt):
+        return f'{type(obj).__name__}({int(obj)})'
+    elif isinstance(obj, (int, bool)):
         return str(obj)
     elif isinstance(obj, list):
         return '[' + ', '.join(map(lambda x: convert_literals(x, class_type, field_tmp), obj)) + ']'
@@ -161,6 +177,8 @@ def infer_type_annotation(obj, name, class_type, field_tmp):
             inner_type = obj[0]
             inner_str = infer_type_annotation(inner_type, name, type(inner_type), field_tmp)
             return f'Array[{inner_str}, {len(obj)}]'
+    elif isinstance(obj, _SignedInt):
+        return type(obj).__name__
     elif isinstance(obj, int) and not isinstance(obj, bool):
         return 'int'
     elif isinstance(obj, bool):
@@ -30,7 +30,196 @@ class Public(Generic[T]):
 class Private(Generic[T]):
     pass
 
+class RAM:
+    """Marks an array for memory-checking lowering: `Array[field, 1024, RAM]`."""
+    pass
+
 class Array(Generic[T, N]):
+    def __class_getitem__(cls, params):
+        # The RAM marker only matters to the compiler
+        if isinstance(params, tuple) and len(params) == 3 and params[2] is RAM:
+            params = params[:2]
+        return super().__class_getitem__(params)
+
     def __getitem__(self, key: int) -> T:
         return self[key]
-    
\ No newline at end of file
+    
+
+
+class _SignedInt(int):
+    """Fixed-width two's complement integer (wraps around like the circuit)."""
+    bits = 0
+
+    def __new__(cls, value=0):
+        value = int(value) & ((1 << cls.bits) - 1)
+        if value >= 1 << (cls.bits - 1):
+            value -= 1 << cls.bits
+        return super().__new__(cls, value)
+
+    def _wrapping(op, reflected=False):
+        # Mixing widths is a type error in the circuit, so only plain ints mix in
+        def method(self, other):
+            if not isinstance(other, int) or (isinstance(other, _SignedInt) and type(other) is not type(self)):
+                return NotImplemented
+            a, b = (int(other), int(self)) if reflected else (int(self), int(other))
+            return type(self)(op(a, b))
+        return method
+
+    # `/` rounds down like `//`, as the circuit has no integer true division
+    __add__ = _wrapping(lambda a, b: a + b)
+    __sub__ = _wrapping(lambda a, b: a - b)
+    __mul__ = _wrapping(lambda a, b: a * b)
+    __truediv__ = _wrapping(lambda a, b: a // b)
+    __floordiv__ = _wrapping(lambda a, b: a // b)
+    __mod__ = _wrapping(lambda a, b: a % b)
+    __pow__ = _wrapping(lambda a, b: a ** b)
+    __lshift__ = _wrapping(lambda a, b: a << b)
+    __rshift__ = _wrapping(lambda a, b: a >> b)
+    __and__ = _wrapping(lambda a, b: a & b)
+    __or__ = _wrapping(lambda a, b: a | b)
+    __xor__ = _wrapping(lambda a, b: a ^ b)
+    __radd__ = _wrapping(lambda a, b: a + b, True)
+    __rsub__ = _wrapping(lambda a, b: a - b, True)
+    __rmul__ = _wrapping(lambda a, b: a * b, True)
+    __rtruediv__ = _wrapping(lambda a, b: a // b, True)
+    __rfloordiv__ = _wrapping(lambda a, b: a // b, True)
+    __rmod__ = _wrapping(lambda a, b: a % b, True)
+    __rand__ = _wrapping(lambda a, b: a & b, True)
+    __ror__ = _wrapping(lambda a, b: a | b, True)
+    __rxor__ = _wrapping(lambda a, b: a ^ b, True)
+    del _wrapping
+
+    def __neg__(self):
+        return type(self)(-int(self))
+
+    def __pos__(self):
+        return self
+
+    def __invert__(self):
+        return type(self)(~int(self))
+
+    def __repr__(self):
+        return f"{type(self).__name__}({int(self)})"
+
+class int8(_SignedInt):
+    bits = 8
+
+class int16(_SignedInt):
+    bits = 16
+
+class int32(_SignedInt):
+    bits = 32
+
+class int64(_SignedInt):
+    bits = 64
+
+class Fixed:
+    """Fixed-point number with `int_bits` (including the sign bit) and `frac_bits`,
+    stored as a two's complement integer scaled by 2**frac_bits. Use `Fixed[I, F]`
+    to get the class of a given format. Arithmetic rounds down; like in the
+    circuit, `+`, `-`, `*`, `/` and negation must not overflow the format,
+    while conversions from integers wrap around."""
+    int_bits = 0
+    frac_bits = 0
+    _formats = {}
+
+    def __class_getitem__(cls, params):
+        int_bits, frac_bits = params
+        if int_bits < 1 or frac_bits < 0:
+            raise ValueError(f"Invalid fixed-point format Fixed[{int_bits}, {frac_bits}]")
+        if params not in Fixed._formats:
+            Fixed._formats[params] = type(
+                f"Fixed[{int_bits}, {frac_bits}]",
+                (Fixed,),
+                {"int_bits": int_bits, "frac_bits": frac_bits},
+            )
+        return Fixed._formats[params]
+
+    def __init__(self, value=0):
+        self.raw = self._wrap(int(value * (1 << self.frac_bits) // 1))
+
+    @classmethod
+    def _wrap(cls, raw):
+        bits = cls.int_bits + cls.frac_bits
+        raw &= (1 << bits) - 1
+        return raw - (1 << bits) if raw >= 1 << (bits - 1) else raw
+
+    @classmethod
+    def _checked(cls, raw):
+        bits = cls.int_bits + cls.frac_bits
+        if not -(1 << (bits - 1)) <= raw < 1 << (bits - 1):
+            raise OverflowError(f"{cls.__name__} overflow")
+        return cls.from_raw(raw)
+
+    @classmethod
+    def from_raw(cls, raw):
+        x = cls.__new__(cls)
+        x.raw = cls._wrap(raw)
+        return x
+
+    def _operand(self, other):
+        if isinstance(other, int):
+            return type(self)(other).raw
+        if type(other) is type(self):
+            return other.raw
+        return NotImplemented
+
+    def __add__(self, other):
+        b = self._operand(other)
+        return NotImplemented if b is NotImplemented else self._checked(self.raw + b)
+
+    def __sub__(self, other):
+        b = self._operand(other)
+        return NotImplemented if b is NotImplemented else self._checked(self.raw - b)
+
+    def __mul__(self, other):
+        b = self._operand(other)
+        return NotImplemented if b is NotImplemented else self._checked((self.raw * b) >> self.frac_bits)
+
+    def __truediv__(self, other):
+        b = self._operand(other)
+        return NotImplemented if b is NotImplemented else self._checked((self.raw << self.frac_bits) // b)
+
+    def __radd__(self, other):
+        return self + other
+
+    def __rsub__(self, other):
+        return type(self)(other) - self
+
+    def __rmul__(self, other):
+        return self * other
+
+    def __rtruediv__(self, other):
+        return type(self)(other) / self
+
+    def __neg__(self):
+        return self._checked(-self.raw)
+
+    def __eq__(self, other):
+        b = self._operand(other)
+        return NotImplemented if b is NotImplemented else self.raw == b
+
+    def __lt__(self, other):
+        b = self._operand(other)
+        return NotImplemented if b is NotImplemented else self.raw < b
+
+    def __le__(self, other):
+        b = self._operand(other)
+        return NotImplemented if b is NotImplemented else self.raw <= b
+
+    def __gt__(self, other):
+        b = self._operand(other)
+        return NotImplemented if b is NotImplemented else self.raw > b
+
+    def __ge__(self, other):
+        b = self._operand(other)
+        return NotImplemented if b is NotImplemented else self.raw >= b
+
+    def __hash__(self):
+        return hash((type(self), self.raw))
+
+    def __float__(self):
+        return self.raw / (1 << self.frac_bits)
+
+    def __repr__(self):
+        return f"{type(self).__name__}({float(self)})"
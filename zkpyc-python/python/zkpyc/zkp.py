@@ -22,10 +22,9 @@ class ZKP:
             raise RuntimeError("Only one instance of the ZKP class is allowed.")
         return cls._instance
 
-    def compile(self, func, includes=None, global_vars=None, local_vars=None):
-        # Get the function implementation and name
+    def _source(self, func, includes, global_vars, local_vars):
+        # Get the function implementation
         func_impl = represent_object(func, current_module=self.module, is_entry_fct=True)
-        func_name = func.__name__
 
         # Get the processed objects
         if includes is None:
@@ -34,9 +33,21 @@ class ZKP:
             obj_impl = process_includes(includes, self.field, self.module, global_vars, local_vars)
 
         # Concatenate the function definition and processed objects
-        code = f"{obj_impl}{func_impl}"
+        return f"{obj_impl}{func_impl}"
+
+    def compile(self, func, includes=None, global_vars=None, local_vars=None, array_strategy="annotated", max_inline_depth=None):
+        # array_strategy is one of "annotated" (RAM only for Array[T, N, RAM]),
+        # "linear-scan" or "ram"; max_inline_depth bounds how deep calls nest
+        # (64 by default), counting every nested call, not only recursive ones
+        code = self._source(func, includes, global_vars, local_vars)
         # print(code)
-        return compiler.compile(func_name, code, self.id, f"<{self.module}>")
+        kwargs = {} if max_inline_depth is None else {"max_inline_depth": max_inline_depth}
+        return compiler.compile(func.__name__, code, self.id, f"<{self.module}>", array_strategy, **kwargs)
+
+    def count_array_strategies(self, func, includes=None, global_vars=None, local_vars=None):
+        # Constraint count for each array strategy, e.g. {"annotated": 1200, ...}
+        code = self._source(func, includes, global_vars, local_vars)
+        return dict(compiler.count_array_strategies(func.__name__, code, f"<{self.module}>"))
 
     def prepare_proof(self, func, *args, **kwargs):
         argument_names = func.__code__.co_varnames[:func.__code__.co_argcount]
@@ -3,6 +3,7 @@ use bincode;
 use pyo3::{prelude::*, exceptions, types::PyBytes};
 use zkinterface::{Reader, consumers::stats::Stats, Workspace};
 use zkinterface_bulletproofs::r1cs::R1CSProof;
+use zkpyc_core::utilities::{plonk, spartan};
 
 use crate::utilities::create_folder;
 
@@ -40,6 +41,14 @@ fn setup(
                     Ok(_) => Ok(()),
                     Err(err) => Err(exceptions::PyRuntimeError::new_err(format!("An error occurred: {}", err))),
             }
+            "plonk" => match plonk::zkif_backend::setup(&reader, &zkp_key_workspace, &key_name) {
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(exceptions::PyRuntimeError::new_err(format!("An error occurred: {}", err))),
+            }
+            "spartan" => match spartan::zkif_backend::setup(&reader, &zkp_key_workspace, &key_name) {
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(exceptions::PyRuntimeError::new_err(format!("An error occurred: {}", err))),
+            }
             e => Err(exceptions::PyValueError::new_err(format!("The backend: {}, is currently not supported.", e)))
         }
         None => Err(exceptions::PyValueError::new_err(format!("No backend provided for trusted setup.")))
@@ -99,6 +108,14 @@ fn prove(
                 Ok(_) => Ok(()),
                 Err(err) => Err(exceptions::PyRuntimeError::new_err(format!("An error occurred: {}", err))),
             }
+            "plonk" => match plonk::zkif_backend::prove(&reader, &zkp_key_workspace, &key_name, &proof_name) {
+                Ok(_) => Ok(()),
+                Err(err) => Err(exceptions::PyRuntimeError::new_err(format!("An error occurred: {}", err))),
+            }
+            "spartan" => match spartan::zkif_backend::prove(&reader, &zkp_key_workspace, &key_name, &proof_name) {
+                Ok(_) => Ok(()),
+                Err(err) => Err(exceptions::PyRuntimeError::new_err(format!("An error occurred: {}", err))),
+            }
             "bulletproofs" => {
                 let generators_count = (stats.multiplications.next_power_of_two()*2) as usize;
                 let proof_path = zkp_key_workspace.join(proof_name);
@@ -165,6 +182,14 @@ fn verify(
                 Ok(res) => Ok(res),
                 Err(err) => Err(exceptions::PyRuntimeError::new_err(format!("An error occurred: {}", err))),
             }
+            "plonk" => match plonk::zkif_backend::verify(&reader, &zkp_key_workspace, &key_name, &proof_name) {
+                Ok(res) => Ok(res),
+                Err(err) => Err(exceptions::PyRuntimeError::new_err(format!("An error occurred: {}", err))),
+            }
+            "spartan" => match spartan::zkif_backend::verify(&reader, &zkp_key_workspace, &key_name, &proof_name) {
+                Ok(res) => Ok(res),
+                Err(err) => Err(exceptions::PyRuntimeError::new_err(format!("An error occurred: {}", err))),
+            }
             "bulletproofs" => {
                 let generators_count = (stats.multiplications.next_power_of_two()*2) as usize;
                 let proof_path = zkp_key_workspace.join(proof_name);
@@ -1,10 +1,12 @@
-use pyo3::{prelude::*, exceptions};
+use pyo3::{prelude::*, exceptions, create_exception};
 
-use circ::ir::{opt::Opt, opt::opt, term::Computations};
+use circ::ir::{opt::opt, term::Computations};
 use circ_opt::CircOpt;
 use circ::cfg::cfg;
 use zkpyc_core::export::{write_constraints, prepare_prover_statements, prepare_verifier_statements};
 use zkpyc_core::front::{self, Mode::Proof, FrontEnd, python::Inputs, SourceInput};
+use zkpyc_core::front::python::{proof_opts, ArrayStrategy, DEFAULT_MAX_INLINE_DEPTH};
+use zkpyc_core::front::diagnostic::{Diagnostic, Diagnostics, ErrorCode};
 use zkpyc_core::utilities::r1cs::{ProverData, VerifierData};
 use zkpyc_core::utilities::proof::serialize_into_file;
 use zkpyc_core::utilities::scalar_fields::PrimeField;
@@ -24,6 +26,110 @@ enum Modulus {
     Integer(rug::Integer)
 }
 
+// Compilation used to fail with a SyntaxError, which existing callers catch
+create_exception!(compiler, ZKPyCError, exceptions::PySyntaxError, "Base class of the errors raised by ZKPyC, a subclass of SyntaxError.");
+create_exception!(compiler, CompileError, ZKPyCError, "A program that does not compile. `diagnostics` lists all errors found; the other attributes are those of the first.");
+create_exception!(compiler, ParseError, CompileError, "The source does not parse.");
+create_exception!(compiler, UndefinedNameError, CompileError, "A variable, function, class or field that does not exist.");
+create_exception!(compiler, TypeMismatchError, CompileError, "An operation applied to values of the wrong type.");
+create_exception!(compiler, UnsupportedFeatureError, CompileError, "Python that cannot be compiled to constraints (yet).");
+create_exception!(compiler, NotConstantError, CompileError, "A value that must be known at compile time is not.");
+create_exception!(compiler, OutOfBoundsError, CompileError, "An index or literal that does not fit.");
+create_exception!(compiler, ArityError, CompileError, "A call with the wrong number of arguments.");
+create_exception!(compiler, AnnotationError, CompileError, "A malformed type or visibility annotation.");
+create_exception!(compiler, ModuleImportError, CompileError, "A module that cannot be imported.");
+create_exception!(compiler, LoopBoundError, CompileError, "A loop that cannot be unrolled.");
+create_exception!(compiler, InternalCompilerError, CompileError, "A bug in the compiler rather than in the program.");
+
+/// One error of a [CompileError]
+#[pyclass(name = "Diagnostic", module = "zkpyc.compiler", get_all, frozen)]
+#[derive(Clone)]
+struct PyDiagnostic {
+    severity: String,
+    code: String,
+    message: String,
+    file: String,
+    line: usize,
+    column: usize,
+    end_line: usize,
+    end_column: usize,
+    function: Option<String>,
+    suggestion: Option<String>,
+    rendered: String,
+}
+
+#[pymethods]
+impl PyDiagnostic {
+    fn __str__(&self) -> String {
+        self.rendered.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Diagnostic {} at {}:{}:{}: {}>", self.code, self.file, self.line, self.column, self.message)
+    }
+}
+
+impl From<&Diagnostic> for PyDiagnostic {
+    fn from(d: &Diagnostic) -> Self {
+        PyDiagnostic {
+            severity: d.severity.to_string(),
+            code: d.code.to_string(),
+            message: d.message.clone(),
+            file: d.file.display().to_string(),
+            line: d.line,
+            column: d.column,
+            end_line: d.end_line,
+            end_column: d.end_column,
+            function: d.function.clone(),
+            suggestion: d.suggestion.clone(),
+            rendered: d.render(),
+        }
+    }
+}
+
+// The exception for failed compilation, of the class of its first error
+fn compile_error(diagnostics: Diagnostics) -> PyErr {
+    let msg = diagnostics.to_string();
+    let err = match diagnostics.0.first().map(|d| d.code) {
+        Some(ErrorCode::Syntax) => ParseError::new_err(msg),
+        Some(ErrorCode::UndefinedName) => UndefinedNameError::new_err(msg),
+        Some(ErrorCode::TypeMismatch) => TypeMismatchError::new_err(msg),
+        Some(ErrorCode::Unsupported) => UnsupportedFeatureError::new_err(msg),
+        Some(ErrorCode::NotConstant) => NotConstantError::new_err(msg),
+        Some(ErrorCode::OutOfBounds) => OutOfBoundsError::new_err(msg),
+        Some(ErrorCode::Arity) => ArityError::new_err(msg),
+        Some(ErrorCode::InvalidAnnotation) => AnnotationError::new_err(msg),
+        Some(ErrorCode::Import) => ModuleImportError::new_err(msg),
+        Some(ErrorCode::LoopBound) => LoopBoundError::new_err(msg),
+        Some(ErrorCode::Internal) => InternalCompilerError::new_err(msg),
+        None => CompileError::new_err(msg),
+    };
+    Python::with_gil(|py| match set_diagnostic_attrs(py, &err, &diagnostics) {
+        Ok(()) => err,
+        Err(e) => e,
+    })
+}
+
+fn set_diagnostic_attrs(py: Python, err: &PyErr, diagnostics: &Diagnostics) -> PyResult<()> {
+    let value = err.value(py);
+    let ds = diagnostics
+        .0
+        .iter()
+        .map(|d| Py::new(py, PyDiagnostic::from(d)))
+        .collect::<PyResult<Vec<_>>>()?;
+    if let Some(first) = ds.first() {
+        let first = first.borrow(py);
+        value.setattr("severity", &first.severity)?;
+        value.setattr("code", &first.code)?;
+        value.setattr("file", &first.file)?;
+        value.setattr("line", first.line)?;
+        value.setattr("column", first.column)?;
+        value.setattr("function", first.function.clone())?;
+        value.setattr("suggestion", first.suggestion.clone())?;
+    }
+    value.setattr("diagnostics", ds)
+}
+
 trait ProverOrVerifier {
     fn identifier() -> &'static str;
     fn input_type() -> &'static str;
@@ -99,40 +205,17 @@ impl ProverOrVerifier for Verifier {
     }
 }
 
+// Same pipeline as the `zkpyc` binary; which arrays get memory-checking
+// is decided by the front-end's array strategy.
 fn optimize_computations(cs: Computations) -> Computations {
-    let mut opts = Vec::new();
-
-    opts.push(Opt::ScalarizeVars);
-    opts.push(Opt::Flatten);
-    opts.push(Opt::Sha);
-    opts.push(Opt::ConstantFold(Box::new([])));
-    opts.push(Opt::ParseCondStores);
-    // Tuples must be eliminated before oblivious array elim
-    opts.push(Opt::Tuple);
-    opts.push(Opt::ConstantFold(Box::new([])));
-    opts.push(Opt::Tuple);
-    opts.push(Opt::Obliv);
-    // The obliv elim pass produces more tuples, that must be eliminated
-    opts.push(Opt::Tuple);
-    // The following optimizations are run when the ram option is enabled
-    // We will assume it is true by default because cfg() does not expose
-    // CircOpt yet (it is still private), and a workaround isn't worth it.
-    opts.push(Opt::PersistentRam);
-    opts.push(Opt::VolatileRam);
-    opts.push(Opt::SkolemizeChallenges);
-    opts.push(Opt::LinearScan);
-    // The linear scan pass produces more tuples, that must be eliminated
-    opts.push(Opt::Tuple);
-    opts.push(Opt::Flatten);
-    opts.push(Opt::ConstantFold(Box::new([])));
-    opt(cs, opts)
+    opt(cs, proof_opts())
 }
 
 fn run_zkpyc_compiler(
     f_name: &String,
     inputs: Inputs,
 ) -> PyResult<(ProverData, VerifierData, usize)> {    
-    let cs = front::python::PythonFE::gen(inputs);
+    let cs = front::python::PythonFE::try_gen(inputs).map_err(compile_error)?;
     let cs = optimize_computations(cs);
     let cs = cs.get(f_name);
     let mut r1cs = to_r1cs(cs, cfg());
@@ -161,15 +244,53 @@ fn cleanup(id: usize) -> PyResult<()> {
     Ok(fs::remove_dir_all(workspace)?)
 }
 
+fn parse_array_strategy(array_strategy: &str) -> PyResult<ArrayStrategy> {
+    array_strategy.parse().map_err(exceptions::PyValueError::new_err)
+}
+
+// Catch the panics of the compiler and turn them into Python errors
+fn catch_compiler_panic<T>(f: impl FnOnce() -> PyResult<T> + panic::UnwindSafe) -> PyResult<T> {
+    panic::set_hook(Box::new(|_info| {
+        // do nothing
+    }));
+    match panic::catch_unwind(f) {
+        Ok(res) => res,
+        Err(panic_payload) => {
+            // Try to extract the panic message from the payload
+            let panic_msg = if let Some(msg) = panic_payload.downcast_ref::<&str>() {
+                msg.to_string()
+            } else if let Some(msg) = panic_payload.downcast_ref::<String>() {
+                msg.clone()
+            } else {
+                "Unknown panic message".to_string()
+            };
+
+            // Return the panic message as a Python error
+            Err(ZKPyCError::new_err(panic_msg))
+        }
+    }
+}
+
 #[pyfunction]
-#[pyo3(signature = (f_name, input, id=0, module_name=String::from("<__main__>")))]
+#[pyo3(signature = (
+    f_name,
+    input,
+    id=0,
+    module_name=String::from("<__main__>"),
+    array_strategy="annotated",
+    max_inline_depth=DEFAULT_MAX_INLINE_DEPTH,
+))]
 fn compile(
     _py: Python,
     f_name: String,
     input: String,
     id: usize,
     module_name: String,
+    array_strategy: &str,
+    max_inline_depth: usize,
 ) -> PyResult<usize> {
+    let array_strategy = parse_array_strategy(array_strategy)?;
+
     // Define directory where ZKP data will be stored
     let workspace = create_folder(Path::new("."), &format!("cache_id_{}", id));
     
@@ -191,40 +312,12 @@ fn compile(
         source,
         entry_point: f_name.clone(),
         mode: Proof,
+        array_strategy,
+        max_inline_depth,
     };
 
     // Run ZKPyC and catch panic or other PyErrors
-    panic::set_hook(Box::new(|_info| {
-        // do nothing
-    }));
-    let result = panic::catch_unwind(|| run_zkpyc_compiler(&f_name, inputs));
-
-    // Remove temporary function definition file.
-    let (pd, vd, constr_count) = match result {
-        // Ok(Ok(res)) => {
-        //     remove_file(&file_path)?;
-        //     res
-        // }
-        // Ok(Err(err)) => {
-        //     remove_file(&file_path)?;
-        //     return Err(err);
-        // }
-        Ok(Ok(res)) => res,
-        Ok(Err(err)) => return Err(err),
-        Err(panic_payload) => {
-            // Try to extract the panic message from the payload
-            let panic_msg = if let Some(msg) = panic_payload.downcast_ref::<&str>() {
-                msg.to_string()
-            } else if let Some(msg) = panic_payload.downcast_ref::<String>() {
-                msg.clone()
-            } else {
-                "Unknown panic message".to_string()
-            };
-
-            // Return the panic message as a Python error
-            return Err(exceptions::PySyntaxError::new_err(panic_msg));
-        }
-    };
+    let (pd, vd, constr_count) = catch_compiler_panic(|| run_zkpyc_compiler(&f_name, inputs))?;
 
     let zkif_workspace = create_folder(&workspace, "zkif_export");
     match Modulus::Integer(cfg().field().modulus().clone()) {
@@ -253,6 +346,32 @@ fn compile(
     Ok(constr_count)
 }
 
+/// Constraint count of `f_name` for each array lowering strategy, so that
+/// the cheapest one can be picked.
+#[pyfunction]
+#[pyo3(signature = (f_name, input, module_name=String::from("<__main__>")))]
+fn count_array_strategies(
+    _py: Python,
+    f_name: String,
+    input: String,
+    module_name: String,
+) -> PyResult<Vec<(String, usize)>> {
+    ArrayStrategy::ALL
+        .into_iter()
+        .map(|array_strategy| {
+            let inputs = Inputs {
+                source: SourceInput::String(input.clone(), PathBuf::default(), module_name.clone()),
+                entry_point: f_name.clone(),
+                mode: Proof,
+                array_strategy,
+                max_inline_depth: DEFAULT_MAX_INLINE_DEPTH,
+            };
+            let (_, _, count) = catch_compiler_panic(|| run_zkpyc_compiler(&f_name, inputs))?;
+            Ok((array_strategy.to_string(), count))
+        })
+        .collect()
+}
+
 fn setup_proof_or_verification<PV: ProverOrVerifier>(
     _py: Python,
     f_name: String,
@@ -334,8 +453,23 @@ pub(crate) fn create_submodule(py: pyo3::Python<'_>) -> pyo3::PyResult<&pyo3::pr
     let submod = pyo3::prelude::PyModule::new(py, "compiler")?;
     submod.add_function(pyo3::wrap_pyfunction!(init, submod)?)?;
     submod.add_function(pyo3::wrap_pyfunction!(compile, submod)?)?;
+    submod.add_function(pyo3::wrap_pyfunction!(count_array_strategies, submod)?)?;
     submod.add_function(pyo3::wrap_pyfunction!(cleanup, submod)?)?;
     submod.add_function(pyo3::wrap_pyfunction!(setup_proof, submod)?)?;
     submod.add_function(pyo3::wrap_pyfunction!(setup_verification, submod)?)?;
+    submod.add_class::<PyDiagnostic>()?;
+    submod.add("ZKPyCError", py.get_type::<ZKPyCError>())?;
+    submod.add("CompileError", py.get_type::<CompileError>())?;
+    submod.add("ParseError", py.get_type::<ParseError>())?;
+    submod.add("UndefinedNameError", py.get_type::<UndefinedNameError>())?;
+    submod.add("TypeMismatchError", py.get_type::<TypeMismatchError>())?;
+    submod.add("UnsupportedFeatureError", py.get_type::<UnsupportedFeatureError>())?;
+    submod.add("NotConstantError", py.get_type::<NotConstantError>())?;
+    submod.add("OutOfBoundsError", py.get_type::<OutOfBoundsError>())?;
+    submod.add("ArityError", py.get_type::<ArityError>())?;
+    submod.add("AnnotationError", py.get_type::<AnnotationError>())?;
+    submod.add("ModuleImportError", py.get_type::<ModuleImportError>())?;
+    submod.add("LoopBoundError", py.get_type::<LoopBoundError>())?;
+    submod.add("InternalCompilerError", py.get_type::<InternalCompilerError>())?;
     Ok(submod)
 }
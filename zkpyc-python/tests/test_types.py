@@ -0,0 +1,56 @@
+import unittest
+
+from zkpyc.types import Fixed, int8, int16, int32, int64
+
+
+class SignedIntTest(unittest.TestCase):
+    def test_construction_wraps(self):
+        self.assertEqual(int8(127), 127)
+        self.assertEqual(int8(128), -128)
+        self.assertEqual(int16(-32769), 32767)
+        self.assertEqual(int64(1 << 63), -(1 << 63))
+
+    def test_arithmetic_wraps(self):
+        self.assertEqual(int8(127) + 1, -128)
+        self.assertEqual(int8(-128) - 1, 127)
+        self.assertEqual(int8(64) * 2, -128)
+        self.assertEqual(-int8(-128), -128)
+        self.assertEqual(int8(1) << 7, -128)
+        self.assertEqual(int32(2) ** 31, -(1 << 31))
+        self.assertEqual(1 - int8(-128), -127)
+        self.assertIs(type(int8(1) + 1), int8)
+        self.assertIs(type(1 + int8(1)), int8)
+
+    def test_division_rounds_down(self):
+        self.assertEqual(int8(-7) / 2, -4)
+        self.assertEqual(int8(-7) // 2, -4)
+        self.assertEqual(int8(-7) % 2, 1)
+        self.assertEqual(int8(-128) // -1, -128)
+        self.assertEqual(int8(-16) >> 2, -4)
+
+    def test_bitwise(self):
+        self.assertEqual(~int8(0), -1)
+        self.assertEqual(int8(-1) & 0x0f, 15)
+        self.assertEqual(int8(0x70) | 0x0f, 127)
+        self.assertEqual(int8(-1) ^ 0x80, 127)
+
+    def test_mixed_widths_are_rejected(self):
+        with self.assertRaises(TypeError):
+            int8(1) + int16(1)
+        with self.assertRaises(TypeError):
+            int16(1) * int8(1)
+
+
+class FixedTest(unittest.TestCase):
+    def test_overflow_is_an_error(self):
+        # Fixed[8, 4] holds [-128, 128)
+        F = Fixed[8, 4]
+        self.assertEqual(F(3) / F(0.5), 6)
+        self.assertEqual(-F(127), -127)
+        for op in (lambda: F(100) + 100, lambda: F(16) * 8, lambda: F(64) / F(0.5), lambda: -F(-128)):
+            with self.assertRaises(OverflowError):
+                op()
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -1,5 +1,5 @@
 
-from zkpyc.types import Array, field # zk_ignore
+from zkpyc.types import Array, field, Fixed # zk_ignore
 from typing import Union, Any, List #zk_ignore
 from math import floor, log2 #zk_ignore
 
@@ -55,3 +55,46 @@ def get_field_size() -> int:
 sum_ = sum # zk_ignore
 def sum(x: Array[Union[int, field], Any]) -> Union[int, field]:
     return sum_(x) # type: ignore
+
+
+# Rounding modes for fixed_rescale
+ROUND_FLOOR: int = 0
+ROUND_NEAREST: int = 1
+ROUND_ZERO: int = 2
+
+
+def fixed_from_uint(x: int, int_bits: int, frac_bits: int) -> Fixed:
+    return Fixed[int_bits, frac_bits](x) # type: ignore
+
+
+def fixed_from_field(x: field, int_bits: int, frac_bits: int) -> Fixed:
+    if int(x) >= 1 << (int_bits - 1): # type: ignore
+        raise ValueError("Field element does not fit in the integer bits")
+    return Fixed[int_bits, frac_bits](int(x)) # type: ignore
+
+
+def fixed_from_ratio(num: int, den: int, int_bits: int, frac_bits: int) -> Fixed:
+    return Fixed[int_bits, frac_bits].from_raw((num << frac_bits) // den) # type: ignore
+
+
+def fixed_to_uint(x: Fixed) -> int:
+    return (x.raw >> x.frac_bits) & 0xffffffff
+
+
+def fixed_to_field(x: Fixed) -> field:
+    return field(x.raw >> x.frac_bits) # type: ignore
+
+
+def fixed_rescale(x: Fixed, int_bits: int, frac_bits: int, mode: int) -> Fixed:
+    d = x.frac_bits - frac_bits
+    if d <= 0:
+        raw = x.raw << -d
+    elif mode == ROUND_FLOOR:
+        raw = x.raw >> d
+    elif mode == ROUND_NEAREST:
+        raw = (x.raw + (1 << (d - 1))) >> d
+    elif mode == ROUND_ZERO:
+        raw = -((-x.raw) >> d) if x.raw < 0 else x.raw >> d
+    else:
+        raise ValueError(f"Unknown rounding mode {mode}")
+    return Fixed[int_bits, frac_bits]._checked(raw) # type: ignore
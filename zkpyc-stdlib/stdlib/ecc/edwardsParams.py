@@ -12,3 +12,16 @@ class EdwardsParams:
 	INFINITY: Array[field, 2]
 	G: Array[field, 2]
 	H: Array[field, 2]
+
+	# Add two points on the curve
+	# https://en.wikipedia.org/wiki/Twisted_Edwards_curve#Addition_on_twisted_Edwards_curves
+	def add(self, pt1: Array[field, 2], pt2: Array[field, 2]) -> Array[field, 2]:
+		u1: field = pt1[0]
+		v1: field = pt1[1]
+		u2: field = pt2[0]
+		v2: field = pt2[1]
+
+		uOut: field = (u1*v2 + v1*u2) / (field(1) + self.EDWARDS_D*u1*u2*v1*v2)
+		vOut: field = (v1*v2 - self.EDWARDS_A*u1*u2) / (field(1) - self.EDWARDS_D*u1*u2*v1*v2)
+
+		return [uOut, vOut]
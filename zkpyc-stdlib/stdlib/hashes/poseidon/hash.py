@@ -1,7 +1,7 @@
 from zkpyc.types import Private, Array, field # zk_ignore
 from zkpyc.stdlib.hashes.poseidon.poseidon import poseidon
 
-# let N = 6 for now
+# entry points take concrete sizes; poseidon itself takes 1 to 6 inputs
 def hash(inputs: Private[Array[field, 6]]) -> field:
     out: field = poseidon(inputs)
     return out
\ No newline at end of file